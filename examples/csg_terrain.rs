@@ -0,0 +1,67 @@
+//! Carves a spherical crater out of a heightfield terrain and writes the
+//! result to a binary STL file.
+//!
+//! ```sh
+//! cargo run --example csg_terrain -- [output.stl] [max_res]
+//! ```
+
+use dual_contouring::source::from_fn;
+use dual_contouring::{
+    DualContouring, FiniteDifference, IndexedSeparateNormals, WithIndexedSeparateNormals,
+};
+use glam::Vec3;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+const CRATER_CENTER: Vec3 = Vec3::new(0.2, 0.1, -0.1);
+const CRATER_RADIUS: f32 = 0.45;
+
+/// Rolling hills, as a (bound of the) distance to the ground.
+fn terrain(p: Vec3) -> f32 {
+    let height = 0.15 * (3.0 * p.x).sin() * (2.0 * p.z).cos() - 0.2;
+    p.y - height
+}
+
+fn crater(p: Vec3) -> f32 {
+    (p - CRATER_CENTER).length() - CRATER_RADIUS
+}
+
+fn run<W: Write>(max_res: u32, writer: W) -> Result<(), Box<dyn Error>> {
+    // Subtracting a shape is intersecting with its complement.
+    let source = FiniteDifference::new(from_fn(|p| terrain(p).max(-crater(p))), 1e-3);
+
+    let mut mesh = IndexedSeparateNormals::default();
+    DualContouring::new(&source, max_res, 1e-4)
+        .extract(WithIndexedSeparateNormals::new(&mut mesh, &source))?;
+
+    mesh.write_stl(writer)?;
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().unwrap_or_else(|| "terrain.stl".to_owned());
+
+    let max_res = match args.next() {
+        Some(arg) => arg.parse()?,
+        None => 128,
+    };
+
+    run(max_res, BufWriter::new(File::create(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_terrain() {
+        let mut stl = Vec::new();
+        run(8, &mut stl).unwrap();
+
+        let count = u32::from_le_bytes(stl[80..84].try_into().unwrap());
+        assert!(count > 0);
+        assert_eq!(stl.len(), 84 + 50 * count as usize);
+    }
+}
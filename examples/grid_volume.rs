@@ -0,0 +1,87 @@
+//! Contours a volume of raw little-endian `f32` values, such as a baked
+//! distance field, and writes it to stdout in the OBJ format.
+//!
+//! The volume is mapped onto the `[-1, 1]³` cube.
+//!
+//! ```sh
+//! cargo run --example grid_volume -- <volume.raw> <nx> <ny> <nz> [max_res] > volume.obj
+//! ```
+
+use dual_contouring::{
+    Bounds, DualContouring, GridSource, IndexedSeparateNormals, WithIndexedSeparateNormals,
+};
+use glam::{UVec3, Vec3};
+use std::error::Error;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+fn bounds() -> Bounds {
+    Bounds::new(Vec3::splat(-1.0), Vec3::ONE)
+}
+
+fn run<W: Write>(path: &Path, dims: UVec3, max_res: u32, writer: W) -> Result<(), Box<dyn Error>> {
+    let grid = GridSource::open(path, dims, bounds())?;
+
+    let mut mesh = IndexedSeparateNormals::default();
+    DualContouring::new(&grid, max_res, 1e-4)
+        .with_bounds(bounds())
+        .extract(WithIndexedSeparateNormals::new(&mut mesh, &grid))?;
+
+    mesh.write_obj(writer)?;
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if !(4..=5).contains(&args.len()) {
+        return Err("usage: grid_volume <volume.raw> <nx> <ny> <nz> [max_res]".into());
+    }
+
+    let dims = UVec3::new(args[1].parse()?, args[2].parse()?, args[3].parse()?);
+
+    let max_res = match args.get(4) {
+        Some(arg) => arg.parse()?,
+        None => 64,
+    };
+
+    run(
+        Path::new(&args[0]),
+        dims,
+        max_res,
+        BufWriter::new(io::stdout().lock()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contours_raw_volume() {
+        // A sphere sampled on a 9³ grid.
+        let n = 9;
+        let spacing = bounds().size() / (n - 1) as f32;
+        let mut bytes = Vec::new();
+
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    let p = bounds().min + UVec3::new(x, y, z).as_vec3() * spacing;
+                    bytes.extend((p.length() - 0.6).to_le_bytes());
+                }
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!("grid_volume_{}.raw", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+
+        let mut obj = Vec::new();
+        let result = run(&path, UVec3::splat(n), 8, &mut obj);
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.lines().any(|line| line.starts_with("f ")));
+    }
+}
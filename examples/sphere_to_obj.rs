@@ -0,0 +1,48 @@
+//! Extracts an analytic sphere and writes it to stdout in the OBJ format.
+//!
+//! ```sh
+//! cargo run --example sphere_to_obj -- [max_res] > sphere.obj
+//! ```
+
+use dual_contouring::source::hermite_from_fn;
+use dual_contouring::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+use glam::Vec3;
+use std::error::Error;
+use std::io::{self, BufWriter, Write};
+
+const RADIUS: f32 = 0.8;
+
+fn run<W: Write>(max_res: u32, writer: W) -> Result<(), Box<dyn Error>> {
+    let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+
+    let mut mesh = IndexedSeparateNormals::default();
+    DualContouring::new(&sphere, max_res, 1e-4)
+        .extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))?;
+
+    mesh.write_obj(writer)?;
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let max_res = match std::env::args().nth(1) {
+        Some(arg) => arg.parse()?,
+        None => 64,
+    };
+
+    run(max_res, BufWriter::new(io::stdout().lock()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_sphere() {
+        let mut obj = Vec::new();
+        run(8, &mut obj).unwrap();
+
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.lines().any(|line| line.starts_with("v ")));
+        assert!(obj.lines().any(|line| line.starts_with("f ")));
+    }
+}
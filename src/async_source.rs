@@ -0,0 +1,655 @@
+//! Extraction from sources sampled asynchronously and in batches, e.g.,
+//! fields read back from a GPU or served over a network, for which a round
+//! trip per point is hopeless.
+
+use crate::budget::OctreeBudget;
+use crate::collections::Map;
+use crate::contour::{self, Contour, EdgeIntersection, PlacedVertices};
+use crate::octree::sampled_corners;
+use crate::source::{HermiteSource, Source, ZeroIsInside};
+use crate::topology::{Edge, OctreeCell};
+use crate::{
+    may_contain_surface, BoundaryPolicy, DualContouring, ExtractSurfaceError, ExtractionReport,
+    Extractor,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::future::Future;
+use core::mem;
+use core::ops::ControlFlow;
+use glam::Vec3;
+
+/// A source sampled in batches of points, asynchronously, extracted with
+/// [`DualContouring::extract_async`].
+///
+/// Both methods return one result per point, in the order of the points, and
+/// may be implemented with `async fn`. The futures needn't be `Send`, but the
+/// extraction is only `Send` if they are.
+pub trait AsyncSource {
+    /// Samples the source at the given points, see [`Source::sample`].
+    fn sample_batch(&self, points: &[Vec3]) -> impl Future<Output = Vec<f32>>;
+
+    /// Samples the outward unit normals at the given points, see
+    /// [`HermiteSource::sample_normal`].
+    fn sample_normal_batch(&self, points: &[Vec3]) -> impl Future<Output = Vec<Vec3>>;
+
+    /// See [`Source::zero_is_inside`].
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        ZeroIsInside::default()
+    }
+}
+
+impl<T: AsyncSource + ?Sized> AsyncSource for &T {
+    fn sample_batch(&self, points: &[Vec3]) -> impl Future<Output = Vec<f32>> {
+        (**self).sample_batch(points)
+    }
+
+    fn sample_normal_batch(&self, points: &[Vec3]) -> impl Future<Output = Vec<Vec3>> {
+        (**self).sample_normal_batch(points)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        (**self).zero_is_inside()
+    }
+}
+
+/// Results fetched from an [`AsyncSource`], by the bits of their points,
+/// with the points requested since the last fetch.
+struct Fetched<T> {
+    /// The results, or `None` for requested points.
+    results: Map<[u32; 3], Option<T>>,
+    pending: Vec<Vec3>,
+}
+
+impl<T: Copy> Fetched<T> {
+    fn new() -> Self {
+        Self {
+            results: Map::default(),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the result at `point`, if it was fetched, requesting it if it
+    /// wasn't yet and `request` is `true`.
+    fn get(&mut self, point: Vec3, request: bool) -> Option<T> {
+        let key = point.to_array().map(f32::to_bits);
+
+        match self.results.get(&key) {
+            Some(result) => *result,
+            None => {
+                if request {
+                    self.results.insert(key, None);
+                    self.pending.push(point);
+                }
+
+                None
+            }
+        }
+    }
+
+    /// Returns the points requested since the last call.
+    fn take_pending(&mut self) -> Vec<Vec3> {
+        mem::take(&mut self.pending)
+    }
+
+    /// Stores the results fetched at `points`.
+    ///
+    /// # Panics
+    ///
+    /// If there isn't one result per point.
+    fn insert(&mut self, points: Vec<Vec3>, results: Vec<T>) {
+        assert_eq!(
+            results.len(),
+            points.len(),
+            "the batch must have one result per point"
+        );
+
+        for (point, result) in points.into_iter().zip(results) {
+            self.results
+                .insert(point.to_array().map(f32::to_bits), Some(result));
+        }
+    }
+}
+
+/// Which of the pending points an attempt samples it requests, see
+/// [`Cache::attempt`].
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Requests {
+    /// Only the first one, as the points sampled after it may depend on its
+    /// value, e.g., along a bisection.
+    First,
+    /// All of them, as they don't depend on each other's values.
+    All,
+}
+
+/// The values and normals of an [`AsyncSource`] fetched so far, as a
+/// synchronous source.
+///
+/// Points that weren't fetched yet are pending, which is tracked apart from
+/// the values of the source: the source is sampled within attempts, which
+/// request the pending points they sample and are thrown away if there were
+/// any, to be made again once the points are fetched. So a pending point
+/// never passes for an undefined one.
+struct Cache<'a, S> {
+    source: &'a S,
+    values: RefCell<Fetched<f32>>,
+    normals: RefCell<Fetched<Vec3>>,
+    /// Which pending points the current attempt requests.
+    requests: Cell<Requests>,
+    /// Whether the current attempt sampled a pending point.
+    missed: Cell<bool>,
+}
+
+impl<'a, S: AsyncSource> Cache<'a, S> {
+    fn new(source: &'a S) -> Self {
+        Self {
+            source,
+            values: RefCell::new(Fetched::new()),
+            normals: RefCell::new(Fetched::new()),
+            requests: Cell::new(Requests::All),
+            missed: Cell::new(false),
+        }
+    }
+
+    /// Runs `f` over the points fetched so far, returning its result unless
+    /// it sampled pending points, which are requested as by `requests`.
+    ///
+    /// Within the attempt, pending points are undefined and their values and
+    /// normals are 0, so that `f` stops early at the first one, but its
+    /// result is thrown away anyway.
+    fn attempt<T>(&self, requests: Requests, f: impl FnOnce() -> T) -> Option<T> {
+        self.requests.set(requests);
+        self.missed.set(false);
+        let result = f();
+        (!self.missed.get()).then_some(result)
+    }
+
+    /// Returns the result at `point` if it was fetched, and requests it
+    /// otherwise, if the current attempt does.
+    fn get<T: Copy>(&self, fetched: &RefCell<Fetched<T>>, point: Vec3) -> Option<T> {
+        let request = !self.missed.get() || self.requests.get() == Requests::All;
+        let result = fetched.borrow_mut().get(point, request);
+        self.missed.set(self.missed.get() || result.is_none());
+        result
+    }
+
+    /// Fetches the requested values in a batch, then the requested normals
+    /// in another, returning whether there were any.
+    async fn fetch(&mut self) -> bool {
+        let (values, normals) = (self.values.get_mut(), self.normals.get_mut());
+        let (value_points, normal_points) = (values.take_pending(), normals.take_pending());
+        let fetched = !value_points.is_empty() || !normal_points.is_empty();
+
+        if !value_points.is_empty() {
+            let results = self.source.sample_batch(&value_points).await;
+            values.insert(value_points, results);
+        }
+
+        if !normal_points.is_empty() {
+            let results = self.source.sample_normal_batch(&normal_points).await;
+            normals.insert(normal_points, results);
+        }
+
+        fetched
+    }
+}
+
+impl<S: AsyncSource> Source for Cache<'_, S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.get(&self.values, point).unwrap_or(0.0)
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.get(&self.values, point).is_some()
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+impl<S: AsyncSource> HermiteSource for Cache<'_, S> {
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        self.get(&self.normals, point).unwrap_or(Vec3::ZERO)
+    }
+}
+
+/// How far an extraction from an [`AsyncSource`] is, see
+/// [`DualContouring::extract_async`].
+enum Stage {
+    /// Sampling the octree of [`assume_lipschitz`] down from the cells in
+    /// `frontier`, whose corners weren't fetched yet.
+    ///
+    /// [`assume_lipschitz`]: DualContouring::assume_lipschitz
+    Octree { frontier: Vec<OctreeCell> },
+    /// Intersecting the minimal edges of the octree, those in `pending`
+    /// waiting for points to be fetched, the others being in `contour`.
+    Edges {
+        budget: OctreeBudget,
+        contour: Contour,
+        pending: Vec<Edge>,
+    },
+    /// Placing the vertices of the sorted `contour`.
+    Vertices {
+        budget: OctreeBudget,
+        contour: Contour,
+    },
+}
+
+impl Stage {
+    fn new(assume_lipschitz: bool) -> Self {
+        let frontier = if assume_lipschitz {
+            vec![OctreeCell::root()]
+        } else {
+            Vec::new()
+        };
+
+        Stage::Octree { frontier }
+    }
+
+    /// Advances the extraction over `dc` as far as the points fetched into
+    /// `cache` allow, returning the stage waiting for the points it
+    /// requested, or the result once done.
+    ///
+    /// `extractor_cost` is the bytes the extractor is estimated to take per
+    /// vertex, see [`Extractor::reserve_hint`].
+    fn advance<S: HermiteSource, A: AsyncSource>(
+        mut self,
+        dc: &DualContouring<S>,
+        cache: &Cache<'_, A>,
+        extractor_cost: usize,
+    ) -> ControlFlow<Result<PlacedVertices, ExtractSurfaceError>, Self> {
+        loop {
+            self = match self {
+                Stage::Octree { mut frontier } => {
+                    sample_octree(dc, cache, &mut frontier);
+
+                    // Every corner the octree samples is fetched by then.
+                    let built = frontier.is_empty().then(|| {
+                        cache.attempt(Requests::All, || {
+                            dc.build_octree_within_budget(extractor_cost)
+                        })
+                    });
+
+                    let Some(Some((octree, budget))) = built else {
+                        return ControlFlow::Continue(Stage::Octree { frontier });
+                    };
+
+                    let mut contour = Contour::default();
+                    let mut pending = Vec::new();
+
+                    octree.for_each_minimal_edge(|edge| match intersect(dc, cache, edge) {
+                        Some(intersection) => {
+                            contour.add_intersection(edge, intersection);
+                        }
+                        None => pending.push(*edge),
+                    });
+
+                    Stage::Edges {
+                        budget,
+                        contour,
+                        pending,
+                    }
+                }
+                Stage::Edges {
+                    budget,
+                    mut contour,
+                    mut pending,
+                } => {
+                    pending.retain(|edge| match intersect(dc, cache, edge) {
+                        Some(intersection) => {
+                            contour.add_intersection(edge, intersection);
+                            false
+                        }
+                        None => true,
+                    });
+
+                    if !pending.is_empty() {
+                        return ControlFlow::Continue(Stage::Edges {
+                            budget,
+                            contour,
+                            pending,
+                        });
+                    }
+
+                    if let Err(error) = contour.check() {
+                        return ControlFlow::Break(Err(error));
+                    }
+
+                    contour.sort();
+                    Stage::Vertices { budget, contour }
+                }
+                Stage::Vertices { budget, contour } => {
+                    // The normals sampled at the vertices, and at the quads
+                    // with `QuadSplit::AlignWithNormal`, are independent.
+                    let domain = dc.octree_domain();
+                    let placed = cache.attempt(Requests::All, || {
+                        contour::place_contour(dc, &domain, &contour)
+                    });
+
+                    let Some(mut placed) = placed else {
+                        return ControlFlow::Continue(Stage::Vertices { budget, contour });
+                    };
+
+                    budget.report(&mut placed.report);
+                    return ControlFlow::Break(Ok(placed));
+                }
+            };
+        }
+    }
+}
+
+/// Subdivides the cells in `frontier` as [`Octree::build_sampled`] does with
+/// [`assume_lipschitz`], regardless of the memory budget, as far as their
+/// corners were fetched, leaving the cells whose corners weren't.
+///
+/// [`Octree::build_sampled`]: crate::Octree::build_sampled
+/// [`assume_lipschitz`]: DualContouring::assume_lipschitz
+fn sample_octree<S: HermiteSource, A: AsyncSource>(
+    dc: &DualContouring<S>,
+    cache: &Cache<'_, A>,
+    frontier: &mut Vec<OctreeCell>,
+) {
+    let (domain, max_level) = (dc.octree_domain(), dc.max_level());
+    let mut waiting = Vec::new();
+
+    while let Some(cell) = frontier.pop() {
+        // Leaves at the resolution aren't sampled.
+        if cell.level() >= max_level {
+            continue;
+        }
+
+        let points = sampled_corners(&domain, max_level, &cell);
+
+        let Some(corners) = cache.attempt(Requests::All, || {
+            points.map(|point| dc.source.sample(point))
+        }) else {
+            waiting.push(cell);
+            continue;
+        };
+
+        if dc.straddles_bounds(&cell)
+            || (dc.may_subdivide(&cell) && may_contain_surface(&cell.bounds(&domain), &corners))
+        {
+            frontier.extend(cell.sub_cells());
+        }
+    }
+
+    *frontier = waiting;
+}
+
+/// Intersects the surface of `dc` with a minimal edge, unless that samples
+/// points that weren't fetched yet.
+///
+/// Further points along the edge depend on the values at its ends, so only
+/// the first pending point is requested, but for the ends themselves, which
+/// are requested together.
+fn intersect<S: HermiteSource, A: AsyncSource>(
+    dc: &DualContouring<S>,
+    cache: &Cache<'_, A>,
+    edge: &Edge,
+) -> Option<EdgeIntersection> {
+    cache
+        .attempt(Requests::First, || {
+            contour::intersect_minimal_edge(dc, edge)
+        })
+        .or_else(|| {
+            let ends = edge.segment(&dc.octree_domain());
+            cache.attempt(Requests::All, || ends.map(|point| dc.source.sample(point)));
+            None
+        })
+}
+
+impl<S: AsyncSource> DualContouring<S> {
+    /// Like [`DualContouring::extract`], over an [`AsyncSource`] sampled in
+    /// batches rather than a point at a time.
+    ///
+    /// The extraction runs in rounds, each going as far as the samples
+    /// fetched so far allow and requesting those it's missing, which are
+    /// fetched in a batch of values and a batch of normals before the next
+    /// round. With [`assume_lipschitz`], the octree is sampled a level per
+    /// round first. The ends of the minimal edges are then fetched in a
+    /// single round, and the bisections of the edges whose intersections
+    /// aren't found yet advance a step per round. So the number of batches
+    /// grows with the number of levels and bisection steps, rather than with
+    /// the number of points. The last round feeds `extractor`, and the mesh
+    /// and the report are the same as those of [`DualContouring::extract`]
+    /// over a synchronous source sampling the same field.
+    ///
+    /// Every round only revisits the cells and edges still waiting for
+    /// samples, which replay the samples they already took from those
+    /// fetched. Points that weren't fetched yet are never taken for points
+    /// where the source is undefined, i.e., NaN.
+    ///
+    /// Extractors that sample a [`HermiteSource`] themselves, such as
+    /// [`WithIndexedSeparateNormals`], need a synchronous one. The normals at
+    /// the vertices can be fetched with [`AsyncSource::sample_normal_batch`]
+    /// afterwards instead.
+    ///
+    /// # Panics
+    ///
+    /// If a batch doesn't have one result per point.
+    ///
+    /// [`assume_lipschitz`]: DualContouring::assume_lipschitz
+    /// [`WithIndexedSeparateNormals`]: crate::WithIndexedSeparateNormals
+    pub async fn extract_async(
+        &self,
+        mut extractor: impl Extractor,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        if self.max_res.min_element() < 2 {
+            return Err(ExtractSurfaceError::EmptyDomain);
+        }
+
+        let mut cache = Cache::new(&self.source);
+        let mut stage = Stage::new(self.assume_lipschitz);
+        let extractor_cost = extractor.reserve_hint(1, 2);
+
+        let placed = loop {
+            let round = {
+                let dc = self.with_source(&cache);
+
+                match self.boundary_policy {
+                    BoundaryPolicy::Open => stage.advance(&dc, &cache, extractor_cost),
+                    BoundaryPolicy::Cap => stage.advance(&dc.capped(), &cache, extractor_cost),
+                }
+            };
+
+            if !extractor.should_continue() {
+                return Err(ExtractSurfaceError::Cancelled);
+            }
+
+            stage = match round {
+                ControlFlow::Break(result) => break result?,
+                ControlFlow::Continue(stage) => stage,
+            };
+
+            let fetched = cache.fetch().await;
+            assert!(fetched, "unfinished rounds request points");
+        };
+
+        contour::generate_faces(&placed, &mut extractor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{hermite_from_fn, InsideOutside};
+    use crate::{Bounds, IntersectionConfig, QuadSplit};
+    use core::cell::Cell;
+    use core::pin::{pin, Pin};
+    use core::task::{Context, Poll, Waker};
+
+    /// Polls a future to completion on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    /// A future that's pending a number of times, as if waiting for a
+    /// remote answer.
+    struct Latency(u32);
+
+    impl Future for Latency {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 == 0 {
+                return Poll::Ready(());
+            }
+
+            self.0 -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    /// A remote sphere, undefined past `x = cut`, counting the batches and
+    /// the points sampled.
+    struct RemoteSphere {
+        cut: f32,
+        batches: Cell<usize>,
+        points: Cell<usize>,
+    }
+
+    impl Default for RemoteSphere {
+        fn default() -> Self {
+            Self::new(f32::INFINITY)
+        }
+    }
+
+    impl RemoteSphere {
+        fn new(cut: f32) -> Self {
+            Self {
+                cut,
+                batches: Cell::new(0),
+                points: Cell::new(0),
+            }
+        }
+
+        fn value(cut: f32, p: Vec3) -> f32 {
+            if p.x > cut {
+                f32::NAN
+            } else {
+                p.length() - 0.6
+            }
+        }
+
+        async fn answer<T>(&self, points: &[Vec3], f: impl Fn(Vec3) -> T) -> Vec<T> {
+            self.batches.set(self.batches.get() + 1);
+            self.points.set(self.points.get() + points.len());
+            Latency(3).await;
+            points.iter().map(|&point| f(point)).collect()
+        }
+    }
+
+    impl AsyncSource for RemoteSphere {
+        async fn sample_batch(&self, points: &[Vec3]) -> Vec<f32> {
+            let cut = self.cut;
+            self.answer(points, |p| Self::value(cut, p)).await
+        }
+
+        async fn sample_normal_batch(&self, points: &[Vec3]) -> Vec<Vec3> {
+            self.answer(points, |p| p.normalize_or_zero()).await
+        }
+    }
+
+    #[derive(Default, Debug, PartialEq)]
+    struct Mesh {
+        positions: Vec<Vec3>,
+        faces: Vec<[u32; 3]>,
+    }
+
+    impl Extractor for Mesh {
+        fn extract_vertex(&mut self, position: Vec3) {
+            self.positions.push(position);
+        }
+
+        fn extract_face(&mut self, face: [u32; 3]) {
+            self.faces.push(face);
+        }
+    }
+
+    #[test]
+    fn batches_per_level_and_bisection_step() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+
+        for assume_lipschitz in [false, true] {
+            let mut expected = Mesh::default();
+            let expected_report = DualContouring::new(&sphere, 16, 1e-6)
+                .assume_lipschitz(assume_lipschitz)
+                .extract(&mut expected)
+                .unwrap();
+
+            let remote = RemoteSphere::default();
+            let mut mesh = Mesh::default();
+            let report = block_on(
+                DualContouring::new(&remote, 16, 1e-6)
+                    .assume_lipschitz(assume_lipschitz)
+                    .extract_async(&mut mesh),
+            )
+            .unwrap();
+
+            assert!(!mesh.faces.is_empty());
+            assert_eq!(mesh, expected);
+            assert_eq!(report, expected_report);
+
+            // A round per level, and per bisection step, each fetching
+            // values and normals in a batch at most.
+            let (batches, points) = (remote.batches.get(), remote.points.get());
+            let rounds = 5 + IntersectionConfig::default().max_iter + 2;
+            assert!(batches <= 2 * rounds, "{batches}");
+            assert!(points > 100 * batches, "{points} {batches}");
+        }
+    }
+
+    /// Configurations exercising the undefined region, the caps and the
+    /// normals sampled while placing the vertices.
+    fn configure<S>(dc: DualContouring<S>, case: usize) -> DualContouring<S> {
+        match case {
+            0 => dc,
+            1 => dc.with_undefined_as(InsideOutside::Outside),
+            2 => dc
+                .with_bounds(Bounds::new(Vec3::splat(-0.5), Vec3::splat(0.5)))
+                .with_boundary_policy(BoundaryPolicy::Cap),
+            _ => dc
+                .with_quad_split(QuadSplit::AlignWithNormal)
+                .with_self_intersection_check(true),
+        }
+    }
+
+    #[test]
+    fn matches_sync_extraction() {
+        const CUT: f32 = 0.3;
+        let sphere = hermite_from_fn(|p: Vec3| RemoteSphere::value(CUT, p), |p| p);
+
+        for case in 0..4 {
+            let mut expected = Mesh::default();
+            let expected_report = configure(DualContouring::new(&sphere, 16, 1e-6), case)
+                .extract(&mut expected)
+                .unwrap();
+
+            let remote = RemoteSphere::new(CUT);
+            let mut mesh = Mesh::default();
+            let report = block_on(
+                configure(DualContouring::new(&remote, 16, 1e-6), case).extract_async(&mut mesh),
+            )
+            .unwrap();
+
+            assert!(!mesh.faces.is_empty());
+            assert_eq!(mesh, expected, "{case}");
+            assert_eq!(report, expected_report, "{case}");
+
+            if case == 0 {
+                assert!(!report.undefined_cells.is_empty());
+            }
+        }
+    }
+}
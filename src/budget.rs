@@ -0,0 +1,100 @@
+//! The memory an extraction is estimated to take as its octree is built, see
+//! [`DualContouring::with_memory_budget`](crate::DualContouring::with_memory_budget).
+
+use crate::morton::MortonKey;
+use crate::topology::OctreeCell;
+use crate::ExtractionReport;
+use alloc::vec::Vec;
+
+/// The bytes an interior cell of the octree is estimated to take, as a key
+/// in a set, with as much again for the overhead of the set.
+const INTERIOR_COST: usize = 2 * size_of::<MortonKey>();
+
+/// Accounts for the cells of an octree as it's built, within a budget if
+/// any, and records how deep it gets in each octant of the root and which
+/// cells were left indeterminate.
+pub(crate) struct OctreeBudget {
+    /// The bytes left, or `None` without a budget.
+    remaining: Option<usize>,
+    /// The bytes a leaf is estimated to take.
+    leaf_cost: usize,
+    exceeded: bool,
+    max_levels: [u32; 8],
+    indeterminate: Vec<MortonKey>,
+}
+
+impl OctreeBudget {
+    /// Starts with the root as the only leaf.
+    pub(crate) fn new(bytes: Option<usize>, leaf_cost: usize) -> Self {
+        Self {
+            remaining: bytes.map(|bytes| bytes.saturating_sub(leaf_cost)),
+            leaf_cost,
+            exceeded: false,
+            max_levels: [0; 8],
+            indeterminate: Vec::new(),
+        }
+    }
+
+    /// Replacing a leaf with 8 sub-cells adds an interior cell and 7 leaves.
+    fn subdivision_cost(&self) -> usize {
+        INTERIOR_COST + 7 * self.leaf_cost
+    }
+
+    /// Returns whether `cell` may be subdivided within the budget, accounting
+    /// for its sub-cells if so.
+    ///
+    /// Once a cell would exceed the budget, no other cell may be subdivided,
+    /// so the cells not visited yet stay as coarse as they are.
+    pub(crate) fn subdivide(&mut self, cell: &OctreeCell) -> bool {
+        let cost = self.subdivision_cost();
+
+        match &mut self.remaining {
+            _ if self.exceeded => return false,
+            Some(remaining) if *remaining < cost => {
+                self.exceeded = true;
+                return false;
+            }
+            Some(remaining) => *remaining -= cost,
+            None => {}
+        }
+
+        self.record(cell);
+        true
+    }
+
+    /// Accounts for the sub-cells of `cell`, which must be subdivided
+    /// regardless of the budget.
+    pub(crate) fn force_subdivide(&mut self, cell: &OctreeCell) {
+        let cost = self.subdivision_cost();
+
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.saturating_sub(cost);
+        }
+
+        self.record(cell);
+    }
+
+    /// Records that `cell` isn't subdivided as the source is within the
+    /// tolerance of 0 all over it.
+    pub(crate) fn flag_indeterminate(&mut self, cell: &OctreeCell) {
+        self.indeterminate.push(cell.key());
+    }
+
+    fn record(&mut self, cell: &OctreeCell) {
+        let level = cell.level() + 1;
+
+        if cell.level() == 0 {
+            self.max_levels = self.max_levels.map(|max| max.max(level));
+        } else {
+            let octant = cell.key().ancestor_at_level(1).coords();
+            let max = &mut self.max_levels[(octant.x | octant.y << 1 | octant.z << 2) as usize];
+            *max = (*max).max(level);
+        }
+    }
+
+    pub(crate) fn report(&self, report: &mut ExtractionReport) {
+        report.budget_exceeded = self.exceeded;
+        report.max_level_reached_per_octant = self.max_levels;
+        report.indeterminate_cells = self.indeterminate.clone();
+    }
+}
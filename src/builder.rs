@@ -0,0 +1,436 @@
+use crate::cell::CurvatureRefinement;
+use crate::contour::{BoundaryPolicy, IntersectionConfig, QuadSplit};
+use crate::geom::Bounds;
+use crate::morton::MortonKey;
+use crate::octree::DetailRegions;
+use crate::points::PointMode;
+use crate::source::{HermiteSource, InsideOutside, IntervalSource, Tolerance};
+use crate::DualContouring;
+use glam::{UVec3, Vec3};
+
+/// The reason a [`DualContouringBuilder`] couldn't build a [`DualContouring`].
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ConfigError {
+    #[error("`max_res` must be a power of two, got {max_res}")]
+    ResolutionNotPowerOfTwo { max_res: u32 },
+    #[error("`max_res` must not exceed `2^MortonKey::LEVELS`, got {max_res}")]
+    ResolutionTooLarge { max_res: u32 },
+    /// The tolerance isn't finite and greater than 0, or is a relative one
+    /// of at least 1.
+    #[error("invalid tolerance {0:?}")]
+    InvalidTolerance(Tolerance),
+    /// The bounds aren't finite, or don't have a positive size.
+    #[error("invalid bounds {0:?}")]
+    InvalidBounds(Bounds),
+    #[error("`max_iter` must be greater than 0")]
+    ZeroMaxIter,
+    #[error("the boundary snap subdivisions must be greater than 0")]
+    ZeroSnapSubdivisions,
+    #[error("the lattice offset must be finite, got {0}")]
+    InvalidLatticeOffset(Vec3),
+    #[error("the memory budget must be greater than 0")]
+    ZeroMemoryBudget,
+    #[error("the curvature refinement angle must be finite and non-negative, got {0}")]
+    InvalidCurvatureAngle(f32),
+}
+
+pub(crate) fn check_max_res(max_res: u32) -> Result<(), ConfigError> {
+    if !max_res.is_power_of_two() {
+        Err(ConfigError::ResolutionNotPowerOfTwo { max_res })
+    } else if max_res.trailing_zeros() > MortonKey::LEVELS {
+        Err(ConfigError::ResolutionTooLarge { max_res })
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) fn check_tolerance(tolerance: Tolerance) -> Result<(), ConfigError> {
+    let t = tolerance.get();
+    let valid =
+        t.is_finite() && t > 0.0 && !matches!(tolerance, Tolerance::Relative(t) if t >= 1.0);
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidTolerance(tolerance))
+    }
+}
+
+pub(crate) fn check_bounds(bounds: Bounds) -> Result<(), ConfigError> {
+    let valid =
+        bounds.min.is_finite() && bounds.max.is_finite() && bounds.min.cmplt(bounds.max).all();
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidBounds(bounds))
+    }
+}
+
+pub(crate) fn check_max_iter(max_iter: usize) -> Result<(), ConfigError> {
+    if max_iter > 0 {
+        Ok(())
+    } else {
+        Err(ConfigError::ZeroMaxIter)
+    }
+}
+
+pub(crate) fn check_snap_subdivisions(subdivisions: u32) -> Result<(), ConfigError> {
+    if subdivisions > 0 {
+        Ok(())
+    } else {
+        Err(ConfigError::ZeroSnapSubdivisions)
+    }
+}
+
+pub(crate) fn check_lattice_offset(offset: Vec3) -> Result<(), ConfigError> {
+    if offset.is_finite() {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidLatticeOffset(offset))
+    }
+}
+
+pub(crate) fn check_memory_budget(bytes: usize) -> Result<(), ConfigError> {
+    if bytes > 0 {
+        Ok(())
+    } else {
+        Err(ConfigError::ZeroMemoryBudget)
+    }
+}
+
+pub(crate) fn check_curvature_angle(max_angle: f32) -> Result<(), ConfigError> {
+    if max_angle.is_finite() && max_angle >= 0.0 {
+        Ok(())
+    } else {
+        Err(ConfigError::InvalidCurvatureAngle(max_angle))
+    }
+}
+
+/// Builds a [`DualContouring`], reporting invalid options as a
+/// [`ConfigError`] rather than panicking like the `with_*` methods of
+/// [`DualContouring`] do.
+///
+/// Created by [`DualContouring::builder`]. Options default to those of
+/// [`DualContouring::new`], with a resolution of 64 and an `epsilon` of
+/// `1e-4`.
+///
+/// Builders are [`Clone`] when sources are, so that one configuration can
+/// build several ones, e.g., over different bounds.
+#[derive(Clone)]
+pub struct DualContouringBuilder<S> {
+    dc: DualContouring<S>,
+}
+
+impl<S> DualContouringBuilder<S> {
+    pub(crate) fn new(source: S) -> Self {
+        DualContouringBuilder {
+            dc: DualContouring::new_unchecked(source, 64, Tolerance::Absolute(1e-4)),
+        }
+    }
+
+    /// Replaces the source, keeping the other options, except for those
+    /// that depend on the type of the source, i.e.,
+    /// [`DualContouring::with_interval_culling`] and
+    /// [`DualContouring::with_curvature_refinement`], which are disabled.
+    pub fn source<T>(self, source: T) -> DualContouringBuilder<T> {
+        DualContouringBuilder {
+            dc: self.dc.with_source(source),
+        }
+    }
+
+    /// Sets the resolution of the finest cells along each axis, which must
+    /// be a power of two.
+    pub fn max_res(mut self, max_res: u32) -> Self {
+        self.dc.max_res = UVec3::splat(max_res);
+        self
+    }
+
+    /// See [`DualContouring::with_max_res_per_axis`].
+    pub fn max_res_per_axis(mut self, max_res: UVec3) -> Self {
+        self.dc.max_res = max_res;
+        self
+    }
+
+    /// Sets a [`Tolerance::Absolute`] tolerance of `epsilon`.
+    pub fn epsilon(self, epsilon: f32) -> Self {
+        self.tolerance(Tolerance::Absolute(epsilon))
+    }
+
+    /// See [`DualContouring::with_tolerance`].
+    pub fn tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.dc.tolerance = Some(tolerance);
+        self
+    }
+
+    /// See [`DualContouring::with_auto_epsilon`].
+    pub fn auto_epsilon(mut self) -> Self {
+        self.dc.tolerance = None;
+        self
+    }
+
+    /// See [`DualContouring::with_lattice_offset`].
+    pub fn lattice_offset(mut self, offset: Vec3) -> Self {
+        self.dc.lattice_offset = Some(offset);
+        self
+    }
+
+    /// See [`DualContouring::with_auto_lattice_offset`].
+    pub fn auto_lattice_offset(mut self) -> Self {
+        self.dc.lattice_offset = None;
+        self
+    }
+
+    /// See [`DualContouring::with_bounds`].
+    pub fn bounds(mut self, bounds: Bounds) -> Self {
+        self.dc.bounds = bounds;
+        self
+    }
+
+    /// See [`DualContouring::with_max_iter`].
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.dc.intersection.max_iter = max_iter;
+        self
+    }
+
+    /// See [`DualContouring::with_intersection_config`].
+    pub fn intersection_config(mut self, config: IntersectionConfig) -> Self {
+        self.dc.intersection = config;
+        self
+    }
+
+    /// See [`DualContouring::with_linear_estimate`].
+    pub fn linear_estimate(mut self, linear_estimate: bool) -> Self {
+        self.dc.linear_estimate = linear_estimate;
+        self
+    }
+
+    /// See [`DualContouring::with_quad_split`].
+    pub fn quad_split(mut self, quad_split: QuadSplit) -> Self {
+        self.dc.quad_split = quad_split;
+        self
+    }
+
+    /// See [`DualContouring::with_self_intersection_check`].
+    pub fn self_intersection_check(mut self, check: bool) -> Self {
+        self.dc.check_self_intersections = check;
+        self
+    }
+
+    /// See [`DualContouring::with_undefined_as`].
+    pub fn undefined_as(mut self, side: InsideOutside) -> Self {
+        self.dc.undefined_is = Some(side);
+        self
+    }
+
+    /// See [`DualContouring::assume_lipschitz`].
+    pub fn assume_lipschitz(mut self, assume_lipschitz: bool) -> Self {
+        self.dc.assume_lipschitz = assume_lipschitz;
+        self
+    }
+
+    /// See [`DualContouring::with_detail_regions`].
+    pub fn detail_regions(mut self, regions: DetailRegions) -> Self {
+        self.dc.detail_regions = Some(regions);
+        self
+    }
+
+    /// See [`DualContouring::with_point_mode`].
+    pub fn point_mode(mut self, point_mode: PointMode) -> Self {
+        self.dc.point_mode = point_mode;
+        self
+    }
+
+    /// See [`DualContouring::with_boundary_policy`].
+    pub fn boundary_policy(mut self, policy: BoundaryPolicy) -> Self {
+        self.dc.boundary_policy = policy;
+        self
+    }
+
+    /// See [`DualContouring::with_memory_budget`].
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.dc.memory_budget = Some(bytes);
+        self
+    }
+
+    /// See [`DualContouring::with_boundary_snap`].
+    pub fn boundary_snap(mut self, subdivisions: u32) -> Self {
+        self.dc.boundary_snap = Some(subdivisions);
+        self
+    }
+
+    /// Validates the options, returning the first invalid one as a
+    /// [`ConfigError`], the error of the `with_*` methods of
+    /// [`DualContouring`] too, rather than a separate build error.
+    pub fn build(self) -> Result<DualContouring<S>, ConfigError> {
+        for max_res in self.dc.max_res.to_array() {
+            check_max_res(max_res)?;
+        }
+
+        if let Some(tolerance) = self.dc.tolerance {
+            check_tolerance(tolerance)?;
+        }
+        check_bounds(self.dc.bounds)?;
+        check_max_iter(self.dc.intersection.max_iter)?;
+
+        if let Some(subdivisions) = self.dc.boundary_snap {
+            check_snap_subdivisions(subdivisions)?;
+        }
+
+        if let Some(offset) = self.dc.lattice_offset {
+            check_lattice_offset(offset)?;
+        }
+
+        if let Some(bytes) = self.dc.memory_budget {
+            check_memory_budget(bytes)?;
+        }
+
+        if let Some(refinement) = &self.dc.curvature {
+            check_curvature_angle(refinement.max_angle)?;
+        }
+        Ok(self.dc)
+    }
+}
+
+impl<S: IntervalSource> DualContouringBuilder<S> {
+    /// See [`DualContouring::with_interval_culling`].
+    pub fn interval_culling(mut self, interval_culling: bool) -> Self {
+        self.dc = self.dc.with_interval_culling(interval_culling);
+        self
+    }
+}
+
+impl<S: HermiteSource> DualContouringBuilder<S> {
+    /// See [`DualContouring::with_curvature_refinement`].
+    pub fn curvature_refinement(mut self, max_angle: f32, min_level: u32) -> Self {
+        self.dc.curvature = Some(CurvatureRefinement::new(max_angle, min_level));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::from_fn;
+    use crate::{
+        Capsule, ExtractionReport, HermiteSource, IndexedSeparateNormals,
+        WithIndexedSeparateNormals,
+    };
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use glam::Vec3;
+
+    #[test]
+    fn invalid_options() {
+        let sphere = from_fn(|p: Vec3| p.length() - 0.5);
+        let builder = || DualContouring::builder(&sphere);
+
+        assert!(builder().build().is_ok());
+        assert!(builder()
+            .max_res(128)
+            .tolerance(Tolerance::Spatial(1e-3))
+            .max_iter(8)
+            .build()
+            .is_ok());
+        assert!(builder().epsilon(0.0).auto_epsilon().build().is_ok());
+
+        assert_eq!(
+            builder().max_res(48).build().err(),
+            Some(ConfigError::ResolutionNotPowerOfTwo { max_res: 48 })
+        );
+        assert_eq!(
+            (builder().max_res_per_axis(UVec3::new(64, 64, 3)).build()).err(),
+            Some(ConfigError::ResolutionNotPowerOfTwo { max_res: 3 })
+        );
+        assert_eq!(
+            builder().max_res(1 << 31).build().err(),
+            Some(ConfigError::ResolutionTooLarge { max_res: 1 << 31 })
+        );
+        assert_eq!(
+            builder().epsilon(0.0).build().err(),
+            Some(ConfigError::InvalidTolerance(Tolerance::Absolute(0.0)))
+        );
+        assert_eq!(
+            builder().tolerance(Tolerance::Relative(1.0)).build().err(),
+            Some(ConfigError::InvalidTolerance(Tolerance::Relative(1.0)))
+        );
+        assert_eq!(
+            builder().max_iter(0).build().err(),
+            Some(ConfigError::ZeroMaxIter)
+        );
+        let config = IntersectionConfig {
+            max_iter: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            builder().intersection_config(config).build().err(),
+            Some(ConfigError::ZeroMaxIter)
+        );
+        assert_eq!(
+            builder().boundary_snap(0).build().err(),
+            Some(ConfigError::ZeroSnapSubdivisions)
+        );
+        let offset = Vec3::new(0.0, f32::NAN, 0.0);
+        assert!(matches!(
+            builder().lattice_offset(offset).build().err(),
+            Some(ConfigError::InvalidLatticeOffset(_))
+        ));
+
+        assert_eq!(
+            builder().memory_budget(0).build().err(),
+            Some(ConfigError::ZeroMemoryBudget)
+        );
+
+        let capsule = Capsule::new(Vec3::ZERO, Vec3::X, 0.5);
+        assert!(DualContouring::builder(capsule)
+            .curvature_refinement(0.0, 2)
+            .build()
+            .is_ok());
+
+        for max_angle in [-0.1, f32::NAN, f32::INFINITY] {
+            let builder = DualContouring::builder(capsule).curvature_refinement(max_angle, 2);
+            assert!(matches!(
+                builder.build().err(),
+                Some(ConfigError::InvalidCurvatureAngle(_))
+            ));
+        }
+
+        let flat = Bounds::new(Vec3::ZERO, Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(
+            builder().bounds(flat).build().err(),
+            Some(ConfigError::InvalidBounds(flat))
+        );
+    }
+
+    #[test]
+    fn defaults_match_new() {
+        type Mesh = (Vec<[u32; 3]>, Vec<[u32; 3]>, ExtractionReport);
+
+        fn extract(dc: DualContouring<impl HermiteSource>) -> Mesh {
+            let mut mesh = IndexedSeparateNormals::default();
+            let sphere = Capsule::new(Vec3::ZERO, Vec3::ZERO, 0.5);
+            let report = dc
+                .extract(WithIndexedSeparateNormals::new(&mut mesh, sphere))
+                .unwrap();
+            let positions = (mesh.positions().iter())
+                .map(|p| p.to_array().map(f32::to_bits))
+                .collect();
+            (positions, mesh.faces, report)
+        }
+
+        let sphere = Capsule::new(Vec3::ZERO, Vec3::ZERO, 0.5);
+        let built = extract(DualContouring::builder(sphere).build().unwrap());
+        assert_eq!(built, extract(DualContouring::new(sphere, 64, 1e-4)));
+
+        // Clones build independently, and a replaced source keeps the options.
+        let builder = DualContouring::builder(sphere).max_res(16);
+        let small = builder.clone().bounds(Bounds::new(Vec3::ZERO, Vec3::ONE));
+        let small = extract(small.build().unwrap());
+        let full = extract(builder.clone().build().unwrap());
+        assert!(small.1.len() < full.1.len());
+
+        let boxed: Box<dyn HermiteSource> = Box::new(sphere);
+        assert_eq!(extract(builder.source(boxed).build().unwrap()), full);
+    }
+}
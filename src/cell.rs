@@ -0,0 +1,290 @@
+//! Contouring of a single cell, the building block of the octree driver, for
+//! custom drivers such as GPU pipelines or other spatial structures.
+//!
+//! [`DualContouring`] doesn't call [`contour_cell`], as it intersects every
+//! edge once and shares the intersection with the up to 4 cells around it.
+//! Both intersect edges and place vertices with the same code, though, and
+//! [`contour_cell`] is kept stable across releases.
+//!
+//! [`DualContouring`]: crate::DualContouring
+
+use crate::classify::CornerMask;
+use crate::contour::{intersect_edge, EdgeIntersection, IntersectionConfig};
+use crate::geom::{Bounds, EdgeKind};
+use crate::source::{HermiteSource, InsideOutside, Sample, Tolerance};
+use crate::topology::bounds_corner_signs;
+use alloc::vec::Vec;
+use glam::Vec3;
+
+pub use crate::qef::{Qef, Qef64};
+
+/// How [`contour_cell`] finds the intersections of the surface with the
+/// edges of a cell.
+///
+/// [`DualContouring::cell_config`] returns the configuration of an
+/// extraction.
+///
+/// [`DualContouring::cell_config`]: crate::DualContouring::cell_config
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CellConfig {
+    /// When a point is considered to be on the surface, see
+    /// [`DualContouring::with_tolerance`](crate::DualContouring::with_tolerance).
+    pub tolerance: Tolerance,
+    /// See [`DualContouring::with_intersection_config`]. Edges whose
+    /// intersection isn't found are contoured with the best estimate when
+    /// the policy is [`FailurePolicy::Abort`], as there's no extraction to
+    /// fail.
+    ///
+    /// [`DualContouring::with_intersection_config`]: crate::DualContouring::with_intersection_config
+    /// [`FailurePolicy::Abort`]: crate::FailurePolicy::Abort
+    pub intersection: IntersectionConfig,
+    /// See [`DualContouring::with_linear_estimate`](crate::DualContouring::with_linear_estimate).
+    pub linear_estimate: bool,
+    /// See [`DualContouring::with_undefined_as`](crate::DualContouring::with_undefined_as).
+    /// Edges with an undefined end are skipped if `None`.
+    pub undefined_is: Option<InsideOutside>,
+}
+
+impl CellConfig {
+    /// Creates a configuration with the given tolerance and the defaults of
+    /// [`DualContouring`](crate::DualContouring) otherwise.
+    pub fn new(tolerance: Tolerance) -> Self {
+        Self {
+            tolerance,
+            intersection: IntersectionConfig::default(),
+            linear_estimate: false,
+            undefined_is: None,
+        }
+    }
+}
+
+/// A cell crossed by the surface, contoured by [`contour_cell`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellResult {
+    /// The corners inside of the surface.
+    pub corners: CornerMask,
+    /// The intersections of the surface with the edges of the cell, with the
+    /// normals there, in the order of [`EdgeKind::ALL`].
+    pub intersections: Vec<(EdgeKind, Sample, Vec3)>,
+    /// The vertex of the cell, minimizing the QEF of the intersections within
+    /// the cell.
+    pub vertex: Vec3,
+    /// The QEF at the vertex, i.e., the sum of squared distances from it to
+    /// the tangent planes at the intersections.
+    pub residual: f32,
+}
+
+/// Contours the cell spanning `bounds`: classifies its corners, intersects
+/// the surface with its edges and places its vertex.
+///
+/// Returns `None` if the surface doesn't cross any edge of the cell, or if
+/// no intersection is usable, e.g., because the source is undefined there.
+///
+/// Only the 12 edges of the cell are intersected. [`DualContouring`] places
+/// the same vertex, bit-for-bit, under [`DualContouring::cell_config`] only
+/// for a leaf whose neighbors are all leaves of the same size, away from the
+/// boundary of the bounds:
+///
+/// - Where a neighbor is finer, the edges of the cell are split, and
+///   [`DualContouring`] uses the intersections of the finer edges instead.
+/// - Edges on the boundary of the bounds aren't contoured at all.
+///
+/// [`DualContouring`]: crate::DualContouring
+/// [`DualContouring::cell_config`]: crate::DualContouring::cell_config
+pub fn contour_cell(
+    source: &impl HermiteSource,
+    bounds: &Bounds,
+    config: &CellConfig,
+) -> Option<CellResult> {
+    let mut intersections = Vec::new();
+    let mut qef = Qef::default();
+
+    for edge in EdgeKind::ALL {
+        let [start, end] = edge.endpoints().map(|corner| bounds.corner(corner));
+
+        if let EdgeIntersection::Found { sample, .. } = intersect_edge(source, config, start, end) {
+            qef.add(sample.point, sample.normal);
+            intersections.push((edge, Sample::new(sample.point, sample.value), sample.normal));
+        }
+    }
+
+    if intersections.is_empty() {
+        return None;
+    }
+
+    let corners = CornerMask::from_signs(bounds_corner_signs(source, bounds));
+    let vertex = solve_within(&qef, bounds);
+
+    Some(CellResult {
+        corners,
+        intersections,
+        vertex,
+        residual: qef.error(vertex),
+    })
+}
+
+/// Places the vertex of the cell spanning `bounds` by minimizing its QEF
+/// within the cell, for both [`contour_cell`] and the octree driver.
+pub(crate) fn solve_within(qef: &Qef, bounds: &Bounds) -> Vec3 {
+    let (position, _) = qef.solve();
+    position.clamp(bounds.min, bounds.max)
+}
+
+/// Estimates how much the surface bends within a cell contoured by
+/// [`contour_cell`], as the largest angle, in radians, between the normals at
+/// its intersections and the normal of `source` at its vertex.
+///
+/// The angle is 0 where the surface is flat, and grows with the curvature
+/// of the surface times the size of the cell, so a threshold on it refines
+/// cells until they're flat enough, see
+/// [`DualContouring::with_curvature_refinement`].
+///
+/// [`DualContouring::with_curvature_refinement`]: crate::DualContouring::with_curvature_refinement
+pub fn cell_curvature(source: &impl HermiteSource, cell: &CellResult) -> f32 {
+    let vertex_normal = source.sample_normal(cell.vertex);
+    let normals = || {
+        let edge_normals = cell.intersections.iter().map(|&(_, _, normal)| normal);
+        edge_normals.chain(core::iter::once(vertex_normal))
+    };
+
+    normals()
+        .enumerate()
+        .flat_map(|(i, a)| normals().skip(i + 1).map(move |b| a.angle_between(b)))
+        .fold(0.0, f32::max)
+}
+
+/// Estimates the curvature of the surface within the cell spanning
+/// `bounds`, or returns `None` if it isn't crossed by the surface, as a
+/// function of the source, so that it can be stored for sources that aren't
+/// [`HermiteSource`]s.
+pub(crate) type CurvatureFn<S> = fn(&S, &Bounds, &CellConfig) -> Option<f32>;
+
+/// Stops subdividing cells that are flat enough, see
+/// [`DualContouring::with_curvature_refinement`].
+///
+/// [`DualContouring::with_curvature_refinement`]: crate::DualContouring::with_curvature_refinement
+pub(crate) struct CurvatureRefinement<S> {
+    pub(crate) curvature: CurvatureFn<S>,
+    pub(crate) max_angle: f32,
+    pub(crate) min_level: u32,
+}
+
+// Not derived, which would require `S: Clone`.
+impl<S> Clone for CurvatureRefinement<S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for CurvatureRefinement<S> {}
+
+impl<S: HermiteSource> CurvatureRefinement<S> {
+    pub(crate) fn new(max_angle: f32, min_level: u32) -> Self {
+        CurvatureRefinement {
+            curvature: bounds_curvature::<S>,
+            max_angle,
+            min_level,
+        }
+    }
+}
+
+impl<S> CurvatureRefinement<S> {
+    /// Returns whether the cell spanning `bounds` at `level` may be
+    /// subdivided, i.e., whether it's too coarse, isn't crossed by the
+    /// surface, or bends more than `max_angle` within it.
+    pub(crate) fn may_subdivide(
+        &self,
+        source: &S,
+        bounds: &Bounds,
+        level: u32,
+        config: &CellConfig,
+    ) -> bool {
+        level < self.min_level
+            || (self.curvature)(source, bounds, config).is_none_or(|c| c > self.max_angle)
+    }
+}
+
+/// Contours the cell spanning `bounds` and estimates its curvature, see
+/// [`CurvatureFn`].
+pub(crate) fn bounds_curvature<S: HermiteSource>(
+    source: &S,
+    bounds: &Bounds,
+    config: &CellConfig,
+) -> Option<f32> {
+    let cell = contour_cell(source, bounds, config)?;
+    Some(cell_curvature(source, &cell))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{
+        DualContouring, IndexedSeparateNormals, MortonKey, WithIndexedSeparateNormals,
+        WithVertexCells,
+    };
+
+    #[test]
+    fn cells_match_extraction() {
+        let sphere = hermite_from_fn(
+            |p: Vec3| (p - Vec3::new(0.05, -0.03, 0.02)).length() - 0.6,
+            |p| p - Vec3::new(0.05, -0.03, 0.02),
+        );
+        let dc = DualContouring::new(&sphere, 32, 1e-6);
+
+        let mut mesh = IndexedSeparateNormals::default();
+        let mut cells = Vec::new();
+        let extractor = WithIndexedSeparateNormals::new(&mut mesh, &sphere);
+        dc.extract(WithVertexCells::new(extractor, &mut cells))
+            .unwrap();
+
+        let octree = dc.build_octree();
+        let config = dc.cell_config();
+        let mut contoured: Vec<(MortonKey, Vec3)> = Vec::new();
+
+        for leaf in octree.leaves() {
+            if let Some(result) = contour_cell(&sphere, &leaf.bounds(octree.domain()), &config) {
+                assert!(!result.intersections.is_empty());
+                assert!(result.residual >= 0.0);
+
+                // Every crossed edge connects corners on opposite sides.
+                for (edge, sample, _) in &result.intersections {
+                    let [a, b] = edge.endpoints();
+                    assert_ne!(result.corners.is_inside(a), result.corners.is_inside(b));
+                    assert!(sample.value.abs() <= 1e-6);
+                }
+
+                contoured.push((leaf.key(), result.vertex));
+            }
+        }
+
+        contoured.sort_unstable_by_key(|(key, _)| *key);
+        let extracted: Vec<_> = cells
+            .into_iter()
+            .zip(mesh.positions().iter().copied())
+            .collect();
+        assert!(!extracted.is_empty());
+        assert_eq!(contoured, extracted);
+    }
+
+    #[test]
+    fn curvature_grows_with_bending() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.5, |p| p);
+        let plane = hermite_from_fn(|p: Vec3| p.dot(Vec3::ONE) - 0.5, |_| Vec3::ONE);
+        let config = CellConfig::new(Tolerance::Absolute(1e-6));
+
+        let curvature = |source: &dyn HermiteSource, size: f32| {
+            let min = Vec3::new(0.5, 0.0, 0.0) - size / 2.0;
+            let cell = contour_cell(&source, &Bounds::new(min, min + size), &config).unwrap();
+            cell_curvature(&source, &cell)
+        };
+
+        assert!(curvature(&plane, 0.1) < 1e-3);
+        assert!(curvature(&plane, 0.4) < 1e-3);
+
+        // Halving the cell about halves the angle over a sphere.
+        let (coarse, fine) = (curvature(&sphere, 0.2), curvature(&sphere, 0.1));
+        assert!(coarse > 0.2 && coarse < 1.0, "{coarse}");
+        assert!((coarse / fine - 2.0).abs() < 0.3, "{coarse} {fine}");
+    }
+}
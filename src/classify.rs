@@ -0,0 +1,287 @@
+use crate::geom::{Bounds, CornerKind, EdgeKind};
+use crate::morton::MortonKey;
+use crate::source::Source;
+use alloc::vec;
+use alloc::vec::Vec;
+use auto_impl::auto_impl;
+use glam::UVec3;
+
+/// Which corners of a cell are inside of the surface, one bit per corner.
+///
+/// The bit of a corner is the one indexed by its [`BMask3`] bits, e.g., bit
+/// 0 for the minimum corner and bit 3 for the `XY` one. Note that this
+/// differs from the order of [`CornerKind::ALL`], which lists corners by the
+/// number of axes they step along.
+///
+/// [`BMask3`]: crate::BMask3
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct CornerMask(pub u8);
+
+impl CornerMask {
+    /// Packs whether each corner is inside, in the order of
+    /// [`CornerKind::ALL`] as returned by [`corner_signs`].
+    ///
+    /// [`corner_signs`]: crate::corner_signs
+    pub fn from_signs(signs: [bool; 8]) -> Self {
+        let bits = CornerKind::ALL.into_iter().zip(signs);
+        CornerMask(bits.fold(0, |mask, (corner, inside)| {
+            mask | ((inside as u8) << corner.0.bits())
+        }))
+    }
+
+    pub const fn is_inside(self, corner: CornerKind) -> bool {
+        self.0 & (1 << corner.0.bits()) != 0
+    }
+
+    /// Returns whether all corners are on the same side of the surface, in
+    /// which case no edge of the cell crosses it.
+    pub const fn is_homogeneous(self) -> bool {
+        self.0 == 0 || self.0 == u8::MAX
+    }
+
+    pub const fn count_inside(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    /// Returns the edges whose endpoints are on different sides of the
+    /// surface as a bitmask, bit `i` standing for the `i`-th edge of
+    /// [`EdgeKind::ALL`], looked up in a precomputed table.
+    ///
+    /// Note that an edge with an end within the tolerance of the surface may
+    /// cross it without separating its corners, see
+    /// [`ClassifySegment::Intersects`], which is why extractions test every
+    /// edge of a cell instead.
+    ///
+    /// [`ClassifySegment::Intersects`]: crate::source::ClassifySegment::Intersects
+    pub const fn active_edge_bits(self) -> u16 {
+        ACTIVE_EDGES[self.0 as usize]
+    }
+
+    /// Returns the edges whose endpoints are on different sides of the
+    /// surface, in the order of [`EdgeKind::ALL`].
+    pub fn active_edges(self) -> impl Iterator<Item = EdgeKind> {
+        let edges = self.active_edge_bits();
+
+        EdgeKind::ALL
+            .into_iter()
+            .enumerate()
+            .filter(move |(i, _)| edges & (1 << i) != 0)
+            .map(|(_, edge)| edge)
+    }
+}
+
+/// The active edges of every corner mask, one bit per edge of
+/// [`EdgeKind::ALL`].
+const ACTIVE_EDGES: [u16; 256] = {
+    let mut table = [0; 256];
+    let mut mask = 0;
+
+    while mask < 256 {
+        let mut i = 0;
+
+        while i < EdgeKind::ALL.len() {
+            let [start, end] = EdgeKind::ALL[i].endpoints();
+
+            if (mask >> start.0.bits()) & 1 != (mask >> end.0.bits()) & 1 {
+                table[mask] |= 1 << i;
+            }
+
+            i += 1;
+        }
+
+        mask += 1;
+    }
+
+    table
+};
+
+/// Classifies the corners of a cell as inside or outside of the surface of
+/// `source`.
+///
+/// Corners whose values are within `epsilon` of 0 are on the surface, and
+/// count as inside. This way, a surface touching a cell only at a corner or
+/// along an edge doesn't make it active. Exact zeros are on the side given by
+/// [`Source::zero_is_inside`] instead, as they may stand for whole regions.
+/// Undefined (non-finite) values count as outside.
+pub fn corner_mask(source: &impl Source, cell_bounds: &Bounds, epsilon: f32) -> CornerMask {
+    let zeros = source.zero_is_inside();
+    let mut mask = 0;
+
+    for corner in CornerKind::ALL {
+        let point = cell_bounds.corner(corner);
+        let value = source.sample(point);
+        let on_surface = value != 0.0 && value <= epsilon;
+
+        if (zeros.is_inside(value) || on_surface) && source.is_defined(point) {
+            mask |= 1 << corner.0.bits();
+        }
+    }
+
+    CornerMask(mask)
+}
+
+/// Receives the leaf cells of an octree classified by
+/// [`DualContouring::classify_volume`].
+///
+/// [`DualContouring::classify_volume`]: crate::DualContouring::classify_volume
+#[auto_impl(&mut, Box)]
+pub trait VoxelSink {
+    /// Receives a cell whose corners are all inside of the surface.
+    fn interior_cell(&mut self, key: MortonKey);
+
+    /// Receives a cell with corners on both sides of the surface.
+    fn boundary_cell(&mut self, key: MortonKey);
+
+    /// Called once all cells have been classified.
+    fn finish(&mut self) {}
+}
+
+/// A [`VoxelSink`] collecting the keys of interior and boundary cells, each
+/// sorted.
+#[derive(Debug, Clone, Default)]
+pub struct CollectVoxels {
+    interior: Vec<MortonKey>,
+    boundary: Vec<MortonKey>,
+}
+
+impl CollectVoxels {
+    pub fn interior(&self) -> &[MortonKey] {
+        &self.interior
+    }
+
+    pub fn boundary(&self) -> &[MortonKey] {
+        &self.boundary
+    }
+
+    /// Returns whether the given cell is within an interior or a boundary
+    /// cell, which may be coarser than it.
+    pub fn contains(&self, key: MortonKey) -> bool {
+        let mut key = key;
+
+        while !key.is_none() {
+            if self.interior.binary_search(&key).is_ok()
+                || self.boundary.binary_search(&key).is_ok()
+            {
+                return true;
+            }
+
+            key = key.parent();
+        }
+
+        false
+    }
+
+    /// Rasterizes the interior and boundary cells into a `res³` grid, with X
+    /// varying fastest, then Y, then Z, as in [`GridSource`]. `res` must be a
+    /// power of two no smaller than the resolution of the finest cell.
+    ///
+    /// [`GridSource`]: crate::GridSource
+    pub fn to_dense_grid(&self, res: u32) -> Vec<bool> {
+        assert!(res.is_power_of_two(), "`res` must be a power of two");
+
+        let max_level = res.trailing_zeros();
+        let mut grid = vec![false; (res as usize).pow(3)];
+
+        for key in self.interior.iter().chain(&self.boundary) {
+            assert!(
+                key.level() <= max_level,
+                "`res` must be at least the resolution of every cell"
+            );
+
+            let scale = 1 << (max_level - key.level());
+            let min = key.coords() * scale;
+
+            for z in 0..scale {
+                for y in 0..scale {
+                    for x in 0..scale {
+                        let p = min + UVec3::new(x, y, z);
+                        grid[(p.x + res * (p.y + res * p.z)) as usize] = true;
+                    }
+                }
+            }
+        }
+
+        grid
+    }
+}
+
+impl VoxelSink for CollectVoxels {
+    fn interior_cell(&mut self, key: MortonKey) {
+        self.interior.push(key);
+    }
+
+    fn boundary_cell(&mut self, key: MortonKey) {
+        self.boundary.push(key);
+    }
+
+    fn finish(&mut self) {
+        self.interior.sort_unstable();
+        self.boundary.sort_unstable();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::from_fn;
+    use crate::topology::corner_signs;
+    use glam::Vec3;
+
+    #[test]
+    fn active_edges_match_brute_force() {
+        for mask in 0..=u8::MAX {
+            let mask = CornerMask(mask);
+
+            let expected: Vec<_> = EdgeKind::ALL
+                .into_iter()
+                .filter(|edge| {
+                    let [start, end] = edge.endpoints();
+                    mask.is_inside(start) != mask.is_inside(end)
+                })
+                .map(|edge| edge.endpoints())
+                .collect();
+
+            let active: Vec<_> = mask.active_edges().map(|edge| edge.endpoints()).collect();
+            assert_eq!(active, expected);
+            assert_eq!(mask.active_edge_bits().count_ones() as usize, active.len());
+            assert_eq!(mask.is_homogeneous(), active.is_empty());
+        }
+    }
+
+    #[test]
+    fn corners_map_to_their_bits() {
+        let bounds = Bounds::new(Vec3::ZERO, Vec3::ONE);
+
+        for corner in CornerKind::ALL {
+            let point = bounds.corner(corner);
+            let source = from_fn(move |p: Vec3| p.distance(point) - 0.5);
+            let mask = corner_mask(&source, &bounds, 1e-4);
+
+            assert_eq!(mask, CornerMask(1 << corner.0.bits()));
+            assert_eq!(
+                CornerMask::from_signs(corner_signs(&source, bounds.min, 1.0)),
+                mask
+            );
+            assert!(mask.is_inside(corner));
+            assert_eq!(mask.count_inside(), 1);
+            assert_eq!(mask.active_edges().count(), 3);
+        }
+    }
+
+    #[test]
+    fn on_surface_corners_are_inside() {
+        let bounds = Bounds::new(Vec3::ZERO, Vec3::ONE);
+        let epsilon = 1e-3;
+
+        // The plane passes within epsilon of the `X = 0` face, from outside.
+        let plane = from_fn(move |p: Vec3| epsilon - p.x);
+        let mask = corner_mask(&plane, &bounds, epsilon);
+        assert_eq!(mask.count_inside(), 8);
+        assert!(mask.is_homogeneous());
+
+        let plane = from_fn(move |p: Vec3| 2.0 * epsilon - p.x);
+        let mask = corner_mask(&plane, &bounds, epsilon);
+        assert_eq!(mask.count_inside(), 4);
+        assert_eq!(mask.active_edges().count(), 4);
+    }
+}
@@ -0,0 +1,1184 @@
+use crate::cell::{solve_within, CellConfig};
+use crate::collections::Map;
+use crate::extractor::{Extractor, GroupedExtractor};
+use crate::geom::{AxisKind, Bounds};
+use crate::morton::MortonKey;
+use crate::octree::Octree;
+use crate::qef::Qef;
+use crate::source::{
+    sample_defined, ClassifySegment, FindIntersectionError, HermiteSample, HermiteSource,
+    InsideOutside, Sample, Source, Tolerance,
+};
+use crate::topology::{edge_kind_index, edge_quad_order, Edge};
+use crate::validate;
+use crate::{DualContouring, ExtractSurfaceError, ExtractionReport, DEFAULT_MAX_ITER};
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+use glam::{UVec3, Vec3};
+
+/// What to do with an edge crossed by the surface whose intersection can't be
+/// found, see [`IntersectionConfig`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum FailurePolicy {
+    /// Fail the extraction with an error naming the first cell around such
+    /// edges.
+    Abort,
+    /// Use the best estimate of the intersection: the last midpoint of the
+    /// bisection if the iteration limit is reached, or the end closer to the
+    /// surface if both are on it.
+    UseBestSample,
+    /// Use the midpoint of the edge.
+    UseMidpoint,
+    /// Skip the edge, which leaves a hole in the mesh. Cells around it only
+    /// get vertices from their other edges, if any are crossed.
+    SkipEdge,
+}
+
+/// How intersections of the surface with the edges of the octree are found,
+/// see [`DualContouring::with_intersection_config`].
+///
+/// Fallbacks taken for edges whose intersections can't be found are counted
+/// in the [`ExtractionReport`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IntersectionConfig {
+    /// The maximum number of bisection steps, see
+    /// [`DualContouring::with_max_iter`]. Defaults to 50.
+    pub max_iter: usize,
+    /// What to do when the iteration limit is reached. Defaults to
+    /// [`FailurePolicy::Abort`].
+    pub on_iter_limit: FailurePolicy,
+    /// What to do when the ends of an edge are on both sides of the surface
+    /// but both within the tolerance of it, so that the intersection is
+    /// ambiguous. Defaults to [`FailurePolicy::SkipEdge`].
+    pub on_indeterminate: FailurePolicy,
+}
+
+impl Default for IntersectionConfig {
+    fn default() -> Self {
+        Self {
+            max_iter: DEFAULT_MAX_ITER,
+            on_iter_limit: FailurePolicy::Abort,
+            on_indeterminate: FailurePolicy::SkipEdge,
+        }
+    }
+}
+
+/// Why the intersection of an edge wasn't found.
+#[derive(Copy, Clone)]
+pub(crate) enum Failure {
+    IterLimit,
+    Indeterminate,
+}
+
+/// The point where the surface crosses a minimal edge.
+struct Crossing {
+    sample: HermiteSample,
+    /// Whether the outside of the surface is towards the end of the edge.
+    outward: bool,
+    /// Why the intersection wasn't found, in which case `sample` is the best
+    /// estimate.
+    failure: Option<Failure>,
+}
+
+/// The source is undefined around an edge, which is skipped.
+struct Undefined;
+
+fn find_crossing<S: HermiteSource>(
+    source: &S,
+    config: &CellConfig,
+    start: Vec3,
+    end: Vec3,
+) -> Result<Option<Crossing>, Undefined> {
+    let (tolerance, max_iter) = (config.tolerance, config.intersection.max_iter);
+
+    let estimate = match source.classify_segment(start, end, tolerance) {
+        ClassifySegment::Undefined => {
+            return match config.undefined_is {
+                Some(side) => Ok(find_boundary(source, start, end, max_iter, side)),
+                None => Err(Undefined),
+            };
+        }
+        ClassifySegment::Indeterminate => return Ok(closer_end(source, start, end)),
+        ClassifySegment::ChangesSign(v_start, v_end) if config.linear_estimate => {
+            linear_estimate(source, start, end, tolerance, [v_start, v_end])
+        }
+        classify if !classify.has_sign_change() => return Ok(None),
+        _ => None,
+    };
+
+    let (sample, failure) = match estimate {
+        Some(sample) => (sample, None),
+        None => match source.find_hermite_intersection(start, end, tolerance, max_iter) {
+            Ok(sample) => (sample, None),
+            Err(FindIntersectionError::IterLimit(sample)) => (sample, Some(Failure::IterLimit)),
+            Err(FindIntersectionError::Undefined) => return Err(Undefined),
+            Err(_) => return Ok(None),
+        },
+    };
+
+    let outward = !source.zero_is_inside().is_inside(source.sample(end));
+
+    Ok(Some(Crossing {
+        sample,
+        outward,
+        failure,
+    }))
+}
+
+/// The fallback taken for an edge whose intersection wasn't found.
+pub(crate) enum Fallback {
+    /// [`FailurePolicy::Abort`], the edge is contoured but the extraction
+    /// fails.
+    Abort(Failure),
+    /// [`FailurePolicy::UseBestSample`].
+    BestSample,
+    /// [`FailurePolicy::UseMidpoint`].
+    Midpoint,
+}
+
+/// The intersection of the surface with an edge, once the failure policies
+/// are applied.
+pub(crate) enum EdgeIntersection {
+    /// The surface doesn't cross the edge.
+    None,
+    /// The source is undefined around the edge.
+    Undefined,
+    /// The intersection wasn't found, and the edge is skipped with
+    /// [`FailurePolicy::SkipEdge`].
+    Skipped,
+    /// The value or the normal at the intersection isn't finite, or the
+    /// normal is zero.
+    Degenerate,
+    Found {
+        sample: HermiteSample,
+        /// Whether the outside of the surface is towards the end of the edge.
+        outward: bool,
+        fallback: Option<Fallback>,
+    },
+}
+
+/// Intersects the surface of `source` with the edge from `start` to `end`.
+///
+/// This is where the Hermite data of both [`DualContouring`] and
+/// [`contour_cell`](crate::cell::contour_cell) comes from: the former calls
+/// it once per minimal edge, and the latter once per edge of its cell.
+pub(crate) fn intersect_edge<S: HermiteSource>(
+    source: &S,
+    config: &CellConfig,
+    start: Vec3,
+    end: Vec3,
+) -> EdgeIntersection {
+    let mut crossing = match find_crossing(source, config, start, end) {
+        Ok(Some(crossing)) => crossing,
+        Ok(None) => return EdgeIntersection::None,
+        Err(Undefined) => return EdgeIntersection::Undefined,
+    };
+
+    let fallback = match crossing.failure {
+        Some(failure) => {
+            let policy = match failure {
+                Failure::IterLimit => config.intersection.on_iter_limit,
+                Failure::Indeterminate => config.intersection.on_indeterminate,
+            };
+
+            match policy {
+                FailurePolicy::Abort => Some(Fallback::Abort(failure)),
+                FailurePolicy::UseBestSample => Some(Fallback::BestSample),
+                FailurePolicy::UseMidpoint => {
+                    crossing.sample = source.sample_hermite((start + end) / 2.0);
+                    Some(Fallback::Midpoint)
+                }
+                FailurePolicy::SkipEdge => return EdgeIntersection::Skipped,
+            }
+        }
+        None => None,
+    };
+
+    let HermiteSample { value, normal, .. } = crossing.sample;
+
+    if !value.is_finite() || !normal.is_finite() || normal == Vec3::ZERO {
+        return EdgeIntersection::Degenerate;
+    }
+
+    EdgeIntersection::Found {
+        sample: crossing.sample,
+        outward: crossing.outward,
+        fallback,
+    }
+}
+
+/// Returns the end closer to the surface of an edge both ends of which are
+/// within the tolerance of it, as an indeterminate crossing, if they're on
+/// opposite sides of it.
+fn closer_end<S: HermiteSource>(source: &S, start: Vec3, end: Vec3) -> Option<Crossing> {
+    let (a, b) = (
+        Sample::from_source(source, start),
+        Sample::from_source(source, end),
+    );
+
+    if a.is_inside() == b.is_inside() {
+        return None;
+    }
+
+    let closer = if b.closer_to_surface(&a) { b } else { a };
+
+    Some(Crossing {
+        sample: closer.with_normal(source.sample_normal(closer.point)),
+        outward: !b.is_inside(),
+        failure: Some(Failure::Indeterminate),
+    })
+}
+
+/// Interpolates the values at the ends of a segment crossing the surface,
+/// returning the estimate if its value is within the tolerance. This is
+/// exact for linear fields, such as planes and the faces of boxes, where it
+/// saves the bisection.
+///
+/// An end with a zero value may be anywhere in a region of zeros, which
+/// isn't linear, so such segments are left to the bisection.
+fn linear_estimate<S: HermiteSource>(
+    source: &S,
+    start: Vec3,
+    end: Vec3,
+    tolerance: Tolerance,
+    [v_start, v_end]: [f32; 2],
+) -> Option<HermiteSample> {
+    if v_start == 0.0 || v_end == 0.0 {
+        return None;
+    }
+
+    let point = ClassifySegment::ChangesSign(v_start, v_end).intersection_point(start, end)?;
+    let value = sample_defined(source, point)?;
+    let threshold = tolerance.value_threshold(v_start, v_end, start.distance(end));
+
+    (value.abs() <= threshold)
+        .then(|| HermiteSample::new(point, value, source.sample_normal(point)))
+}
+
+/// Finds where an edge with an undefined end leaves the defined region, if
+/// the surface crosses it there when undefined points are considered to be
+/// on `side` of it.
+///
+/// The normal at the crossing points along the edge, since the source can't
+/// tell anything about the shape of the boundary.
+fn find_boundary<S: Source>(
+    source: &S,
+    start: Vec3,
+    end: Vec3,
+    max_iter: usize,
+    side: InsideOutside,
+) -> Option<Crossing> {
+    // `a` is defined and `b` isn't.
+    let (mut a, mut b, value) = match (sample_defined(source, start), sample_defined(source, end)) {
+        (Some(value), None) => (start, end, value),
+        (None, Some(value)) => (end, start, value),
+        _ => return None,
+    };
+    let towards_end = a == start;
+
+    // No crossing if the defined end is on the same side as undefined points.
+    if source.zero_is_inside().is_inside(value) == (side == InsideOutside::Inside) {
+        return None;
+    }
+
+    for _ in 0..max_iter {
+        let c = (a + b) / 2.0;
+
+        match sample_defined(source, c) {
+            Some(_) => a = c,
+            None => b = c,
+        }
+    }
+
+    let outward = (side == InsideOutside::Outside) == towards_end;
+    let direction = (end - start).normalize();
+
+    Some(Crossing {
+        sample: HermiteSample::new(a, 0.0, if outward { direction } else { -direction }),
+        outward,
+        failure: None,
+    })
+}
+
+/// What to do where the surface leaves the bounds.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum BoundaryPolicy {
+    /// Leave the mesh open along the boundary of the bounds.
+    #[default]
+    Open,
+    /// Close the mesh with caps along the sides of the bounds, as if the
+    /// outside of the bounds was outside of the surface.
+    ///
+    /// The caps are planes half a finest cell inside the bounds, through the
+    /// middle of the outermost cells, where the extraction has all the
+    /// intersections it needs. Like the rest of the surface, they're wound
+    /// counter-clockwise when looking at them from the outside, i.e., from
+    /// outside of the bounds, so the mesh stays consistently oriented and
+    /// closed.
+    Cap,
+}
+
+/// How to split the quads generated around minimal edges into triangles.
+///
+/// The four vertices of a quad are generally not coplanar, especially near
+/// sharp features, so the choice of the diagonal affects the shape of the
+/// surface. All strategies are deterministic and prefer the diagonal through
+/// the first vertex of the quad on ties.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum QuadSplit {
+    /// Always split along the diagonal through the first vertex of the quad.
+    /// This is the fastest strategy, but ignores the geometry entirely.
+    Fixed,
+    /// Split along the shorter diagonal.
+    ShortestDiagonal,
+    /// Split along the diagonal that maximizes the smallest angle of the two
+    /// triangles.
+    #[default]
+    MaxMinAngle,
+    /// Split along the diagonal whose two triangles best agree with the
+    /// surface normal sampled at the centroid of the quad. This requires one
+    /// extra normal sample per quad.
+    AlignWithNormal,
+}
+
+impl QuadSplit {
+    /// Splits a quad whose vertices are at `positions` into two triangles,
+    /// preserving its winding.
+    pub(crate) fn split<S: HermiteSource>(
+        self,
+        quad: [u32; 4],
+        positions: [Vec3; 4],
+        source: &S,
+    ) -> [[u32; 3]; 2] {
+        const SPLITS: [[[usize; 3]; 2]; 2] = [[[0, 1, 2], [0, 2, 3]], [[0, 1, 3], [1, 2, 3]]];
+
+        let triangles = |split: [[usize; 3]; 2]| split.map(|t| t.map(|i| positions[i]));
+
+        let use_second = match self {
+            QuadSplit::Fixed => false,
+            QuadSplit::ShortestDiagonal => {
+                let [a, b, c, d] = positions;
+                b.distance_squared(d) < a.distance_squared(c)
+            }
+            QuadSplit::MaxMinAngle => {
+                let score = |split| triangles(split).map(min_angle).into_iter().reduce(f32::min);
+                score(SPLITS[1]) > score(SPLITS[0])
+            }
+            QuadSplit::AlignWithNormal => {
+                let centroid = positions.iter().sum::<Vec3>() / 4.0;
+                let normal = source.sample_normal(centroid);
+
+                let score = |split| {
+                    triangles(split)
+                        .map(|[a, b, c]| (b - a).cross(c - a).normalize_or_zero().dot(normal))
+                        .into_iter()
+                        .reduce(f32::min)
+                };
+
+                score(SPLITS[1]) > score(SPLITS[0])
+            }
+        };
+
+        SPLITS[use_second as usize].map(|t| t.map(|i| quad[i]))
+    }
+}
+
+/// Returns the smallest angle of a triangle, or 0 if it's degenerate.
+fn min_angle([a, b, c]: [Vec3; 3]) -> f32 {
+    let angle = |p: Vec3, q: Vec3, r: Vec3| {
+        let (u, v) = (q - p, r - p);
+
+        if u == Vec3::ZERO || v == Vec3::ZERO {
+            0.0
+        } else {
+            u.angle_between(v)
+        }
+    };
+
+    angle(a, b, c).min(angle(b, c, a)).min(angle(c, a, b))
+}
+
+/// Edges whose intersections weren't found, failing the extraction.
+#[derive(Default)]
+struct Failures {
+    edges: usize,
+    /// The first cell around any of the edges, in the order of their keys.
+    cell: Option<MortonKey>,
+}
+
+impl Failures {
+    fn add(&mut self, edge: &Edge) {
+        let first = edge
+            .neighbors()
+            .map(|cell| cell.key())
+            .into_iter()
+            .min()
+            .unwrap();
+        self.edges += 1;
+        self.cell = Some(self.cell.map_or(first, |cell| cell.min(first)));
+    }
+}
+
+/// An intersection to add to the QEF of a cell.
+struct CellSample {
+    slot: u32,
+    /// The index in `EdgeKind::ALL` of the edge of the cell, and the key of
+    /// the minimal edge.
+    edge: (u8, MortonKey),
+    point: Vec3,
+    normal: Vec3,
+}
+
+/// The bytes a vertex is estimated to take while contouring, see
+/// [`DualContouring::with_memory_budget`].
+///
+/// On a closed surface, there are about as many crossed minimal edges as
+/// vertices, every edge adding its intersection to the 4 cells around it and
+/// producing a quad, which is split into 2 faces. Maps are counted twice,
+/// for their overhead.
+pub(crate) const VERTEX_COST: usize = 2 * size_of::<(MortonKey, u32)>()
+    + size_of::<(MortonKey, Qef)>()
+    + 4 * size_of::<CellSample>()
+    + size_of::<((MortonKey, AxisKind), [u32; 4])>()
+    + size_of::<MortonKey>()
+    + 3 * size_of::<Vec3>()
+    + 2 * size_of::<[u32; 3]>();
+
+/// Accumulates the Hermite data and the connectivity of minimal edges.
+///
+/// Every minimal edge crossed by the surface contributes its intersection to
+/// the QEFs of the (up to 4) distinct cells around it, and produces a quad
+/// connecting their vertices. Cells are numbered in the order they're first
+/// encountered, until [`sort`](Contour::sort) is called, which also builds
+/// their QEFs.
+#[derive(Default)]
+pub(crate) struct Contour {
+    slots: Map<MortonKey, u32>,
+    cells: Vec<(MortonKey, Qef)>,
+    /// The sums of the normals at the intersections of every cell, which are
+    /// only built once sorted, like the QEFs.
+    normals: Vec<Vec3>,
+    /// The intersections to add to the QEFs of the cells once sorted.
+    samples: Vec<CellSample>,
+    /// The quads along with the keys of their edges.
+    quads: Vec<((MortonKey, AxisKind), [u32; 4])>,
+    /// Edges whose intersection didn't converge, with
+    /// [`FailurePolicy::Abort`].
+    unconverged: Failures,
+    /// Indeterminate edges, with [`FailurePolicy::Abort`].
+    indeterminate: Failures,
+    /// Number of edges contoured with [`FailurePolicy::UseBestSample`].
+    best_sample: usize,
+    /// Number of edges contoured with [`FailurePolicy::UseMidpoint`].
+    midpoint: usize,
+    /// Number of edges skipped with [`FailurePolicy::SkipEdge`].
+    skipped: usize,
+    /// Number of edges skipped because the normal isn't finite (or is zero)
+    /// at their intersection.
+    degenerate: usize,
+    /// Cells around edges skipped because the source is undefined there.
+    undefined: BTreeSet<MortonKey>,
+}
+
+/// Intersects the surface of `dc` with a minimal edge of its octree.
+pub(crate) fn intersect_minimal_edge<S: HermiteSource>(
+    dc: &DualContouring<S>,
+    edge: &Edge,
+) -> EdgeIntersection {
+    // Edges around cells outside of the bounds, with different resolutions
+    // per axis, aren't part of the surface.
+    if dc.is_stretched() && !edge.neighbors().iter().all(|c| dc.is_within_bounds(c)) {
+        return EdgeIntersection::None;
+    }
+
+    let [start, end] = edge.segment(&dc.octree_domain());
+    intersect_edge(&dc.source, &dc.cell_config(), start, end)
+}
+
+impl Contour {
+    pub fn add_edge<S: HermiteSource>(
+        &mut self,
+        dc: &DualContouring<S>,
+        edge: &Edge,
+    ) -> Option<HermiteSample> {
+        self.add_intersection(edge, intersect_minimal_edge(dc, edge))
+    }
+
+    /// Adds a minimal edge given its intersection, see
+    /// [`intersect_minimal_edge`], returning the intersection if the edge
+    /// is contoured.
+    ///
+    /// Once sorted, the contour doesn't depend on the order edges were added
+    /// in.
+    pub fn add_intersection(
+        &mut self,
+        edge: &Edge,
+        intersection: EdgeIntersection,
+    ) -> Option<HermiteSample> {
+        let (sample, outward) = match intersection {
+            EdgeIntersection::Found {
+                sample,
+                outward,
+                fallback,
+            } => {
+                match fallback {
+                    // The edge is still contoured, but the extraction fails.
+                    Some(Fallback::Abort(Failure::IterLimit)) => self.unconverged.add(edge),
+                    Some(Fallback::Abort(Failure::Indeterminate)) => self.indeterminate.add(edge),
+                    Some(Fallback::BestSample) => self.best_sample += 1,
+                    Some(Fallback::Midpoint) => self.midpoint += 1,
+                    None => {}
+                }
+
+                (sample, outward)
+            }
+            EdgeIntersection::None => return None,
+            EdgeIntersection::Undefined => {
+                self.undefined
+                    .extend(edge.neighbors().map(|cell| cell.key()));
+                return None;
+            }
+            EdgeIntersection::Skipped => {
+                self.skipped += 1;
+                return None;
+            }
+            EdgeIntersection::Degenerate => {
+                self.degenerate += 1;
+                return None;
+            }
+        };
+
+        let cells = edge.neighbors_by_position();
+        let order = edge_quad_order(edge.axis(), !outward);
+        let quad = order.map(|i| {
+            let key = cells[i].key();
+
+            *self.slots.entry(key).or_insert_with(|| {
+                self.cells.push((key, Qef::default()));
+                self.cells.len() as u32 - 1
+            })
+        });
+
+        for (i, (slot, position)) in quad.iter().zip(order).enumerate() {
+            // Coarse cells may appear twice around the same edge.
+            if !quad[..i].contains(slot) {
+                self.samples.push(CellSample {
+                    slot: *slot,
+                    edge: (edge_kind_index(edge.axis(), position) as u8, edge.key()),
+                    point: sample.point,
+                    normal: sample.normal,
+                });
+            }
+        }
+
+        self.quads.push(((edge.key(), edge.axis()), quad));
+        Some(sample)
+    }
+
+    /// Orders the cells by their keys and the quads by the keys of their
+    /// edges, so that the output doesn't depend on the order edges were added
+    /// in.
+    pub fn sort(&mut self) {
+        self.cells.sort_unstable_by_key(|(key, _)| *key);
+
+        let mut renumbered = vec![0; self.cells.len()];
+
+        for (slot, (key, _)) in self.cells.iter().enumerate() {
+            let previous = self.slots.insert(*key, slot as u32).unwrap();
+            renumbered[previous as usize] = slot as u32;
+        }
+
+        for (_, quad) in &mut self.quads {
+            *quad = quad.map(|cell| renumbered[cell as usize]);
+        }
+
+        // Rounding makes QEFs depend on the order of their planes, which is
+        // that of the edges of each cell in `EdgeKind::ALL`, so that they
+        // match `contour_cell`, then of their keys for the sub-edges of
+        // coarse cells.
+        for sample in &mut self.samples {
+            sample.slot = renumbered[sample.slot as usize];
+        }
+
+        self.samples
+            .sort_unstable_by_key(|sample| (sample.slot, sample.edge));
+
+        self.normals = vec![Vec3::ZERO; self.cells.len()];
+
+        for sample in self.samples.drain(..) {
+            let (_, qef) = &mut self.cells[sample.slot as usize];
+            qef.add(sample.point, sample.normal);
+            self.normals[sample.slot as usize] += sample.normal;
+        }
+
+        // Minimal edges have distinct keys.
+        self.quads.sort_unstable_by_key(|(key, _)| *key);
+    }
+
+    /// Fails if any edge is degenerate, or if any intersection wasn't found
+    /// with [`FailurePolicy::Abort`].
+    pub fn check(&self) -> Result<(), ExtractSurfaceError> {
+        if self.degenerate > 0 {
+            return Err(ExtractSurfaceError::SourceDegenerate {
+                edges: self.degenerate,
+            });
+        }
+
+        if let Some(cell) = self.unconverged.cell {
+            return Err(ExtractSurfaceError::SolverDidNotConverge {
+                edges: self.unconverged.edges,
+                cell,
+            });
+        }
+
+        if let Some(cell) = self.indeterminate.cell {
+            return Err(ExtractSurfaceError::IndeterminateIntersections {
+                edges: self.indeterminate.edges,
+                cell,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the cells around edges skipped because the source is undefined
+    /// there, in the order of their keys.
+    pub fn undefined_cells(&self) -> impl Iterator<Item = MortonKey> + '_ {
+        self.undefined.iter().copied()
+    }
+
+    /// Returns the report of an extraction from this contour, listing the
+    /// undefined cells and counting the fallbacks taken.
+    pub fn report(&self) -> ExtractionReport {
+        ExtractionReport {
+            undefined_cells: self.undefined_cells().collect(),
+            best_sample_edges: self.best_sample,
+            midpoint_edges: self.midpoint,
+            skipped_edges: self.skipped,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the cells around crossed edges and their accumulated QEFs,
+    /// which are only built once sorted.
+    pub fn cells(&self) -> &[(MortonKey, Qef)] {
+        &self.cells
+    }
+
+    /// Returns the average normal at the intersections of every cell, i.e.,
+    /// the Hermite data its QEF was built from, like
+    /// [`cells`](Contour::cells) only once sorted.
+    ///
+    /// Normals cancelling out, e.g., in a cell around a thin sheet, are
+    /// sampled from `source` at the vertex in `positions` instead.
+    pub fn normals(&self, positions: &[Vec3], source: &impl HermiteSource) -> Vec<Vec3> {
+        (self.normals.iter().zip(positions))
+            .map(|(sum, &position)| {
+                sum.try_normalize()
+                    .unwrap_or_else(|| source.sample_normal(position))
+            })
+            .collect()
+    }
+
+    /// Returns the triangles of all quads, in terms of cell numbers, along
+    /// with the quad each of them comes from.
+    ///
+    /// `positions` holds the vertex position of every cell.
+    pub fn triangles<'a, S: HermiteSource>(
+        &'a self,
+        positions: &'a [Vec3],
+        split: QuadSplit,
+        source: &'a S,
+    ) -> impl Iterator<Item = ([u32; 3], &'a [u32; 4])> {
+        self.quads.iter().flat_map(move |(_, quad)| {
+            let quad_positions = quad.map(|cell| positions[cell as usize]);
+
+            split
+                .split(*quad, quad_positions, source)
+                .into_iter()
+                .filter(|f| f[0] != f[1] && f[1] != f[2] && f[2] != f[0])
+                .map(move |face| (face, quad))
+        })
+    }
+}
+
+/// Places the vertex of a cell by minimizing its QEF within the cell.
+pub(crate) fn place_vertex(key: MortonKey, qef: &Qef, domain: &Bounds) -> Vec3 {
+    solve_within(qef, &key.cell_bounds(domain))
+}
+
+/// Maximum number of times the vertices of intersecting faces are re-solved.
+const MAX_REPAIRS: usize = 4;
+
+/// Regularization weight of the first repair, multiplied by 4 on each
+/// subsequent one.
+const REPAIR_WEIGHT: f32 = 0.5;
+
+/// Re-solves the vertices of intersecting faces, pulling them towards the
+/// mass points of their cells, until no intersections remain or
+/// `MAX_REPAIRS` is reached. Returns the cells still involved in
+/// intersections.
+///
+/// Only the vertices of the cells for which `movable` returns `true` are
+/// re-solved, the others being fixed.
+pub(crate) fn repair_self_intersections<S: HermiteSource>(
+    contour: &Contour,
+    positions: &mut [Vec3],
+    domain: &Bounds,
+    split: QuadSplit,
+    source: &S,
+    movable: impl Fn(u32) -> bool,
+) -> Vec<MortonKey> {
+    let mut weight = REPAIR_WEIGHT;
+
+    for repair in 0..=MAX_REPAIRS {
+        let faces: Vec<[u32; 3]> = contour
+            .triangles(positions, split, source)
+            .map(|(face, _)| face)
+            .collect();
+
+        let offending: BTreeSet<u32> = validate::find_self_intersections(positions, &faces)
+            .into_iter()
+            .flat_map(|[i, j]| faces[i].into_iter().chain(faces[j]))
+            .collect();
+
+        if offending.is_empty() {
+            return Vec::new();
+        }
+
+        if repair == MAX_REPAIRS {
+            let cells = contour.cells();
+            return offending
+                .iter()
+                .map(|&cell| cells[cell as usize].0)
+                .collect();
+        }
+
+        for &cell in offending.iter().filter(|&&cell| movable(cell)) {
+            let (key, qef) = &contour.cells()[cell as usize];
+            let bounds = key.cell_bounds(domain);
+            let (position, _) = qef.solve_regularized(weight);
+            positions[cell as usize] = position.clamp(bounds.min, bounds.max);
+        }
+
+        weight *= 4.0;
+    }
+
+    unreachable!()
+}
+
+/// Number of minimal edges visited between polls of
+/// [`Extractor::should_continue`].
+const POLL_INTERVAL: usize = 256;
+
+/// Accumulates the Hermite data of every minimal edge of `octree` crossed by
+/// the surface, calling `on_crossing` with each intersection.
+///
+/// `should_continue` is polled every [`POLL_INTERVAL`] edges, and once more
+/// at the end.
+pub(crate) fn walk_edges<S: HermiteSource>(
+    dc: &DualContouring<S>,
+    octree: &Octree,
+    should_continue: impl Fn() -> bool,
+    mut on_crossing: impl FnMut(&HermiteSample),
+) -> Result<Contour, ExtractSurfaceError> {
+    let mut contour = Contour::default();
+    let mut visited = 0;
+
+    let walk = octree.try_for_each_minimal_edge_near(
+        |_| true,
+        |edge| {
+            visited += 1;
+
+            if visited % POLL_INTERVAL == 0 && !should_continue() {
+                return ControlFlow::Break(());
+            }
+
+            if let Some(sample) = &contour.add_edge(dc, edge) {
+                on_crossing(sample);
+            }
+
+            ControlFlow::Continue(())
+        },
+    );
+
+    if walk.is_break() || !should_continue() {
+        return Err(ExtractSurfaceError::Cancelled);
+    }
+
+    contour.check()?;
+    contour.sort();
+    Ok(contour)
+}
+
+/// The dual vertices of an extraction and the faces connecting them, computed
+/// by [`DualContouring::place_vertices`] and turned into a mesh by
+/// [`DualContouring::generate_faces`], which doesn't sample the source again.
+///
+/// Vertices are ordered by the key of the cell producing them, and faces by
+/// the key of the minimal edge they come from, as with
+/// [`DualContouring::extract`].
+///
+/// [`DualContouring::place_vertices`]: crate::DualContouring::place_vertices
+/// [`DualContouring::generate_faces`]: crate::DualContouring::generate_faces
+/// [`DualContouring::extract`]: crate::DualContouring::extract
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlacedVertices {
+    /// The bounds and the resolution of the extraction.
+    pub(crate) bounds: Bounds,
+    pub(crate) max_res: UVec3,
+    pub(crate) cells: Vec<MortonKey>,
+    pub(crate) positions: Vec<Vec3>,
+    pub(crate) normals: Vec<Vec3>,
+    pub(crate) faces: Vec<[u32; 3]>,
+    pub(crate) report: ExtractionReport,
+}
+
+impl Default for PlacedVertices {
+    /// No vertices, over the default bounds of [`DualContouring::new`] and a
+    /// resolution of 0.
+    ///
+    /// [`DualContouring::new`]: crate::DualContouring::new
+    fn default() -> Self {
+        Self {
+            bounds: Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0)),
+            max_res: UVec3::ZERO,
+            cells: Vec::new(),
+            positions: Vec::new(),
+            normals: Vec::new(),
+            faces: Vec::new(),
+            report: ExtractionReport::default(),
+        }
+    }
+}
+
+impl PlacedVertices {
+    /// The leaf cell producing every vertex, in increasing order.
+    pub fn cells(&self) -> &[MortonKey] {
+        &self.cells
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    /// The normal of every vertex, averaged from the normals at the
+    /// intersections of the surface with the edges of its cell, which the
+    /// extraction already sampled, see [`NormalMode::AveragedFromHermiteData`].
+    ///
+    /// [`NormalMode::AveragedFromHermiteData`]: crate::NormalMode::AveragedFromHermiteData
+    pub fn normals(&self) -> &[Vec3] {
+        &self.normals
+    }
+
+    /// The triangles, with quads already split according to
+    /// [`DualContouring::with_quad_split`].
+    ///
+    /// [`DualContouring::with_quad_split`]: crate::DualContouring::with_quad_split
+    pub fn faces(&self) -> &[[u32; 3]] {
+        &self.faces
+    }
+
+    pub fn report(&self) -> &ExtractionReport {
+        &self.report
+    }
+}
+
+/// Runs dual contouring over the leaves of `octree` up to the faces, which
+/// is everything that samples the source.
+pub(crate) fn place_vertices<S: HermiteSource>(
+    dc: &DualContouring<S>,
+    octree: &Octree,
+    should_continue: impl Fn() -> bool,
+) -> Result<PlacedVertices, ExtractSurfaceError> {
+    let contour = walk_edges(dc, octree, should_continue, |_| {})?;
+    Ok(place_contour(dc, octree.domain(), &contour))
+}
+
+/// Places the vertices of the cells of a sorted `contour` over `domain` and
+/// connects them.
+pub(crate) fn place_contour<S: HermiteSource>(
+    dc: &DualContouring<S>,
+    domain: &Bounds,
+    contour: &Contour,
+) -> PlacedVertices {
+    let source = &dc.source;
+    let split = dc.quad_split;
+
+    let mut positions: Vec<Vec3> = contour
+        .cells()
+        .iter()
+        .map(|(key, qef)| place_vertex(*key, qef, domain))
+        .collect();
+
+    let mut report = contour.report();
+
+    if dc.check_self_intersections {
+        report.self_intersecting_cells =
+            repair_self_intersections(contour, &mut positions, domain, split, source, |_| true);
+    }
+
+    snap_to_boundary(dc, contour, &mut positions);
+
+    let faces = contour
+        .triangles(&positions, split, source)
+        .map(|(face, _)| face)
+        .collect();
+
+    PlacedVertices {
+        bounds: dc.bounds,
+        max_res: dc.max_res,
+        cells: contour.cells().iter().map(|(key, _)| *key).collect(),
+        normals: contour.normals(&positions, source),
+        positions,
+        faces,
+        report,
+    }
+}
+
+/// Snaps the vertices of the cells of `contour` near the boundary of the
+/// bounds, see [`DualContouring::with_boundary_snap`].
+pub(crate) fn snap_to_boundary<S: HermiteSource>(
+    dc: &DualContouring<S>,
+    contour: &Contour,
+    positions: &mut [Vec3],
+) {
+    let Some(subdivisions) = dc.boundary_snap else {
+        return;
+    };
+
+    let cell_size = dc.bounds.size() / dc.max_res.as_vec3();
+    let step = Vec3::from_array(
+        (cell_size / subdivisions as f32)
+            .to_array()
+            .map(nearest_power_of_two),
+    );
+
+    for ((key, _), position) in contour.cells().iter().zip(positions) {
+        if dc.is_near_bounds(*key) {
+            *position = (*position / step).round() * step;
+        }
+    }
+}
+
+/// Returns the power of two closest to `x` on a logarithmic scale, given a
+/// positive normal `x`.
+fn nearest_power_of_two(x: f32) -> f32 {
+    // Clearing the mantissa rounds down, and a mantissa of at least √2
+    // rounds up.
+    let bits = x.to_bits();
+    let power = f32::from_bits(bits & 0xff80_0000);
+
+    if bits & 0x007f_ffff >= 0x0035_04f3 {
+        power * 2.0
+    } else {
+        power
+    }
+}
+
+/// Feeds the mesh of `placed` to `extractor`.
+///
+/// Vertices are emitted in the order of the keys of their cells, followed by
+/// all the faces, in the order of the keys of the minimal edges they come
+/// from. Faces split from the same quad are emitted together.
+pub(crate) fn generate_faces<E: Extractor>(
+    placed: &PlacedVertices,
+    extractor: &mut E,
+) -> Result<ExtractionReport, ExtractSurfaceError> {
+    extractor.reserve(placed.positions.len(), placed.faces.len());
+
+    let vertices = placed.cells.iter().zip(&placed.positions);
+
+    for ((cell, position), normal) in vertices.zip(&placed.normals) {
+        extractor.extract_vertex_with_normal(*position, *normal, *cell);
+    }
+
+    for &face in &placed.faces {
+        extractor.extract_face(face);
+    }
+
+    extractor.finish()?;
+    Ok(placed.report.clone())
+}
+
+/// Feeds the mesh of `placed` to `extractor`, grouping the faces by the
+/// ancestor at `level` of the cells of their vertices, or by the cells
+/// themselves where they're coarser. Faces spanning several groups go to the
+/// one with the smallest key.
+///
+/// Groups are emitted in the order of their keys, each with the vertices its
+/// faces use, in the order of their cells, followed by its faces in the
+/// order of [`generate_faces`].
+pub(crate) fn generate_groups<E: GroupedExtractor>(
+    placed: &PlacedVertices,
+    level: u32,
+    extractor: &mut E,
+) -> Result<ExtractionReport, ExtractSurfaceError> {
+    let group_of = |i: u32| {
+        let cell = placed.cells[i as usize];
+        cell.ancestor_at_level(level.min(cell.level()))
+    };
+
+    let mut groups: BTreeMap<MortonKey, Vec<[u32; 3]>> = BTreeMap::new();
+
+    for &face in &placed.faces {
+        let key = face.map(group_of).into_iter().min().unwrap();
+        groups.entry(key).or_default().push(face);
+    }
+
+    for (key, faces) in groups {
+        let mut vertices: Vec<u32> = faces.iter().flatten().copied().collect();
+        vertices.sort_unstable();
+        vertices.dedup();
+
+        extractor.begin_group(key);
+
+        for &i in &vertices {
+            extractor.extract_vertex(placed.positions[i as usize], placed.normals[i as usize]);
+        }
+
+        for face in faces {
+            extractor.extract_face(face.map(|i| vertices.binary_search(&i).unwrap() as u32));
+        }
+
+        extractor.end_group();
+    }
+
+    extractor.finish()?;
+    Ok(placed.report.clone())
+}
+
+/// Runs dual contouring over the leaves of `octree`, feeding the resulting
+/// mesh to `extractor`.
+pub(crate) fn contour<S, E>(
+    dc: &DualContouring<S>,
+    octree: &Octree,
+    extractor: &mut E,
+) -> Result<ExtractionReport, ExtractSurfaceError>
+where
+    S: HermiteSource,
+    E: Extractor,
+{
+    let placed = place_vertices(dc, octree, || extractor.should_continue())?;
+    generate_faces(&placed, extractor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{from_fn, FiniteDifference};
+    use crate::{
+        DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals, WithVertexCells,
+    };
+    use glam::Quat;
+
+    fn rotated_cube() -> impl HermiteSource {
+        let rotation = Quat::from_euler(glam::EulerRot::XYZ, 0.5, 0.7, 0.3);
+
+        let cube = from_fn(move |p: Vec3| {
+            let q = (rotation.inverse() * p).abs() - Vec3::splat(0.45);
+            q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+        });
+
+        FiniteDifference::new(cube, 1e-4)
+    }
+
+    /// Counts the faces deviating more than 60° from the normal sampled at
+    /// their centroid, among those whose vertices lie on the same side of the
+    /// cube (where the sampled normal is meaningful for the whole face).
+    fn deviating_faces(split: QuadSplit) -> usize {
+        let cube = rotated_cube();
+        let mut mesh = IndexedSeparateNormals::default();
+
+        DualContouring::new(&cube, 16, 1e-4)
+            .with_quad_split(split)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &cube))
+            .unwrap();
+
+        mesh.faces
+            .iter()
+            .filter(|face| {
+                let [na, nb, nc] = face.map(|i| mesh.vertices.normals[i as usize]);
+                na.dot(nb) > 0.99 && nb.dot(nc) > 0.99
+            })
+            .filter(|face| {
+                let [a, b, c] = face.map(|i| mesh.vertices.positions[i as usize]);
+                let normal = (b - a).cross(c - a).normalize_or_zero();
+                let sampled = cube.sample_normal((a + b + c) / 3.0);
+                normal.dot(sampled) < 0.5
+            })
+            .count()
+    }
+
+    #[test]
+    fn align_with_normal_avoids_creases() {
+        assert!(deviating_faces(QuadSplit::Fixed) > 0);
+        assert_eq!(deviating_faces(QuadSplit::AlignWithNormal), 0);
+    }
+
+    /// Returns the bytes of the vertices and faces of `mesh`.
+    fn mesh_bytes(mesh: &IndexedSeparateNormals) -> Vec<u8> {
+        let vertices = mesh.positions().iter().chain(mesh.normals());
+        let floats = vertices.flat_map(|v| v.to_array()).map(f32::to_bits);
+        let indices = mesh.faces.iter().flatten().copied();
+
+        floats.chain(indices).flat_map(u32::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let cube = rotated_cube();
+        let dc = DualContouring::new(&cube, 16, 1e-4);
+
+        let runs: Vec<_> = (0..2)
+            .map(|_| {
+                let mut mesh = IndexedSeparateNormals::default();
+                let mut cells = Vec::new();
+                let extractor = WithIndexedSeparateNormals::new(&mut mesh, &cube);
+                dc.extract(WithVertexCells::new(extractor, &mut cells))
+                    .unwrap();
+
+                assert!(cells.is_sorted());
+                mesh_bytes(&mesh)
+            })
+            .collect();
+
+        assert!(!runs[0].is_empty());
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[test]
+    fn sphere_quads_are_convex() {
+        let sphere = crate::source::hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-4);
+        let octree = dc.build_octree();
+        let contour = walk_edges(&dc, &octree, || true, |_| {}).unwrap();
+
+        let positions: Vec<Vec3> = contour
+            .cells()
+            .iter()
+            .map(|(key, qef)| place_vertex(*key, qef, octree.domain()))
+            .collect();
+
+        assert!(!contour.quads.is_empty());
+
+        for (_, quad) in &contour.quads {
+            let corners = quad.map(|cell| positions[cell as usize]);
+
+            // Newell's normal of the best-fit plane.
+            let normal: Vec3 = (0..4).map(|i| corners[i].cross(corners[(i + 1) % 4])).sum();
+
+            for i in 0..4 {
+                let [a, b, c] = [0, 1, 2].map(|j| corners[(i + j) % 4]);
+                assert!((b - a).cross(c - b).dot(normal) > 0.0, "{corners:?}");
+            }
+
+            // And it faces outward.
+            let center = corners.iter().sum::<Vec3>() / 4.0;
+            assert!(normal.dot(center) > 0.0);
+        }
+    }
+}
@@ -0,0 +1,817 @@
+//! Dual contouring in 2D, turning the zero level set of a planar field into
+//! polylines, e.g., to slice a solid for 3D printing, or to outline glyphs
+//! from signed distance fields, see [`DualContouring2D`].
+//!
+//! The square is split by a quadtree, whose cells are identified by
+//! [`MortonKey`]s like those of the octree in 3D, restricted to 2 bits per
+//! level.
+
+use crate::builder;
+use crate::cell::CellConfig;
+use crate::collections::{Map, Set};
+use crate::contour::{intersect_edge, EdgeIntersection};
+use crate::geom::{AxisKind, BMask3, Bounds, CornerKind};
+use crate::qef::Qef;
+use crate::source::{FiniteDifference, HermiteSource, Source, Tolerance, ZeroIsInside};
+use crate::{check, may_contain_surface, ExtractSurfaceError, FinishError, MortonKey};
+use alloc::vec;
+use alloc::vec::Vec;
+use auto_impl::auto_impl;
+use glam::{UVec2, Vec2, Vec3};
+
+/// The step of the central differences estimating gradients, relative to the
+/// size of the cells, see [`Source2::gradient`].
+const GRADIENT_STEP: f32 = 0.01;
+
+/// A scalar field over the plane, negative inside of the shape and positive
+/// outside of it, like a [`Source`].
+#[auto_impl(&, &mut, Box, Rc, Arc)]
+pub trait Source2 {
+    /// Samples the field at a given point.
+    fn sample(&self, point: Vec2) -> f32;
+
+    /// Returns the gradient of the field at a given point, which points
+    /// outward and isn't normalized, if it's known.
+    ///
+    /// Defaults to `None`, in which case [`DualContouring2D`] estimates it
+    /// by central differences a hundredth of a cell apart.
+    fn gradient(&self, point: Vec2) -> Option<Vec2> {
+        let _ = point;
+        None
+    }
+
+    /// Returns whether the source is defined at a given point, like
+    /// [`Source::is_defined`]. Edges with an undefined end aren't contoured.
+    fn is_defined(&self, point: Vec2) -> bool {
+        let _ = point;
+        true
+    }
+
+    /// Returns which side of the curve points where the source is exactly
+    /// zero are on, like [`Source::zero_is_inside`]. Defaults to the outside.
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        ZeroIsInside::default()
+    }
+}
+
+/// The slice of a 3D [`HermiteSource`] by the plane at a given height along
+/// Z, as a [`Source2`] over its XY coordinates, whose gradient is that of
+/// the source projected onto the plane.
+///
+/// Sources without gradients can be wrapped in a [`FiniteDifference`].
+#[derive(Debug, Copy, Clone)]
+pub struct SliceZ<S> {
+    source: S,
+    z: f32,
+}
+
+impl<S: HermiteSource> SliceZ<S> {
+    pub fn new(source: S, z: f32) -> Self {
+        Self { source, z }
+    }
+
+    pub fn z(&self) -> f32 {
+        self.z
+    }
+}
+
+impl<S: HermiteSource> Source2 for SliceZ<S> {
+    fn sample(&self, point: Vec2) -> f32 {
+        self.source.sample(point.extend(self.z))
+    }
+
+    fn gradient(&self, point: Vec2) -> Option<Vec2> {
+        Some(self.source.gradient(point.extend(self.z)).truncate())
+    }
+
+    fn is_defined(&self, point: Vec2) -> bool {
+        self.source.is_defined(point.extend(self.z))
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+/// A [`Source2`] extruded along Z into a [`HermiteSource`], so that its
+/// edges are intersected by the same code as in 3D.
+struct Extruded<'a, S> {
+    source: &'a S,
+    /// The step of the central differences, see [`Source2::gradient`].
+    step: f32,
+}
+
+impl<S: Source2> Source for Extruded<'_, S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.source.sample(point.truncate())
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(point.truncate())
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+impl<S: Source2> HermiteSource for Extruded<'_, S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        match self.source.gradient(point.truncate()) {
+            Some(gradient) => gradient.extend(0.0),
+            None => FiniteDifference::new(self, self.step).gradient(point),
+        }
+    }
+}
+
+/// Receives the polylines of [`DualContouring2D::extract`].
+#[auto_impl(&mut, Box)]
+pub trait PolylineExtractor {
+    fn extract_vertex(&mut self, position: Vec2);
+
+    /// Extracts a segment, given the indices of its ends in the order they
+    /// were extracted. Segments are oriented with the inside on their left,
+    /// so that they run counter-clockwise around shapes and clockwise around
+    /// their holes.
+    fn extract_segment(&mut self, segment: [u32; 2]);
+
+    /// Called once all vertices and segments have been extracted, like
+    /// [`Extractor::finish`](crate::Extractor::finish).
+    fn finish(&mut self) -> Result<(), FinishError> {
+        Ok(())
+    }
+}
+
+/// Collects the polylines of [`DualContouring2D::extract`], then chains
+/// their segments into loops.
+#[derive(Debug, Clone, Default)]
+pub struct CollectLoops {
+    pub vertices: Vec<Vec2>,
+    pub segments: Vec<[u32; 2]>,
+    /// The closed loops, as the indices of their vertices, without repeating
+    /// the first one. Outer boundaries run counter-clockwise and holes
+    /// clockwise, alternating with their nesting.
+    pub loops: Vec<Vec<u32>>,
+    /// The chains that don't close, i.e., that leave the bounds, from their
+    /// first vertex to their last.
+    pub open: Vec<Vec<u32>>,
+}
+
+impl CollectLoops {
+    /// Returns the positions of the vertices of a chain.
+    pub fn positions<'a>(&'a self, chain: &'a [u32]) -> impl Iterator<Item = Vec2> + 'a {
+        chain.iter().map(|&i| self.vertices[i as usize])
+    }
+
+    /// Chains the segments, starting from the vertices without incoming
+    /// ones, then around the remaining loops. Where several segments leave
+    /// the same vertex, e.g., at a saddle, the first one left is followed.
+    fn chain(&mut self) {
+        let mut incoming = vec![0u32; self.vertices.len()];
+        let mut outgoing = vec![Vec::new(); self.vertices.len()];
+
+        for (s, &[a, b]) in self.segments.iter().enumerate() {
+            outgoing[a as usize].push(s as u32);
+            incoming[b as usize] += 1;
+        }
+
+        let mut used = vec![false; self.segments.len()];
+        let heads =
+            (0..self.segments.len()).filter(|&s| incoming[self.segments[s][0] as usize] == 0);
+        let heads: Vec<usize> = heads.chain(0..self.segments.len()).collect();
+
+        for s in heads {
+            if used[s] {
+                continue;
+            }
+
+            let first = self.segments[s][0];
+            let mut chain = vec![first];
+            let mut next = Some(s);
+
+            while let Some(s) = next {
+                used[s] = true;
+                let end = self.segments[s][1];
+
+                if end == first {
+                    break;
+                }
+
+                chain.push(end);
+                next = (outgoing[end as usize].iter())
+                    .map(|&s| s as usize)
+                    .find(|&s| !used[s]);
+            }
+
+            let closed = next.is_some();
+
+            if closed {
+                self.loops.push(chain);
+            } else {
+                self.open.push(chain);
+            }
+        }
+    }
+
+    /// Reverses the loops whose winding doesn't match their nesting, i.e.,
+    /// the number of other loops around them.
+    fn orient(&mut self) {
+        let polygons: Vec<Vec<Vec2>> = (self.loops.iter())
+            .map(|chain| self.positions(chain).collect())
+            .collect();
+
+        for (k, chain) in self.loops.iter_mut().enumerate() {
+            let point = polygons[k][0];
+            let depth = (polygons.iter().enumerate())
+                .filter(|&(other, polygon)| other != k && contains(polygon, point))
+                .count();
+
+            if (signed_area(&polygons[k]) > 0.0) != (depth % 2 == 0) {
+                chain.reverse();
+            }
+        }
+    }
+}
+
+impl PolylineExtractor for CollectLoops {
+    fn extract_vertex(&mut self, position: Vec2) {
+        self.vertices.push(position);
+    }
+
+    fn extract_segment(&mut self, segment: [u32; 2]) {
+        self.segments.push(segment);
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        self.loops.clear();
+        self.open.clear();
+        self.chain();
+        self.orient();
+        Ok(())
+    }
+}
+
+/// Returns the signed area of a polygon, positive when counter-clockwise.
+pub fn signed_area(polygon: &[Vec2]) -> f32 {
+    let edges = polygon.iter().zip(polygon.iter().cycle().skip(1));
+    edges.map(|(a, b)| a.perp_dot(*b)).sum::<f32>() / 2.0
+}
+
+/// Returns whether `point` is inside of `polygon`, by the even-odd rule.
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let edges = polygon.iter().zip(polygon.iter().cycle().skip(1));
+
+    edges
+        .filter(|(a, b)| (a.y > point.y) != (b.y > point.y))
+        .filter(|(a, b)| {
+            let t = (point.y - a.y) / (b.y - a.y);
+            point.x < a.x + t * (b.x - a.x)
+        })
+        .count()
+        % 2
+        == 1
+}
+
+/// A quadtree over a square, the cells of which are identified by
+/// [`MortonKey`]s restricted to 2 bits per level, i.e., whose Z bits are
+/// clear.
+struct Quadtree {
+    /// The keys of the interior cells.
+    interior: Set<MortonKey>,
+}
+
+impl Quadtree {
+    /// Builds a quadtree by subdividing cells, starting from the root, for as
+    /// long as `subdivide` returns `true` and `max_level` isn't reached.
+    fn build<F>(max_level: u32, mut subdivide: F) -> Self
+    where
+        F: FnMut(&MortonKey) -> bool,
+    {
+        let mut interior = Set::new();
+        let mut stack = vec![MortonKey::root()];
+
+        while let Some(key) = stack.pop() {
+            if key.level() < max_level && subdivide(&key) {
+                interior.insert(key);
+                stack.extend(quadrants(&key));
+            }
+        }
+
+        Self { interior }
+    }
+
+    fn is_leaf(&self, key: &MortonKey) -> bool {
+        !self.interior.contains(key)
+    }
+
+    /// Calls `f` with every minimal edge, i.e., every edge between two leaves
+    /// that isn't split by a subdivided cell, as the axis the edge separates
+    /// them along and the keys of the leaves, the one with the lower
+    /// coordinate along that axis being first.
+    ///
+    /// The quadtree is walked depth-first from the root, visiting the
+    /// quadrants of a cell before the edges between them, without recursion.
+    fn for_each_minimal_edge<F>(&self, mut f: F)
+    where
+        F: FnMut(AxisKind, [MortonKey; 2]),
+    {
+        enum Work {
+            Cell(MortonKey),
+            Edge(AxisKind, [MortonKey; 2]),
+        }
+
+        let mut stack = vec![Work::Cell(MortonKey::root())];
+
+        while let Some(work) = stack.pop() {
+            match work {
+                Work::Cell(key) if !self.is_leaf(&key) => {
+                    let quadrant = |x, y| quadrant(&key, x, y);
+
+                    for half in [true, false] {
+                        let edges = [
+                            (AxisKind::Y, [quadrant(half, false), quadrant(half, true)]),
+                            (AxisKind::X, [quadrant(false, half), quadrant(true, half)]),
+                        ];
+                        stack.extend(edges.map(|(axis, cells)| Work::Edge(axis, cells)));
+                    }
+
+                    stack.extend(quadrants(&key).into_iter().rev().map(Work::Cell));
+                }
+                Work::Cell(_) => {}
+                Work::Edge(axis, [low, high]) => {
+                    if self.is_leaf(&low) && self.is_leaf(&high) {
+                        f(axis, [low, high]);
+                        continue;
+                    }
+
+                    // The halves of the edge are between the quadrants of the
+                    // cells next to it, which leaves stand in for.
+                    let descend = |key: MortonKey, side: bool, half: bool| {
+                        if self.is_leaf(&key) {
+                            key
+                        } else if axis == AxisKind::X {
+                            quadrant(&key, side, half)
+                        } else {
+                            quadrant(&key, half, side)
+                        }
+                    };
+
+                    for half in [true, false] {
+                        let cells = [descend(low, true, half), descend(high, false, half)];
+                        stack.push(Work::Edge(axis, cells));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the child of a quadtree cell in the given quadrant.
+fn quadrant(key: &MortonKey, x: bool, y: bool) -> MortonKey {
+    key.child(BMask3::O.with(AxisKind::X, x).with(AxisKind::Y, y))
+}
+
+/// Returns the 4 children of a quadtree cell, row by row from the bottom.
+fn quadrants(key: &MortonKey) -> [MortonKey; 4] {
+    [(false, false), (true, false), (false, true), (true, true)].map(|(x, y)| quadrant(key, x, y))
+}
+
+/// Returns the ends of the minimal edge between two quadtree cells along
+/// `axis`, which is the side of the smaller of them, in increasing order
+/// along the other axis.
+fn edge_ends(domain: &Bounds, axis: AxisKind, [low, high]: [MortonKey; 2]) -> [Vec3; 2] {
+    let (cell, side) = if low.level() >= high.level() {
+        (low, true)
+    } else {
+        (high, false)
+    };
+
+    let bounds = cell.cell_bounds(domain);
+    let along = if axis == AxisKind::X {
+        AxisKind::Y
+    } else {
+        AxisKind::X
+    };
+
+    [false, true].map(|end| bounds.corner(CornerKind(BMask3::O.with(axis, side).with(along, end))))
+}
+
+/// Dual contouring over a square, extracting the curves where a [`Source2`]
+/// is zero as polylines.
+#[derive(Clone)]
+pub struct DualContouring2D<S> {
+    source: S,
+    min: Vec2,
+    max: Vec2,
+    max_res: u32,
+    epsilon: f32,
+    assume_lipschitz: bool,
+}
+
+impl<S: Source2> DualContouring2D<S> {
+    /// Creates a new `DualContouring2D` over the `[-1, 1]²` square, whose
+    /// finest cells split it into `max_res` along each axis. Intersections
+    /// are searched with a [`Tolerance::Absolute`] tolerance of `epsilon`,
+    /// as in 3D.
+    ///
+    /// # Panics
+    ///
+    /// If `max_res` isn't a power of two no larger than
+    /// `2^MortonKey::LEVELS`, or `epsilon` isn't finite and greater than 0.
+    pub fn new(source: S, max_res: u32, epsilon: f32) -> Self {
+        check(builder::check_max_res(max_res));
+        check(builder::check_tolerance(Tolerance::Absolute(epsilon)));
+
+        Self {
+            source,
+            min: Vec2::splat(-1.0),
+            max: Vec2::splat(1.0),
+            max_res,
+            epsilon,
+            assume_lipschitz: false,
+        }
+    }
+
+    /// Sets the square to extract the curves from.
+    ///
+    /// # Panics
+    ///
+    /// If `min` isn't below `max` along both axes.
+    pub fn with_bounds(mut self, min: Vec2, max: Vec2) -> Self {
+        assert!(min.cmplt(max).all(), "`min` must be below `max`");
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    /// Sets whether the source can be assumed to be Lipschitz continuous
+    /// with a constant of at most 1, like
+    /// [`DualContouring::assume_lipschitz`], so that only the cells that may
+    /// contain the curve are subdivided. Disabled by default, in which case
+    /// every cell is subdivided down to the resolution.
+    ///
+    /// [`DualContouring::assume_lipschitz`]: crate::DualContouring::assume_lipschitz
+    pub fn assume_lipschitz(mut self, assume_lipschitz: bool) -> Self {
+        self.assume_lipschitz = assume_lipschitz;
+        self
+    }
+
+    fn max_level(&self) -> u32 {
+        self.max_res.trailing_zeros()
+    }
+
+    /// Returns the size of the finest cells.
+    fn cell_size(&self) -> Vec2 {
+        (self.max - self.min) / self.max_res as f32
+    }
+
+    /// Returns the square as a box that is flat along Z, so that the keys of
+    /// quadtree cells give their bounds, see [`MortonKey::cell_bounds`].
+    fn domain(&self) -> Bounds {
+        Bounds::new(self.min.extend(0.0), self.max.extend(0.0))
+    }
+
+    /// Returns the point at the given integer coordinates on the lattice of
+    /// the corners of the finest cells.
+    fn lattice_point(&self, point: UVec2) -> Vec2 {
+        self.min + point.as_vec2() * self.cell_size()
+    }
+
+    /// Builds the quadtree, only subdividing the cells that may contain the
+    /// curve with [`assume_lipschitz`](Self::assume_lipschitz).
+    fn build_quadtree(&self) -> Quadtree {
+        if !self.assume_lipschitz {
+            return Quadtree::build(self.max_level(), |_| true);
+        }
+
+        let domain = self.domain();
+        let mut samples: Map<[u32; 2], f32> = Map::new();
+
+        Quadtree::build(self.max_level(), |key| {
+            let size = 1 << (self.max_level() - key.level());
+            let origin = key.coords().truncate() * size;
+
+            // The corners on both sides along Z coincide.
+            let corners = CornerKind::ALL.map(|corner| {
+                let corner = BMask3::from(corner);
+                let offset = UVec2::new(
+                    corner.has(AxisKind::X) as u32,
+                    corner.has(AxisKind::Y) as u32,
+                );
+                let point = origin + offset * size;
+
+                *samples
+                    .entry(point.to_array())
+                    .or_insert_with(|| self.source.sample(self.lattice_point(point)))
+            });
+
+            may_contain_surface(&key.cell_bounds(&domain), &corners)
+        })
+    }
+
+    /// Extracts the curves into `extractor`.
+    ///
+    /// The square is split by a quadtree, down to the resolution wherever it
+    /// may contain the curves. Every minimal edge of the quadtree crossed by
+    /// the curves contributes its intersection and normal to the QEFs of the
+    /// two leaves next to it, which place their vertices, and produces a
+    /// segment connecting them.
+    ///
+    /// Vertices are emitted in the order of the [`MortonKey`] of their cells,
+    /// followed by the segments, in the order of a depth-first walk of the
+    /// quadtree. Curves leaving the square end at the vertices of the cells
+    /// along its sides.
+    ///
+    /// Edges are intersected like those of [`DualContouring`] with the
+    /// default [`IntersectionConfig`], so those with an undefined end, or
+    /// where the gradient vanishes, aren't contoured.
+    ///
+    /// [`DualContouring`]: crate::DualContouring
+    /// [`IntersectionConfig`]: crate::IntersectionConfig
+    pub fn extract(
+        &self,
+        mut extractor: impl PolylineExtractor,
+    ) -> Result<(), ExtractSurfaceError> {
+        if self.max_res < 2 {
+            return Err(ExtractSurfaceError::EmptyDomain);
+        }
+
+        let domain = self.domain();
+        let source = Extruded {
+            source: &self.source,
+            step: self.cell_size().min_element() * GRADIENT_STEP,
+        };
+        let config = CellConfig::new(Tolerance::Absolute(self.epsilon));
+
+        let quadtree = self.build_quadtree();
+        let mut qefs: Map<MortonKey, Qef> = Map::new();
+        // The cells around every crossed minimal edge, in the direction of
+        // its segment.
+        let mut crossings: Vec<[MortonKey; 2]> = Vec::new();
+
+        quadtree.for_each_minimal_edge(|axis, [low, high]| {
+            let [start, end] = edge_ends(&domain, axis, [low, high]);
+            // The start is on the right of the direction from `first` to
+            // `second`.
+            let [first, second] = if axis == AxisKind::X {
+                [low, high]
+            } else {
+                [high, low]
+            };
+
+            let EdgeIntersection::Found {
+                sample, outward, ..
+            } = intersect_edge(&source, &config, start, end)
+            else {
+                return;
+            };
+
+            for cell in [first, second] {
+                let qef = qefs.entry(cell).or_default();
+                qef.add(sample.point, sample.normal);
+            }
+
+            // Going from the second cell to the first, the start is on the
+            // left, where the inside must be.
+            crossings.push(if outward {
+                [second, first]
+            } else {
+                [first, second]
+            });
+        });
+
+        let mut cells: Vec<(MortonKey, Qef)> = qefs.into_iter().collect();
+        cells.sort_unstable_by_key(|&(key, _)| key);
+        let mut vertices: Map<MortonKey, u32> = Map::new();
+
+        for (index, (key, qef)) in cells.iter().enumerate() {
+            // The QEF is flat along Z, which its solution stays at 0 along.
+            let bounds = key.cell_bounds(&domain);
+            let (position, _) = qef.solve();
+            let position = position.clamp(bounds.min, bounds.max).truncate();
+            extractor.extract_vertex(position);
+            vertices.insert(*key, index as u32);
+        }
+
+        for [a, b] in crossings {
+            extractor.extract_segment([vertices[&a], vertices[&b]]);
+        }
+
+        extractor.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{from_fn, hermite_from_fn, ZeroSide};
+    use core::f32::consts::PI;
+    use glam::Vec3;
+
+    fn extract(source: impl Source2) -> CollectLoops {
+        let mut loops = CollectLoops::default();
+        DualContouring2D::new(source, 64, 1e-5)
+            .extract(&mut loops)
+            .unwrap();
+        loops
+    }
+
+    #[test]
+    fn sphere_slice() {
+        let radius = 0.7;
+        let sphere = hermite_from_fn(move |p: Vec3| p.length() - radius, |p| p);
+        let loops = extract(SliceZ::new(sphere, 0.0));
+
+        assert_eq!(loops.loops.len(), 1);
+        assert!(loops.open.is_empty());
+        assert_eq!(loops.loops[0].len(), loops.vertices.len());
+
+        // The segments already run counter-clockwise around the center.
+        for &[a, b] in &loops.segments {
+            let (a, b) = (loops.vertices[a as usize], loops.vertices[b as usize]);
+            assert!(a.perp_dot(b) > 0.0, "{a} {b}");
+        }
+
+        let polygon: Vec<Vec2> = loops.positions(&loops.loops[0]).collect();
+        for p in &polygon {
+            assert!((p.length() - radius).abs() < 1e-3, "{p}");
+        }
+
+        let area = signed_area(&polygon);
+        assert!((area / (PI * radius * radius) - 1.0).abs() < 0.01, "{area}");
+    }
+
+    #[test]
+    fn culls_cells_far_from_the_curve() {
+        let samples = core::cell::Cell::new(0);
+        let radius = 0.7;
+        let sphere = hermite_from_fn(
+            |p: Vec3| {
+                samples.set(samples.get() + 1);
+                p.length() - radius
+            },
+            |p| p,
+        );
+
+        let extract = |assume_lipschitz| {
+            samples.set(0);
+            let mut loops = CollectLoops::default();
+            DualContouring2D::new(SliceZ::new(&sphere, 0.0), 256, 1e-5)
+                .assume_lipschitz(assume_lipschitz)
+                .extract(&mut loops)
+                .unwrap();
+            (loops, samples.get())
+        };
+
+        let (full, full_samples) = extract(false);
+        let (culled, culled_samples) = extract(true);
+
+        // The cells around the crossed edges are refined either way, and the
+        // loop stays closed across the leaves of different sizes.
+        assert!(culled_samples < full_samples / 4);
+        assert_eq!(culled.vertices, full.vertices);
+        assert_eq!(culled.segments, full.segments);
+        assert_eq!(culled.loops.len(), 1);
+        assert!(culled.open.is_empty());
+    }
+
+    #[test]
+    fn estimates_missing_gradients() {
+        struct Circle(f32);
+
+        impl Source2 for Circle {
+            fn sample(&self, point: Vec2) -> f32 {
+                point.length() - self.0
+            }
+        }
+
+        let loops = extract(Circle(0.7));
+        assert_eq!(loops.loops.len(), 1);
+
+        for p in loops.positions(&loops.loops[0]) {
+            assert!((p.length() - 0.7).abs() < 1e-3, "{p}");
+        }
+    }
+
+    #[test]
+    fn torus_slice() {
+        // A torus around the Z axis, sliced through its hole, i.e., an
+        // annulus.
+        let (major, minor) = (0.6, 0.25);
+        let torus =
+            from_fn(move |p: Vec3| Vec2::new(p.truncate().length() - major, p.z).length() - minor);
+        let torus = FiniteDifference::new(torus, 1e-4);
+        let loops = extract(SliceZ::new(torus, 0.0));
+
+        assert_eq!(loops.loops.len(), 2);
+        assert!(loops.open.is_empty());
+
+        let mut polygons: Vec<Vec<Vec2>> = (loops.loops.iter())
+            .map(|chain| loops.positions(chain).collect())
+            .collect();
+        polygons.sort_by(|a, b| signed_area(b).total_cmp(&signed_area(a)));
+        let [outer, hole] = [&polygons[0], &polygons[1]];
+
+        for (polygon, radius) in [(outer, major + minor), (hole, major - minor)] {
+            let area = PI * radius * radius;
+            assert!((signed_area(polygon).abs() / area - 1.0).abs() < 0.02);
+
+            for p in polygon {
+                assert!((p.length() - radius).abs() < 1e-3, "{p}");
+            }
+        }
+
+        // Counter-clockwise outside, clockwise around the hole, which is
+        // within it.
+        assert!(signed_area(outer) > 0.0);
+        assert!(signed_area(hole) < 0.0);
+        assert!(hole.iter().all(|&p| contains(outer, p)));
+    }
+
+    #[test]
+    fn reorients_loops() {
+        // A square with a hole, both wound the wrong way.
+        let square = |half: f32| {
+            [(-1.0, -1.0), (-1.0, 1.0), (1.0, 1.0), (1.0, -1.0)]
+                .map(|(x, y)| Vec2::new(x, y) * half)
+        };
+
+        let mut loops = CollectLoops::default();
+        let square = square(1.0).into_iter().chain(square(0.5).into_iter().rev());
+        square.for_each(|p| loops.extract_vertex(p));
+
+        for [first, len] in [[0, 4], [4, 4]] {
+            for k in 0..len {
+                loops.extract_segment([first + k, first + (k + 1) % len]);
+            }
+        }
+
+        loops.finish().unwrap();
+        assert_eq!(loops.loops, [vec![3, 2, 1, 0], vec![7, 6, 5, 4]]);
+    }
+
+    #[test]
+    fn open_curves_end_at_bounds() {
+        let line = hermite_from_fn(
+            |p: Vec3| p.y - 0.5 * p.x - 0.1,
+            |_| Vec3::new(-0.5, 1.0, 0.0),
+        );
+        let mut loops = CollectLoops::default();
+        DualContouring2D::new(SliceZ::new(line, 0.0), 16, 1e-5)
+            .with_bounds(Vec2::ZERO, Vec2::ONE)
+            .extract(&mut loops)
+            .unwrap();
+
+        assert!(loops.loops.is_empty());
+        assert_eq!(loops.open.len(), 1);
+
+        // With the inside below, the line runs right to left.
+        let chain: Vec<Vec2> = loops.positions(&loops.open[0]).collect();
+        assert_eq!(chain.len(), loops.vertices.len());
+        assert!(chain.windows(2).all(|w| w[1].x < w[0].x));
+
+        for p in chain {
+            assert!((p.y - 0.5 * p.x - 0.1).abs() < 1e-4, "{p}");
+        }
+    }
+
+    #[test]
+    fn zeros_follow_their_side() {
+        // Zero over the band `|x| <= 0.5`, which starts and ends at grid
+        // points, and positive outside of it.
+        let band = hermite_from_fn(
+            |p: Vec3| (p.x.abs() - 0.5).max(0.0),
+            |p: Vec3| Vec3::X * p.x.signum(),
+        );
+
+        let mut loops = CollectLoops::default();
+        DualContouring2D::new(SliceZ::new(&band, 0.0), 8, 1e-5)
+            .extract(&mut loops)
+            .unwrap();
+        assert!(loops.vertices.is_empty());
+
+        let inside = ZeroSide::new(&band, ZeroIsInside(true));
+        let mut loops = CollectLoops::default();
+        DualContouring2D::new(SliceZ::new(inside, 0.0), 8, 1e-5)
+            .extract(&mut loops)
+            .unwrap();
+
+        // Both sides of the band, running down on the left and up on the
+        // right.
+        assert!(loops.loops.is_empty());
+        assert_eq!(loops.open.len(), 2);
+
+        for chain in &loops.open {
+            let chain: Vec<Vec2> = loops.positions(chain).collect();
+            assert_eq!(chain.len(), 8);
+            assert!(
+                chain.iter().all(|p| (p.x.abs() - 0.5).abs() < 1e-4),
+                "{chain:?}"
+            );
+
+            let up = chain[1].y > chain[0].y;
+            assert_eq!(up, chain[0].x > 0.0);
+        }
+    }
+}
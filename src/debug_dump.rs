@@ -0,0 +1,138 @@
+//! Dumps of the intermediate data of an extraction as Wavefront OBJ line
+//! geometry, to inspect in a 3D viewer when a mesh comes out wrong.
+//!
+//! OBJ has no colors, so elements are split into named groups that viewers
+//! can toggle. Vertices are referenced relative to the last one written, so
+//! several dumps can be written one after another into the same file.
+
+use crate::geom::{CornerKind, EdgeKind};
+use crate::octree::Octree;
+use crate::points::CollectPoints;
+use alloc::vec::Vec;
+use glam::Vec3;
+use std::io::{self, Write};
+
+/// Writes the box of every leaf of `octree` as its 12 edges, in one group per
+/// level named `leaves_level_<level>`.
+///
+/// The octree of an extraction is returned by
+/// [`DualContouring::build_octree`](crate::DualContouring::build_octree).
+pub fn dump_octree_wireframe<W: Write>(octree: &Octree, mut writer: W) -> io::Result<()> {
+    let mut leaves: Vec<_> = octree.leaves().collect();
+    leaves.sort_by_key(|cell| cell.level());
+
+    let mut level = None;
+
+    for cell in leaves {
+        if level != Some(cell.level()) {
+            level = Some(cell.level());
+            writeln!(writer, "g leaves_level_{}", cell.level())?;
+        }
+
+        for corner in cell.corner_positions(octree.domain()) {
+            write_vertex(&mut writer, corner)?;
+        }
+
+        // The corners are the last 8 vertices, in the order of
+        // `CornerKind::ALL`.
+        for edge in EdgeKind::ALL {
+            let [a, b] = edge.endpoints().map(|corner| {
+                let i = CornerKind::ALL.iter().position(|&c| c == corner).unwrap();
+                i as i64 - 8
+            });
+            writeln!(writer, "l {a} {b}")?;
+        }
+    }
+
+    writer.flush()
+}
+
+/// Writes the points where the surface crosses the edges of the octree, as
+/// collected by [`DualContouring::extract_points`] with
+/// [`PointMode::EdgeIntersections`], in a group named `intersections`, and
+/// their normals as segments of length `normal_length` in a group named
+/// `normals`.
+///
+/// [`DualContouring::extract_points`]: crate::DualContouring::extract_points
+/// [`PointMode::EdgeIntersections`]: crate::PointMode::EdgeIntersections
+pub fn dump_hermite_data<W: Write>(
+    collected: &CollectPoints,
+    normal_length: f32,
+    mut writer: W,
+) -> io::Result<()> {
+    let count = collected.positions.len() as i64;
+
+    for (&position, &normal) in collected.positions.iter().zip(&collected.normals) {
+        write_vertex(&mut writer, position)?;
+        write_vertex(&mut writer, position + normal * normal_length)?;
+    }
+
+    // Every point is followed by the tip of its normal.
+    let point = |i: i64| 2 * (i - count);
+
+    writeln!(writer, "g intersections")?;
+    for i in 0..count {
+        writeln!(writer, "p {}", point(i))?;
+    }
+
+    writeln!(writer, "g normals")?;
+    for i in 0..count {
+        writeln!(writer, "l {} {}", point(i), point(i) + 1)?;
+    }
+
+    writer.flush()
+}
+
+fn write_vertex<W: Write>(writer: &mut W, v: Vec3) -> io::Result<()> {
+    writeln!(writer, "v {} {} {}", v.x, v.y, v.z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{DualContouring, PointMode};
+
+    fn count_records(obj: &[u8], prefix: &str) -> usize {
+        let obj = core::str::from_utf8(obj).unwrap();
+        obj.lines().filter(|line| line.starts_with(prefix)).count()
+    }
+
+    #[test]
+    fn sphere_dump() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-4).assume_lipschitz(true);
+
+        let octree = dc.build_octree();
+        let leaf_count = octree.leaves().count();
+        let mut wireframe = Vec::new();
+        dump_octree_wireframe(&octree, &mut wireframe).unwrap();
+
+        assert_eq!(count_records(&wireframe, "v "), 8 * leaf_count);
+        assert_eq!(count_records(&wireframe, "l "), 12 * leaf_count);
+        assert!(count_records(&wireframe, "g leaves_level_") > 1);
+        assert_eq!(count_records(&wireframe, "g leaves_level_4"), 1);
+
+        let mut collected = CollectPoints::default();
+        dc.with_point_mode(PointMode::EdgeIntersections)
+            .extract_points(&mut collected)
+            .unwrap();
+        let mut hermite = Vec::new();
+        dump_hermite_data(&collected, 0.05, &mut hermite).unwrap();
+
+        let count = collected.positions.len();
+        assert!(count > 0);
+        assert_eq!(count_records(&hermite, "v "), 2 * count);
+        assert_eq!(count_records(&hermite, "p "), count);
+        assert_eq!(count_records(&hermite, "l "), count);
+
+        // Every relative index refers to a vertex written before.
+        let hermite = core::str::from_utf8(&hermite).unwrap();
+        for line in hermite.lines().filter(|line| line.starts_with(['p', 'l'])) {
+            for index in line.split(' ').skip(1) {
+                let index: i64 = index.parse().unwrap();
+                assert!((-2 * count as i64..0).contains(&index), "{line}");
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+//! Writing meshes in the OBJ, STL and PLY formats, either once extracted or
+//! while extracting them with an [`ObjWriter`].
+//!
+//! Writers aren't buffered internally, so wrapping them in an
+//! [`io::BufWriter`] is recommended.
+
+use crate::extractor::{Extractor, IndexedSeparateNormals, OutputConvention, SeparateNormals};
+use crate::source::HermiteSource;
+use glam::Vec3;
+use std::io::{self, Write};
+
+/// The encoding of a PLY file.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum PlyFormat {
+    Ascii,
+    #[default]
+    BinaryLittleEndian,
+}
+
+impl IndexedSeparateNormals {
+    /// Writes the mesh in the Wavefront OBJ format, with per-vertex normals.
+    pub fn write_obj<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        debug_assert!(self.vertices.is_consistent());
+
+        for position in &self.vertices.positions {
+            writeln!(writer, "v {} {} {}", position.x, position.y, position.z)?;
+        }
+
+        for normal in &self.vertices.normals {
+            writeln!(writer, "vn {} {} {}", normal.x, normal.y, normal.z)?;
+        }
+
+        for face in &self.faces {
+            // OBJ indices are 1-based.
+            let [a, b, c] = face.map(|i| i as u64 + 1);
+            writeln!(writer, "f {a}//{a} {b}//{b} {c}//{c}")?;
+        }
+
+        writer.flush()
+    }
+
+    /// Writes the mesh in the binary STL format.
+    ///
+    /// STL has no notion of shared vertices, so every face is written with
+    /// its own copy of its vertices and its geometric normal.
+    pub fn write_stl<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let count = u32::try_from(self.faces.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many faces for STL"))?;
+
+        writer.write_all(&[0; 80])?;
+        writer.write_all(&count.to_le_bytes())?;
+
+        for [(a, _), (b, _), (c, _)] in self.triangles() {
+            let normal = (b - a).cross(c - a).normalize_or_zero();
+
+            for v in [normal, a, b, c] {
+                write_vec3(&mut writer, v)?;
+            }
+
+            // Attribute byte count, unused.
+            writer.write_all(&[0; 2])?;
+        }
+
+        writer.flush()
+    }
+
+    /// Writes the mesh in the PLY format, with `x y z nx ny nz` vertex
+    /// properties and triangular faces.
+    pub fn write_ply<W: Write>(&self, mut writer: W, format: PlyFormat) -> io::Result<()> {
+        debug_assert!(self.vertices.is_consistent());
+
+        let format_name = match format {
+            PlyFormat::Ascii => "ascii",
+            PlyFormat::BinaryLittleEndian => "binary_little_endian",
+        };
+
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format {format_name} 1.0")?;
+        writeln!(writer, "element vertex {}", self.vertices.positions.len())?;
+
+        for property in ["x", "y", "z", "nx", "ny", "nz"] {
+            writeln!(writer, "property float {property}")?;
+        }
+
+        writeln!(writer, "element face {}", self.faces.len())?;
+        writeln!(writer, "property list uchar uint vertex_indices")?;
+        writeln!(writer, "end_header")?;
+
+        let vertices = self.vertices.positions.iter().zip(&self.vertices.normals);
+
+        match format {
+            PlyFormat::Ascii => {
+                for (p, n) in vertices {
+                    writeln!(writer, "{} {} {} {} {} {}", p.x, p.y, p.z, n.x, n.y, n.z)?;
+                }
+
+                for [a, b, c] in &self.faces {
+                    writeln!(writer, "3 {a} {b} {c}")?;
+                }
+            }
+            PlyFormat::BinaryLittleEndian => {
+                for (p, n) in vertices {
+                    write_vec3(&mut writer, *p)?;
+                    write_vec3(&mut writer, *n)?;
+                }
+
+                for face in &self.faces {
+                    writer.write_all(&[3])?;
+
+                    for i in face {
+                        writer.write_all(&i.to_le_bytes())?;
+                    }
+                }
+            }
+        }
+
+        writer.flush()
+    }
+}
+
+/// An extractor writing the mesh to a stream in the Wavefront OBJ format as
+/// it's extracted, producing the same output as
+/// [`IndexedSeparateNormals::write_obj`].
+///
+/// Every vertex is written as soon as it's extracted, and every face right
+/// after, since all of its vertices have been written by then. Faces are
+/// never retained, but the positions and normals of vertices are cached to
+/// orient the faces, which takes 24 bytes per vertex. That's one vertex per
+/// leaf cell on the surface, i.e., the cache grows with the frontier of the
+/// octree, which the extraction holds in memory anyway, rather than with the
+/// size of the mesh.
+///
+/// I/O errors can't be reported while extracting, so the first one is kept,
+/// further writes are skipped, and the extraction fails once it's over. The
+/// error is also returned by [`into_inner`].
+///
+/// [`into_inner`]: ObjWriter::into_inner
+#[derive(Debug)]
+pub struct ObjWriter<W, S> {
+    writer: W,
+    source: S,
+    convention: OutputConvention,
+    vertices: SeparateNormals,
+    error: Option<io::Error>,
+}
+
+impl<W: Write, S> ObjWriter<W, S> {
+    pub fn new(writer: W, source: S) -> Self {
+        Self::new_with(writer, source, OutputConvention::default())
+    }
+
+    pub fn new_with(writer: W, source: S, convention: OutputConvention) -> Self {
+        Self {
+            writer,
+            source,
+            convention,
+            vertices: SeparateNormals::default(),
+            error: None,
+        }
+    }
+
+    /// Flushes the writer and returns it, or the first error encountered
+    /// while writing.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        match self.error {
+            Some(error) => Err(error),
+            None => self.writer.flush().map(|_| self.writer),
+        }
+    }
+
+    fn write(&mut self, f: impl FnOnce(&mut W) -> io::Result<()>) {
+        if self.error.is_none() {
+            self.error = f(&mut self.writer).err();
+        }
+    }
+}
+
+impl<W: Write, S: HermiteSource> Extractor for ObjWriter<W, S> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        let normal = self.source.sample_normal(position);
+        let (p, n) = (
+            self.convention.transform(position),
+            self.convention.transform_normal(normal),
+        );
+
+        self.vertices.push(p, n);
+
+        self.write(|w| {
+            writeln!(w, "v {} {} {}", p.x, p.y, p.z)?;
+            writeln!(w, "vn {} {} {}", n.x, n.y, n.z)
+        });
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        let face = self.convention.wind(self.vertices.orient(face));
+        let [a, b, c] = face.map(|i| i as u64 + 1);
+        self.write(|w| writeln!(w, "f {a}//{a} {b}//{b} {c}//{c}"));
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        // The error is kept for `into_inner`.
+        match &self.error {
+            Some(error) => Err(io::Error::new(error.kind(), error.to_string())),
+            None => self.writer.flush(),
+        }
+    }
+}
+
+fn write_vec3<W: Write>(writer: &mut W, v: Vec3) -> io::Result<()> {
+    for component in v.to_array() {
+        writer.write_all(&component.to_le_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extractor::SeparateNormals;
+
+    fn triangle() -> IndexedSeparateNormals {
+        IndexedSeparateNormals {
+            vertices: SeparateNormals {
+                positions: vec![Vec3::ZERO, Vec3::X, Vec3::Y],
+                normals: vec![Vec3::Z; 3],
+            },
+            faces: vec![[0, 1, 2]],
+        }
+    }
+
+    #[test]
+    fn obj_uses_one_based_indices() {
+        let mut obj = Vec::new();
+        triangle().write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+
+        assert_eq!(obj.lines().filter(|l| l.starts_with("v ")).count(), 3);
+        assert_eq!(obj.lines().filter(|l| l.starts_with("vn ")).count(), 3);
+        assert!(obj.lines().any(|l| l == "f 1//1 2//2 3//3"));
+    }
+
+    #[test]
+    fn stl_layout() {
+        let mut stl = Vec::new();
+        triangle().write_stl(&mut stl).unwrap();
+
+        assert_eq!(stl.len(), 80 + 4 + 50);
+        assert_eq!(stl[80..84], 1u32.to_le_bytes());
+
+        // The normal of the first (and only) face.
+        let z = f32::from_le_bytes(stl[92..96].try_into().unwrap());
+        assert_eq!(z, 1.0);
+    }
+
+    #[test]
+    fn ply_header_and_body() {
+        let mesh = triangle();
+
+        let mut ascii = Vec::new();
+        mesh.write_ply(&mut ascii, PlyFormat::Ascii).unwrap();
+        let ascii = String::from_utf8(ascii).unwrap();
+        let (header, body) = ascii.split_once("end_header\n").unwrap();
+
+        assert!(header.starts_with("ply\nformat ascii 1.0\n"));
+        assert!(header.contains("element vertex 3\n"));
+        assert!(header.contains("element face 1\n"));
+        assert_eq!(body.lines().nth(1), Some("1 0 0 0 0 1"));
+        assert_eq!(body.lines().last(), Some("3 0 1 2"));
+
+        let mut binary = Vec::new();
+        mesh.write_ply(&mut binary, PlyFormat::BinaryLittleEndian)
+            .unwrap();
+        let end = b"end_header\n";
+        let start = binary.windows(end.len()).position(|w| w == end).unwrap() + end.len();
+
+        // Three vertices of six floats, and a face of a count and three
+        // indices.
+        let body = &binary[start..];
+        assert_eq!(body.len(), 3 * 6 * 4 + 1 + 3 * 4);
+        assert_eq!(body[72], 3);
+        assert_eq!(body[body.len() - 4..], 2u32.to_le_bytes());
+    }
+
+    #[test]
+    fn streamed_obj_matches_buffered() {
+        use crate::source::hermite_from_fn;
+        use crate::{DualContouring, WithIndexedSeparateNormals};
+
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.7, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-4);
+
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+        let mut buffered = Vec::new();
+        mesh.write_obj(&mut buffered).unwrap();
+
+        let mut writer = ObjWriter::new(Vec::new(), &sphere);
+        dc.extract(&mut writer).unwrap();
+        let streamed = writer.into_inner().unwrap();
+
+        let lines = |obj: &[u8]| {
+            let mut lines: Vec<String> = String::from_utf8(obj.to_vec())
+                .unwrap()
+                .lines()
+                .map(String::from)
+                .collect();
+            lines.sort();
+            lines
+        };
+
+        // Vertices are interleaved with their normals, but the lines are the
+        // same.
+        assert_eq!(lines(&streamed), lines(&buffered));
+    }
+
+    #[test]
+    fn streamed_obj_reports_errors() {
+        use crate::source::hermite_from_fn;
+        use crate::{DualContouring, ExtractSurfaceError};
+
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.7, |p| p);
+        let mut buf = [0; 64];
+        let mut writer = ObjWriter::new(&mut buf[..], &sphere);
+        assert!(matches!(
+            DualContouring::new(&sphere, 16, 1e-4).extract(&mut writer),
+            Err(ExtractSurfaceError::Io {
+                kind: io::ErrorKind::WriteZero,
+                ..
+            })
+        ));
+
+        assert_eq!(
+            writer.into_inner().unwrap_err().kind(),
+            io::ErrorKind::WriteZero
+        );
+    }
+}
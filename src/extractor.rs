@@ -1,68 +1,2071 @@
+use crate::collections::Map;
+use crate::geom::{AxisKind, Bounds};
+use crate::morton::MortonKey;
 use crate::source::HermiteSource;
+use crate::validate::{self, MeasureError};
+use alloc::vec;
+use alloc::vec::Vec;
 use auto_impl::auto_impl;
-use glam::Vec3;
+use core::borrow::Borrow;
+use core::ops::Range;
+use glam::{Affine3A, IVec3, Vec3};
+
+/// The error of [`Extractor::finish`], an I/O error with the `std` feature.
+#[cfg(feature = "std")]
+pub type FinishError = std::io::Error;
+
+/// The error of [`Extractor::finish`], an I/O error with the `std` feature.
+#[cfg(not(feature = "std"))]
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[error("{message}")]
+pub struct FinishError {
+    pub message: alloc::string::String,
+}
 
 #[auto_impl(&mut, Box)]
 pub trait Extractor {
     fn extract_vertex(&mut self, position: Vec3);
+
+    /// Extracts a vertex along with the leaf cell that produced it.
+    ///
+    /// It defaults to [`Extractor::extract_vertex`], so only extractors
+    /// interested in the cells, or wrapping other extractors, need to
+    /// implement it.
+    fn extract_vertex_with_cell(&mut self, position: Vec3, cell: MortonKey) {
+        let _ = cell;
+        self.extract_vertex(position);
+    }
+
+    /// Extracts a vertex along with its normal, averaged from the Hermite
+    /// data of its cell, see [`NormalMode::AveragedFromHermiteData`], and
+    /// the leaf cell that produced it.
+    ///
+    /// This is what the extraction calls. It defaults to
+    /// [`Extractor::extract_vertex_with_cell`], so only extractors using the
+    /// normals, or wrapping other extractors, need to implement it.
+    fn extract_vertex_with_normal(&mut self, position: Vec3, normal: Vec3, cell: MortonKey) {
+        let _ = normal;
+        self.extract_vertex_with_cell(position, cell);
+    }
+
+    /// Extracts a face, given the indices of its vertices in the order they
+    /// were extracted. Faces are wound counter-clockwise when looking at
+    /// their outer side.
     fn extract_face(&mut self, face: [u32; 3]);
+
+    /// Called before any vertex or face is extracted, when the extraction
+    /// knows how many there are about to be, e.g., to reserve buffers for
+    /// them up front.
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        let _ = (vertices, faces);
+    }
+
+    /// Returns how many bytes the extractor allocates for the given numbers
+    /// of vertices and faces, so that they're accounted for by
+    /// [`DualContouring::with_memory_budget`]. Defaults to 0, i.e., to leave
+    /// the extractor out of the estimate.
+    ///
+    /// [`DualContouring::with_memory_budget`]: crate::DualContouring::with_memory_budget
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        let _ = (vertices, faces);
+        0
+    }
+
+    /// Returns whether the extraction should go on.
+    ///
+    /// This is polled periodically while the octree is walked, and the
+    /// extraction is aborted with an error as soon as it returns `false`,
+    /// before any vertex or face is extracted. This allows e.g. a UI to
+    /// cancel a long extraction from another thread through an atomic flag.
+    fn should_continue(&self) -> bool {
+        true
+    }
+
+    /// Called once all vertices and faces have been extracted, e.g., to
+    /// compute data that depends on the whole mesh or to flush an output.
+    ///
+    /// An error fails the extraction with [`ExtractSurfaceError::Io`].
+    ///
+    /// [`ExtractSurfaceError::Io`]: crate::ExtractSurfaceError::Io
+    fn finish(&mut self) -> Result<(), FinishError> {
+        Ok(())
+    }
 }
 
-#[derive(Debug, Default)]
-pub struct SeparateNormals {
-    pub positions: Vec<Vec3>,
-    pub normals: Vec<Vec3>,
+/// An extractor that can be split into independent local extractors, e.g.,
+/// one per worker thread, whose outputs are merged back in the end. This
+/// avoids sharing a single extractor behind a lock.
+///
+/// Faces given to a local extractor index the vertices given to it, starting
+/// from 0, and [`merge`] rebases them. [`SerialExtractor`] adapts any
+/// implementation to [`Extractor`], using a single local extractor.
+///
+/// [`merge`]: ParallelExtractor::merge
+#[auto_impl(&mut, Box)]
+pub trait ParallelExtractor {
+    type Local: Extractor;
+
+    fn make_local(&self) -> Self::Local;
+
+    /// Appends the output of `local`, which has already been finished.
+    /// `vertex_offset` is the index of its first vertex in the merged mesh,
+    /// i.e., the number of vertices merged before it, and is added to the
+    /// indices of its faces.
+    fn merge(&mut self, local: Self::Local, vertex_offset: u32);
+
+    /// Called once all local extractors have been merged, like
+    /// [`Extractor::finish`].
+    ///
+    /// An error fails the extraction with [`ExtractSurfaceError::Io`].
+    ///
+    /// [`ExtractSurfaceError::Io`]: crate::ExtractSurfaceError::Io
+    fn finish_merged(&mut self) -> Result<(), FinishError> {
+        Ok(())
+    }
+}
+
+/// Adapts a [`ParallelExtractor`] to [`Extractor`], by extracting into a
+/// single local extractor that's merged when the extraction finishes.
+pub struct SerialExtractor<P: ParallelExtractor> {
+    target: P,
+    local: P::Local,
+}
+
+impl<P: ParallelExtractor> SerialExtractor<P> {
+    pub fn new(target: P) -> Self {
+        let local = target.make_local();
+        Self { target, local }
+    }
+}
+
+impl<P: ParallelExtractor> Extractor for SerialExtractor<P> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        self.local.extract_vertex(position);
+    }
+
+    fn extract_vertex_with_cell(&mut self, position: Vec3, cell: MortonKey) {
+        self.local.extract_vertex_with_cell(position, cell);
+    }
+
+    fn extract_vertex_with_normal(&mut self, position: Vec3, normal: Vec3, cell: MortonKey) {
+        self.local
+            .extract_vertex_with_normal(position, normal, cell);
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        self.local.extract_face(face);
+    }
+
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        self.local.reserve(vertices, faces);
+    }
+
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        self.local.reserve_hint(vertices, faces)
+    }
+
+    fn should_continue(&self) -> bool {
+        self.local.should_continue()
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        self.local.finish()?;
+
+        let local = core::mem::replace(&mut self.local, self.target.make_local());
+        self.target.merge(local, 0);
+        self.target.finish_merged()
+    }
+}
+
+/// Receives a mesh split into groups of faces by octree region, e.g., to
+/// build one collider per group rather than a single one for the whole mesh.
+/// See [`DualContouring::extract_grouped`].
+///
+/// Every group starts with [`begin_group`] and ends with [`end_group`].
+/// Faces index the vertices given since the start of their group, starting
+/// from 0.
+///
+/// [`DualContouring::extract_grouped`]: crate::DualContouring::extract_grouped
+/// [`begin_group`]: GroupedExtractor::begin_group
+/// [`end_group`]: GroupedExtractor::end_group
+#[auto_impl(&mut, Box)]
+pub trait GroupedExtractor {
+    /// Starts the group of the faces within the octree node `key`.
+    fn begin_group(&mut self, key: MortonKey);
+
+    /// Extracts a vertex along with its normal, see
+    /// [`PlacedVertices::normals`].
+    ///
+    /// [`PlacedVertices::normals`]: crate::PlacedVertices::normals
+    fn extract_vertex(&mut self, position: Vec3, normal: Vec3);
+
+    /// Extracts a face, wound counter-clockwise when looking at its outer
+    /// side.
+    fn extract_face(&mut self, face: [u32; 3]);
+
+    fn end_group(&mut self);
+
+    /// Called once all groups have been extracted, like
+    /// [`Extractor::finish`].
+    fn finish(&mut self) -> Result<(), FinishError> {
+        Ok(())
+    }
 }
 
+/// Collects every group of a [`GroupedExtractor`] into a mesh of its own.
 #[derive(Debug, Default)]
-pub struct IndexedSeparateNormals {
-    pub vertices: SeparateNormals,
-    pub faces: Vec<[u32; 3]>,
+pub struct CollectGroups {
+    pub groups: Vec<(MortonKey, IndexedSeparateNormals)>,
 }
 
-pub struct WithIndexedSeparateNormals<'a, S> {
-    buf: &'a mut IndexedSeparateNormals,
-    source: S,
+impl GroupedExtractor for CollectGroups {
+    fn begin_group(&mut self, key: MortonKey) {
+        self.groups.push((key, IndexedSeparateNormals::default()));
+    }
+
+    fn extract_vertex(&mut self, position: Vec3, normal: Vec3) {
+        let (_, mesh) = self.groups.last_mut().expect("no group was begun");
+        mesh.vertices.push(position, normal);
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        let (_, mesh) = self.groups.last_mut().expect("no group was begun");
+        mesh.faces.push(face);
+    }
+
+    fn end_group(&mut self) {}
 }
 
-impl<'a, S> WithIndexedSeparateNormals<'a, S> {
-    pub fn new(buffer: &'a mut IndexedSeparateNormals, source: S) -> Self {
+/// Wraps an extractor, recording the leaf cell that produced every vertex.
+///
+/// The keys are pushed to a vector, parallel to the vertices given to the
+/// wrapped extractor. Vertices extracted without a cell get
+/// [`MortonKey::none`].
+pub struct WithVertexCells<'a, E> {
+    inner: E,
+    cells: &'a mut Vec<MortonKey>,
+}
+
+impl<'a, E: Extractor> WithVertexCells<'a, E> {
+    pub fn new(inner: E, cells: &'a mut Vec<MortonKey>) -> Self {
+        Self { inner, cells }
+    }
+}
+
+impl<E: Extractor> Extractor for WithVertexCells<'_, E> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        self.extract_vertex_with_cell(position, MortonKey::none());
+    }
+
+    fn extract_vertex_with_cell(&mut self, position: Vec3, cell: MortonKey) {
+        self.cells.push(cell);
+        self.inner.extract_vertex_with_cell(position, cell);
+    }
+
+    fn extract_vertex_with_normal(&mut self, position: Vec3, normal: Vec3, cell: MortonKey) {
+        self.cells.push(cell);
+        self.inner
+            .extract_vertex_with_normal(position, normal, cell);
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        self.inner.extract_face(face);
+    }
+
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        self.cells.reserve(vertices);
+        self.inner.reserve(vertices, faces);
+    }
+
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        vertices * size_of::<MortonKey>() + self.inner.reserve_hint(vertices, faces)
+    }
+
+    fn should_continue(&self) -> bool {
+        self.inner.should_continue()
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        self.inner.finish()
+    }
+}
+
+/// The leaf cells that produced the vertices of a mesh, filled by
+/// [`DualContouring::extract_with_map`].
+///
+/// Vertices are extracted in the order of the keys of their cells, so the
+/// vertices of a cell are contiguous and looked up by binary search.
+///
+/// [`DualContouring::extract_with_map`]: crate::DualContouring::extract_with_map
+#[derive(Debug, Clone, Default)]
+pub struct CellVertexMap {
+    cells: Vec<MortonKey>,
+    positions: Vec<Vec3>,
+}
+
+impl CellVertexMap {
+    /// Returns the number of vertices.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Returns the cell that produced a vertex.
+    ///
+    /// # Panics
+    ///
+    /// If there is no such vertex.
+    pub fn cell_of(&self, vertex: u32) -> MortonKey {
+        self.cells[vertex as usize]
+    }
+
+    /// Returns the vertices produced by a cell, which are empty unless it's a
+    /// leaf crossed by the surface.
+    pub fn vertices_of(&self, cell: MortonKey) -> Range<u32> {
+        let start = self.cells.partition_point(|&key| key < cell);
+        let end = start + self.cells[start..].partition_point(|&key| key == cell);
+        start as u32..end as u32
+    }
+
+    /// Returns the vertices within `query`, in order, given the box covered
+    /// by the root of the octree, see [`Octree::domain`].
+    ///
+    /// This scans all vertices, rejecting those whose cells miss `query`
+    /// before testing their positions, which lie within their cells.
+    ///
+    /// [`Octree::domain`]: crate::Octree::domain
+    pub fn vertices_in_bounds<'a>(
+        &'a self,
+        domain: &'a Bounds,
+        query: &'a Bounds,
+    ) -> impl Iterator<Item = u32> + 'a {
+        self.cells
+            .iter()
+            .zip(&self.positions)
+            .enumerate()
+            .filter(|(_, (key, position))| {
+                key.cell_bounds(domain).intersects(query) && query.contains(**position)
+            })
+            .map(|(vertex, _)| vertex as u32)
+    }
+
+    /// Removes all vertices, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.positions.clear();
+    }
+}
+
+/// Wraps an extractor, filling a [`CellVertexMap`].
+pub(crate) struct WithCellVertexMap<'a, E> {
+    inner: E,
+    map: &'a mut CellVertexMap,
+}
+
+impl<'a, E: Extractor> WithCellVertexMap<'a, E> {
+    pub(crate) fn new(inner: E, map: &'a mut CellVertexMap) -> Self {
+        map.clear();
+        Self { inner, map }
+    }
+}
+
+impl<E: Extractor> Extractor for WithCellVertexMap<'_, E> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        self.extract_vertex_with_cell(position, MortonKey::none());
+    }
+
+    fn extract_vertex_with_cell(&mut self, position: Vec3, cell: MortonKey) {
+        debug_assert!(self.map.cells.last().is_none_or(|&last| last <= cell));
+        self.map.cells.push(cell);
+        self.map.positions.push(position);
+        self.inner.extract_vertex_with_cell(position, cell);
+    }
+
+    fn extract_vertex_with_normal(&mut self, position: Vec3, normal: Vec3, cell: MortonKey) {
+        debug_assert!(self.map.cells.last().is_none_or(|&last| last <= cell));
+        self.map.cells.push(cell);
+        self.map.positions.push(position);
+        self.inner
+            .extract_vertex_with_normal(position, normal, cell);
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        self.inner.extract_face(face);
+    }
+
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        self.map.cells.reserve(vertices);
+        self.map.positions.reserve(vertices);
+        self.inner.reserve(vertices, faces);
+    }
+
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        vertices * size_of::<(MortonKey, Vec3)>() + self.inner.reserve_hint(vertices, faces)
+    }
+
+    fn should_continue(&self) -> bool {
+        self.inner.should_continue()
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        self.inner.finish()
+    }
+}
+
+/// An extractor only counting the vertices and faces, e.g., to reserve
+/// buffers of the right size before extracting again, as extractions are
+/// deterministic.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct CountingExtractor {
+    pub vertex_count: usize,
+    pub face_count: usize,
+}
+
+impl Extractor for CountingExtractor {
+    fn extract_vertex(&mut self, _position: Vec3) {
+        self.vertex_count += 1;
+    }
+
+    fn extract_face(&mut self, _face: [u32; 3]) {
+        self.face_count += 1;
+    }
+}
+
+impl ParallelExtractor for CountingExtractor {
+    type Local = CountingExtractor;
+
+    fn make_local(&self) -> Self::Local {
+        CountingExtractor::default()
+    }
+
+    fn merge(&mut self, local: Self::Local, _vertex_offset: u32) {
+        self.vertex_count += local.vertex_count;
+        self.face_count += local.face_count;
+    }
+}
+
+/// Wraps an extractor, accumulating the surface area and the signed volume
+/// of the mesh as its faces are extracted.
+///
+/// Extract into `&mut MeshMetrics` to read the results afterwards. Like
+/// [`validate::signed_volume`], the volume sums the signed volumes of the
+/// tetrahedra formed by the origin and every face, so it's only meaningful
+/// for closed meshes wound consistently, which can be checked with
+/// [`validate::boundary_edges`]. The positions of the vertices are retained
+/// for that purpose.
+pub struct MeshMetrics<E> {
+    inner: E,
+    positions: Vec<Vec3>,
+    surface_area: f64,
+    signed_volume: f64,
+}
+
+impl<E: Extractor> MeshMetrics<E> {
+    pub fn new(inner: E) -> Self {
         Self {
-            buf: buffer,
-            source,
+            inner,
+            positions: Vec::new(),
+            surface_area: 0.0,
+            signed_volume: 0.0,
         }
     }
-}
 
-impl<S: HermiteSource> WithIndexedSeparateNormals<'_, S> {
-    fn average_vertex_normal(&self, face: [u32; 3]) -> Vec3 {
-        let normals = &self.buf.vertices.normals;
-        face.map(|i| normals[i as usize]).iter().sum::<Vec3>() / 3.0
+    /// Returns the total area of the faces extracted so far.
+    pub fn surface_area(&self) -> f64 {
+        self.surface_area
     }
 
-    fn face_plane_normal(&self, face: [u32; 3]) -> Vec3 {
-        plane_normal(&face.map(|i| self.buf.vertices.positions[i as usize]))
+    /// Returns the volume enclosed by the faces extracted so far, positive if
+    /// they face outward.
+    pub fn signed_volume(&self) -> f64 {
+        self.signed_volume
+    }
+
+    pub fn into_inner(self) -> E {
+        self.inner
     }
 }
 
-impl<S: HermiteSource> Extractor for WithIndexedSeparateNormals<'_, S> {
+impl<E: Extractor> Extractor for MeshMetrics<E> {
     fn extract_vertex(&mut self, position: Vec3) {
-        self.buf.vertices.positions.push(position);
-        let normal = self.source.sample_normal(position);
-        self.buf.vertices.normals.push(normal);
+        self.positions.push(position);
+        self.inner.extract_vertex(position);
+    }
+
+    fn extract_vertex_with_cell(&mut self, position: Vec3, cell: MortonKey) {
+        self.positions.push(position);
+        self.inner.extract_vertex_with_cell(position, cell);
+    }
+
+    fn extract_vertex_with_normal(&mut self, position: Vec3, normal: Vec3, cell: MortonKey) {
+        self.positions.push(position);
+        self.inner
+            .extract_vertex_with_normal(position, normal, cell);
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        let triangle = face.map(|i| self.positions[i as usize]);
+        self.surface_area += validate::triangle_area(triangle);
+        self.signed_volume += validate::tetrahedron_volume(triangle);
+        self.inner.extract_face(face);
+    }
+
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        self.positions.reserve(vertices);
+        self.inner.reserve(vertices, faces);
+    }
+
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        vertices * size_of::<Vec3>() + self.inner.reserve_hint(vertices, faces)
+    }
+
+    fn should_continue(&self) -> bool {
+        self.inner.should_continue()
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        self.inner.finish()
+    }
+}
+
+/// The order of a face's vertices when looking at its front side, the outer
+/// one unless the [`Orientation`] is inward.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Winding {
+    /// Counter-clockwise, as in OpenGL and Vulkan.
+    #[default]
+    Ccw,
+    /// Clockwise, as in Direct3D.
+    Cw,
+}
+
+/// Which side of the surface the front sides of faces and the normals face.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Orientation {
+    /// Towards the outside of the surface.
+    #[default]
+    Outward,
+    /// Towards the inside of the surface, e.g., to mesh a cavity or the
+    /// complement of a solid. Positions are the same either way.
+    Inward,
+}
+
+/// Coordinate system and winding conventions of the extracted mesh.
+///
+/// Winding is measured in the output coordinate system using the right-hand
+/// rule, so flipping an axis, which mirrors the mesh, also reverses the order
+/// of face vertices to keep the front sides facing the same way.
+///
+/// An [`Orientation::Inward`] mesh is obtained by negating the normals as
+/// they're sampled, and faces are then oriented against these like any
+/// others, rather than by reversing the faces afterwards. The diagonals
+/// quads are split along don't depend on the orientation.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct OutputConvention {
+    pub winding: Winding,
+    /// Axis to negate positions and normals along, if any.
+    pub flip_axis: Option<AxisKind>,
+    pub orientation: Orientation,
+}
+
+impl OutputConvention {
+    pub const fn new(winding: Winding, flip_axis: Option<AxisKind>) -> Self {
+        Self {
+            winding,
+            flip_axis,
+            orientation: Orientation::Outward,
+        }
+    }
+
+    pub const fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Maps a position or a normal to the output coordinate system.
+    ///
+    /// Mirroring is its own inverse transpose, so normals are mapped the same
+    /// way as positions.
+    pub fn transform(&self, v: Vec3) -> Vec3 {
+        match self.flip_axis {
+            Some(axis) => {
+                let mut v = v;
+                v[axis as usize] = -v[axis as usize];
+                v
+            }
+            None => v,
+        }
+    }
+
+    /// Maps a normal of the source, which points outward, to the output
+    /// coordinate system and orientation.
+    pub fn transform_normal(&self, normal: Vec3) -> Vec3 {
+        match self.orientation {
+            Orientation::Outward => self.transform(normal),
+            Orientation::Inward => -self.transform(normal),
+        }
+    }
+
+    /// Reorders the vertices of a face wound counter-clockwise in the output
+    /// coordinate system to match the winding convention.
+    pub fn wind(&self, mut face: [u32; 3]) -> [u32; 3] {
+        if self.winding == Winding::Cw {
+            face.reverse();
+        }
+
+        face
+    }
+}
+
+/// Vertex positions along with their normals.
+///
+/// Both vectors must have the same length, one normal per position. Prefer
+/// [`push`] over pushing to the vectors separately.
+///
+/// [`push`]: SeparateNormals::push
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeparateNormals {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+}
+
+impl SeparateNormals {
+    /// Adds a vertex.
+    pub fn push(&mut self, position: Vec3, normal: Vec3) {
+        debug_assert!(self.is_consistent());
+        self.positions.push(position);
+        self.normals.push(normal);
+    }
+
+    /// Returns whether there are as many normals as positions.
+    pub fn is_consistent(&self) -> bool {
+        self.positions.len() == self.normals.len()
+    }
+
+    fn average_vertex_normal(&self, face: [u32; 3]) -> Vec3 {
+        face.map(|i| self.normals[i as usize]).iter().sum::<Vec3>() / 3.0
+    }
+
+    fn face_plane_normal(&self, face: [u32; 3]) -> Vec3 {
+        plane_normal(&face.map(|i| self.positions[i as usize]))
     }
 
-    fn extract_face(&mut self, mut face: [u32; 3]) {
+    /// Reorders the vertices of a face so that it's counter-clockwise when
+    /// looking against the vertex normals.
+    pub(crate) fn orient(&self, mut face: [u32; 3]) -> [u32; 3] {
+        debug_assert!(self.is_consistent());
         let normal = self.average_vertex_normal(face);
 
         if normal.dot(self.face_plane_normal(face)) < 0.0 {
             face.reverse();
         }
 
-        self.buf.faces.push(face);
+        face
     }
 }
 
-fn plane_normal(points: &[Vec3; 3]) -> Vec3 {
-    (points[1] - points[0]).cross(points[2] - points[1])
+/// How vertex normals are computed.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum NormalMode {
+    /// Sample the normal of the source at every vertex.
+    #[default]
+    SampledFromSource,
+    /// Average the normals of the faces around every vertex, weighted by the
+    /// angles of the faces at the vertex.
+    AngleWeightedFromFaces,
+    /// Average the normals of the faces around every vertex, weighted by the
+    /// areas of the faces.
+    AreaWeightedFromFaces,
+    /// Average the normals at the intersections of the surface with the
+    /// edges of the cell of every vertex, i.e., the Hermite data its position
+    /// was solved from, without sampling the source again.
+    ///
+    /// These agree with [`NormalMode::SampledFromSource`] on smooth
+    /// surfaces, and are smoother where the gradient of the source is noisy,
+    /// e.g., that of a [`GridSource`], as every normal averages up to a dozen
+    /// samples. Vertices extracted without their normal, i.e., by
+    /// [`Extractor::extract_vertex`], sample the source instead.
+    ///
+    /// [`GridSource`]: crate::GridSource
+    AveragedFromHermiteData,
+}
+
+impl NormalMode {
+    /// Returns whether vertex normals are only known once all faces are.
+    pub(crate) fn is_from_faces(self) -> bool {
+        matches!(
+            self,
+            NormalMode::AngleWeightedFromFaces | NormalMode::AreaWeightedFromFaces
+        )
+    }
+}
+
+/// The reason flat buffers don't form an [`IndexedSeparateNormals`], see
+/// [`IndexedSeparateNormals::from_flat`].
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum FlatMeshError {
+    /// A buffer's length isn't a multiple of 3.
+    #[error("the {buffer} buffer has {len} elements, which isn't a multiple of 3")]
+    NotTriples { buffer: &'static str, len: usize },
+    /// There aren't as many normals as positions.
+    #[error("there are {positions} positions but {normals} normals")]
+    MismatchedNormals { positions: usize, normals: usize },
+    /// An index doesn't refer to any vertex.
+    #[error("index {index} is out of range for {vertex_count} vertices")]
+    IndexOutOfRange { index: u32, vertex_count: usize },
+}
+
+#[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IndexedSeparateNormals {
+    pub vertices: SeparateNormals,
+    pub faces: Vec<[u32; 3]>,
+}
+
+impl IndexedSeparateNormals {
+    /// Creates a mesh from flat buffers, with three components per position
+    /// and normal, and three indices per face, e.g., as passed through an FFI
+    /// boundary.
+    pub fn from_flat(
+        positions: Vec<f32>,
+        normals: Vec<f32>,
+        indices: Vec<u32>,
+    ) -> Result<Self, FlatMeshError> {
+        for (buffer, len) in [
+            ("positions", positions.len()),
+            ("normals", normals.len()),
+            ("indices", indices.len()),
+        ] {
+            if len % 3 != 0 {
+                return Err(FlatMeshError::NotTriples { buffer, len });
+            }
+        }
+
+        if positions.len() != normals.len() {
+            return Err(FlatMeshError::MismatchedNormals {
+                positions: positions.len() / 3,
+                normals: normals.len() / 3,
+            });
+        }
+
+        let vertex_count = positions.len() / 3;
+
+        if let Some(&index) = indices.iter().find(|&&i| i as usize >= vertex_count) {
+            return Err(FlatMeshError::IndexOutOfRange {
+                index,
+                vertex_count,
+            });
+        }
+
+        let to_vec3 = |flat: Vec<f32>| flat.chunks_exact(3).map(Vec3::from_slice).collect();
+
+        Ok(Self {
+            vertices: SeparateNormals {
+                positions: to_vec3(positions),
+                normals: to_vec3(normals),
+            },
+            faces: bytemuck::cast_slice(&indices).to_vec(),
+        })
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.vertices.positions
+    }
+
+    pub fn normals(&self) -> &[Vec3] {
+        &self.vertices.normals
+    }
+
+    /// Returns the components of all positions, three per vertex, without
+    /// copying.
+    pub fn positions_flat(&self) -> &[f32] {
+        bytemuck::cast_slice(&self.vertices.positions)
+    }
+
+    /// Returns the components of all normals, three per vertex, without
+    /// copying.
+    pub fn normals_flat(&self) -> &[f32] {
+        bytemuck::cast_slice(&self.vertices.normals)
+    }
+
+    /// Returns the vertex indices of all faces, three per face.
+    pub fn indices(&self) -> &[u32] {
+        self.faces.as_flattened()
+    }
+
+    /// Removes all vertices and faces, keeping the allocated capacity, so
+    /// that the mesh can be reused for another extraction.
+    pub fn reset(&mut self) {
+        self.vertices.positions.clear();
+        self.vertices.normals.clear();
+        self.faces.clear();
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    /// Returns the total area of the faces, see [`validate::surface_area`].
+    pub fn surface_area(&self) -> f64 {
+        validate::surface_area(self.positions(), &self.faces)
+    }
+
+    /// Returns the enclosed volume, see [`validate::signed_volume`].
+    pub fn signed_volume(&self) -> Result<f64, MeasureError> {
+        validate::signed_volume(self.positions(), &self.faces)
+    }
+
+    /// Returns the centroid of the surface, see [`validate::centroid`].
+    pub fn centroid(&self) -> Vec3 {
+        validate::centroid(self.positions(), &self.faces)
+    }
+
+    /// Returns the position and the normal of every vertex of every face.
+    pub fn triangles(&self) -> impl ExactSizeIterator<Item = [(Vec3, Vec3); 3]> + '_ {
+        debug_assert!(self.vertices.is_consistent());
+        self.faces.iter().map(|face| {
+            face.map(|i| {
+                (
+                    self.vertices.positions[i as usize],
+                    self.vertices.normals[i as usize],
+                )
+            })
+        })
+    }
+
+    /// Returns the minimum and maximum corners of the bounding box of all
+    /// positions, or `None` if there are none.
+    pub fn aabb(&self) -> Option<(Vec3, Vec3)> {
+        let (first, rest) = self.vertices.positions.split_first()?;
+
+        Some(
+            rest.iter()
+                .fold((*first, *first), |(min, max), p| (min.min(*p), max.max(*p))),
+        )
+    }
+
+    /// Maps every position through `affine`, and every normal through its
+    /// inverse transpose.
+    ///
+    /// If `affine` mirrors the mesh, i.e., its determinant is negative, faces
+    /// are reversed to keep their front sides facing outward.
+    pub fn transform(&mut self, affine: Affine3A) {
+        let normal_matrix = affine.matrix3.inverse().transpose();
+
+        for position in &mut self.vertices.positions {
+            *position = affine.transform_point3(*position);
+        }
+
+        for normal in &mut self.vertices.normals {
+            *normal = normal_matrix
+                .mul_vec3a((*normal).into())
+                .normalize_or_zero()
+                .into();
+        }
+
+        if affine.matrix3.determinant() < 0.0 {
+            for face in &mut self.faces {
+                face.reverse();
+            }
+        }
+    }
+
+    /// Removes the faces whose area is at most `area_epsilon`, e.g., slivers
+    /// left by [`weld`] or by vertices placed on the same point, returning
+    /// how many were removed.
+    ///
+    /// Vertices are kept, see [`remove_unreferenced_vertices`] to drop those
+    /// no longer used by any face.
+    ///
+    /// [`weld`]: IndexedSeparateNormals::weld
+    /// [`remove_unreferenced_vertices`]: IndexedSeparateNormals::remove_unreferenced_vertices
+    pub fn remove_degenerate(&mut self, area_epsilon: f32) -> usize {
+        let positions = &self.vertices.positions;
+        let count = self.faces.len();
+
+        self.faces.retain(|face| {
+            let triangle = face.map(|i| positions[i as usize]);
+            validate::triangle_area(triangle) > area_epsilon as f64
+        });
+
+        count - self.faces.len()
+    }
+
+    /// Removes the vertices not referenced by any face, keeping the order of
+    /// the others and remapping the faces, and returns how many were removed.
+    pub fn remove_unreferenced_vertices(&mut self) -> usize {
+        debug_assert!(self.vertices.is_consistent());
+        let count = self.vertices.positions.len();
+        let mut remap = vec![u32::MAX; count];
+
+        for &i in self.faces.as_flattened() {
+            remap[i as usize] = 0;
+        }
+
+        let mut kept = 0;
+        for (i, index) in remap.iter_mut().enumerate() {
+            if *index == 0 {
+                *index = kept;
+                self.vertices.positions[kept as usize] = self.vertices.positions[i];
+                self.vertices.normals[kept as usize] = self.vertices.normals[i];
+                kept += 1;
+            }
+        }
+
+        self.vertices.positions.truncate(kept as usize);
+        self.vertices.normals.truncate(kept as usize);
+
+        for face in &mut self.faces {
+            *face = face.map(|i| remap[i as usize]);
+        }
+
+        count - kept as usize
+    }
+
+    /// Recomputes the normals from the faces around every vertex, weighted by
+    /// their angles, see [`NormalMode::AngleWeightedFromFaces`]. Vertices
+    /// without faces, or whose faces cancel out, keep their normals.
+    pub(crate) fn recompute_normals(&mut self) {
+        let vertices = &mut self.vertices;
+        let mut normals = vec![Vec3::ZERO; vertices.positions.len()];
+        add_face_normals(
+            &vertices.positions,
+            &mut normals,
+            &self.faces,
+            NormalMode::AngleWeightedFromFaces,
+        );
+
+        for (normal, sum) in vertices.normals.iter_mut().zip(normals) {
+            *normal = sum.try_normalize().unwrap_or(*normal);
+        }
+    }
+
+    /// Merges vertices closer than `tolerance` to each other, e.g., to close
+    /// seams between meshes extracted separately.
+    ///
+    /// Every vertex is merged into the closest of the preceding vertices kept
+    /// within `tolerance`, if any, which keeps its position. The normals of
+    /// merged vertices are averaged. Faces are remapped to the kept vertices,
+    /// and those left with fewer than three distinct vertices are removed.
+    ///
+    /// # Panics
+    ///
+    /// If `tolerance` isn't finite and greater than 0.
+    pub fn weld(&mut self, tolerance: f32) {
+        assert!(
+            tolerance.is_finite() && tolerance > 0.0,
+            "`tolerance` must be finite and greater than 0"
+        );
+        debug_assert!(self.vertices.is_consistent());
+
+        // Kept vertices are bucketed in a grid of `tolerance`-sized cells, so
+        // only the 27 cells around a vertex can hold vertices close to it.
+        let cell = |p: Vec3| (p / tolerance).floor().as_ivec3();
+        let mut grid: Map<[i32; 3], Vec<u32>> = Map::default();
+        let mut kept = SeparateNormals::default();
+        let mut merged = Vec::new();
+        let mut remap = Vec::with_capacity(self.vertices.positions.len());
+
+        for (&position, &normal) in self.vertices.positions.iter().zip(&self.vertices.normals) {
+            let center = cell(position);
+            let closest = (0..27)
+                .map(|i| center + IVec3::new(i % 3, i / 3 % 3, i / 9) - 1)
+                .filter_map(|neighbor| grid.get(&neighbor.to_array()))
+                .flatten()
+                .map(|&i| (i, kept.positions[i as usize].distance_squared(position)))
+                .filter(|&(_, distance)| distance <= tolerance * tolerance)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+
+            let index = match closest {
+                Some((index, _)) => {
+                    kept.normals[index as usize] += normal;
+                    merged[index as usize] = true;
+                    index
+                }
+                None => {
+                    let index = kept.positions.len() as u32;
+                    kept.push(position, normal);
+                    merged.push(false);
+                    grid.entry(center.to_array()).or_default().push(index);
+                    index
+                }
+            };
+
+            remap.push(index);
+        }
+
+        for (normal, _) in kept.normals.iter_mut().zip(merged).filter(|(_, m)| *m) {
+            *normal = normal.normalize_or_zero();
+        }
+
+        self.vertices = kept;
+        self.faces.retain_mut(|face| {
+            *face = face.map(|i| remap[i as usize]);
+            face[0] != face[1] && face[1] != face[2] && face[2] != face[0]
+        });
+    }
+}
+
+pub struct WithIndexedSeparateNormals<'a, S> {
+    buf: &'a mut IndexedSeparateNormals,
+    source: S,
+    convention: OutputConvention,
+    normal_mode: NormalMode,
+    /// Number of vertices and faces in the buffer before the extraction.
+    offsets: (usize, usize),
+}
+
+impl<'a, S> WithIndexedSeparateNormals<'a, S> {
+    pub fn new(buffer: &'a mut IndexedSeparateNormals, source: S) -> Self {
+        Self::new_with(buffer, source, OutputConvention::default())
+    }
+
+    pub fn new_with(
+        buffer: &'a mut IndexedSeparateNormals,
+        source: S,
+        convention: OutputConvention,
+    ) -> Self {
+        let offsets = (buffer.vertices.positions.len(), buffer.faces.len());
+
+        Self {
+            buf: buffer,
+            source,
+            convention,
+            normal_mode: NormalMode::default(),
+            offsets,
+        }
+    }
+
+    /// Returns the mesh extracted into, along with the number of vertices and
+    /// faces it had before the extraction.
+    pub(crate) fn buffer(&mut self) -> (&mut IndexedSeparateNormals, (usize, usize)) {
+        (self.buf, self.offsets)
+    }
+
+    /// Sets how vertex normals are computed.
+    ///
+    /// Normals sampled at the vertices can be off when a vertex is clamped
+    /// away from the surface, or when the gradient of the source is noisy.
+    /// Computing them from the faces instead avoids that, at the cost of
+    /// sampling one reference normal per face to orient it, and averaging
+    /// them from the Hermite data of the cells smooths the noise out without
+    /// sampling the source at all.
+    pub fn with_normal_mode(mut self, normal_mode: NormalMode) -> Self {
+        self.normal_mode = normal_mode;
+        self
+    }
+}
+
+impl<S: HermiteSource> WithIndexedSeparateNormals<'_, S> {
+    fn push_vertex(&mut self, position: Vec3, normal: Vec3) {
+        let convention = &self.convention;
+        self.buf.vertices.push(
+            convention.transform(position),
+            convention.transform_normal(normal),
+        );
+    }
+}
+
+impl<S: HermiteSource> Extractor for WithIndexedSeparateNormals<'_, S> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        // Normals computed from the faces are filled in by `finish`.
+        let normal = if self.normal_mode.is_from_faces() {
+            Vec3::ZERO
+        } else {
+            self.source.sample_normal(position)
+        };
+
+        self.push_vertex(position, normal);
+    }
+
+    fn extract_vertex_with_normal(&mut self, position: Vec3, normal: Vec3, cell: MortonKey) {
+        if self.normal_mode == NormalMode::AveragedFromHermiteData {
+            self.push_vertex(position, normal);
+        } else {
+            self.extract_vertex_with_cell(position, cell);
+        }
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        // Faces index the vertices of this extraction, which follow those
+        // already in the buffer.
+        let mut face = face.map(|i| i + self.offsets.0 as u32);
+
+        // Both the normals and the positions are already in the output
+        // coordinate system, so this makes the face counter-clockwise there.
+        if !self.normal_mode.is_from_faces() {
+            face = self.buf.vertices.orient(face);
+        } else {
+            // Vertex normals aren't known yet, so orient the face against the
+            // normal sampled at its centroid instead. The transform is its own
+            // inverse, which maps the centroid back to the source.
+            let points = face.map(|i| self.buf.vertices.positions[i as usize]);
+            let centroid = points.iter().sum::<Vec3>() / 3.0;
+            let convention = &self.convention;
+            let reference = convention
+                .transform_normal(self.source.sample_normal(convention.transform(centroid)));
+
+            if reference.dot(plane_normal(&points)) < 0.0 {
+                face.reverse();
+            }
+        }
+
+        self.buf.faces.push(self.convention.wind(face));
+    }
+
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        self.buf.vertices.positions.reserve(vertices);
+        self.buf.vertices.normals.reserve(vertices);
+        self.buf.faces.reserve(faces);
+    }
+
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        mesh_size(vertices, faces)
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        if !self.normal_mode.is_from_faces() {
+            return Ok(());
+        }
+
+        let (vertex_offset, face_offset) = self.offsets;
+        let vertices = &mut self.buf.vertices;
+
+        // Undo the winding convention to get normals facing the same side as
+        // the faces.
+        let convention = &self.convention;
+        let faces = self.buf.faces[face_offset..]
+            .iter()
+            .map(|face| convention.wind(*face));
+        add_face_normals(
+            &vertices.positions,
+            &mut vertices.normals,
+            faces,
+            self.normal_mode,
+        );
+
+        // Vertices without faces, or whose faces cancel out, fall back to the
+        // normal of the source.
+        for (position, normal) in vertices.positions[vertex_offset..]
+            .iter()
+            .zip(&mut vertices.normals[vertex_offset..])
+        {
+            *normal = normal.try_normalize().unwrap_or_else(|| {
+                convention
+                    .transform_normal(self.source.sample_normal(convention.transform(*position)))
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: HermiteSource + Clone> ParallelExtractor for WithIndexedSeparateNormals<'_, S> {
+    type Local = LocalIndexedSeparateNormals<S>;
+
+    fn make_local(&self) -> Self::Local {
+        LocalIndexedSeparateNormals {
+            mesh: IndexedSeparateNormals::default(),
+            source: self.source.clone(),
+            convention: self.convention,
+            normal_mode: self.normal_mode,
+        }
+    }
+
+    fn merge(&mut self, local: Self::Local, vertex_offset: u32) {
+        let LocalIndexedSeparateNormals { mesh, .. } = local;
+        let vertex_offset = vertex_offset + self.offsets.0 as u32;
+
+        self.buf.vertices.positions.extend(mesh.vertices.positions);
+        self.buf.vertices.normals.extend(mesh.vertices.normals);
+        self.buf.faces.extend(
+            mesh.faces
+                .into_iter()
+                .map(|face| face.map(|i| i + vertex_offset)),
+        );
+    }
+}
+
+/// The local extractor of [`WithIndexedSeparateNormals`], extracting into a
+/// mesh of its own with the same options.
+///
+/// Normals computed from the faces only account for the faces of the local
+/// mesh.
+pub struct LocalIndexedSeparateNormals<S> {
+    mesh: IndexedSeparateNormals,
+    source: S,
+    convention: OutputConvention,
+    normal_mode: NormalMode,
+}
+
+impl<S> LocalIndexedSeparateNormals<S> {
+    fn with(&mut self) -> WithIndexedSeparateNormals<'_, &S> {
+        WithIndexedSeparateNormals {
+            buf: &mut self.mesh,
+            source: &self.source,
+            convention: self.convention,
+            normal_mode: self.normal_mode,
+            offsets: (0, 0),
+        }
+    }
+}
+
+impl<S: HermiteSource> Extractor for LocalIndexedSeparateNormals<S> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        self.with().extract_vertex(position);
+    }
+
+    fn extract_vertex_with_normal(&mut self, position: Vec3, normal: Vec3, cell: MortonKey) {
+        self.with()
+            .extract_vertex_with_normal(position, normal, cell);
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        self.with().extract_face(face);
+    }
+
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        self.with().reserve(vertices, faces);
+    }
+
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        mesh_size(vertices, faces)
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        self.with().finish()
+    }
+}
+
+/// Returns the bytes an [`IndexedSeparateNormals`] takes for the given
+/// numbers of vertices and faces.
+fn mesh_size(vertices: usize, faces: usize) -> usize {
+    vertices * 2 * size_of::<Vec3>() + faces * size_of::<[u32; 3]>()
+}
+
+/// Adds the normals of `faces` to the `normals` of their vertices, weighted
+/// as by `mode`, which computes normals from the faces.
+pub(crate) fn add_face_normals(
+    positions: &[Vec3],
+    normals: &mut [Vec3],
+    faces: impl IntoIterator<Item = impl Borrow<[u32; 3]>>,
+    mode: NormalMode,
+) {
+    debug_assert!(mode.is_from_faces());
+
+    for face in faces {
+        let face = *face.borrow();
+        let points = face.map(|i| positions[i as usize]);
+
+        // Twice the area, along the normal.
+        let normal = plane_normal(&points);
+
+        for (k, &i) in face.iter().enumerate() {
+            let weight = match mode {
+                NormalMode::AngleWeightedFromFaces => {
+                    let p = points[k];
+                    let (u, v) = (points[(k + 1) % 3] - p, points[(k + 2) % 3] - p);
+                    u.angle_between(v) * normal.normalize_or_zero()
+                }
+                _ => normal,
+            };
+
+            if weight.is_finite() {
+                normals[i as usize] += weight;
+            }
+        }
+    }
+}
+
+pub(crate) fn plane_normal(points: &[Vec3; 3]) -> Vec3 {
+    (points[1] - points[0]).cross(points[2] - points[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{hermite_from_fn, HermiteSource, Source};
+    use crate::{Bounds, DualContouring, QuadSplit};
+    use glam::{Quat, UVec3};
+
+    const RADIUS: f32 = 0.7;
+
+    fn extract_sphere(convention: OutputConvention) -> IndexedSeparateNormals {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+        let mut mesh = IndexedSeparateNormals::default();
+        let extractor = WithIndexedSeparateNormals::new_with(&mut mesh, &sphere, convention);
+        DualContouring::new(&sphere, 16, 1e-4)
+            .extract(extractor)
+            .unwrap();
+        mesh
+    }
+
+    #[test]
+    fn counting_matches_extraction() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-4);
+
+        let mut counts = CountingExtractor::default();
+        dc.extract(&mut counts).unwrap();
+        let mut merged = CountingExtractor::default();
+        dc.extract(SerialExtractor::new(&mut merged)).unwrap();
+        assert_eq!(merged, counts);
+
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+        assert_eq!(mesh.positions().len(), counts.vertex_count);
+        assert_eq!(mesh.faces.len(), counts.face_count);
+    }
+
+    #[test]
+    fn mesh_metrics_match_post_processing() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+        let mut mesh = IndexedSeparateNormals::default();
+        let mut metrics = MeshMetrics::new(WithIndexedSeparateNormals::new(&mut mesh, &sphere));
+        DualContouring::new(&sphere, 16, 1e-4)
+            .extract(&mut metrics)
+            .unwrap();
+
+        let (area, volume) = (metrics.surface_area(), metrics.signed_volume());
+        drop(metrics);
+        assert!(volume > 0.0);
+        assert!((volume - mesh.signed_volume().unwrap()).abs() < 1e-9);
+        assert!((area - mesh.surface_area()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sphere_is_closed_and_outward() {
+        let mesh = extract_sphere(OutputConvention::default());
+        assert!(!mesh.faces.is_empty());
+
+        for position in &mesh.vertices.positions {
+            assert!((position.length() - RADIUS).abs() < 0.05);
+        }
+
+        for face in &mesh.faces {
+            let points = face.map(|i| mesh.vertices.positions[i as usize]);
+            let centroid = points.iter().sum::<Vec3>() / 3.0;
+            assert!(plane_normal(&points).dot(centroid) > 0.0);
+        }
+
+        // Every directed edge must be matched by exactly one opposite edge.
+        let mut edges = crate::collections::Map::new();
+
+        for face in &mesh.faces {
+            for i in 0..3 {
+                *edges.entry((face[i], face[(i + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+
+        for (&(a, b), &count) in &edges {
+            assert_eq!(count, 1);
+            assert_eq!(edges.get(&(b, a)), Some(&1));
+        }
+    }
+
+    #[test]
+    fn cw_is_reversed_ccw() {
+        let ccw = extract_sphere(OutputConvention::new(Winding::Ccw, None));
+        let mut cw = extract_sphere(OutputConvention::new(Winding::Cw, None));
+
+        for face in &mut cw.faces {
+            face.reverse();
+        }
+
+        assert_eq!(cw.vertices.positions, ccw.vertices.positions);
+        assert_eq!(cw.vertices.normals, ccw.vertices.normals);
+        assert_eq!(cw.faces, ccw.faces);
+    }
+
+    #[test]
+    fn inward_is_reversed_outward() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-4).with_quad_split(QuadSplit::AlignWithNormal);
+
+        for normal_mode in [
+            NormalMode::SampledFromSource,
+            NormalMode::AngleWeightedFromFaces,
+            NormalMode::AreaWeightedFromFaces,
+        ] {
+            let extract = |orientation| {
+                let convention = OutputConvention::default().with_orientation(orientation);
+                let mut mesh = IndexedSeparateNormals::default();
+                dc.extract(
+                    WithIndexedSeparateNormals::new_with(&mut mesh, &sphere, convention)
+                        .with_normal_mode(normal_mode),
+                )
+                .unwrap();
+                mesh
+            };
+
+            let outward = extract(Orientation::Outward);
+            let mut inward = extract(Orientation::Inward);
+
+            for face in &mut inward.faces {
+                face.reverse();
+            }
+
+            assert_eq!(inward.positions(), outward.positions());
+            assert_eq!(inward.faces, outward.faces);
+
+            for (p, (a, b)) in inward
+                .positions()
+                .iter()
+                .zip(inward.normals().iter().zip(outward.normals()))
+            {
+                assert!(a.abs_diff_eq(-*b, 1e-5), "{a} {b}");
+                assert!(a.dot(*p) < 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn flipped_axis_keeps_normals_outward() {
+        let mesh = extract_sphere(OutputConvention::new(Winding::Ccw, Some(AxisKind::Z)));
+        let vertices = &mesh.vertices;
+
+        for (position, normal) in vertices.positions.iter().zip(&vertices.normals) {
+            assert!(normal.dot(*position) > 0.0);
+        }
+
+        for face in &mesh.faces {
+            let points = face.map(|i| vertices.positions[i as usize]);
+            let centroid = points.iter().sum::<Vec3>() / 3.0;
+            assert!(plane_normal(&points).dot(centroid) > 0.0);
+        }
+    }
+
+    #[test]
+    fn face_normals_ignore_noisy_gradient() {
+        // A sphere the gradient of which is perturbed by high-frequency noise,
+        // as with a gradient estimated from a noisy volume.
+        let noise = |p: Vec3| {
+            let p = p * Vec3::new(97.0, 89.0, 83.0);
+            Vec3::new(p.y.sin(), p.z.sin(), p.x.sin())
+        };
+        let sphere = hermite_from_fn(
+            |p: Vec3| p.length() - RADIUS,
+            |p| p.normalize() + 0.3 * noise(p),
+        );
+
+        let mean_error = |mode| {
+            let mut mesh = IndexedSeparateNormals::default();
+            let extractor =
+                WithIndexedSeparateNormals::new(&mut mesh, &sphere).with_normal_mode(mode);
+            DualContouring::new(&sphere, 32, 1e-4)
+                .extract(extractor)
+                .unwrap();
+
+            let vertices = &mesh.vertices;
+            vertices
+                .positions
+                .iter()
+                .zip(&vertices.normals)
+                .map(|(p, n)| p.normalize().angle_between(*n))
+                .sum::<f32>()
+                / vertices.positions.len() as f32
+        };
+
+        let sampled = mean_error(NormalMode::SampledFromSource);
+        let angle_weighted = mean_error(NormalMode::AngleWeightedFromFaces);
+        let area_weighted = mean_error(NormalMode::AreaWeightedFromFaces);
+
+        assert!(
+            angle_weighted < sampled / 2.0,
+            "{angle_weighted} vs. {sampled}"
+        );
+        assert!(
+            area_weighted < sampled / 2.0,
+            "{area_weighted} vs. {sampled}"
+        );
+    }
+
+    /// A source counting the normals sampled from it.
+    struct CountingNormals<S> {
+        inner: S,
+        normals: core::cell::Cell<usize>,
+    }
+
+    impl<S> CountingNormals<S> {
+        fn new(inner: S) -> Self {
+            let normals = Default::default();
+            Self { inner, normals }
+        }
+
+        fn take(&self) -> usize {
+            self.normals.take()
+        }
+    }
+
+    impl<S: Source> Source for CountingNormals<S> {
+        fn sample(&self, point: Vec3) -> f32 {
+            self.inner.sample(point)
+        }
+    }
+
+    impl<S: HermiteSource> HermiteSource for CountingNormals<S> {
+        fn sample_normal(&self, point: Vec3) -> Vec3 {
+            self.normals.set(self.normals.get() + 1);
+            self.inner.sample_normal(point)
+        }
+    }
+
+    fn extract_with_normals(
+        dc: &DualContouring<impl HermiteSource>,
+        source: impl HermiteSource,
+        mode: NormalMode,
+    ) -> IndexedSeparateNormals {
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, source).with_normal_mode(mode))
+            .unwrap();
+        mesh
+    }
+
+    #[test]
+    fn hermite_normals_skip_sampling() {
+        let sphere = CountingNormals::new(hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p));
+        let dc = DualContouring::new(&sphere, 32, 1e-4);
+
+        // Only the intersections are sampled, as by an extractor ignoring
+        // normals.
+        dc.extract(CountingExtractor::default()).unwrap();
+        let intersections = sphere.take();
+
+        let averaged = extract_with_normals(&dc, &sphere, NormalMode::AveragedFromHermiteData);
+        assert_eq!(sphere.take(), intersections);
+
+        let sampled = extract_with_normals(&dc, &sphere, NormalMode::SampledFromSource);
+        assert_eq!(sphere.take(), intersections + sampled.positions().len());
+
+        assert_eq!(averaged.positions(), sampled.positions());
+        assert_eq!(averaged.faces, sampled.faces);
+
+        for (a, b) in averaged.normals().iter().zip(sampled.normals()) {
+            assert!(a.is_normalized());
+            assert!(a.angle_between(*b) < 2f32.to_radians(), "{a} vs. {b}");
+        }
+    }
+
+    #[test]
+    fn hermite_normals_smooth_grid() {
+        // A sphere baked into a coarse grid, the interpolated gradient of
+        // which jumps across voxels.
+        let bounds = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let dims = UVec3::splat(12);
+        let spacing = bounds.size() / (dims - 1).as_vec3();
+        let values = (0..dims.z)
+            .flat_map(|z| (0..dims.y).flat_map(move |y| (0..dims.x).map(move |x| (x, y, z))))
+            .map(|(x, y, z)| {
+                (bounds.min + spacing * UVec3::new(x, y, z).as_vec3()).length() - RADIUS
+            })
+            .collect();
+        let grid = crate::GridSource::new(dims, bounds, values);
+        let dc = DualContouring::new(&grid, 32, 1e-4);
+
+        // The mean squared angle between the normals of adjacent vertices.
+        let roughness = |mesh: &IndexedSeparateNormals| {
+            let normals = mesh.normals();
+            let edges = mesh
+                .faces
+                .iter()
+                .flat_map(|face| (0..3).map(|k| (face[k], face[(k + 1) % 3])));
+            let (sum, count) = edges.fold((0.0, 0), |(sum, count), (a, b)| {
+                let angle = normals[a as usize].angle_between(normals[b as usize]);
+                (sum + angle * angle, count + 1)
+            });
+            sum / count as f32
+        };
+
+        let sampled = roughness(&extract_with_normals(
+            &dc,
+            &grid,
+            NormalMode::SampledFromSource,
+        ));
+        let averaged = roughness(&extract_with_normals(
+            &dc,
+            &grid,
+            NormalMode::AveragedFromHermiteData,
+        ));
+        assert!(averaged < sampled, "{averaged} vs. {sampled}");
+    }
+
+    #[test]
+    fn mesh_accessors() {
+        let mesh = extract_sphere(OutputConvention::default());
+        assert_eq!(mesh.positions().len(), mesh.normals().len());
+        assert_eq!(mesh.indices().len(), 3 * mesh.triangle_count());
+        assert_eq!(mesh.triangles().len(), mesh.triangle_count());
+
+        let first = mesh.triangles().next().unwrap();
+        for (k, (position, normal)) in first.into_iter().enumerate() {
+            let i = mesh.indices()[k] as usize;
+            assert_eq!(position, mesh.positions()[i]);
+            assert_eq!(normal, mesh.normals()[i]);
+        }
+    }
+
+    #[test]
+    fn separate_normals_consistency() {
+        let mut vertices = SeparateNormals::default();
+        vertices.push(Vec3::X, Vec3::Y);
+        assert!(vertices.is_consistent());
+
+        vertices.normals.pop();
+        assert!(!vertices.is_consistent());
+    }
+
+    #[test]
+    fn transform_keeps_normals_outward() {
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        let scale = Vec3::new(2.0, 0.5, -1.0);
+
+        let mut mesh = extract_sphere(OutputConvention::default());
+        let original = mesh.positions().to_vec();
+        mesh.transform(Affine3A::from_scale_rotation_translation(
+            scale,
+            Quat::from_rotation_z(0.3),
+            center,
+        ));
+
+        assert_eq!(mesh.positions().len(), original.len());
+
+        for (position, normal) in mesh.positions().iter().zip(mesh.normals()) {
+            assert!((normal.length() - 1.0).abs() < 1e-5);
+            assert!(normal.dot(*position - center) > 0.0);
+        }
+
+        for face in &mesh.faces {
+            let points = face.map(|i| mesh.positions()[i as usize]);
+            let centroid = points.iter().sum::<Vec3>() / 3.0;
+            assert!(plane_normal(&points).dot(centroid - center) > 0.0);
+        }
+    }
+
+    #[test]
+    fn extracts_after_existing_vertices() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-4);
+
+        for mode in [
+            NormalMode::SampledFromSource,
+            NormalMode::AngleWeightedFromFaces,
+        ] {
+            let mut mesh = extract_sphere(OutputConvention::default());
+            let (offset, first_faces) = (mesh.positions().len(), mesh.faces.len());
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere).with_normal_mode(mode))
+                .unwrap();
+
+            assert_eq!(mesh.positions().len(), 2 * offset);
+            assert_eq!(mesh.faces.len(), 2 * first_faces);
+
+            for face in &mesh.faces[first_faces..] {
+                assert!(face.iter().all(|&i| i as usize >= offset), "{face:?}");
+
+                let points = face.map(|i| mesh.positions()[i as usize]);
+                let centroid = points.iter().sum::<Vec3>() / 3.0;
+                assert!(plane_normal(&points).dot(centroid) > 0.0);
+            }
+
+            for (p, n) in mesh.positions()[offset..]
+                .iter()
+                .zip(&mesh.normals()[offset..])
+            {
+                assert!(n.dot(p.normalize()) > 0.9, "{mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn sphere_aabb() {
+        assert_eq!(IndexedSeparateNormals::default().aabb(), None);
+
+        let mesh = extract_sphere(OutputConvention::default());
+        let (min, max) = mesh.aabb().unwrap();
+        assert!(min.abs_diff_eq(Vec3::splat(-RADIUS), 0.05));
+        assert!(max.abs_diff_eq(Vec3::splat(RADIUS), 0.05));
+    }
+
+    #[test]
+    fn vertex_cells() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-4);
+        let mut mesh = IndexedSeparateNormals::default();
+        let mut cells = Vec::new();
+        dc.extract(WithVertexCells::new(
+            WithIndexedSeparateNormals::new(&mut mesh, &sphere),
+            &mut cells,
+        ))
+        .unwrap();
+
+        assert!(!cells.is_empty());
+        assert_eq!(cells.len(), mesh.positions().len());
+
+        let domain = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+
+        for (key, position) in cells.iter().zip(mesh.positions()) {
+            assert_eq!(key.level(), 4);
+            assert!(key.cell_bounds(&domain).contains(*position));
+        }
+
+        let mut unique = cells.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), cells.len());
+    }
+
+    /// Records the vertices and faces of an extraction.
+    #[derive(Default)]
+    struct Recorder {
+        positions: Vec<Vec3>,
+        faces: Vec<[u32; 3]>,
+        reserved: Option<(usize, usize)>,
+    }
+
+    impl Extractor for Recorder {
+        fn extract_vertex(&mut self, position: Vec3) {
+            self.positions.push(position);
+        }
+
+        fn extract_face(&mut self, face: [u32; 3]) {
+            self.faces.push(face);
+        }
+
+        fn reserve(&mut self, vertices: usize, faces: usize) {
+            assert!(self.positions.is_empty() && self.reserved.is_none());
+            self.reserved = Some((vertices, faces));
+        }
+    }
+
+    #[test]
+    fn reserve_exact_counts() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+        let mut recorder = Recorder::default();
+        let mut cells = Vec::new();
+        DualContouring::new(&sphere, 16, 1e-4)
+            .extract(MeshMetrics::new(WithVertexCells::new(
+                &mut recorder,
+                &mut cells,
+            )))
+            .unwrap();
+
+        let counts = (recorder.positions.len(), recorder.faces.len());
+        assert!(counts.1 > 0);
+        assert_eq!(recorder.reserved, Some(counts));
+        assert!(cells.capacity() >= counts.0);
+    }
+
+    /// Extracts two spheres on either side of `X = 0`, one local extractor
+    /// per sphere, as a parallel driver splitting the domain in two would.
+    /// The second sphere is merged first, so the output is ordered
+    /// differently from a serial extraction.
+    fn extract_in_halves<P: ParallelExtractor>(
+        dc: &DualContouring<impl HermiteSource>,
+        target: &mut P,
+    ) {
+        let mut recorder = Recorder::default();
+        dc.extract(&mut recorder).unwrap();
+
+        let mut vertex_offset = 0;
+
+        for negative in [false, true] {
+            let mut local = target.make_local();
+            let mut indices = vec![u32::MAX; recorder.positions.len()];
+            let mut count = 0;
+
+            for (i, position) in recorder.positions.iter().enumerate() {
+                if (position.x < 0.0) == negative {
+                    local.extract_vertex(*position);
+                    indices[i] = count;
+                    count += 1;
+                }
+            }
+
+            for face in &recorder.faces {
+                let face = face.map(|i| indices[i as usize]);
+
+                if face.iter().all(|&i| i != u32::MAX) {
+                    local.extract_face(face);
+                }
+            }
+
+            local.finish().unwrap();
+            target.merge(local, vertex_offset);
+            vertex_offset += count;
+        }
+
+        target.finish_merged().unwrap();
+    }
+
+    /// Lists the faces by the bits of their positions and normals, starting
+    /// from their smallest vertex, in order.
+    fn canonical_faces(mesh: &IndexedSeparateNormals) -> Vec<[[u32; 6]; 3]> {
+        let mut faces: Vec<_> = mesh
+            .triangles()
+            .map(|triangle| {
+                let mut face = triangle.map(|(position, normal)| {
+                    let [a, b, c] = position.to_array().map(f32::to_bits);
+                    let [d, e, f] = normal.to_array().map(f32::to_bits);
+                    [a, b, c, d, e, f]
+                });
+
+                let first = (0..3).min_by_key(|&k| face[k]).unwrap();
+                face.rotate_left(first);
+                face
+            })
+            .collect();
+
+        faces.sort_unstable();
+        faces
+    }
+
+    fn two_spheres() -> impl HermiteSource {
+        hermite_from_fn(
+            |p: Vec3| {
+                (p - Vec3::X * 0.5)
+                    .length()
+                    .min((p + Vec3::X * 0.5).length())
+                    - 0.3
+            },
+            |p: Vec3| p - Vec3::X * 0.5 * p.x.signum(),
+        )
+    }
+
+    #[test]
+    fn merged_matches_serial() {
+        let spheres = two_spheres();
+        let dc = DualContouring::new(&spheres, 32, 1e-4);
+        let convention = OutputConvention::new(Winding::Cw, Some(AxisKind::Y));
+
+        for normal_mode in [
+            NormalMode::SampledFromSource,
+            NormalMode::AngleWeightedFromFaces,
+        ] {
+            let mut serial = IndexedSeparateNormals::default();
+            let extractor = WithIndexedSeparateNormals::new_with(&mut serial, &spheres, convention)
+                .with_normal_mode(normal_mode);
+            dc.extract(SerialExtractor::new(extractor)).unwrap();
+
+            let mut expected = IndexedSeparateNormals::default();
+            dc.extract(
+                WithIndexedSeparateNormals::new_with(&mut expected, &spheres, convention)
+                    .with_normal_mode(normal_mode),
+            )
+            .unwrap();
+            assert_eq!(serial.faces, expected.faces);
+
+            let mut merged = IndexedSeparateNormals::default();
+            extract_in_halves(
+                &dc,
+                &mut WithIndexedSeparateNormals::new_with(&mut merged, &spheres, convention)
+                    .with_normal_mode(normal_mode),
+            );
+            assert_ne!(merged.faces, serial.faces);
+            assert_eq!(canonical_faces(&merged), canonical_faces(&serial));
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn streamed_merged_matches_serial() {
+        let spheres = two_spheres();
+        let dc = DualContouring::new(&spheres, 32, 1e-4);
+        let convention = OutputConvention::new(Winding::Cw, Some(AxisKind::Y));
+
+        let mut serial = Vec::new();
+        dc.extract(SerialExtractor::new(crate::StreamingWriter::new_with(
+            &mut serial,
+            &spheres,
+            convention,
+        )))
+        .unwrap();
+
+        let mut merged = Vec::new();
+        extract_in_halves(
+            &dc,
+            &mut crate::StreamingWriter::new_with(&mut merged, &spheres, convention),
+        );
+
+        let serial = IndexedSeparateNormals::read_chunked(&serial[..]).unwrap();
+        let merged = IndexedSeparateNormals::read_chunked(&merged[..]).unwrap();
+        assert_eq!(canonical_faces(&merged), canonical_faces(&serial));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let mesh = extract_sphere(OutputConvention::default());
+
+        let json = serde_json::to_string(&mesh).unwrap();
+        let bytes = bincode::serialize(&mesh).unwrap();
+
+        for decoded in [
+            serde_json::from_str::<IndexedSeparateNormals>(&json).unwrap(),
+            bincode::deserialize(&bytes).unwrap(),
+        ] {
+            assert_eq!(decoded.positions(), mesh.positions());
+            assert_eq!(decoded.normals(), mesh.normals());
+            assert_eq!(decoded.faces, mesh.faces);
+        }
+    }
+
+    #[test]
+    fn weld() {
+        let mesh = extract_sphere(OutputConvention::default());
+
+        // Give every face its own jittered vertices, as if extracted apart.
+        let mut split = IndexedSeparateNormals::default();
+        for (i, triangle) in mesh.triangles().enumerate() {
+            for (position, normal) in triangle {
+                let jitter = Vec3::new(1.0, -1.0, 0.5) * 1e-6 * (i % 3) as f32;
+                split.vertices.push(position + jitter, normal);
+            }
+            let first = 3 * i as u32;
+            split.faces.push([first, first + 1, first + 2]);
+        }
+
+        split.weld(1e-4);
+        assert_eq!(split.positions().len(), mesh.positions().len());
+        assert_eq!(split.faces.len(), mesh.faces.len());
+        assert!(validate::boundary_edges(&split.faces).is_empty());
+
+        for (welded, original) in split.triangles().zip(mesh.triangles()) {
+            for ((p_a, n_a), (p_b, n_b)) in welded.into_iter().zip(original) {
+                assert!(p_a.distance(p_b) < 1e-5 && n_a.distance(n_b) < 1e-5);
+            }
+        }
+
+        // Merging two vertices of a face collapses it.
+        let mut strip = IndexedSeparateNormals::default();
+        for (position, normal) in [
+            (Vec3::ZERO, Vec3::Z),
+            (Vec3::X, Vec3::Z),
+            (Vec3::new(1.001, 0.0, 0.0), Vec3::Y),
+            (Vec3::Y, Vec3::Z),
+        ] {
+            strip.vertices.push(position, normal);
+        }
+        strip.faces = vec![[0, 1, 3], [1, 2, 3]];
+
+        strip.weld(0.01);
+        assert_eq!(strip.positions(), [Vec3::ZERO, Vec3::X, Vec3::Y]);
+        assert_eq!(strip.normals()[1], (Vec3::Y + Vec3::Z).normalize());
+        assert_eq!(strip.faces, [[0, 1, 2]]);
+    }
+
+    #[test]
+    fn remove_degenerate() {
+        let mut mesh = IndexedSeparateNormals::default();
+        for position in [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::X * 2.0, Vec3::Z] {
+            mesh.vertices.push(position, Vec3::Z);
+        }
+        // A proper triangle, a collinear one, and a sliver.
+        mesh.faces = vec![[0, 1, 2], [0, 1, 3], [1, 3, 4]];
+        mesh.vertices.positions[4] = Vec3::new(1.5, 1e-4, 0.0);
+
+        assert_eq!(mesh.remove_degenerate(0.0), 1);
+        assert_eq!(mesh.faces, [[0, 1, 2], [1, 3, 4]]);
+        assert_eq!(mesh.remove_degenerate(1e-3), 1);
+        assert_eq!(mesh.faces, [[0, 1, 2]]);
+        assert_eq!(mesh.positions().len(), 5);
+
+        mesh.faces = vec![[0, 2, 4]];
+        assert_eq!(mesh.remove_unreferenced_vertices(), 2);
+        assert_eq!(
+            mesh.positions(),
+            [Vec3::ZERO, Vec3::Y, Vec3::new(1.5, 1e-4, 0.0)]
+        );
+        assert_eq!(mesh.normals().len(), 3);
+        assert_eq!(mesh.faces, [[0, 1, 2]]);
+    }
+
+    #[test]
+    fn flat_buffers() {
+        // The flat views rely on `Vec3` being three tightly packed `f32`s.
+        assert_eq!(size_of::<Vec3>(), 3 * size_of::<f32>());
+        assert_eq!(align_of::<Vec3>(), align_of::<f32>());
+        assert_eq!(size_of::<[u32; 3]>(), 3 * size_of::<u32>());
+
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.5, |p| p);
+        let dc = DualContouring::new(&sphere, 8, 1e-4);
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+
+        let positions = mesh.positions_flat();
+        assert_eq!(positions.len(), 3 * mesh.positions().len());
+        assert_eq!(positions.as_ptr(), mesh.positions().as_ptr().cast());
+
+        for (flat, p) in positions.chunks_exact(3).zip(mesh.positions()) {
+            assert_eq!(flat, p.to_array());
+        }
+
+        for (flat, n) in mesh.normals_flat().chunks_exact(3).zip(mesh.normals()) {
+            assert_eq!(flat, n.to_array());
+        }
+
+        let copy = IndexedSeparateNormals::from_flat(
+            mesh.positions_flat().to_vec(),
+            mesh.normals_flat().to_vec(),
+            mesh.indices().to_vec(),
+        )
+        .unwrap();
+        assert_eq!(copy.positions(), mesh.positions());
+        assert_eq!(copy.normals(), mesh.normals());
+        assert_eq!(copy.faces, mesh.faces);
+
+        // Reusing the mesh keeps its buffers.
+        let capacity = mesh.vertices.positions.capacity();
+        mesh.reset();
+        assert!(mesh.positions().is_empty() && mesh.faces.is_empty());
+        assert_eq!(mesh.vertices.positions.capacity(), capacity);
+
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+        assert_eq!(mesh.faces, copy.faces);
+        assert_eq!(mesh.vertices.positions.capacity(), capacity);
+    }
+
+    #[test]
+    fn invalid_flat_buffers() {
+        let triangle = || (vec![0.0; 9], vec![0.0; 9], vec![0, 1, 2]);
+
+        let (p, n, i) = triangle();
+        assert!(IndexedSeparateNormals::from_flat(p, n, i).is_ok());
+
+        let (mut p, n, i) = triangle();
+        p.pop();
+        assert_eq!(
+            IndexedSeparateNormals::from_flat(p, n, i).unwrap_err(),
+            FlatMeshError::NotTriples {
+                buffer: "positions",
+                len: 8
+            }
+        );
+
+        let (p, mut n, i) = triangle();
+        n.truncate(6);
+        assert_eq!(
+            IndexedSeparateNormals::from_flat(p, n, i).unwrap_err(),
+            FlatMeshError::MismatchedNormals {
+                positions: 3,
+                normals: 2
+            }
+        );
+
+        let (p, n, mut i) = triangle();
+        i[1] = 3;
+        assert_eq!(
+            IndexedSeparateNormals::from_flat(p, n, i).unwrap_err(),
+            FlatMeshError::IndexOutOfRange {
+                index: 3,
+                vertex_count: 3
+            }
+        );
+    }
 }
@@ -1,4 +1,5 @@
-use std::mem;
+use crate::source::Source;
+use glam::{BVec3, Vec3};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct BMask3(u8);
@@ -23,6 +24,35 @@ impl BMask3 {
         BMask3(self.0 | step.0)
     }
 
+    /// Returns whether the bit corresponding to `axis` is set.
+    pub const fn has(&self, axis: AxisKind) -> bool {
+        self.0 & (1 << axis as u8) != 0
+    }
+
+    /// Returns a copy of this mask with the bit corresponding to `axis` set
+    /// to `value`.
+    pub const fn with(&self, axis: AxisKind, value: bool) -> BMask3 {
+        let bit = 1 << axis as u8;
+        if value {
+            BMask3(self.0 | bit)
+        } else {
+            BMask3(self.0 & !bit)
+        }
+    }
+
+    /// Returns a copy of this mask with the bits of `mask` toggled.
+    pub const fn toggle(&self, mask: BMask3) -> BMask3 {
+        BMask3(self.0 ^ mask.0)
+    }
+
+    pub const fn to_bvec(self) -> BVec3 {
+        BVec3::new(
+            self.has(AxisKind::X),
+            self.has(AxisKind::Y),
+            self.has(AxisKind::Z),
+        )
+    }
+
     const fn const_eq(&self, rhs: BMask3) -> bool {
         self.0 == rhs.0
     }
@@ -38,15 +68,15 @@ pub enum DirKind {
 
 impl DirKind {
     pub const fn axis(&self) -> AxisKind {
-        // SAFETY:
-        // - Enums with primitive representation are guaranteed to have
-        //   well-defined discriminant values.
-        // - The discriminant never has more than 2 trailing zeroes in binary.
-        unsafe { mem::transmute((*self as u8).trailing_zeros() as u8) }
+        match *self {
+            DirKind::X => AxisKind::X,
+            DirKind::Y => AxisKind::Y,
+            DirKind::Z => AxisKind::Z,
+        }
     }
 
-    pub const fn to_mask(&self) -> BMask3 {
-        BMask3(*self as u8)
+    pub const fn to_mask(self) -> BMask3 {
+        BMask3(self as u8)
     }
 }
 
@@ -57,7 +87,7 @@ impl From<DirKind> for BMask3 {
 }
 
 #[repr(u8)]
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub enum AxisKind {
     X = 0,
     Y = 1,
@@ -65,9 +95,31 @@ pub enum AxisKind {
 }
 
 impl AxisKind {
+    pub const ALL: [AxisKind; 3] = [AxisKind::X, AxisKind::Y, AxisKind::Z];
+
+    /// Returns the next axis in the cyclic `X -> Y -> Z -> X` order.
+    ///
+    /// Together with `self`, the next two axes form a right-handed basis.
+    pub const fn next(&self) -> AxisKind {
+        match *self {
+            AxisKind::X => AxisKind::Y,
+            AxisKind::Y => AxisKind::Z,
+            AxisKind::Z => AxisKind::X,
+        }
+    }
+
+    pub const fn to_mask(self) -> BMask3 {
+        BMask3(1 << self as u8)
+    }
+
+    /// Returns the faces normal to the axis, the one on the negative side
+    /// first.
     pub const fn faces(&self) -> [FaceKind; 2] {
-        let discriminant = *self as u8;
-        unsafe { mem::transmute([discriminant << 1, (discriminant << 1) + 1]) }
+        match *self {
+            AxisKind::X => [FaceKind::Left, FaceKind::Right],
+            AxisKind::Y => [FaceKind::Bottom, FaceKind::Top],
+            AxisKind::Z => [FaceKind::Back, FaceKind::Front],
+        }
     }
 }
 
@@ -93,12 +145,23 @@ impl FaceKind {
     ];
 
     pub const fn normal_axis(&self) -> AxisKind {
-        // SAFETY:
-        // - Enums with primitive representation are guaranteed to have
-        //   well-defined discriminant values.
-        // - `FaceKind` is defined such that discriminants of faces along the
-        //   same normal axis are consecutive integers, starting from 0.
-        unsafe { mem::transmute((*self as u8) >> 1) }
+        match *self {
+            FaceKind::Left | FaceKind::Right => AxisKind::X,
+            FaceKind::Bottom | FaceKind::Top => AxisKind::Y,
+            FaceKind::Back | FaceKind::Front => AxisKind::Z,
+        }
+    }
+
+    /// Returns the face on the other side of the cell, along the same axis.
+    pub const fn opposite(&self) -> FaceKind {
+        match *self {
+            FaceKind::Left => FaceKind::Right,
+            FaceKind::Right => FaceKind::Left,
+            FaceKind::Bottom => FaceKind::Top,
+            FaceKind::Top => FaceKind::Bottom,
+            FaceKind::Back => FaceKind::Front,
+            FaceKind::Front => FaceKind::Back,
+        }
     }
 
     pub const fn corners(&self) -> [CornerKind; 4] {
@@ -121,6 +184,21 @@ impl FaceKind {
     }
 }
 
+// Every face is listed by the axis it's normal to, in the order of
+// `FaceKind::ALL`, and the faces of an axis are opposite to each other.
+const _: () = {
+    let mut i = 0;
+
+    while i < FaceKind::ALL.len() {
+        let face = FaceKind::ALL[i];
+        let faces = face.normal_axis().faces();
+
+        assert!(faces[i % 2] as u8 == face as u8);
+        assert!(faces[1 - i % 2] as u8 == face.opposite() as u8);
+        i += 1;
+    }
+};
+
 #[repr(transparent)]
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct CornerKind(pub BMask3);
@@ -144,7 +222,7 @@ impl From<CornerKind> for BMask3 {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct EdgeKind(CornerKind, DirKind);
 
 impl EdgeKind {
@@ -160,7 +238,7 @@ impl EdgeKind {
         EdgeKind(CornerKind(BMask3::Z), DirKind::Y),
         EdgeKind(CornerKind(BMask3::XY), DirKind::Z),
         EdgeKind(CornerKind(BMask3::XZ), DirKind::Y),
-        EdgeKind(CornerKind(BMask3::XY), DirKind::Z),
+        EdgeKind(CornerKind(BMask3::YZ), DirKind::X),
     ];
 
     pub const fn new(start: CornerKind, dir: DirKind) -> Self {
@@ -178,6 +256,141 @@ impl EdgeKind {
     }
 }
 
+/// An axis-aligned box.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    pub const fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    /// Returns the position of the given corner of the box.
+    pub fn corner(&self, corner: CornerKind) -> Vec3 {
+        Vec3::select(corner.0.to_bvec(), self.max, self.min)
+    }
+
+    /// Returns whether the given point lies within the box, boundary included.
+    pub fn contains(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Returns whether the two boxes overlap, touching boundaries included.
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        self.min.cmple(other.max).all() && other.min.cmple(self.max).all()
+    }
+
+    /// Returns the box grown by `margin` on every side.
+    pub fn expand(&self, margin: Vec3) -> Bounds {
+        Bounds::new(self.min - margin, self.max + margin)
+    }
+
+    /// Returns the smallest box containing both boxes.
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Like [`Bounds::estimate_with_lipschitz`], with a Lipschitz constant
+    /// of 1, i.e., for a signed distance field or an underestimate of one.
+    pub fn estimate(source: &impl Source, seed: Vec3, max_extent: f32) -> Option<Bounds> {
+        Self::estimate_with_lipschitz(source, seed, max_extent, 1.0)
+    }
+
+    /// Estimates a box containing the surface of `source` around `seed`, for
+    /// sources without obvious bounds, or returns `None` if no surface is
+    /// found within `max_extent` of `seed` along the axes and the diagonals.
+    ///
+    /// The values of the source divided by `lipschitz` must not exceed the
+    /// distance to the surface, as with [`DualContouring::assume_lipschitz`].
+    /// Rays are sphere-traced from `seed` along the 6 axis directions and the
+    /// 8 diagonals, and the box grows to contain the balls around every
+    /// point inside that the surface is known to stay out of. A ray stops
+    /// once it's farther from the surface than the half-diagonal of the box
+    /// so far. The rays are cast again from the center of the box until it
+    /// stops growing.
+    ///
+    /// This is a heuristic, which can miss parts of the surface hidden
+    /// between the rays, such as other disconnected components. The box is
+    /// clamped to `max_extent` around `seed`, which bounds the work for
+    /// unbounded surfaces.
+    ///
+    /// [`DualContouring::assume_lipschitz`]: crate::DualContouring::assume_lipschitz
+    pub fn estimate_with_lipschitz(
+        source: &impl Source,
+        seed: Vec3,
+        max_extent: f32,
+        lipschitz: f32,
+    ) -> Option<Bounds> {
+        const ROUNDS: usize = 4;
+        const MAX_STEPS: usize = 10_000;
+
+        let limit = Bounds::new(seed - max_extent, seed + max_extent);
+        let min_step = max_extent * 1e-5;
+        let cube = Bounds::new(Vec3::NEG_ONE, Vec3::ONE);
+        let directions = Vec3::AXES
+            .into_iter()
+            .flat_map(|axis| [axis, -axis])
+            .chain(CornerKind::ALL.map(|corner| cube.corner(corner).normalize()));
+
+        let mut found: Option<Bounds> = None;
+        let mut origin = seed;
+
+        for _ in 0..ROUNDS {
+            let before = found;
+
+            for dir in directions.clone() {
+                let mut t = 0.0;
+
+                for _ in 0..MAX_STEPS {
+                    let point = origin + dir * t;
+
+                    if !limit.contains(point) {
+                        break;
+                    }
+
+                    let distance = source.sample(point) / lipschitz;
+
+                    if !distance.is_finite() {
+                        t += min_step;
+                        continue;
+                    }
+
+                    if distance <= min_step {
+                        // The surface stays out of the ball around a point
+                        // inside, so the ball is within its box.
+                        let ball =
+                            Bounds::new(point, point).expand(Vec3::splat(min_step.max(-distance)));
+                        found = Some(found.map_or(ball, |found| found.union(&ball)));
+                    } else if found.is_some_and(|found| distance > found.size().length() / 2.0) {
+                        break;
+                    }
+
+                    t += distance.abs().max(min_step);
+                }
+            }
+
+            match found {
+                Some(bounds) if found != before => origin = bounds.center(),
+                _ => break,
+            }
+        }
+
+        found.map(|bounds| Bounds::new(bounds.min.max(limit.min), bounds.max.min(limit.max)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -192,7 +405,7 @@ mod tests {
     #[test]
     fn face_normal_axis() {
         assert_eq!(FaceKind::Left.normal_axis(), AxisKind::X);
-        assert_eq!(FaceKind::Right.normal_axis(), AxisKind::Y);
+        assert_eq!(FaceKind::Right.normal_axis(), AxisKind::X);
         assert_eq!(FaceKind::Bottom.normal_axis(), AxisKind::Y);
         assert_eq!(FaceKind::Top.normal_axis(), AxisKind::Y);
         assert_eq!(FaceKind::Back.normal_axis(), AxisKind::Z);
@@ -205,4 +418,68 @@ mod tests {
         assert_eq!(AxisKind::Y.faces(), [FaceKind::Bottom, FaceKind::Top]);
         assert_eq!(AxisKind::Z.faces(), [FaceKind::Back, FaceKind::Front]);
     }
+
+    #[test]
+    fn axis_faces_round_trip() {
+        for axis in AxisKind::ALL {
+            assert_eq!(axis.faces().map(|f| f.normal_axis()), [axis, axis]);
+        }
+
+        for dir in [DirKind::X, DirKind::Y, DirKind::Z] {
+            assert_eq!(dir.axis().to_mask(), dir.to_mask());
+        }
+    }
+
+    #[test]
+    fn opposite_faces() {
+        assert_eq!(FaceKind::Left.opposite(), FaceKind::Right);
+        assert_eq!(FaceKind::Bottom.opposite(), FaceKind::Top);
+        assert_eq!(FaceKind::Back.opposite(), FaceKind::Front);
+
+        for face in FaceKind::ALL {
+            assert_ne!(face.opposite(), face);
+            assert_eq!(face.opposite().opposite(), face);
+            assert_eq!(face.opposite().normal_axis(), face.normal_axis());
+        }
+    }
+
+    #[test]
+    fn edges_are_unique() {
+        for (i, a) in EdgeKind::ALL.iter().enumerate() {
+            for b in &EdgeKind::ALL[..i] {
+                assert_ne!(a.endpoints(), b.endpoints());
+            }
+        }
+    }
+
+    #[test]
+    fn estimate_bounds() {
+        use crate::source::from_fn;
+
+        let (center, radius) = (Vec3::new(5.0, 3.0, -2.0), 2.0);
+        let sphere = from_fn(move |p: Vec3| p.distance(center) - radius);
+
+        for seed in [center, Vec3::new(4.0, 2.5, -1.0), Vec3::new(7.5, 3.0, -2.0)] {
+            let bounds = Bounds::estimate(&sphere, seed, 100.0).unwrap();
+            let padding = [center - radius - bounds.min, bounds.max - center - radius];
+
+            for padding in padding {
+                assert!(padding.min_element() >= 0.0, "{seed} {bounds:?}");
+                assert!(padding.max_element() < 0.2 * radius, "{seed} {bounds:?}");
+            }
+        }
+
+        // Nothing near the seed.
+        assert_eq!(
+            Bounds::estimate(&sphere, Vec3::new(-50.0, 0.0, 0.0), 20.0),
+            None
+        );
+
+        // An unbounded surface is clamped.
+        let plane = from_fn(|p: Vec3| p.y);
+        let bounds = Bounds::estimate(&plane, Vec3::new(0.0, 1.0, 0.0), 10.0).unwrap();
+        assert_eq!(bounds.min.x, -10.0);
+        assert_eq!(bounds.max.z, 10.0);
+        assert!(bounds.max.y <= 1.0 && bounds.min.y == -9.0);
+    }
 }
@@ -0,0 +1,193 @@
+//! Writing meshes as binary glTF (GLB) files.
+//!
+//! As with the other formats, the writer isn't buffered internally, so
+//! wrapping it in an [`io::BufWriter`] is recommended.
+
+use crate::extractor::IndexedSeparateNormals;
+use glam::Vec3;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+const JSON_CHUNK: &[u8; 4] = b"JSON";
+const BIN_CHUNK: &[u8; 4] = b"BIN\0";
+
+/// Sizes of the GLB header and of a chunk header.
+const HEADER_SIZE: usize = 12;
+const CHUNK_HEADER_SIZE: usize = 8;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+impl IndexedSeparateNormals {
+    /// Writes the mesh as a self-contained binary glTF 2.0 (GLB) file, with a
+    /// single mesh made of one indexed triangle primitive with positions and
+    /// normals.
+    ///
+    /// glTF is right-handed with counter-clockwise front faces, which
+    /// matches the default [`OutputConvention`]. glTF doesn't allow empty
+    /// accessors, so an empty mesh is rejected with
+    /// [`io::ErrorKind::InvalidInput`].
+    ///
+    /// [`OutputConvention`]: crate::OutputConvention
+    pub fn write_glb<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        debug_assert!(self.vertices.is_consistent());
+
+        if self.faces.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "glTF can't hold an empty mesh",
+            ));
+        }
+
+        let positions = &self.vertices.positions;
+        let vertices_size = positions.len() * 12;
+        let indices_size = self.faces.len() * 12;
+        let bin_size = 2 * vertices_size + indices_size;
+
+        let (min, max) = self.aabb().unwrap();
+        let mut json = gltf_json(
+            positions.len(),
+            3 * self.faces.len(),
+            [min, max],
+            [vertices_size, indices_size],
+        );
+
+        // The JSON chunk is padded with spaces, the binary one with zeros,
+        // and both must be aligned to 4 bytes. The binary data is made of
+        // 4-byte values only, so it's already aligned.
+        while !json.len().is_multiple_of(4) {
+            json.push(' ');
+        }
+
+        let total_size = HEADER_SIZE + 2 * CHUNK_HEADER_SIZE + json.len() + bin_size;
+        let total_size = u32::try_from(total_size)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too large for GLB"))?;
+
+        writer.write_all(GLB_MAGIC)?;
+        writer.write_all(&GLB_VERSION.to_le_bytes())?;
+        writer.write_all(&total_size.to_le_bytes())?;
+
+        writer.write_all(&(json.len() as u32).to_le_bytes())?;
+        writer.write_all(JSON_CHUNK)?;
+        writer.write_all(json.as_bytes())?;
+
+        writer.write_all(&(bin_size as u32).to_le_bytes())?;
+        writer.write_all(BIN_CHUNK)?;
+
+        for v in positions.iter().chain(&self.vertices.normals) {
+            for c in v.to_array() {
+                writer.write_all(&c.to_le_bytes())?;
+            }
+        }
+
+        for i in self.faces.as_flattened() {
+            writer.write_all(&i.to_le_bytes())?;
+        }
+
+        writer.flush()
+    }
+}
+
+/// Builds the JSON chunk of a GLB file holding `vertex_count` positions and
+/// normals, followed by `index_count` indices, in this order, in its binary
+/// chunk.
+fn gltf_json(
+    vertex_count: usize,
+    index_count: usize,
+    [min, max]: [Vec3; 2],
+    [vertices_size, indices_size]: [usize; 2],
+) -> String {
+    let vec3 = |v: Vec3| format!("[{},{},{}]", v.x, v.y, v.z);
+    let mut json = String::new();
+
+    write!(
+        json,
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"dual_contouring"}},"#,
+            r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#,
+            r#""meshes":[{{"primitives":[{{"attributes":{{"POSITION":0,"NORMAL":1}},"indices":2}}]}}],"#,
+            r#""buffers":[{{"byteLength":{buffer_size}}}],"#,
+            r#""bufferViews":["#,
+            r#"{{"buffer":0,"byteOffset":0,"byteLength":{vertices_size},"target":{ARRAY_BUFFER}}},"#,
+            r#"{{"buffer":0,"byteOffset":{vertices_size},"byteLength":{vertices_size},"target":{ARRAY_BUFFER}}},"#,
+            r#"{{"buffer":0,"byteOffset":{indices_offset},"byteLength":{indices_size},"target":{ELEMENT_ARRAY_BUFFER}}}],"#,
+            r#""accessors":["#,
+            r#"{{"bufferView":0,"componentType":{FLOAT},"count":{vertex_count},"type":"VEC3","min":{min},"max":{max}}},"#,
+            r#"{{"bufferView":1,"componentType":{FLOAT},"count":{vertex_count},"type":"VEC3"}},"#,
+            r#"{{"bufferView":2,"componentType":{UNSIGNED_INT},"count":{index_count},"type":"SCALAR"}}]}}"#,
+        ),
+        buffer_size = 2 * vertices_size + indices_size,
+        vertices_size = vertices_size,
+        indices_offset = 2 * vertices_size,
+        indices_size = indices_size,
+        ARRAY_BUFFER = ARRAY_BUFFER,
+        ELEMENT_ARRAY_BUFFER = ELEMENT_ARRAY_BUFFER,
+        FLOAT = FLOAT,
+        UNSIGNED_INT = UNSIGNED_INT,
+        vertex_count = vertex_count,
+        index_count = index_count,
+        min = vec3(min),
+        max = vec3(max),
+    )
+    .unwrap();
+
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{DualContouring, WithIndexedSeparateNormals};
+
+    #[test]
+    fn glb_sphere() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.7, |p| p);
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(&sphere, 16, 1e-4)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+
+        let mut bytes = Vec::new();
+        mesh.write_glb(&mut bytes).unwrap();
+        assert_eq!(bytes.len() % 4, 0);
+
+        let glb = gltf::Gltf::from_slice(&bytes).unwrap();
+        let blob = glb.blob.as_deref().unwrap();
+        let (min, max) = mesh.aabb().unwrap();
+
+        let primitive = glb.meshes().next().unwrap().primitives().next().unwrap();
+        let reader = primitive.reader(|_| Some(blob));
+
+        let position = primitive.get(&gltf::Semantic::Positions).unwrap();
+        assert_eq!(position.count(), mesh.positions().len());
+        assert_eq!(bound_to_vec3(position.min().unwrap()), min);
+        assert_eq!(bound_to_vec3(position.max().unwrap()), max);
+
+        let positions: Vec<_> = reader.read_positions().unwrap().map(Vec3::from).collect();
+        let normals: Vec<_> = reader.read_normals().unwrap().map(Vec3::from).collect();
+        let indices: Vec<_> = reader.read_indices().unwrap().into_u32().collect();
+        assert_eq!(positions, mesh.positions());
+        assert_eq!(normals, mesh.normals());
+        assert_eq!(indices, mesh.indices());
+
+        assert!(IndexedSeparateNormals::default()
+            .write_glb(&mut Vec::new())
+            .is_err());
+    }
+
+    fn bound_to_vec3(bound: gltf::json::Value) -> Vec3 {
+        let c: Vec<f32> = bound
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c.as_f64().unwrap() as f32)
+            .collect();
+
+        Vec3::from_slice(&c)
+    }
+}
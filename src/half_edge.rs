@@ -0,0 +1,251 @@
+//! A half-edge mesh built during extraction, see [`HalfEdgeMesh`].
+
+use crate::extractor::{Extractor, FinishError};
+use alloc::vec::Vec;
+use glam::Vec3;
+
+/// A directed edge of a face of a [`HalfEdgeMesh`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HalfEdge {
+    /// The vertex the half-edge leaves.
+    pub origin: u32,
+    /// The half-edge along the same edge in the opposite direction, in the
+    /// adjacent face, or `None` on the boundary of the mesh.
+    pub twin: Option<u32>,
+}
+
+/// A triangle mesh with half-edge connectivity, e.g., for smoothing or
+/// remeshing, which need to walk around vertices and across edges. Extract
+/// into `&mut` an empty one.
+///
+/// The half-edges of face `f` are `3 * f`, `3 * f + 1` and `3 * f + 2`, in
+/// counter-clockwise order, so the next and previous half-edges within a face
+/// and the face of a half-edge are implied by their indices.
+///
+/// Twins are matched as faces are extracted: only the half-edges leaving the
+/// other end of a new half-edge can be its twin, and every vertex keeps
+/// those until the extraction finishes, so no table of all edges is built. An
+/// edge shared by more than two faces, which dual contouring produces where
+/// sheets of the surface touch, pairs its half-edges in the order the faces
+/// are extracted, and leaves the others without twins.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HalfEdgeMesh {
+    pub vertices: Vec<Vec3>,
+    pub half_edges: Vec<HalfEdge>,
+    /// A half-edge leaving every vertex, or `None` for vertices without
+    /// faces. Vertices on the boundary get the half-edge along it, from
+    /// which [`HalfEdgeMesh::vertex_neighbors`] reaches all of their
+    /// neighbors.
+    pub vertex_half_edges: Vec<Option<u32>>,
+    /// The half-edges leaving every vertex, while extracting.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    outgoing: Vec<Vec<u32>>,
+}
+
+impl HalfEdgeMesh {
+    pub fn face_count(&self) -> usize {
+        self.half_edges.len() / 3
+    }
+
+    /// Returns the vertices of face `face`, counter-clockwise.
+    pub fn face_vertices(&self, face: u32) -> [u32; 3] {
+        let first = 3 * face as usize;
+        [0, 1, 2].map(|k| self.half_edges[first + k].origin)
+    }
+
+    /// Returns the half-edge following `half_edge` in its face.
+    pub fn next(&self, half_edge: u32) -> u32 {
+        half_edge - half_edge % 3 + (half_edge + 1) % 3
+    }
+
+    /// Returns the half-edge preceding `half_edge` in its face.
+    pub fn prev(&self, half_edge: u32) -> u32 {
+        half_edge - half_edge % 3 + (half_edge + 2) % 3
+    }
+
+    /// Returns the vertex `half_edge` points to.
+    pub fn dest(&self, half_edge: u32) -> u32 {
+        self.half_edges[self.next(half_edge) as usize].origin
+    }
+
+    /// Returns the vertices sharing an edge with `vertex`, by walking around
+    /// it across twins, so that the neighbors of a vertex where several fans
+    /// of faces meet are only those of the fan of its half-edge.
+    pub fn vertex_neighbors(&self, vertex: u32) -> Vec<u32> {
+        let mut neighbors = Vec::new();
+        let Some(start) = self.vertex_half_edges[vertex as usize] else {
+            return neighbors;
+        };
+
+        let mut half_edge = start;
+
+        loop {
+            neighbors.push(self.dest(half_edge));
+            let prev = self.prev(half_edge);
+
+            match self.half_edges[prev as usize].twin {
+                Some(twin) if twin != start => half_edge = twin,
+                Some(_) => break,
+                // The other boundary edge at the vertex.
+                None => {
+                    neighbors.push(self.half_edges[prev as usize].origin);
+                    break;
+                }
+            }
+        }
+
+        neighbors
+    }
+
+    /// Returns whether every half-edge has a twin, i.e., whether the mesh has
+    /// no boundary.
+    pub fn is_closed(&self) -> bool {
+        self.half_edges
+            .iter()
+            .all(|half_edge| half_edge.twin.is_some())
+    }
+}
+
+impl Extractor for HalfEdgeMesh {
+    fn extract_vertex(&mut self, position: Vec3) {
+        self.vertices.push(position);
+        self.vertex_half_edges.push(None);
+        self.outgoing.push(Vec::new());
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        let first = self.half_edges.len() as u32;
+
+        for origin in face {
+            self.half_edges.push(HalfEdge { origin, twin: None });
+        }
+
+        for k in 0..3 {
+            let half_edge = first + k;
+            let (origin, dest) = (face[k as usize], face[(k as usize + 1) % 3]);
+
+            let twin = self.outgoing[dest as usize]
+                .iter()
+                .copied()
+                .find(|&h| self.dest(h) == origin && self.half_edges[h as usize].twin.is_none());
+
+            if let Some(twin) = twin {
+                self.half_edges[half_edge as usize].twin = Some(twin);
+                self.half_edges[twin as usize].twin = Some(half_edge);
+            }
+
+            self.outgoing[origin as usize].push(half_edge);
+            self.vertex_half_edges[origin as usize].get_or_insert(half_edge);
+        }
+    }
+
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        self.vertices.reserve(vertices);
+        self.vertex_half_edges.reserve(vertices);
+        self.half_edges.reserve(3 * faces);
+    }
+
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        vertices * (size_of::<Vec3>() + size_of::<Option<u32>>())
+            + 3 * faces * size_of::<HalfEdge>()
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        self.outgoing = Vec::new();
+
+        // Start walking around boundary vertices from their boundary edges.
+        for half_edge in 0..self.half_edges.len() as u32 {
+            let HalfEdge { origin, twin } = self.half_edges[half_edge as usize];
+
+            if twin.is_none() {
+                self.vertex_half_edges[origin as usize] = Some(half_edge);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::validate::boundary_edges;
+    use crate::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+    use alloc::vec;
+
+    fn extract(dc: &DualContouring<impl crate::HermiteSource>) -> HalfEdgeMesh {
+        let mut mesh = HalfEdgeMesh::default();
+        dc.extract(&mut mesh).unwrap();
+        mesh
+    }
+
+    /// Checks that twins are mutual and opposite, and that walking around
+    /// every vertex finds the same neighbors as the faces.
+    fn check_connectivity(mesh: &HalfEdgeMesh, faces: &[[u32; 3]]) {
+        assert_eq!(mesh.face_count(), faces.len());
+
+        for (f, face) in faces.iter().enumerate() {
+            assert_eq!(mesh.face_vertices(f as u32), *face);
+        }
+
+        for (h, half_edge) in mesh.half_edges.iter().enumerate() {
+            if let Some(twin) = half_edge.twin {
+                assert_eq!(mesh.half_edges[twin as usize].twin, Some(h as u32));
+                assert_eq!(mesh.dest(twin), half_edge.origin);
+                assert_eq!(mesh.half_edges[twin as usize].origin, mesh.dest(h as u32));
+            }
+        }
+
+        let mut expected = vec![Vec::new(); mesh.vertices.len()];
+
+        for face in faces {
+            for k in 0..3 {
+                expected[face[k] as usize].push(face[(k + 1) % 3]);
+                expected[face[k] as usize].push(face[(k + 2) % 3]);
+            }
+        }
+
+        for (v, mut expected) in expected.into_iter().enumerate() {
+            expected.sort_unstable();
+            expected.dedup();
+            let mut neighbors = mesh.vertex_neighbors(v as u32);
+            neighbors.sort_unstable();
+            assert_eq!(neighbors, expected, "{v}");
+        }
+    }
+
+    #[test]
+    fn closed_sphere() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-6);
+        let mesh = extract(&dc);
+
+        let mut indexed = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut indexed, &sphere))
+            .unwrap();
+
+        assert!(mesh.is_closed());
+        assert_eq!(mesh.vertices, indexed.vertices.positions);
+        check_connectivity(&mesh, &indexed.faces);
+    }
+
+    #[test]
+    fn open_plane() {
+        // The plane leaves the bounds, so its rim is a boundary.
+        let plane = hermite_from_fn(|p: Vec3| p.y - 0.1 * p.x - 0.03, |_| Vec3::Y);
+        let dc = DualContouring::new(&plane, 8, 1e-6);
+        let mesh = extract(&dc);
+
+        let mut indexed = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut indexed, &plane))
+            .unwrap();
+
+        let boundary = mesh.half_edges.iter().filter(|h| h.twin.is_none()).count();
+        assert!(!mesh.is_closed());
+        assert_eq!(boundary, boundary_edges(&indexed.faces).len());
+        check_connectivity(&mesh, &indexed.faces);
+    }
+}
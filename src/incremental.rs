@@ -0,0 +1,676 @@
+use crate::collections::{Map, Set};
+use crate::contour::{place_vertex, repair_self_intersections, snap_to_boundary, Contour};
+use crate::extractor::{add_face_normals, plane_normal, IndexedSeparateNormals, NormalMode};
+use crate::geom::Bounds;
+use crate::morton::MortonKey;
+use crate::octree::Octree;
+use crate::source::HermiteSource;
+use crate::topology::OctreeCell;
+use crate::{BoundaryPolicy, DualContouring, ExtractSurfaceError, ExtractionReport};
+use alloc::vec;
+use alloc::vec::Vec;
+use glam::{UVec3, Vec3};
+
+/// A mesh that can be re-extracted locally after the source is edited.
+///
+/// Alongside the mesh, this retains the octree and the mapping between
+/// vertices and the cells that produced them, so that [`update`] only has to
+/// re-contour the cells around the edited region. The mesh is the same as
+/// that of [`DualContouring::extract`] into [`WithIndexedSeparateNormals`]
+/// with the same [`NormalMode`], up to the order of its vertices and faces,
+/// unless the extraction exceeds its memory budget, or
+/// [`with_self_intersection_check`] has to move vertices outside of the
+/// edited region.
+///
+/// Vertices of cells away from the edited region keep their indices across
+/// updates. Vertices whose cells no longer intersect the surface are dead:
+/// no face references them and [`vertex_cell`] returns `None` for them, but
+/// they stay in the buffer, with stale positions and normals, until their
+/// slots are reused by new vertices or removed by [`compact`].
+///
+/// [`update`]: IncrementalMesher::update
+/// [`vertex_cell`]: IncrementalMesher::vertex_cell
+/// [`compact`]: IncrementalMesher::compact
+/// [`WithIndexedSeparateNormals`]: crate::WithIndexedSeparateNormals
+/// [`with_self_intersection_check`]: DualContouring::with_self_intersection_check
+#[derive(Debug)]
+pub struct IncrementalMesher {
+    octree: Octree,
+    max_res: UVec3,
+    normal_mode: NormalMode,
+    mesh: IndexedSeparateNormals,
+    /// The cell of every vertex, or `MortonKey::none()` for dead slots.
+    vertex_cells: Vec<MortonKey>,
+    cell_vertices: Map<MortonKey, u32>,
+    /// The vertices of the quad every face comes from.
+    face_quads: Vec<[u32; 4]>,
+    /// The dead slots, reused before the buffer grows.
+    free: Vec<u32>,
+}
+
+impl IncrementalMesher {
+    /// Extracts the whole surface of `dc`, sampling the normals of the
+    /// vertices from the source.
+    ///
+    /// The octree follows the detail regions of `dc`, which are kept for
+    /// later updates, but isn't pruned away from the surface even if `dc`
+    /// assumes a Lipschitz source, as edits may move the surface anywhere.
+    pub fn new<S: HermiteSource>(dc: &DualContouring<S>) -> Result<Self, ExtractSurfaceError> {
+        Self::new_with(dc, NormalMode::default())
+    }
+
+    /// Like [`IncrementalMesher::new`], but computes the normals of the
+    /// vertices as by `normal_mode`.
+    ///
+    /// Normals computed from the faces are recomputed for the whole mesh on
+    /// every update, which doesn't sample the source but for the vertices
+    /// without faces.
+    pub fn new_with<S: HermiteSource>(
+        dc: &DualContouring<S>,
+        normal_mode: NormalMode,
+    ) -> Result<Self, ExtractSurfaceError> {
+        if dc.max_res.min_element() < 2 {
+            return Err(ExtractSurfaceError::EmptyDomain);
+        }
+
+        let mut mesher = Self {
+            octree: Octree::build(dc.octree_domain(), dc.max_level(), |cell| {
+                dc.may_subdivide(cell)
+            }),
+            max_res: dc.max_res,
+            normal_mode,
+            mesh: IndexedSeparateNormals::default(),
+            vertex_cells: Vec::new(),
+            cell_vertices: Map::new(),
+            face_quads: Vec::new(),
+            free: Vec::new(),
+        };
+
+        mesher.update(dc, dc.bounds)?;
+        Ok(mesher)
+    }
+
+    /// Returns the mesh, which may contain dead vertices, see
+    /// [`IncrementalMesher::compact`].
+    pub fn mesh(&self) -> &IndexedSeparateNormals {
+        &self.mesh
+    }
+
+    /// Returns the cell that produced the given vertex, or `None` if the
+    /// vertex is dead.
+    pub fn vertex_cell(&self, vertex: u32) -> Option<MortonKey> {
+        let key = *self.vertex_cells.get(vertex as usize)?;
+        (!key.is_none()).then_some(key)
+    }
+
+    /// Returns the number of dead vertices in the mesh.
+    pub fn dead_vertices(&self) -> usize {
+        self.free.len()
+    }
+
+    /// Re-extracts the surface of `dc` within `dirty`, assuming that the
+    /// source is unchanged outside of it.
+    ///
+    /// Every leaf cell intersecting `dirty`, padded by one cell, is
+    /// re-contoured, along with the faces connecting it to its neighbors.
+    /// The configuration of `dc` applies to these cells as it does to
+    /// [`DualContouring::extract`], but for the memory budget. Vertices of
+    /// other cells are fixed, so [`with_self_intersection_check`] only
+    /// repairs the new ones, and the returned report only covers the
+    /// re-contoured cells.
+    ///
+    /// On error, the mesh is left unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dc` doesn't have the same bounds and resolution as the one
+    /// this mesher was created with.
+    ///
+    /// [`with_self_intersection_check`]: DualContouring::with_self_intersection_check
+    pub fn update<S: HermiteSource>(
+        &mut self,
+        dc: &DualContouring<S>,
+        dirty: Bounds,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        match dc.boundary_policy {
+            BoundaryPolicy::Open => self.update_open(dc, dirty),
+            BoundaryPolicy::Cap => self.update_open(&dc.capped(), dirty),
+        }
+    }
+
+    fn update_open<S: HermiteSource>(
+        &mut self,
+        dc: &DualContouring<S>,
+        dirty: Bounds,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        assert!(
+            dc.octree_domain() == *self.octree.domain() && dc.max_res == self.max_res,
+            "`dc` must have the same bounds and resolution as the mesher"
+        );
+
+        let domain = *self.octree.domain();
+        let cell_size = domain.size() / self.max_res.max_element() as f32;
+        let region = dirty.expand(cell_size);
+        let is_dirty = |cell: &OctreeCell| cell.bounds(&domain).intersects(&region);
+
+        // Re-contour every minimal edge around dirty cells, failing before
+        // the mesh is touched.
+        let mut contour = Contour::default();
+
+        self.octree.for_each_minimal_edge_near(is_dirty, |edge| {
+            contour.add_edge(dc, edge);
+        });
+
+        contour.check()?;
+        contour.sort();
+
+        // Retire the vertices of dirty cells, along with every face that
+        // depends on them.
+        let mut stale = Map::new();
+
+        self.cell_vertices.retain(|&key, &mut vertex| {
+            let dirty = is_dirty(&OctreeCell::new(key).unwrap());
+
+            if dirty {
+                stale.insert(key, vertex);
+            }
+
+            !dirty
+        });
+
+        let stale_vertices: Set<u32> = stale.values().copied().collect();
+        let mut kept = 0;
+
+        for i in 0..self.face_quads.len() {
+            if !self.face_quads[i]
+                .iter()
+                .any(|vertex| stale_vertices.contains(vertex))
+            {
+                self.face_quads[kept] = self.face_quads[i];
+                self.mesh.faces[kept] = self.mesh.faces[i];
+                kept += 1;
+            }
+        }
+
+        self.face_quads.truncate(kept);
+        self.mesh.faces.truncate(kept);
+
+        // Place the vertices of dirty cells, those of clean cells, the edges
+        // of which are unchanged, being fixed.
+        let clean: Vec<Option<u32>> = contour
+            .cells()
+            .iter()
+            .map(|(key, _)| self.cell_vertices.get(key).copied())
+            .collect();
+
+        let mut positions: Vec<Vec3> = contour
+            .cells()
+            .iter()
+            .zip(&clean)
+            .map(|((key, qef), vertex)| match vertex {
+                Some(vertex) => self.mesh.vertices.positions[*vertex as usize],
+                None => place_vertex(*key, qef, &domain),
+            })
+            .collect();
+
+        let mut report = contour.report();
+
+        if dc.check_self_intersections {
+            report.self_intersecting_cells = repair_self_intersections(
+                &contour,
+                &mut positions,
+                &domain,
+                dc.quad_split,
+                &dc.source,
+                |cell| clean[cell as usize].is_none(),
+            );
+        }
+
+        snap_to_boundary(dc, &contour, &mut positions);
+
+        let hermite_normals = (self.normal_mode == NormalMode::AveragedFromHermiteData)
+            .then(|| contour.normals(&positions, &dc.source));
+
+        let vertices: Vec<u32> = contour
+            .cells()
+            .iter()
+            .enumerate()
+            .map(|(cell, (key, _))| {
+                if let Some(vertex) = clean[cell] {
+                    return vertex;
+                }
+
+                let position = positions[cell];
+
+                // Normals computed from the faces are filled in once all
+                // faces are.
+                let normal = match &hermite_normals {
+                    Some(normals) => normals[cell],
+                    None if self.normal_mode.is_from_faces() => Vec3::ZERO,
+                    None => dc.source.sample_normal(position),
+                };
+
+                let vertex = self.allocate(*key, stale.remove(key));
+                self.mesh.vertices.positions[vertex as usize] = position;
+                self.mesh.vertices.normals[vertex as usize] = normal;
+                vertex
+            })
+            .collect();
+
+        // Free the remaining slots in a fixed order, so that they're reused
+        // the same way across runs.
+        let mut retired: Vec<_> = stale.into_iter().collect();
+        retired.sort_unstable();
+
+        for (_, vertex) in retired {
+            self.vertex_cells[vertex as usize] = MortonKey::none();
+            self.free.push(vertex);
+        }
+
+        for (face, quad) in contour.triangles(&positions, dc.quad_split, &dc.source) {
+            let mut face = face.map(|cell| vertices[cell as usize]);
+
+            if !self.normal_mode.is_from_faces() {
+                face = self.mesh.vertices.orient(face);
+            } else {
+                // Orient the face against the normal sampled at its centroid,
+                // as vertex normals aren't known yet.
+                let points = face.map(|i| self.mesh.vertices.positions[i as usize]);
+                let centroid = points.iter().sum::<Vec3>() / 3.0;
+
+                if dc.source.sample_normal(centroid).dot(plane_normal(&points)) < 0.0 {
+                    face.reverse();
+                }
+            }
+
+            self.mesh.faces.push(face);
+            self.face_quads
+                .push(quad.map(|cell| vertices[cell as usize]));
+        }
+
+        if self.normal_mode.is_from_faces() {
+            self.recompute_face_normals(&dc.source);
+        }
+
+        Ok(report)
+    }
+
+    /// Removes the dead vertices, keeping the order of the others and
+    /// remapping the faces.
+    ///
+    /// Returns the new index of every previous vertex, or `None` for the
+    /// removed ones, e.g., to update buffers derived from the mesh.
+    pub fn compact(&mut self) -> Vec<Option<u32>> {
+        let mut remap = vec![None; self.vertex_cells.len()];
+        let vertices = &mut self.mesh.vertices;
+        let mut kept = 0;
+
+        for (i, index) in remap.iter_mut().enumerate() {
+            let key = self.vertex_cells[i];
+
+            if !key.is_none() {
+                *index = Some(kept);
+                vertices.positions[kept as usize] = vertices.positions[i];
+                vertices.normals[kept as usize] = vertices.normals[i];
+                self.vertex_cells[kept as usize] = key;
+                self.cell_vertices.insert(key, kept);
+                kept += 1;
+            }
+        }
+
+        vertices.positions.truncate(kept as usize);
+        vertices.normals.truncate(kept as usize);
+        self.vertex_cells.truncate(kept as usize);
+        self.free.clear();
+
+        // Faces only reference live vertices.
+        let index = |i: u32| remap[i as usize].unwrap();
+
+        for face in &mut self.mesh.faces {
+            *face = face.map(index);
+        }
+
+        for quad in &mut self.face_quads {
+            *quad = quad.map(index);
+        }
+
+        remap
+    }
+
+    /// Finds a slot for the vertex of the given cell, preferring the one it
+    /// previously had.
+    fn allocate(&mut self, key: MortonKey, previous: Option<u32>) -> u32 {
+        let vertex = match previous.or_else(|| self.free.pop()) {
+            Some(vertex) => vertex,
+            None => {
+                let vertices = &mut self.mesh.vertices;
+                vertices.push(Vec3::ZERO, Vec3::ZERO);
+                self.vertex_cells.push(MortonKey::none());
+                self.vertex_cells.len() as u32 - 1
+            }
+        };
+
+        self.vertex_cells[vertex as usize] = key;
+        self.cell_vertices.insert(key, vertex);
+        vertex
+    }
+
+    /// Recomputes the normals of all live vertices from the faces around
+    /// them, falling back to the normal of `source` for vertices without
+    /// faces, or whose faces cancel out, like [`WithIndexedSeparateNormals`].
+    ///
+    /// [`WithIndexedSeparateNormals`]: crate::WithIndexedSeparateNormals
+    fn recompute_face_normals(&mut self, source: &impl HermiteSource) {
+        let vertices = &mut self.mesh.vertices;
+        let mut sums = vec![Vec3::ZERO; vertices.positions.len()];
+        add_face_normals(
+            &vertices.positions,
+            &mut sums,
+            &self.mesh.faces,
+            self.normal_mode,
+        );
+
+        let live = self.vertex_cells.iter().map(|key| !key.is_none());
+        let normals = vertices.positions.iter().zip(&mut vertices.normals);
+
+        for ((sum, live), (position, normal)) in sums.into_iter().zip(live).zip(normals) {
+            if live {
+                *normal = sum
+                    .try_normalize()
+                    .unwrap_or_else(|| source.sample_normal(*position));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{HermiteSource, WithIndexedSeparateNormals};
+
+    const BUMP: Vec3 = Vec3::new(0.6, 0.0, 0.0);
+
+    fn sphere(p: Vec3) -> f32 {
+        p.length() - 0.6
+    }
+
+    fn bump(p: Vec3) -> f32 {
+        (p - BUMP).length() - 0.15
+    }
+
+    /// Returns the sphere, with a bump sculpted on it if `bumped`.
+    fn source(bumped: bool) -> impl HermiteSource {
+        hermite_from_fn(
+            move |p| {
+                if bumped {
+                    sphere(p).min(bump(p))
+                } else {
+                    sphere(p)
+                }
+            },
+            move |p| {
+                if bumped && bump(p) < sphere(p) {
+                    p - BUMP
+                } else {
+                    p
+                }
+            },
+        )
+    }
+
+    fn dirty() -> Bounds {
+        Bounds::new(BUMP - 0.15, BUMP + 0.15)
+    }
+
+    /// Returns the triangles of the mesh as (rotation-normalized) position
+    /// bits, i.e., the mesh welded with zero tolerance.
+    fn triangles(mesh: &IndexedSeparateNormals) -> Vec<[[u32; 3]; 3]> {
+        let mut triangles: Vec<_> = mesh
+            .faces
+            .iter()
+            .map(|face| {
+                let t = face.map(|i| {
+                    mesh.vertices.positions[i as usize]
+                        .to_array()
+                        .map(f32::to_bits)
+                });
+                let first = (0..3).min_by_key(|&i| t[i]).unwrap();
+                [t[first], t[(first + 1) % 3], t[(first + 2) % 3]]
+            })
+            .collect();
+
+        triangles.sort();
+        triangles
+    }
+
+    /// Returns the position bits and the normal of every vertex referenced
+    /// by a face, ordered by position.
+    fn vertices(mesh: &IndexedSeparateNormals) -> Vec<([u32; 3], Vec3)> {
+        let mut referenced: Vec<u32> = mesh.faces.iter().flatten().copied().collect();
+        referenced.sort_unstable();
+        referenced.dedup();
+
+        let mut vertices: Vec<_> = referenced
+            .into_iter()
+            .map(|i| {
+                let position = mesh.vertices.positions[i as usize];
+                let normal = mesh.vertices.normals[i as usize];
+                (position.to_array().map(f32::to_bits), normal)
+            })
+            .collect();
+
+        vertices.sort_by_key(|(position, _)| *position);
+        vertices
+    }
+
+    fn extract(
+        dc: &DualContouring<impl HermiteSource>,
+        normal_mode: NormalMode,
+    ) -> IndexedSeparateNormals {
+        // The mesher samples the normals of the source it contours, which is
+        // capped with `BoundaryPolicy::Cap`.
+        let capped = dc.capped();
+        let mut mesh = IndexedSeparateNormals::default();
+
+        match dc.boundary_policy {
+            BoundaryPolicy::Open => dc.extract(
+                WithIndexedSeparateNormals::new(&mut mesh, &dc.source)
+                    .with_normal_mode(normal_mode),
+            ),
+            BoundaryPolicy::Cap => dc.extract(
+                WithIndexedSeparateNormals::new(&mut mesh, &capped.source)
+                    .with_normal_mode(normal_mode),
+            ),
+        }
+        .unwrap();
+
+        mesh
+    }
+
+    fn assert_same_mesh(mesher: &IncrementalMesher, expected: &IndexedSeparateNormals) {
+        assert_eq!(triangles(mesher.mesh()), triangles(expected));
+
+        // Face normals are summed in a different order.
+        let (actual, expected) = (vertices(mesher.mesh()), vertices(expected));
+        assert_eq!(actual.len(), expected.len());
+
+        for ((position, normal), (expected_position, expected_normal)) in
+            actual.iter().zip(&expected)
+        {
+            assert_eq!(position, expected_position);
+            assert!(
+                normal.abs_diff_eq(*expected_normal, 1e-5),
+                "{normal} {expected_normal}"
+            );
+        }
+    }
+
+    /// Checks that both the mesher made from `original` and its update to
+    /// `sculpted` match fresh extractions.
+    fn assert_update_matches<S: HermiteSource>(
+        original: DualContouring<S>,
+        sculpted: DualContouring<S>,
+        normal_mode: NormalMode,
+    ) -> IncrementalMesher {
+        let mut mesher = IncrementalMesher::new_with(&original, normal_mode).unwrap();
+        assert_same_mesh(&mesher, &extract(&original, normal_mode));
+
+        mesher.update(&sculpted, dirty()).unwrap();
+        assert_same_mesh(&mesher, &extract(&sculpted, normal_mode));
+        assert_ne!(
+            triangles(mesher.mesh()),
+            triangles(&extract(&original, normal_mode))
+        );
+
+        mesher
+    }
+
+    #[test]
+    fn full_matches_extract() {
+        let dc = DualContouring::new(source(false), 16, 1e-4);
+        let mesher = IncrementalMesher::new(&dc).unwrap();
+        assert_same_mesh(&mesher, &extract(&dc, NormalMode::default()));
+        assert_eq!(mesher.dead_vertices(), 0);
+    }
+
+    #[test]
+    fn sculpted_bump_matches_fresh_extraction() {
+        let original = DualContouring::new(source(false), 32, 1e-4);
+        let sculpted = DualContouring::new(source(true), 32, 1e-4);
+
+        let mut mesher = original.incremental().unwrap();
+        let before = mesher.mesh().vertices.positions.clone();
+
+        let report = mesher.update(&sculpted, dirty()).unwrap();
+        assert_eq!(report, ExtractionReport::default());
+        assert_same_mesh(&mesher, &extract(&sculpted, NormalMode::default()));
+
+        // Vertices far from the edit keep their slots.
+        for (vertex, position) in before.iter().enumerate() {
+            if position.distance(BUMP) > 0.5 {
+                assert_eq!(mesher.mesh().vertices.positions[vertex], *position);
+            }
+        }
+    }
+
+    #[test]
+    fn update_follows_normal_mode() {
+        for normal_mode in [
+            NormalMode::SampledFromSource,
+            NormalMode::AngleWeightedFromFaces,
+            NormalMode::AreaWeightedFromFaces,
+            NormalMode::AveragedFromHermiteData,
+        ] {
+            assert_update_matches(
+                DualContouring::new(source(false), 32, 1e-4),
+                DualContouring::new(source(true), 32, 1e-4),
+                normal_mode,
+            );
+        }
+    }
+
+    #[test]
+    fn update_follows_boundary_policy() {
+        // The bump sticks out of the bounds, and gets capped.
+        let bounds = Bounds::new(Vec3::splat(-0.7), Vec3::splat(0.7));
+        let dc = |bumped| {
+            DualContouring::new(source(bumped), 32, 1e-4)
+                .with_bounds(bounds)
+                .with_boundary_policy(BoundaryPolicy::Cap)
+        };
+
+        let mesher = assert_update_matches(dc(false), dc(true), NormalMode::default());
+        let plane = 0.7 - 1.4 / 32.0 / 2.0;
+        assert!(mesher
+            .mesh()
+            .positions()
+            .iter()
+            .any(|p| (p.x - plane).abs() < 1e-4));
+    }
+
+    #[test]
+    fn update_snaps_to_boundary() {
+        let bounds = Bounds::new(Vec3::splat(-0.7), Vec3::splat(0.7));
+        let dc = |bumped| {
+            DualContouring::new(source(bumped), 32, 1e-4)
+                .with_bounds(bounds)
+                .with_boundary_snap(256)
+        };
+
+        assert_update_matches(dc(false), dc(true), NormalMode::default());
+    }
+
+    #[test]
+    fn update_checks_self_intersections() {
+        let dc = |bumped| {
+            DualContouring::new(source(bumped), 32, 1e-4).with_self_intersection_check(true)
+        };
+
+        assert_update_matches(dc(false), dc(true), NormalMode::default());
+    }
+
+    #[test]
+    fn failed_update_leaves_mesh_unchanged() {
+        let original = DualContouring::new(source(false), 16, 1e-4);
+        let mut mesher = original.incremental().unwrap();
+        let mesh = |mesher: &IncrementalMesher| {
+            let mesh = mesher.mesh();
+            (
+                mesh.positions().to_vec(),
+                mesh.normals().to_vec(),
+                mesh.faces.clone(),
+            )
+        };
+        let before = mesh(&mesher);
+
+        // A single bisection step can't find the intersections.
+        let sculpted = DualContouring::new(source(true), 16, 1e-4).with_max_iter(1);
+        let error = mesher.update(&sculpted, dirty()).unwrap_err();
+        assert!(matches!(
+            error,
+            ExtractSurfaceError::SolverDidNotConverge { .. }
+        ));
+        assert_eq!(mesh(&mesher), before);
+    }
+
+    #[test]
+    fn compact_removes_dead_vertices() {
+        let original = DualContouring::new(source(true), 32, 1e-4);
+        let sculpted = DualContouring::new(source(false), 32, 1e-4);
+
+        // Removing the bump leaves its vertices dead.
+        let mut mesher = original.incremental().unwrap();
+        mesher.update(&sculpted, dirty()).unwrap();
+        let dead = mesher.dead_vertices();
+        assert!(dead > 0);
+
+        let (before, positions) = (triangles(mesher.mesh()), mesher.mesh().positions().to_vec());
+        let cells: Vec<_> = (0..positions.len() as u32)
+            .map(|vertex| mesher.vertex_cell(vertex))
+            .collect();
+        let remap = mesher.compact();
+
+        assert_eq!(remap.len(), cells.len());
+        assert_eq!(remap.iter().filter(|i| i.is_none()).count(), dead);
+        assert_eq!(mesher.mesh().vertices.positions.len(), cells.len() - dead);
+        assert_eq!(mesher.dead_vertices(), 0);
+        assert_eq!(triangles(mesher.mesh()), before);
+
+        for (old, new) in remap.iter().enumerate() {
+            assert_eq!(new.is_some(), cells[old].is_some());
+
+            if let Some(new) = *new {
+                assert_eq!(mesher.vertex_cell(new), cells[old]);
+                assert_eq!(
+                    mesher.mesh().vertices.positions[new as usize],
+                    positions[old]
+                );
+            }
+        }
+
+        // Updates still work on the compacted mesh.
+        mesher.update(&original, dirty()).unwrap();
+        assert_same_mesh(&mesher, &extract(&original, NormalMode::default()));
+    }
+}
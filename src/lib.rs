@@ -1,40 +1,2232 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "async")]
+mod async_source;
+mod budget;
+mod builder;
+pub mod cell;
+mod classify;
+mod contour;
+pub mod dc2d;
+#[cfg(feature = "debug-dump")]
+pub mod debug_dump;
+#[cfg(feature = "std")]
+mod export;
 mod extractor;
 mod geom;
+#[cfg(feature = "gltf")]
+mod gltf;
+mod half_edge;
+mod incremental;
 mod morton;
-mod source;
+mod octree;
+#[cfg(feature = "std")]
+mod persist;
+mod points;
+mod qef;
+pub mod simplify;
+mod smooth;
+pub mod source;
+#[cfg(feature = "std")]
+mod stream;
 mod topology;
+mod uv;
+pub mod validate;
+
+#[cfg(feature = "async")]
+pub use async_source::AsyncSource;
+pub use builder::{ConfigError, DualContouringBuilder};
+pub use classify::{corner_mask, CollectVoxels, CornerMask, VoxelSink};
+pub use contour::{BoundaryPolicy, FailurePolicy, IntersectionConfig, PlacedVertices, QuadSplit};
+#[cfg(feature = "std")]
+pub use export::{ObjWriter, PlyFormat};
+pub use extractor::{
+    CellVertexMap, CollectGroups, CountingExtractor, Extractor, FinishError, FlatMeshError,
+    GroupedExtractor, IndexedSeparateNormals, LocalIndexedSeparateNormals, MeshMetrics, NormalMode,
+    Orientation, OutputConvention, ParallelExtractor, SeparateNormals, SerialExtractor, Winding,
+    WithIndexedSeparateNormals, WithVertexCells,
+};
+pub use geom::{AxisKind, BMask3, Bounds, CornerKind, DirKind, EdgeKind, FaceKind};
+pub use half_edge::{HalfEdge, HalfEdgeMesh};
+pub use incremental::IncrementalMesher;
+pub use morton::MortonKey;
+pub use octree::{DetailRegions, Octree};
+#[cfg(feature = "std")]
+pub use persist::PersistError;
+pub use points::{CollectPoints, PointMode, PointSink};
+pub use smooth::SmoothOptions;
+pub use source::{
+    Capsule, Cylinder, Difference, DoublePrecision, FallbackInterval, FiniteDifference, GridSource,
+    HermiteSample, HermiteSource, HermiteSource64, InsideOutside, Interpolation, Intersection,
+    IntervalSource, Isovalue, Mirror, Negated, Plane, Repeat, Sample, Source, Source64, Tolerance,
+    Union, ZeroIsInside, ZeroSide,
+};
+#[cfg(feature = "std")]
+pub use stream::{LocalStreamingWriter, MeshStats, StreamingWriter};
+pub use topology::{corner_signs, edge_quad_order, CellSeq, Edge, OctreeCell, OctreeFace};
+pub use uv::{UvMode, WithUvs};
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use budget::OctreeBudget;
+use cell::{CellConfig, CurvatureRefinement};
+use extractor::WithCellVertexMap;
+use glam::{UVec3, Vec3};
+use source::{Capped, IntervalFn, SampleCache};
+#[cfg(feature = "std")]
+use std::io;
+
+/// Hash-based collections with `std`, and ordered ones otherwise, as `alloc`
+/// has no hash maps.
+mod collections {
+    #[cfg(feature = "std")]
+    pub(crate) use std::collections::{HashMap as Map, HashSet as Set};
+
+    #[cfg(not(feature = "std"))]
+    pub(crate) use alloc::collections::{BTreeMap as Map, BTreeSet as Set};
+}
 
-pub use extractor::{Extractor, IndexedSeparateNormals, WithIndexedSeparateNormals};
-pub use source::{FiniteDifference, HermiteSource, Source};
+/// Default maximum number of bisection steps when looking for an edge
+/// intersection.
+const DEFAULT_MAX_ITER: usize = 50;
+
+/// How far from the seed [`DualContouring::new_auto_bounds`] looks for the
+/// surface.
+pub const AUTO_BOUNDS_MAX_EXTENT: f32 = 1000.0;
+
+/// The reason the surface couldn't be extracted.
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum ExtractSurfaceError {
+    /// The source produced non-finite or zero normals at the intersections of
+    /// some edges.
+    #[error("the source is degenerate at the intersections of {edges} edges")]
+    SourceDegenerate { edges: usize },
+    /// The intersections of some edges weren't found within the iteration
+    /// limit. Retrying with a larger limit or a looser tolerance may help, or
+    /// so may another [`FailurePolicy`]. `cell` is the first cell, in the
+    /// order of their keys, around any of these edges.
+    #[error(
+        "the intersections of {edges} edges didn't converge within the iteration limit, \
+         the first around cell {cell:?}"
+    )]
+    SolverDidNotConverge { edges: usize, cell: MortonKey },
+    /// Both ends of some edges crossed by the surface are within the
+    /// tolerance of it, so their intersections are ambiguous, and
+    /// [`IntersectionConfig::on_indeterminate`] is [`FailurePolicy::Abort`].
+    /// `cell` is the first cell, in the order of their keys, around any of
+    /// these edges.
+    #[error(
+        "both ends of {edges} edges crossed by the surface are on it, \
+         the first around cell {cell:?}"
+    )]
+    IndeterminateIntersections { edges: usize, cell: MortonKey },
+    /// The octree has no interior edges, i.e., the resolution is 1.
+    #[error("the domain has no interior edges to extract the surface from")]
+    EmptyDomain,
+    /// The extraction was cancelled through [`Extractor::should_continue`].
+    #[error("the extraction was cancelled")]
+    Cancelled,
+    /// The extractor failed to write the mesh out, as reported by
+    /// [`Extractor::finish`].
+    #[error("failed to write the mesh: {message}")]
+    Io {
+        #[cfg(feature = "std")]
+        kind: io::ErrorKind,
+        message: String,
+    },
+}
+
+impl From<FinishError> for ExtractSurfaceError {
+    fn from(error: FinishError) -> Self {
+        ExtractSurfaceError::Io {
+            #[cfg(feature = "std")]
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+}
 
-pub struct ExtractSurfaceError;
+/// Details about a successful extraction.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ExtractionReport {
+    /// Cells whose vertices are still involved in self-intersections after
+    /// repairing, if [`DualContouring::with_self_intersection_check`] is
+    /// enabled.
+    pub self_intersecting_cells: Vec<MortonKey>,
+    /// Cells around edges that were skipped because the source is undefined
+    /// at either of their ends, or somewhere in between. The mesh has holes
+    /// there, unless [`DualContouring::with_undefined_as`] is set.
+    pub undefined_cells: Vec<MortonKey>,
+    /// Number of edges whose intersections weren't found, contoured with
+    /// [`FailurePolicy::UseBestSample`].
+    pub best_sample_edges: usize,
+    /// Number of edges whose intersections weren't found, contoured with
+    /// [`FailurePolicy::UseMidpoint`].
+    pub midpoint_edges: usize,
+    /// Number of edges whose intersections weren't found, skipped with
+    /// [`FailurePolicy::SkipEdge`].
+    pub skipped_edges: usize,
+    /// Whether the octree was coarsened to stay within
+    /// [`DualContouring::with_memory_budget`].
+    pub budget_exceeded: bool,
+    /// The level of the finest leaf within each octant of the octree's
+    /// root, indexed by the [`BMask3`] bits of the octant, e.g., to tell
+    /// which parts of the mesh were coarsened by the memory budget.
+    pub max_level_reached_per_octant: [u32; 8],
+    /// The largest cells over which the source is within the tolerance of
+    /// the surface everywhere, with [`DualContouring::with_interval_culling`].
+    /// They aren't subdivided, as the surface is ambiguous within them.
+    pub indeterminate_cells: Vec<MortonKey>,
+}
 
+#[derive(Clone)]
 pub struct DualContouring<S> {
     source: S,
-    max_res: u32,
-    epsilon: f32,
+    bounds: Bounds,
+    max_res: UVec3,
+    /// The tolerance, or `None` to derive it from the bounds and resolution.
+    tolerance: Option<Tolerance>,
+    /// The offset of the lattice of the octree from the bounds, or `None` to
+    /// derive it from the size of the cells.
+    lattice_offset: Option<Vec3>,
+    intersection: IntersectionConfig,
+    assume_lipschitz: bool,
+    /// Bounds the values of the source over a box, if culling by intervals.
+    interval: Option<IntervalFn<S>>,
+    curvature: Option<CurvatureRefinement<S>>,
+    quad_split: QuadSplit,
+    check_self_intersections: bool,
+    undefined_is: Option<InsideOutside>,
+    detail_regions: Option<DetailRegions>,
+    point_mode: PointMode,
+    linear_estimate: bool,
+    /// The subdivisions of a cell that vertices of cells on the boundary of
+    /// the bounds are snapped to, if any.
+    boundary_snap: Option<u32>,
+    boundary_policy: BoundaryPolicy,
+    memory_budget: Option<usize>,
 }
 
 impl<S> DualContouring<S> {
+    /// Creates a new `DualContouring` over the `[-1, 1]³` cube.
+    ///
+    /// # Panics
+    ///
+    /// If `max_res` isn't a power of two no larger than
+    /// `2^MortonKey::LEVELS`, or `epsilon` isn't finite and greater than 0.
+    /// [`DualContouring::builder`] reports these as errors instead.
     pub fn new(source: S, max_res: u32, epsilon: f32) -> Self {
-        assert!(
-            max_res.is_power_of_two(),
-            "`max_res` must be a power of two"
-        );
+        let builder = Self::builder(source).max_res(max_res).epsilon(epsilon);
+        check(builder.build())
+    }
 
-        assert!(epsilon.is_finite(), "`epsilon` must be finite");
-        assert!(epsilon > 0.0, "`epsilon` must be greater than 0");
+    /// Like [`DualContouring::new`], with a resolution per axis, e.g., to
+    /// extract a volume whose slices are farther apart than its samples
+    /// within a slice. See [`DualContouring::with_max_res_per_axis`].
+    ///
+    /// The resolution needn't match the dimensions of a [`GridSource`], which
+    /// interpolates between its samples.
+    ///
+    /// # Panics
+    ///
+    /// If any component of `max_res` isn't a power of two no larger than
+    /// `2^MortonKey::LEVELS`, or `epsilon` isn't finite and greater than 0.
+    pub fn with_resolution(source: S, max_res: UVec3, epsilon: f32) -> Self {
+        Self::new(source, max_res.max_element(), epsilon).with_max_res_per_axis(max_res)
+    }
 
+    /// Like [`DualContouring::new`], over a box containing the surface
+    /// around `seed`, estimated with [`Bounds::estimate`] within
+    /// [`AUTO_BOUNDS_MAX_EXTENT`] of `seed` and padded by a cell on every
+    /// side. Returns `None` if no surface is found.
+    ///
+    /// # Panics
+    ///
+    /// Like [`DualContouring::new`].
+    pub fn new_auto_bounds(source: S, seed: Vec3, max_res: u32, epsilon: f32) -> Option<Self>
+    where
+        S: Source,
+    {
+        let bounds = Bounds::estimate(&source, seed, AUTO_BOUNDS_MAX_EXTENT)?;
+        let cell_size = bounds.size().max_element() / max_res as f32;
+        let bounds = bounds.expand(Vec3::splat(cell_size));
+        Some(Self::new(source, max_res, epsilon).with_bounds(bounds))
+    }
+
+    /// Returns a builder starting from `source`, which validates the options
+    /// as a whole rather than panicking.
+    pub fn builder(source: S) -> DualContouringBuilder<S> {
+        DualContouringBuilder::new(source)
+    }
+
+    fn new_unchecked(source: S, max_res: u32, tolerance: Tolerance) -> Self {
         DualContouring {
             source,
-            max_res,
-            epsilon,
+            bounds: Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0)),
+            max_res: UVec3::splat(max_res),
+            tolerance: Some(tolerance),
+            lattice_offset: Some(Vec3::ZERO),
+            intersection: IntersectionConfig::default(),
+            assume_lipschitz: false,
+            interval: None,
+            curvature: None,
+            quad_split: QuadSplit::default(),
+            check_self_intersections: false,
+            undefined_is: None,
+            detail_regions: None,
+            point_mode: PointMode::CellVertices,
+            linear_estimate: false,
+            boundary_snap: None,
+            boundary_policy: BoundaryPolicy::Open,
+            memory_budget: None,
+        }
+    }
+
+    /// Sets the box to extract the surface within.
+    pub fn with_bounds(mut self, bounds: Bounds) -> Self {
+        check(builder::check_bounds(bounds));
+        self.bounds = bounds;
+        self
+    }
+
+    /// Sets the resolution of the finest cells along each axis separately,
+    /// e.g., to resolve a volume more finely along one axis.
+    ///
+    /// The octree stays cubic in index space, with the resolution of the
+    /// finest axis. It spans a box stretched along the coarser axes, so that
+    /// the bounds only cover `max_res` of its finest cells along each axis,
+    /// and the cells outside of the bounds are neither subdivided nor
+    /// contoured. Cells are thus stretched in world space along the coarser
+    /// axes.
+    ///
+    /// Vertices are still placed by minimizing the QEF of world-space
+    /// intersections and normals, and [`QuadSplit`] compares world-space
+    /// normals, so neither is skewed by the stretched cells, as long as the
+    /// source's gradients are in world units.
+    ///
+    /// # Panics
+    ///
+    /// If any component isn't a power of two no larger than
+    /// `2^MortonKey::LEVELS`.
+    pub fn with_max_res_per_axis(mut self, max_res: UVec3) -> Self {
+        for res in max_res.to_array() {
+            check(builder::check_max_res(res));
+        }
+
+        self.max_res = max_res;
+        self
+    }
+
+    /// Sets the tolerance used to find the intersections of the surface with
+    /// the edges of the octree.
+    ///
+    /// [`DualContouring::new`] sets a [`Tolerance::Absolute`] tolerance of
+    /// `epsilon`, which serves as both the field value and the distance
+    /// threshold. See also [`DualContouring::with_auto_epsilon`]. Fields
+    /// that aren't unit-scale distance fields, such as metaballs or
+    /// densities, are better served by [`Tolerance::Spatial`].
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        check(builder::check_tolerance(tolerance));
+        self.tolerance = Some(tolerance);
+        self
+    }
+
+    /// Derives the tolerance from the bounds and the resolution, whenever
+    /// they're set, instead of using a fixed one.
+    ///
+    /// This is a [`Tolerance::Spatial`] tolerance of a thousandth of the
+    /// size of the finest cells: intersections are refined to that distance
+    /// along edges, and field values are compared to that distance scaled by
+    /// the secant slope of the field over each edge. Unlike an absolute
+    /// `epsilon`, this neither snaps intersections to the ends of the edges
+    /// of a small domain nor asks for more precision than `f32` has over a
+    /// large one.
+    pub fn with_auto_epsilon(mut self) -> Self {
+        self.tolerance = None;
+        self
+    }
+
+    /// Shifts the lattice of the octree by `offset` from the bounds, which
+    /// should be smaller than a finest cell along every axis, e.g., so that
+    /// planes of the surface don't coincide with the faces of the cells.
+    /// Defaults to no offset.
+    ///
+    /// Only the cells move, not the surface: vertices are still placed in
+    /// the coordinates of the source. The octree spans the bounds shifted by
+    /// `offset`, so the extracted region shifts along. Chunks of a larger
+    /// volume extracted separately agree on their lattices as long as they
+    /// share the offset and their bounds are a whole number of cells apart.
+    ///
+    /// # Panics
+    ///
+    /// If `offset` isn't finite.
+    pub fn with_lattice_offset(mut self, offset: Vec3) -> Self {
+        check(builder::check_lattice_offset(offset));
+        self.lattice_offset = Some(offset);
+        self
+    }
+
+    /// Shifts the lattice of the octree by `(0.5/φ, 0.5/φ², 0.5/φ³)` finest
+    /// cells from the bounds, where φ is the golden ratio, whenever the
+    /// bounds and resolution are set, see
+    /// [`DualContouring::with_lattice_offset`].
+    ///
+    /// These fractions are irrational, and unrelated along every axis, so
+    /// that axis-aligned planes at round coordinates, e.g., `p.y - 0.5`, as
+    /// well as the edges and corners of axis-aligned boxes, are away from
+    /// the faces, edges and corners of the cells. The offset only depends on
+    /// the size of the cells, so chunks of the same resolution share it.
+    pub fn with_auto_lattice_offset(mut self) -> Self {
+        self.lattice_offset = None;
+        self
+    }
+
+    /// Returns the offset of the lattice of the octree from the bounds,
+    /// deriving it if it's automatic (see
+    /// [`DualContouring::with_auto_lattice_offset`]).
+    pub(crate) fn lattice_offset(&self) -> Vec3 {
+        const PHI: f32 = 1.618_034;
+
+        self.lattice_offset.unwrap_or_else(|| {
+            let cell_size = self.bounds.size() / self.max_res.as_vec3();
+            cell_size * Vec3::new(0.5 / PHI, 0.5 / (PHI * PHI), 0.5 / (PHI * PHI * PHI))
+        })
+    }
+
+    /// Sets the maximum number of bisection steps when looking for the
+    /// intersection of the surface with an edge. Defaults to 50.
+    ///
+    /// Each step halves the bracket around the intersection, which starts as
+    /// the whole edge, so reaching a distance of `epsilon` along an edge of a
+    /// finest cell takes about `log2(size / max_res / epsilon)` steps, where
+    /// `size` is the size of the bounds. For instance, a resolution of 256
+    /// over the default bounds and an `epsilon` of `1e-6` take 13 steps.
+    ///
+    /// Steps stop early once the field value is within the tolerance, but a
+    /// field much steeper than a distance field (e.g., a near-tangent or
+    /// high-frequency one) may only get there once the bracket is much
+    /// smaller than `epsilon`. Edges whose intersections aren't found within
+    /// the limit make the extraction fail with
+    /// [`ExtractSurfaceError::SolverDidNotConverge`], unless set otherwise
+    /// with [`DualContouring::with_intersection_config`].
+    pub fn with_max_iter(mut self, max_iter: usize) -> Self {
+        check(builder::check_max_iter(max_iter));
+        self.intersection.max_iter = max_iter;
+        self
+    }
+
+    /// Sets the maximum number of bisection steps, see
+    /// [`DualContouring::with_max_iter`], and what to do with edges whose
+    /// intersections can't be found, e.g., to get a mesh out of a noisy
+    /// volume where a few edges misbehave.
+    pub fn with_intersection_config(mut self, config: IntersectionConfig) -> Self {
+        check(builder::check_max_iter(config.max_iter));
+        self.intersection = config;
+        self
+    }
+
+    /// Sets whether to first estimate the intersection of the surface with
+    /// an edge by interpolating the values at its ends, and only bisect if
+    /// the value there isn't within the tolerance.
+    ///
+    /// This takes a single sample per edge where the field is locally
+    /// linear, e.g., along planes and the faces of boxes, but costs one
+    /// wasted sample per edge elsewhere. Disabled by default.
+    pub fn with_linear_estimate(mut self, linear_estimate: bool) -> Self {
+        self.linear_estimate = linear_estimate;
+        self
+    }
+
+    /// Sets how quads are split into triangles. Defaults to
+    /// [`QuadSplit::MaxMinAngle`].
+    pub fn with_quad_split(mut self, quad_split: QuadSplit) -> Self {
+        self.quad_split = quad_split;
+        self
+    }
+
+    /// Sets whether to check the extracted mesh for self-intersections.
+    ///
+    /// When enabled, every face is tested against the nearby faces (those
+    /// whose bounding boxes overlap it). The vertices of intersecting faces are
+    /// re-solved with increasing regularization towards the mass points of
+    /// their cells, a few times at most, and the cells that still intersect
+    /// are listed in [`ExtractionReport::self_intersecting_cells`]. Disabled
+    /// by default.
+    pub fn with_self_intersection_check(mut self, check: bool) -> Self {
+        self.check_self_intersections = check;
+        self
+    }
+
+    /// Sets which side of the surface the regions where the source is
+    /// undefined are on (see [`Source::is_defined`]).
+    ///
+    /// By default, edges with an undefined end are skipped, leaving holes in
+    /// the mesh, and their cells are listed in
+    /// [`ExtractionReport::undefined_cells`]. With this set, the surface is
+    /// closed along the boundary of the defined region wherever the defined
+    /// side is on the other side of the surface. Edges along which the source
+    /// is only undefined in between their ends are skipped either way.
+    pub fn with_undefined_as(mut self, side: InsideOutside) -> Self {
+        self.undefined_is = Some(side);
+        self
+    }
+
+    /// Sets whether the source can be assumed to be Lipschitz continuous with
+    /// a constant of at most 1, as is the case for exact signed distance
+    /// fields.
+    ///
+    /// This allows skipping the subdivision of cells that are provably far
+    /// from the surface, which greatly speeds up the extraction of sparse
+    /// surfaces. If the source isn't actually a distance field (or a bound of
+    /// one), parts of the surface may be missing. Disabled by default.
+    pub fn assume_lipschitz(mut self, assume_lipschitz: bool) -> Self {
+        self.assume_lipschitz = assume_lipschitz;
+        self
+    }
+
+    /// Sets how deep the octree may be subdivided in different regions of
+    /// the bounds, in addition to the resolution. By default, every cell
+    /// may be subdivided down to the resolution.
+    ///
+    /// The surface stays closed across regions of different levels, as faces
+    /// are generated around the minimal edges of the octree.
+    pub fn with_detail_regions(mut self, regions: DetailRegions) -> Self {
+        self.detail_regions = Some(regions);
+        self
+    }
+
+    /// Sets which points [`DualContouring::extract_points`] emits. Defaults
+    /// to [`PointMode::CellVertices`].
+    pub fn with_point_mode(mut self, point_mode: PointMode) -> Self {
+        self.point_mode = point_mode;
+        self
+    }
+
+    /// Snaps the vertices of the cells within a cell of the boundary of the
+    /// bounds to a lattice `subdivisions` times finer than the cells, e.g.,
+    /// 256, so that chunks of a larger volume extracted separately agree on
+    /// them exactly. Disabled by default.
+    ///
+    /// The cells on the boundary miss the intersections with the edges on the
+    /// boundary itself, so chunks whose bounds overlap by three cells only
+    /// place the vertices of the middle cells of the overlap from the same
+    /// Hermite data. These vertices may still differ by rounding errors, as
+    /// the octrees of the chunks sample slightly different points, which
+    /// leaves cracks between the meshes of the chunks. Snapping is a function
+    /// of the position alone, so it maps both vertices to the same point
+    /// unless they straddle the middle of two lattice points. The spacing of
+    /// the lattice is rounded to a power of two, and so is the same for
+    /// chunks whose cell sizes only differ by rounding errors. Normals
+    /// sampled at the vertices, as by [`WithIndexedSeparateNormals`], are
+    /// sampled at the snapped positions and thus agree too.
+    ///
+    /// # Panics
+    ///
+    /// If `subdivisions` is 0.
+    pub fn with_boundary_snap(mut self, subdivisions: u32) -> Self {
+        check(builder::check_snap_subdivisions(subdivisions));
+        self.boundary_snap = Some(subdivisions);
+        self
+    }
+
+    /// Sets what to do where the surface leaves the bounds of the extracted
+    /// mesh. Defaults to [`BoundaryPolicy::Open`].
+    ///
+    /// This doesn't affect [`DualContouring::extract_points`] nor
+    /// [`DualContouring::classify_volume`].
+    pub fn with_boundary_policy(mut self, policy: BoundaryPolicy) -> Self {
+        self.boundary_policy = policy;
+        self
+    }
+
+    /// Limits the memory the extraction is estimated to take to `bytes`,
+    /// coarsening the mesh rather than running out of memory when the
+    /// resolution is too high, e.g., on targets with little memory.
+    ///
+    /// The estimate accounts for the octree and for the data contouring
+    /// takes per vertex, assuming a vertex in every leaf, which is closest
+    /// with [`assume_lipschitz`], where only leaves near the surface are
+    /// subdivided. The buffers of the extractor are accounted for too if it
+    /// implements [`Extractor::reserve_hint`].
+    ///
+    /// The octree is built depth-first. Once subdividing a cell would exceed
+    /// the budget, no further cell is subdivided, so the parts of the octree
+    /// not visited yet stay coarse. The surface is contoured across cells of
+    /// different sizes as usual, so the mesh has no cracks between fine and
+    /// coarse parts. [`ExtractionReport::budget_exceeded`] and
+    /// [`ExtractionReport::max_level_reached_per_octant`] tell whether and
+    /// where the mesh was coarsened.
+    ///
+    /// This doesn't affect [`DualContouring::extract_uniform`] nor
+    /// incremental meshing.
+    ///
+    /// # Panics
+    ///
+    /// If `bytes` is 0.
+    ///
+    /// [`assume_lipschitz`]: DualContouring::assume_lipschitz
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        check(builder::check_memory_budget(bytes));
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Returns the same configuration over a different source, without
+    /// [interval culling](DualContouring::with_interval_culling) nor
+    /// [curvature refinement](DualContouring::with_curvature_refinement),
+    /// which are only known for the original one.
+    fn with_source<T>(&self, source: T) -> DualContouring<T> {
+        DualContouring {
+            source,
+            bounds: self.bounds,
+            max_res: self.max_res,
+            tolerance: self.tolerance,
+            lattice_offset: self.lattice_offset,
+            intersection: self.intersection,
+            assume_lipschitz: self.assume_lipschitz,
+            interval: None,
+            curvature: None,
+            quad_split: self.quad_split,
+            check_self_intersections: self.check_self_intersections,
+            undefined_is: self.undefined_is,
+            detail_regions: self.detail_regions.clone(),
+            point_mode: self.point_mode,
+            linear_estimate: self.linear_estimate,
+            boundary_snap: self.boundary_snap,
+            boundary_policy: self.boundary_policy,
+            memory_budget: self.memory_budget,
+        }
+    }
+
+    fn max_level(&self) -> u32 {
+        self.max_res.max_element().trailing_zeros()
+    }
+
+    /// Returns the box covered by the octree, which extends past the bounds
+    /// along the axes of lower resolution (see
+    /// [`DualContouring::with_max_res_per_axis`]), shifted by the lattice
+    /// offset (see [`DualContouring::with_lattice_offset`]).
+    pub(crate) fn octree_domain(&self) -> Bounds {
+        let stretch = UVec3::splat(self.max_res.max_element()) / self.max_res;
+        let min = self.bounds.min + self.lattice_offset();
+        let size = self.bounds.size() * stretch.as_vec3();
+        Bounds::new(min, min + size)
+    }
+
+    /// Returns whether the octree extends past the bounds, i.e., whether
+    /// resolutions differ by axis.
+    pub(crate) fn is_stretched(&self) -> bool {
+        self.max_res.min_element() != self.max_res.max_element()
+    }
+
+    /// Returns the range of `cell` along each axis, in finest cells of the
+    /// octree, the bounds covering `0..max_res`.
+    fn cell_range(&self, cell: &OctreeCell) -> (UVec3, UVec3) {
+        let size = 1 << (self.max_level() - cell.level());
+        let start = cell.key().coords() * size;
+        (start, start + size)
+    }
+
+    /// Returns whether the cell `key` is within a finest cell of the
+    /// boundary of the bounds, or lies partly outside of them.
+    pub(crate) fn is_near_bounds(&self, key: MortonKey) -> bool {
+        let size = 1 << (self.max_level() - key.level());
+        let start = key.coords() * size;
+        start.cmple(UVec3::ONE).any() || (start + size + 1).cmpge(self.max_res).any()
+    }
+
+    /// Returns whether `cell` lies entirely within the bounds.
+    pub(crate) fn is_within_bounds(&self, cell: &OctreeCell) -> bool {
+        let (_, end) = self.cell_range(cell);
+        end.cmple(self.max_res).all()
+    }
+
+    /// Returns whether `cell` lies partly outside of the bounds, which only
+    /// happens with different resolutions per axis. Such cells must be
+    /// subdivided.
+    pub(crate) fn straddles_bounds(&self, cell: &OctreeCell) -> bool {
+        let (start, _) = self.cell_range(cell);
+        start.cmplt(self.max_res).all() && !self.is_within_bounds(cell)
+    }
+
+    /// Returns the configuration of [`contour_cell`](cell::contour_cell)
+    /// matching this extraction.
+    pub fn cell_config(&self) -> CellConfig {
+        CellConfig {
+            tolerance: self.tolerance(),
+            intersection: self.intersection,
+            linear_estimate: self.linear_estimate,
+            undefined_is: self.undefined_is,
+        }
+    }
+
+    /// Returns the tolerance as a field value, which only absolute
+    /// tolerances are, and 0 otherwise.
+    fn absolute_epsilon(&self) -> f32 {
+        match self.tolerance() {
+            Tolerance::Absolute(epsilon) => epsilon,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the tolerance, deriving it if it's automatic (see
+    /// [`DualContouring::with_auto_epsilon`]).
+    pub(crate) fn tolerance(&self) -> Tolerance {
+        self.tolerance.unwrap_or_else(|| {
+            let cell_size = (self.bounds.size() / self.max_res.as_vec3()).min_element();
+            Tolerance::Spatial(cell_size * 1e-3)
+        })
+    }
+
+    /// Returns whether `cell` may be subdivided, which only cells within the
+    /// bounds allowed by the detail regions and cells straddling the bounds
+    /// may.
+    pub(crate) fn may_subdivide(&self, cell: &OctreeCell) -> bool {
+        if !self.is_within_bounds(cell) {
+            return self.straddles_bounds(cell);
+        }
+
+        self.detail_regions.as_ref().is_none_or(|regions| {
+            cell.key().level() < regions.max_level(&cell.bounds(&self.octree_domain()))
+        })
+    }
+}
+
+/// Panics with the error, for the methods of [`DualContouring`] that don't
+/// return it.
+#[track_caller]
+fn check<T>(result: Result<T, ConfigError>) -> T {
+    result.unwrap_or_else(|error| panic!("{error}"))
+}
+
+impl<S: IntervalSource> DualContouring<S> {
+    /// Sets whether to skip the cells the surface provably doesn't cross,
+    /// as told by the bounds of the source over them (see
+    /// [`IntervalSource`]), without sampling them. Disabled by default.
+    ///
+    /// Unlike [`DualContouring::assume_lipschitz`], this holds for any
+    /// source whose bounds are valid, and takes no samples to build the
+    /// octree. Both can be enabled, in which case a cell is only subdivided
+    /// if neither rules out the surface. Cells over which the source is
+    /// within an absolute tolerance of 0 everywhere aren't subdivided
+    /// either, and are listed in [`ExtractionReport::indeterminate_cells`].
+    ///
+    /// The bounds don't account for where the source is undefined, so the
+    /// surface closed along undefined regions by
+    /// [`DualContouring::with_undefined_as`] may be culled. Neither
+    /// [`BoundaryPolicy::Cap`] nor [`DualContouring::extract_isosurfaces`]
+    /// culls cells, as they extract a different source.
+    pub fn with_interval_culling(mut self, interval_culling: bool) -> Self {
+        self.interval = interval_culling.then_some(S::sample_interval as IntervalFn<S>);
+        self
+    }
+}
+
+impl<S: Source> DualContouring<S> {
+    /// Builds the octree the surface is extracted from.
+    ///
+    /// With different resolutions per axis, the octree spans more than the
+    /// bounds, see [`DualContouring::with_max_res_per_axis`].
+    pub fn build_octree(&self) -> Octree {
+        self.build_octree_within_budget(0).0
+    }
+
+    /// Builds the octree within the memory budget, if any, given the bytes
+    /// the extractor is estimated to take per vertex.
+    fn build_octree_within_budget(&self, extractor_cost: usize) -> (Octree, OctreeBudget) {
+        let domain = self.octree_domain();
+        let mut budget =
+            OctreeBudget::new(self.memory_budget, contour::VERTEX_COST + extractor_cost);
+
+        let epsilon = self.absolute_epsilon();
+        let config = self.cell_config();
+
+        let mut subdivide = |cell: &OctreeCell, may_contain_surface: bool| {
+            if self.straddles_bounds(cell) {
+                budget.force_subdivide(cell);
+                return true;
+            }
+
+            if !self.may_subdivide(cell) || !may_contain_surface {
+                return false;
+            }
+
+            if let Some(interval) = self.interval {
+                let bounds = cell.bounds(&domain);
+                let (lo, hi) = interval(&self.source, bounds.min, bounds.max);
+
+                if lo > 0.0 || hi < 0.0 {
+                    return false;
+                } else if lo >= -epsilon && hi <= epsilon {
+                    budget.flag_indeterminate(cell);
+                    return false;
+                }
+            }
+
+            if let Some(refinement) = &self.curvature {
+                let bounds = cell.bounds(&domain);
+
+                if !refinement.may_subdivide(&self.source, &bounds, cell.level(), &config) {
+                    return false;
+                }
+            }
+
+            budget.subdivide(cell)
+        };
+
+        let octree = if !self.assume_lipschitz {
+            Octree::build(domain, self.max_level(), |cell| subdivide(cell, true))
+        } else {
+            Octree::build_sampled(
+                domain,
+                self.max_level(),
+                |point| self.source.sample(point),
+                |cell, corners| {
+                    subdivide(cell, may_contain_surface(&cell.bounds(&domain), corners))
+                },
+            )
+        };
+
+        (octree, budget)
+    }
+
+    /// Classifies every leaf cell of the octree by the corner mask of its
+    /// cell (see [`corner_mask`]), without extracting the surface.
+    ///
+    /// Cells whose corners are all inside are fed to `sink` as interior
+    /// cells, and cells with corners on both sides as boundary cells. Cells
+    /// whose corners are all outside are skipped. Note that a thin feature
+    /// may cross a cell without separating its corners, so the boundary
+    /// cells aren't guaranteed to cover the whole surface.
+    ///
+    /// With [`assume_lipschitz`], leaves far from the surface are coarser
+    /// than the resolution, and are reported as such. So are leaves limited
+    /// by [`with_detail_regions`].
+    ///
+    /// [`assume_lipschitz`]: DualContouring::assume_lipschitz
+    /// [`with_detail_regions`]: DualContouring::with_detail_regions
+    pub fn classify_volume(&self, mut sink: impl VoxelSink) {
+        let epsilon = self.absolute_epsilon();
+
+        let octree = self.build_octree();
+
+        for cell in octree.leaves().filter(|cell| self.is_within_bounds(cell)) {
+            let mask = corner_mask(&self.source, &cell.bounds(octree.domain()), epsilon);
+
+            if mask.count_inside() == 8 {
+                sink.interior_cell(cell.key());
+            } else if !mask.is_homogeneous() {
+                sink.boundary_cell(cell.key());
+            }
         }
+
+        sink.finish();
     }
 }
 
+/// Returns `false` if the surface of a Lipschitz source provably doesn't
+/// intersect `bounds`, i.e., if all of its corners are on the same side of
+/// the surface and farther from it than the length of its diagonal, given
+/// the values at its corners.
+fn may_contain_surface(bounds: &Bounds, values: &[f32; 8]) -> bool {
+    let diagonal = bounds.size().length();
+    let inside = source::is_inside(values[0]);
+
+    !values
+        .iter()
+        .all(|&v| source::is_inside(v) == inside && v.abs() > diagonal)
+}
+
 impl<S: HermiteSource> DualContouring<S> {
-    pub fn extract(&self, _extractor: impl Extractor) -> Result<(), ExtractSurfaceError> {
-        todo!()
+    /// Sets the octree to only be subdivided where the surface bends, so
+    /// that the mesh is dense where it's curved and sparse where it's flat.
+    ///
+    /// Cells crossed by the surface below `min_level` are subdivided as
+    /// usual. Deeper ones are only subdivided if their curvature, as
+    /// estimated by [`cell_curvature`], exceeds `max_angle`, in radians.
+    /// This contours every such cell once while building the octree. Cells
+    /// whose edges the surface doesn't cross are subdivided as usual, unless
+    /// culled otherwise, as they may still contain parts of it.
+    ///
+    /// A flat cell may hide small features of the surface that don't cross
+    /// its edges, so `min_level` should be deep enough to resolve them.
+    ///
+    /// # Panics
+    ///
+    /// If `max_angle` is negative or isn't finite.
+    ///
+    /// [`cell_curvature`]: cell::cell_curvature
+    pub fn with_curvature_refinement(mut self, max_angle: f32, min_level: u32) -> Self {
+        check(builder::check_curvature_angle(max_angle));
+        self.curvature = Some(CurvatureRefinement::new(max_angle, min_level));
+        self
+    }
+
+    /// Extracts the surface into `extractor`.
+    ///
+    /// The output is deterministic: all vertices are extracted first, ordered
+    /// by the [`MortonKey`] of the cell producing them, followed by the faces,
+    /// ordered by the key of the minimal edge they come from (see
+    /// [`Edge::key`]) and then by its axis. The order doesn't depend on the
+    /// iteration order of hash maps.
+    pub fn extract(
+        &self,
+        mut extractor: impl Extractor,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        match self.boundary_policy {
+            BoundaryPolicy::Open => self.extract_open(&mut extractor),
+            BoundaryPolicy::Cap => self.capped().extract_open(&mut extractor),
+        }
+    }
+
+    fn extract_open(
+        &self,
+        extractor: &mut impl Extractor,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        if self.max_res.min_element() < 2 {
+            return Err(ExtractSurfaceError::EmptyDomain);
+        }
+
+        let (octree, budget) = self.build_octree_within_budget(extractor.reserve_hint(1, 2));
+        let mut report = contour::contour(self, &octree, extractor)?;
+        budget.report(&mut report);
+        Ok(report)
+    }
+
+    /// Returns the configuration over the source clipped half a finest cell
+    /// inside the bounds, shifted by the lattice offset, see
+    /// [`BoundaryPolicy::Cap`].
+    pub(crate) fn capped(&self) -> DualContouring<Capped<&S>> {
+        let margin = self.bounds.size() / self.max_res.as_vec3() / 2.0;
+        let offset = self.lattice_offset();
+        let bounds = Bounds::new(self.bounds.min + offset, self.bounds.max + offset);
+        self.with_source(Capped::new(&self.source, bounds.expand(-margin)))
+    }
+
+    /// Runs the part of [`DualContouring::extract`] that samples the source,
+    /// placing the vertices of the mesh and connecting them, without feeding
+    /// them to an extractor yet.
+    ///
+    /// The result can be passed to [`DualContouring::generate_faces`] later,
+    /// e.g., on another thread, any number of times.
+    pub fn place_vertices(&self) -> Result<PlacedVertices, ExtractSurfaceError> {
+        match self.boundary_policy {
+            BoundaryPolicy::Open => self.place_vertices_open(),
+            BoundaryPolicy::Cap => self.capped().place_vertices_open(),
+        }
+    }
+
+    fn place_vertices_open(&self) -> Result<PlacedVertices, ExtractSurfaceError> {
+        if self.max_res.min_element() < 2 {
+            return Err(ExtractSurfaceError::EmptyDomain);
+        }
+
+        let (octree, budget) = self.build_octree_within_budget(0);
+        let mut placed = contour::place_vertices(self, &octree, || true)?;
+        budget.report(&mut placed.report);
+        Ok(placed)
+    }
+
+    /// Feeds the mesh placed by [`DualContouring::place_vertices`] to
+    /// `extractor`, in the same order as [`DualContouring::extract`], without
+    /// sampling the source.
+    pub fn generate_faces(
+        &self,
+        placed: &PlacedVertices,
+        mut extractor: impl Extractor,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        contour::generate_faces(placed, &mut extractor)
+    }
+
+    /// Like [`DualContouring::extract`], but splits the mesh into groups of
+    /// faces, one per octree node at `level` that the surface crosses (up to
+    /// `8^level` of them), e.g., to build a physics collider out of many
+    /// small pieces rather than a single one.
+    ///
+    /// A face whose vertices fall in different groups is in the group with
+    /// the smallest key only, so the groups partition the faces of the mesh.
+    /// Vertices are duplicated in every group using them, and vertices
+    /// without faces are left out. Vertices of cells coarser than `level` are
+    /// grouped by their cells.
+    pub fn extract_grouped(
+        &self,
+        level: u32,
+        mut extractor: impl GroupedExtractor,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        let placed = self.place_vertices()?;
+        contour::generate_groups(&placed, level, &mut extractor)
+    }
+
+    /// Like [`DualContouring::extract`], but also fills `map` with the leaf
+    /// cell that produced every vertex, e.g., to find the vertices within a
+    /// region of the octree. `map` is cleared first.
+    pub fn extract_with_map(
+        &self,
+        extractor: impl Extractor,
+        map: &mut CellVertexMap,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        self.extract(WithCellVertexMap::new(extractor, map))
+    }
+
+    /// Like [`DualContouring::extract`], but over a uniform grid of cells at
+    /// the resolution, ignoring [`assume_lipschitz`] and
+    /// [`with_detail_regions`].
+    ///
+    /// This is slower, but serves as a reference for the adaptive extraction,
+    /// which gives the same mesh wherever its leaves are at the resolution.
+    ///
+    /// [`assume_lipschitz`]: DualContouring::assume_lipschitz
+    /// [`with_detail_regions`]: DualContouring::with_detail_regions
+    pub fn extract_uniform(
+        &self,
+        mut extractor: impl Extractor,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        if self.max_res.min_element() < 2 {
+            return Err(ExtractSurfaceError::EmptyDomain);
+        }
+
+        let mut budget = OctreeBudget::new(None, 0);
+        let octree = Octree::build(self.octree_domain(), self.max_level(), |cell| {
+            (self.is_within_bounds(cell) || self.straddles_bounds(cell)) && budget.subdivide(cell)
+        });
+
+        let mut report = match self.boundary_policy {
+            BoundaryPolicy::Open => contour::contour(self, &octree, &mut extractor),
+            BoundaryPolicy::Cap => contour::contour(&self.capped(), &octree, &mut extractor),
+        }?;
+        budget.report(&mut report);
+        Ok(report)
+    }
+
+    /// Extracts oriented points on the surface instead of a mesh, which
+    /// skips generating faces. See [`DualContouring::with_point_mode`] for
+    /// which points are emitted.
+    ///
+    /// The cell vertices are the same as those of [`DualContouring::extract`]
+    /// without [`with_self_intersection_check`], in the same order. The
+    /// minimal edges are still walked to place them, but it's faster than
+    /// extracting a mesh. Points can be collected into [`SeparateNormals`],
+    /// e.g., for splatting.
+    ///
+    /// [`with_self_intersection_check`]: DualContouring::with_self_intersection_check
+    pub fn extract_points(
+        &self,
+        mut sink: impl PointSink,
+    ) -> Result<ExtractionReport, ExtractSurfaceError> {
+        if self.max_res.min_element() < 2 {
+            return Err(ExtractSurfaceError::EmptyDomain);
+        }
+
+        let (octree, budget) = self.build_octree_within_budget(0);
+        let mut report = points::extract_points(self, &octree, self.point_mode, &mut sink)?;
+        budget.report(&mut report);
+        Ok(report)
+    }
+
+    /// Extracts the level sets of the source at each of `isovalues`, see
+    /// [`Isovalue`], into an extractor made by `make_extractor` from the
+    /// index of the isovalue.
+    ///
+    /// Unlike extracting each level set separately, this samples the source
+    /// once per point and reuses the values for every isovalue, so only the
+    /// search for the intersections and the normals there take additional
+    /// samples. Every surface is the same as if it was extracted on its own.
+    ///
+    /// Extraction stops at the first error. Otherwise, the reports are
+    /// returned in the order of `isovalues`.
+    pub fn extract_isosurfaces<E: Extractor>(
+        &self,
+        isovalues: &[f32],
+        mut make_extractor: impl FnMut(usize) -> E,
+    ) -> Result<Vec<ExtractionReport>, ExtractSurfaceError> {
+        let cache = SampleCache::new(&self.source);
+
+        isovalues
+            .iter()
+            .enumerate()
+            .map(|(i, &isovalue)| {
+                self.with_source(Isovalue::new(&cache, isovalue))
+                    .extract(make_extractor(i))
+            })
+            .collect()
+    }
+
+    /// Extracts the surface into an [`IncrementalMesher`], which can later be
+    /// updated locally as the source is edited, see
+    /// [`IncrementalMesher::new`].
+    pub fn incremental(&self) -> Result<IncrementalMesher, ExtractSurfaceError> {
+        IncrementalMesher::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{from_fn, hermite_from_fn};
+    use alloc::format;
+    use alloc::vec;
+    use core::cell::Cell;
+    use glam::UVec3;
+
+    fn sorted_positions(mesh: &IndexedSeparateNormals) -> Vec<[u32; 3]> {
+        let mut positions: Vec<_> = mesh
+            .vertices
+            .positions
+            .iter()
+            .map(|p| p.to_array().map(f32::to_bits))
+            .collect();
+
+        positions.sort();
+        positions
+    }
+
+    /// Returns whether every directed edge is matched by exactly one opposite
+    /// edge.
+    fn is_closed(mesh: &IndexedSeparateNormals) -> bool {
+        let mut edges = crate::collections::Map::new();
+
+        for face in &mesh.faces {
+            for i in 0..3 {
+                *edges.entry((face[i], face[(i + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+
+        edges
+            .iter()
+            .all(|(&(a, b), &count)| count == 1 && edges.get(&(b, a)) == Some(&1))
+    }
+
+    #[test]
+    fn spatial_tolerance_handles_tiny_values() {
+        // A density-like metaball field, the values of which are orders of
+        // magnitude below any sensible absolute tolerance.
+        const CENTERS: [Vec3; 2] = [Vec3::new(-0.3, 0.0, 0.0), Vec3::new(0.3, 0.1, 0.0)];
+        const SCALE: f32 = 1e-6;
+
+        let metaballs = hermite_from_fn(
+            |p: Vec3| {
+                let density: f32 = CENTERS.iter().map(|c| 1.0 / p.distance_squared(*c)).sum();
+                SCALE * (16.0 - density)
+            },
+            |p| {
+                CENTERS
+                    .iter()
+                    .map(|c| 2.0 * (p - *c) / p.distance_squared(*c).powi(2))
+                    .sum()
+            },
+        );
+
+        let extract = |tolerance| {
+            let dc = DualContouring::new(&metaballs, 16, 1e-4).with_tolerance(tolerance);
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &metaballs))
+                .unwrap();
+            mesh
+        };
+
+        let absolute = extract(Tolerance::Absolute(1e-4));
+        assert!(absolute.faces.is_empty() || !is_closed(&absolute));
+
+        let spatial = extract(Tolerance::Spatial(1e-4));
+        assert!(!spatial.faces.is_empty());
+        assert!(is_closed(&spatial));
+    }
+
+    #[test]
+    fn auto_epsilon_is_scale_invariant() {
+        let extract = |radius: f32| {
+            let sphere = hermite_from_fn(move |p: Vec3| p.length() - radius, |p| p);
+            let bounds = Bounds::new(Vec3::splat(-1.5 * radius), Vec3::splat(1.5 * radius));
+            let dc = DualContouring::new(&sphere, 32, 1e-4)
+                .with_bounds(bounds)
+                .with_auto_epsilon();
+
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+                .unwrap();
+
+            assert!(is_closed(&mesh));
+            let cell_size = 3.0 * radius / 32.0;
+
+            for p in mesh.positions() {
+                assert!((p.length() - radius).abs() < 0.1 * cell_size);
+            }
+
+            mesh.positions().len()
+        };
+
+        let small = extract(0.01) as f32;
+        let large = extract(1000.0) as f32;
+        assert!((small / large - 1.0).abs() < 0.02, "{small} vs {large}");
+    }
+
+    /// Counts extracted elements, and cancels after being polled a number of
+    /// times.
+    struct Cancelling {
+        polls_left: Cell<u32>,
+        extracted: usize,
+    }
+
+    impl Extractor for Cancelling {
+        fn extract_vertex(&mut self, _: Vec3) {
+            self.extracted += 1;
+        }
+
+        fn extract_face(&mut self, _: [u32; 3]) {
+            self.extracted += 1;
+        }
+
+        fn should_continue(&self) -> bool {
+            let polls_left = self.polls_left.get();
+            self.polls_left.set(polls_left.saturating_sub(1));
+            polls_left > 0
+        }
+    }
+
+    #[test]
+    fn cancellation() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.5, |p| p);
+        let dc = DualContouring::new(sphere, 32, 1e-4);
+
+        let mut cancelled = Cancelling {
+            polls_left: Cell::new(3),
+            extracted: 0,
+        };
+
+        assert_eq!(
+            dc.extract(&mut cancelled),
+            Err(ExtractSurfaceError::Cancelled)
+        );
+        assert_eq!(cancelled.polls_left.get(), 0);
+        assert_eq!(cancelled.extracted, 0);
+
+        let mut completed = Cancelling {
+            polls_left: Cell::new(u32::MAX),
+            extracted: 0,
+        };
+
+        assert!(dc.extract(&mut completed).is_ok());
+        assert!(completed.extracted > 0);
+    }
+
+    #[test]
+    fn self_intersection_check_untangles_twisted_sheet() {
+        // A sheet much thinner than a cell, twisted around the Y axis.
+        let sheet = FiniteDifference::new(
+            from_fn(|p: Vec3| {
+                let (sin, cos) = (10.0 * p.y).sin_cos();
+                let slab = (sin * p.x + cos * p.z).abs() - 0.01;
+                let cylinder = Vec3::new(p.x, 0.0, p.z).length() - 0.7;
+                slab.max(cylinder).max(p.y.abs() - 0.7)
+            }),
+            1e-4,
+        );
+
+        let extract = |check| {
+            let mut mesh = IndexedSeparateNormals::default();
+            let report = DualContouring::new(&sheet, 16, 1e-4)
+                .with_self_intersection_check(check)
+                .extract(WithIndexedSeparateNormals::new(&mut mesh, &sheet))
+                .unwrap();
+            let intersections =
+                validate::find_self_intersections(&mesh.vertices.positions, &mesh.faces);
+            (report, intersections)
+        };
+
+        let (_, unchecked) = extract(false);
+        assert!(!unchecked.is_empty());
+
+        let (report, checked) = extract(true);
+        assert!(checked.is_empty());
+        assert!(report.self_intersecting_cells.is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.5, |p| p);
+        let extract = |dc: DualContouring<_>| {
+            dc.extract(WithIndexedSeparateNormals::new(
+                &mut IndexedSeparateNormals::default(),
+                &sphere,
+            ))
+        };
+
+        assert_eq!(
+            extract(DualContouring::new(&sphere, 1, 1e-4)),
+            Err(ExtractSurfaceError::EmptyDomain)
+        );
+
+        // A discontinuous field is never close enough to 0, and bisection
+        // can't get anywhere near such a tolerance.
+        let step = hermite_from_fn(|p: Vec3| (p.x - 0.3).signum(), |_| Vec3::X);
+        let mut mesh = IndexedSeparateNormals::default();
+        assert!(matches!(
+            DualContouring::new(&step, 8, 1e-4)
+                .with_tolerance(Tolerance::Absolute(1e-20))
+                .extract(WithIndexedSeparateNormals::new(&mut mesh, &step)),
+            Err(ExtractSurfaceError::SolverDidNotConverge { edges, .. }) if edges > 0
+        ));
+
+        // Too few steps to bisect down to the tolerance.
+        assert!(extract(DualContouring::new(&sphere, 8, 1e-4)).is_ok());
+        assert!(matches!(
+            extract(DualContouring::new(&sphere, 8, 1e-4).with_max_iter(2)),
+            Err(ExtractSurfaceError::SolverDidNotConverge { edges, .. }) if edges > 0
+        ));
+
+        // A flat gradient gives no normal to build the QEFs from.
+        let flat = hermite_from_fn(|p: Vec3| p.length() - 0.5, |_| Vec3::ZERO);
+        let mut mesh = IndexedSeparateNormals::default();
+        assert!(matches!(
+            DualContouring::new(&flat, 8, 1e-4).extract(WithIndexedSeparateNormals::new(&mut mesh, &flat)),
+            Err(ExtractSurfaceError::SourceDegenerate { edges }) if edges > 0
+        ));
+    }
+
+    #[test]
+    fn intersection_failure_policies() {
+        // Far too oscillating for a few bisection steps to converge. The
+        // normals ignore the ripples, which would otherwise flip faces.
+        let wavy = hermite_from_fn(
+            |p: Vec3| p.length() - 0.55 + 0.01 * (200.0 * p.x).sin(),
+            |p| p,
+        );
+
+        let extract = |on_iter_limit| {
+            let config = IntersectionConfig {
+                max_iter: 3,
+                on_iter_limit,
+                ..Default::default()
+            };
+            let mut mesh = IndexedSeparateNormals::default();
+            DualContouring::new(&wavy, 8, 1e-4)
+                .with_intersection_config(config)
+                .extract(WithIndexedSeparateNormals::new(&mut mesh, &wavy))
+                .map(|report| (mesh, report))
+        };
+
+        let error = extract(FailurePolicy::Abort).unwrap_err();
+        let ExtractSurfaceError::SolverDidNotConverge { edges, cell } = error else {
+            panic!("{error}");
+        };
+        assert!(edges > 0 && cell.level() == 3);
+        assert!(error.to_string().contains(&format!("{cell:?}")));
+
+        let (best, report) = extract(FailurePolicy::UseBestSample).unwrap();
+        assert!(is_closed(&best));
+        assert_eq!(report.best_sample_edges, edges);
+
+        let (midpoint, report) = extract(FailurePolicy::UseMidpoint).unwrap();
+        assert_eq!(midpoint.faces.len(), best.faces.len());
+        assert_eq!(report.midpoint_edges, edges);
+
+        // None of the edges converge, so no cell gets a vertex.
+        let (skipped, report) = extract(FailurePolicy::SkipEdge).unwrap();
+        assert_eq!(best.faces.len(), 2 * edges);
+        assert!(skipped.positions().is_empty() && skipped.faces.is_empty());
+        assert_eq!(report.skipped_edges, edges);
+
+        // A plane so flat that both ends of the edges crossing it are within
+        // the tolerance. They're skipped by default.
+        let flat = hermite_from_fn(|p: Vec3| (p.x - 0.3) * 1e-6, |_| Vec3::X);
+        let extract = |on_indeterminate| {
+            let config = IntersectionConfig {
+                on_indeterminate,
+                ..Default::default()
+            };
+            let mut mesh = IndexedSeparateNormals::default();
+            DualContouring::new(&flat, 8, 1e-4)
+                .with_intersection_config(config)
+                .extract(WithIndexedSeparateNormals::new(&mut mesh, &flat))
+                .map(|report| (mesh, report))
+        };
+
+        let (skipped, report) = extract(FailurePolicy::SkipEdge).unwrap();
+        assert!(skipped.faces.is_empty() && report.skipped_edges > 0);
+        assert!(matches!(
+            extract(FailurePolicy::Abort),
+            Err(ExtractSurfaceError::IndeterminateIntersections { edges, .. }) if edges == report.skipped_edges
+        ));
+
+        let (best, _) = extract(FailurePolicy::UseBestSample).unwrap();
+        let plane = hermite_from_fn(|p: Vec3| p.x - 0.3, |_| Vec3::X);
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(&plane, 8, 1e-4)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &plane))
+            .unwrap();
+        assert_eq!(best.faces, mesh.faces);
+    }
+
+    #[test]
+    fn cell_vertex_map() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let dc = DualContouring::new(&sphere, 16, 1e-4).assume_lipschitz(true);
+        let domain = dc.octree_domain();
+        let mut mesh = IndexedSeparateNormals::default();
+        let mut map = CellVertexMap::default();
+        dc.extract_with_map(
+            WithIndexedSeparateNormals::new(&mut mesh, &sphere),
+            &mut map,
+        )
+        .unwrap();
+        assert_eq!(map.len(), mesh.positions().len());
+
+        for (vertex, &position) in mesh.positions().iter().enumerate() {
+            let cell = map.cell_of(vertex as u32);
+            assert!(cell.cell_bounds(&domain).contains(position), "{position}");
+            assert_eq!(map.vertices_of(cell), vertex as u32..vertex as u32 + 1);
+        }
+
+        let root = OctreeCell::root().key();
+        assert!(map.vertices_of(root).is_empty());
+
+        let query = Bounds::new(Vec3::new(-0.2, 0.0, -1.0), Vec3::new(0.7, 0.4, 0.3));
+        let expected: Vec<u32> = (0..mesh.positions().len() as u32)
+            .filter(|&i| query.contains(mesh.positions()[i as usize]))
+            .collect();
+        assert!(!expected.is_empty());
+        assert_eq!(
+            map.vertices_in_bounds(&domain, &query).collect::<Vec<_>>(),
+            expected
+        );
+
+        // The map is cleared when reused.
+        mesh.reset();
+        dc.extract_with_map(
+            WithIndexedSeparateNormals::new(&mut mesh, &sphere),
+            &mut map,
+        )
+        .unwrap();
+        assert_eq!(map.len(), mesh.positions().len());
+    }
+
+    #[test]
+    fn undefined_regions() {
+        // A sphere baked into a grid, with a block of voxels masked out.
+        let dims = UVec3::splat(33);
+        let bounds = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let spacing = bounds.size() / (dims - 1).as_vec3();
+        let block = Bounds::new(Vec3::new(0.3, 0.3, -1.0), Vec3::new(1.0, 1.0, 1.0));
+
+        let values = (0..dims.element_product())
+            .map(|i| {
+                let index = UVec3::new(i % dims.x, i / dims.x % dims.y, i / (dims.x * dims.y));
+                let p = bounds.min + index.as_vec3() * spacing;
+
+                if block.contains(p) {
+                    f32::NAN
+                } else {
+                    p.length() - 0.6
+                }
+            })
+            .collect();
+
+        let grid = GridSource::new(dims, bounds, values);
+
+        // Values are interpolated, so the grid is undefined up to one voxel
+        // away from the block.
+        let undefined = Bounds::new(block.min - spacing, block.max);
+
+        let extract = |dc: DualContouring<_>| {
+            let mut mesh = IndexedSeparateNormals::default();
+            let report = dc
+                .with_bounds(bounds)
+                .extract(WithIndexedSeparateNormals::new(&mut mesh, &grid))
+                .unwrap();
+            (mesh, report)
+        };
+
+        let (mesh, report) = extract(DualContouring::new(&grid, 32, 1e-4));
+        assert!(!mesh.faces.is_empty());
+        assert!(!is_closed(&mesh));
+
+        for position in &mesh.vertices.positions {
+            assert!(!block.contains(*position));
+        }
+
+        assert!(!report.undefined_cells.is_empty());
+
+        for key in &report.undefined_cells {
+            assert!(key.cell_bounds(&bounds).intersects(&undefined));
+        }
+
+        // Considering the block to be outside closes the surface around it.
+        // The sharp corners of the block may flip a few faces, so only check
+        // that every edge is shared by two faces.
+        let (mesh, report) =
+            extract(DualContouring::new(&grid, 32, 1e-4).with_undefined_as(InsideOutside::Outside));
+        assert!(report.undefined_cells.is_empty());
+
+        let mut edges = crate::collections::Map::new();
+
+        for face in &mesh.faces {
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+
+        assert!(edges.values().all(|&count| count == 2));
+
+        for position in &mesh.vertices.positions {
+            assert!(!block.contains(*position));
+        }
+    }
+
+    #[test]
+    fn lipschitz_culling_preserves_surface() {
+        let samples = Cell::new(0);
+
+        let source = hermite_from_fn(
+            |p: Vec3| {
+                samples.set(samples.get() + 1);
+                (p - Vec3::splat(0.4)).length() - 0.3
+            },
+            |p| p - Vec3::splat(0.4),
+        );
+
+        let extract = |assume_lipschitz| {
+            samples.set(0);
+            let dc = DualContouring::new(&source, 32, 1e-4).assume_lipschitz(assume_lipschitz);
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &source))
+                .unwrap();
+            (mesh, samples.get())
+        };
+
+        let (full, full_samples) = extract(false);
+        let (culled, culled_samples) = extract(true);
+
+        assert!(!full.faces.is_empty());
+        assert_eq!(full.faces.len(), culled.faces.len());
+        assert_eq!(sorted_positions(&full), sorted_positions(&culled));
+        assert!(culled_samples < full_samples);
+    }
+
+    #[test]
+    fn interval_culling_skips_empty_space() {
+        /// A small sphere in a large domain, counting its samples.
+        struct Counted<'a>(Capsule, &'a Cell<usize>);
+
+        impl Source for Counted<'_> {
+            fn sample(&self, point: Vec3) -> f32 {
+                self.1.set(self.1.get() + 1);
+                self.0.sample(point)
+            }
+        }
+
+        impl HermiteSource for Counted<'_> {
+            fn gradient(&self, point: Vec3) -> Vec3 {
+                self.0.gradient(point)
+            }
+        }
+
+        impl IntervalSource for Counted<'_> {
+            fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+                self.0.sample_interval(min, max)
+            }
+        }
+
+        let samples = Cell::new(0);
+        let sphere = Counted(
+            Capsule::new(Vec3::splat(3.1), Vec3::splat(3.1), 0.7),
+            &samples,
+        );
+        let bounds = Bounds::new(Vec3::splat(-10.0), Vec3::splat(10.0));
+
+        let extract = |interval_culling| {
+            samples.set(0);
+            let dc = DualContouring::new(&sphere, 64, 1e-4)
+                .with_bounds(bounds)
+                .with_interval_culling(interval_culling);
+            let mut mesh = IndexedSeparateNormals::default();
+            let report = dc
+                .extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+                .unwrap();
+            assert!(report.indeterminate_cells.is_empty());
+            (mesh, samples.get())
+        };
+
+        let (full, full_samples) = extract(false);
+        let (culled, culled_samples) = extract(true);
+
+        assert!(!full.faces.is_empty());
+        assert_eq!(full.faces.len(), culled.faces.len());
+        assert_eq!(sorted_positions(&full), sorted_positions(&culled));
+        assert!(
+            culled_samples * 20 < full_samples,
+            "{culled_samples} {full_samples}"
+        );
+    }
+
+    #[test]
+    fn interval_culling_flags_flat_cells() {
+        let zero = FallbackInterval::new(hermite_from_fn(|_| 0.0, |_| Vec3::X), 0.0);
+        let dc = DualContouring::new(&zero, 16, 1e-4).with_interval_culling(true);
+        let report = dc.extract(CountingExtractor::default()).unwrap();
+
+        assert_eq!(report.indeterminate_cells, vec![MortonKey::root()]);
+    }
+
+    #[test]
+    fn curvature_refinement_coarsens_flat_regions() {
+        let cylinder = Cylinder::new(Vec3::new(0.02, -0.03, 0.01), AxisKind::Z, 0.6, 0.5);
+
+        let extract = |refine| {
+            let mut dc = DualContouring::new(cylinder, 64, 1e-6);
+
+            if refine {
+                dc = dc.with_curvature_refinement(0.1, 3);
+            }
+
+            let mut mesh = IndexedSeparateNormals::default();
+            let mut cells = Vec::new();
+            dc.extract(WithVertexCells::new(
+                WithIndexedSeparateNormals::new(&mut mesh, cylinder),
+                &mut cells,
+            ))
+            .unwrap();
+            (mesh, cells)
+        };
+
+        let (full, _) = extract(false);
+        let (refined, cells) = extract(true);
+
+        assert!(is_closed(&refined));
+        assert!(
+            refined.positions().len() * 3 < full.positions().len() * 2,
+            "{} {}",
+            refined.positions().len(),
+            full.positions().len()
+        );
+
+        // The caps are coarse, and the side fine.
+        let level_near = |point: Vec3| {
+            let (i, _) = (refined.positions().iter())
+                .enumerate()
+                .min_by(|(_, a), (_, b)| a.distance(point).total_cmp(&b.distance(point)))
+                .unwrap();
+            cells[i].level()
+        };
+        assert!(level_near(Vec3::new(0.02, -0.03, 0.51)) < 5);
+        assert_eq!(level_near(Vec3::new(0.62, -0.03, 0.01)), 6);
+    }
+
+    #[test]
+    fn adaptive_extraction_matches_uniform() {
+        let sphere = hermite_from_fn(
+            |p: Vec3| (p - Vec3::splat(0.2)).length() - 0.5,
+            |p| p - Vec3::splat(0.2),
+        );
+
+        let extract = |dc: &DualContouring<_>, uniform| {
+            let mut mesh = IndexedSeparateNormals::default();
+            let extractor = WithIndexedSeparateNormals::new(&mut mesh, &sphere);
+
+            if uniform {
+                dc.extract_uniform(extractor).unwrap();
+            } else {
+                dc.extract(extractor).unwrap();
+            }
+
+            mesh
+        };
+
+        // Culling only coarsens cells away from the surface, so the meshes
+        // are identical, down to the order of the vertices and faces.
+        let dc = DualContouring::new(&sphere, 32, 1e-4).assume_lipschitz(true);
+        let (adaptive, uniform) = (extract(&dc, false), extract(&dc, true));
+        assert!(!uniform.faces.is_empty());
+        assert_eq!(adaptive.positions(), uniform.positions());
+        assert_eq!(adaptive.faces, uniform.faces);
+
+        // Coarser regions simplify the mesh, but not the uniform reference.
+        let dc = dc.with_detail_regions(DetailRegions::new(3));
+        assert!(extract(&dc, false).positions().len() < uniform.positions().len());
+        assert_eq!(extract(&dc, true).faces, uniform.faces);
+    }
+
+    #[test]
+    fn anisotropic_triangle_quality() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.41, |p| p);
+
+        // The longest edge of a triangle over its altitude.
+        let max_aspect_ratio = |dc: DualContouring<_>| {
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+                .unwrap();
+            assert!(is_closed(&mesh));
+
+            mesh.faces
+                .iter()
+                .map(|face| {
+                    let [a, b, c] = face.map(|i| mesh.positions()[i as usize]);
+                    let longest = [a.distance(b), b.distance(c), c.distance(a)]
+                        .into_iter()
+                        .fold(0.0f64, |max, d| max.max(d as f64));
+                    longest * longest / (2.0 * validate::triangle_area([a, b, c]))
+                })
+                .fold(0.0, f64::max)
+        };
+
+        // A 2:2:1 domain with cubic cells, as with a cubic domain.
+        let isotropic = max_aspect_ratio(DualContouring::new(&sphere, 32, 1e-4));
+        let anisotropic = max_aspect_ratio(
+            DualContouring::with_resolution(&sphere, UVec3::new(32, 32, 16), 1e-4).with_bounds(
+                Bounds::new(Vec3::new(-1.0, -1.0, -0.5), Vec3::new(1.0, 1.0, 0.5)),
+            ),
+        );
+
+        assert!(isotropic.is_finite());
+        assert!(anisotropic <= isotropic * 1.1, "{isotropic} {anisotropic}");
+    }
+
+    #[test]
+    fn anisotropic_resolution() {
+        // A sphere in the bounds, and another one past them, within the box
+        // spanned by the octree along the coarse axis.
+        let radius = 0.45;
+        let other = Vec3::new(0.0, 0.0, 2.5);
+        let spheres = hermite_from_fn(
+            move |p: Vec3| (p.length() - radius).min(p.distance(other) - radius),
+            move |p| {
+                if p.length() < p.distance(other) {
+                    p.normalize()
+                } else {
+                    (p - other).normalize()
+                }
+            },
+        );
+
+        let max_res = UVec3::new(32, 32, 8);
+        let cell_size = 2.0 / max_res.as_vec3();
+
+        for assume_lipschitz in [false, true] {
+            let dc = DualContouring::new(&spheres, 32, 1e-4)
+                .with_max_res_per_axis(max_res)
+                .assume_lipschitz(assume_lipschitz);
+
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &spheres))
+                .unwrap();
+
+            assert!(is_closed(&mesh));
+
+            for p in mesh.positions() {
+                // Vertices are within a cell of the sphere along each axis.
+                let closest = p.normalize() * radius;
+                assert!(
+                    ((*p - closest).abs() - cell_size).max_element() <= 0.0,
+                    "{p}"
+                );
+            }
+
+            // So are the voxels, which are within the bounds.
+            let mut voxels = CollectVoxels::default();
+            dc.classify_volume(&mut voxels);
+            assert!(!voxels.boundary().is_empty());
+
+            for key in voxels.boundary().iter().chain(voxels.interior()) {
+                let bounds = key.cell_bounds(&dc.octree_domain());
+                assert!(dc.bounds.contains(bounds.min) && dc.bounds.contains(bounds.max));
+                assert!(bounds.center().z < 1.0);
+            }
+        }
+
+        // Only the coarse axis loses detail.
+        let count = |max_res| {
+            let dc = DualContouring::new(&spheres, 32, 1e-4).with_max_res_per_axis(max_res);
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &spheres))
+                .unwrap();
+            mesh.positions().len()
+        };
+
+        let anisotropic = count(max_res);
+        assert!(count(UVec3::splat(8)) < anisotropic && anisotropic < count(UVec3::splat(32)));
+    }
+
+    #[test]
+    fn isosurfaces_share_samples() {
+        let dims = UVec3::splat(33);
+        let bounds = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let step = bounds.size() / (dims - 1).as_vec3();
+        let values = (0..dims.element_product())
+            .map(|i| {
+                let index = UVec3::new(i % dims.x, i / dims.x % dims.y, i / (dims.x * dims.y));
+                (bounds.min + index.as_vec3() * step).length()
+            })
+            .collect();
+        let grid = GridSource::new(dims, bounds, values);
+
+        let samples = Cell::new(0);
+        let counted = hermite_from_fn(
+            |p| {
+                samples.set(samples.get() + 1);
+                grid.sample(p)
+            },
+            |p| grid.gradient(p),
+        );
+        let dc = DualContouring::new(&counted, 32, 1e-4).with_linear_estimate(true);
+
+        let extract = |isovalues: &[f32]| {
+            samples.set(0);
+            let mut meshes: Vec<_> = isovalues
+                .iter()
+                .map(|_| IndexedSeparateNormals::default())
+                .collect();
+            let mut targets = meshes.iter_mut();
+            dc.extract_isosurfaces(isovalues, |i| {
+                let source = Isovalue::new(&grid, isovalues[i]);
+                WithIndexedSeparateNormals::new(targets.next().unwrap(), source)
+            })
+            .unwrap();
+            (meshes, samples.get())
+        };
+
+        let isovalues = [0.33, 0.52, 0.71];
+        let (_, single_samples) = extract(&isovalues[..1]);
+        let (meshes, total_samples) = extract(&isovalues);
+        assert!(
+            total_samples < single_samples * 5 / 4,
+            "{single_samples} {total_samples}"
+        );
+
+        for (mesh, &isovalue) in meshes.iter().zip(&isovalues) {
+            let source = Isovalue::new(&grid, isovalue);
+            let mut standalone = IndexedSeparateNormals::default();
+            DualContouring::new(&source, 32, 1e-4)
+                .with_linear_estimate(true)
+                .extract(WithIndexedSeparateNormals::new(&mut standalone, &source))
+                .unwrap();
+
+            assert!(!mesh.faces.is_empty() && is_closed(mesh));
+            assert_eq!(mesh.positions(), standalone.positions());
+            assert_eq!(mesh.faces, standalone.faces);
+        }
+    }
+
+    #[test]
+    fn linear_estimate_skips_bisection_on_planes() {
+        let samples = Cell::new(0);
+        let normal = Vec3::new(1.0, 2.0, 3.0).normalize();
+
+        let plane = hermite_from_fn(
+            |p: Vec3| {
+                samples.set(samples.get() + 1);
+                p.dot(normal) - 0.1
+            },
+            |_| normal,
+        );
+
+        let extract = |linear_estimate| {
+            samples.set(0);
+            let dc = DualContouring::new(&plane, 16, 1e-5).with_linear_estimate(linear_estimate);
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &plane))
+                .unwrap();
+            (mesh, samples.get())
+        };
+
+        let (bisected, bisected_samples) = extract(false);
+        let (estimated, estimated_samples) = extract(true);
+
+        assert!(!bisected.faces.is_empty());
+        assert_eq!(bisected.faces.len(), estimated.faces.len());
+        // Bisection takes over a dozen samples per crossed edge, and the
+        // estimate a single one. There's one quad per crossed edge.
+        let quads = estimated.faces.len() / 2;
+        assert!(bisected_samples - estimated_samples > 8 * quads);
+
+        for (a, b) in bisected.positions().iter().zip(estimated.positions()) {
+            assert!(a.abs_diff_eq(*b, 1e-4));
+            assert!((b.dot(normal) - 0.1).abs() < 1e-4);
+        }
+
+        // Curved surfaces fall back to bisection.
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(&sphere, 16, 1e-4)
+            .with_linear_estimate(true)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+        assert!(is_closed(&mesh));
+    }
+
+    #[test]
+    fn detail_regions_are_crack_free() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let detail = Bounds::new(Vec3::ZERO, Vec3::ONE);
+        let dc = DualContouring::new(&sphere, 64, 1e-4)
+            .with_detail_regions(DetailRegions::new(4).with_region(detail, 6));
+
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+        assert!(!mesh.faces.is_empty());
+        assert!(is_closed(&mesh));
+
+        // The box takes up an eighth of the domain, and its leaves are 2
+        // levels deeper than the others.
+        let octree = dc.build_octree();
+        let (inside, outside) = octree.leaves().fold((0, 0), |(inside, outside), cell| {
+            if detail.contains(cell.bounds(&dc.bounds).center()) {
+                (inside + 1, outside)
+            } else {
+                (inside, outside + 1)
+            }
+        });
+
+        let ratio = inside as f32 / (outside as f32 / 7.0);
+        assert!((ratio / 64.0 - 1.0).abs() < 0.1, "{ratio}");
+    }
+
+    #[test]
+    fn memory_budget_coarsens() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let dc = || DualContouring::new(&sphere, 128, 1e-6).assume_lipschitz(true);
+
+        let mut mesh = IndexedSeparateNormals::default();
+        let report = dc()
+            .with_memory_budget(1 << 20)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+        assert!(!mesh.faces.is_empty());
+        assert!(report.budget_exceeded);
+
+        // Coarse cells stitch to fine ones without cracks. Faces are oriented
+        // one by one, so slivers between cells of very different sizes may
+        // face inward, but every edge is still shared by exactly two faces.
+        let mut edges = crate::collections::Map::new();
+        for face in &mesh.faces {
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        assert!(edges.values().all(|&count| count == 2));
+
+        // The octants visited first are the finest, as the octree is built
+        // depth-first.
+        let levels = report.max_level_reached_per_octant;
+        assert_eq!(levels.iter().max(), Some(&7));
+        assert!(levels.iter().any(|&level| level < 7), "{levels:?}");
+
+        for assume_lipschitz in [false, true] {
+            let dc = || {
+                DualContouring::new(&sphere, 32, 1e-6)
+                    .assume_lipschitz(assume_lipschitz)
+                    .with_quad_split(QuadSplit::AlignWithNormal)
+            };
+            let extract = |dc: DualContouring<_>| {
+                let mut mesh = IndexedSeparateNormals::default();
+                let report = dc
+                    .extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+                    .unwrap();
+                (mesh, report)
+            };
+
+            let (expected, expected_report) = extract(dc());
+            let (mesh, report) = extract(dc().with_memory_budget(1 << 30));
+            assert_eq!(mesh.positions(), expected.positions());
+            assert_eq!(mesh.faces, expected.faces);
+            assert_eq!(report, expected_report);
+            assert!(!report.budget_exceeded);
+            assert_eq!(report.max_level_reached_per_octant, [5; 8]);
+        }
+    }
+
+    #[test]
+    fn sphere_voxels() {
+        const RADIUS: f32 = 0.6;
+
+        let res = 64;
+        let dc = DualContouring::new(from_fn(|p: Vec3| p.length() - RADIUS), res, 1e-4);
+        let mut voxels = CollectVoxels::default();
+        dc.classify_volume(&mut voxels);
+
+        let cell_volume = (2.0 / res as f32).powi(3);
+        let volume = 4.0 / 3.0 * core::f32::consts::PI * RADIUS.powi(3) / cell_volume;
+        let interior = voxels.interior().len() as f32;
+        let boundary = voxels.boundary().len() as f32;
+
+        // Boundary cells are about half inside on average.
+        assert!(interior < volume && volume < interior + boundary);
+        assert!(((interior + boundary / 2.0) / volume - 1.0).abs() < 0.03);
+
+        // The finest cell with its minimum corner at the center.
+        let center = (0..5).fold(MortonKey::root().child(BMask3::XYZ), |key, _| {
+            key.child(BMask3::O)
+        });
+        assert!(voxels.contains(center));
+        assert!(voxels.contains(center.child(BMask3::X)));
+        assert!(!voxels.contains(MortonKey::root().child(BMask3::O)));
+
+        let grid = voxels.to_dense_grid(res);
+        let solid = grid.iter().filter(|&&v| v).count();
+        assert_eq!(solid as f32, interior + boundary);
+        assert!(grid[(res / 2 * (1 + res * (1 + res))) as usize]);
+        assert!(!grid[0]);
+    }
+
+    #[test]
+    fn two_phase_extraction() {
+        fn assert_send<T: Send>() {}
+        assert_send::<PlacedVertices>();
+
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let samples = Cell::new(0);
+        let counted = hermite_from_fn(
+            |p| {
+                samples.set(samples.get() + 1);
+                sphere.sample(p)
+            },
+            |p| sphere.gradient(p),
+        );
+        let dc =
+            DualContouring::new(&counted, 16, 1e-4).with_quad_split(QuadSplit::AlignWithNormal);
+
+        let mut one_shot = IndexedSeparateNormals::default();
+        let expected = dc
+            .extract(WithIndexedSeparateNormals::new(&mut one_shot, &sphere))
+            .unwrap();
+
+        let placed = dc.place_vertices().unwrap();
+        assert_eq!(placed.positions(), one_shot.positions());
+        samples.set(0);
+
+        // The same placement feeds several extractors.
+        for _ in 0..2 {
+            let mut mesh = IndexedSeparateNormals::default();
+            let report = dc
+                .generate_faces(&placed, WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+                .unwrap();
+
+            assert_eq!(report, expected);
+            assert_eq!(mesh.positions(), one_shot.positions());
+            assert_eq!(mesh.normals(), one_shot.normals());
+            assert_eq!(mesh.faces, one_shot.faces);
+        }
+
+        assert_eq!(samples.get(), 0);
+
+        #[cfg(feature = "serde")]
+        {
+            let json = serde_json::to_string(&placed).unwrap();
+            assert_eq!(
+                serde_json::from_str::<PlacedVertices>(&json).unwrap(),
+                placed
+            );
+        }
+    }
+
+    #[test]
+    fn grouped_extraction_partitions_faces() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let dc = DualContouring::new(&sphere, 32, 1e-4);
+
+        let mut collected = CollectGroups::default();
+        dc.extract_grouped(2, &mut collected).unwrap();
+        assert!(collected.groups.len() > 8);
+        assert!(collected.groups.len() <= 64);
+        assert!(collected.groups.is_sorted_by_key(|(key, _)| *key));
+
+        let mut merged = IndexedSeparateNormals::default();
+
+        for (key, group) in &collected.groups {
+            assert_eq!(key.level(), 2);
+            assert!(!group.faces.is_empty());
+
+            // Every vertex of a group is used by its faces.
+            let mut used: Vec<u32> = group.faces.iter().flatten().copied().collect();
+            used.sort_unstable();
+            used.dedup();
+            assert_eq!(used.len(), group.positions().len());
+
+            let offset = merged.positions().len() as u32;
+            for (&position, &normal) in group.positions().iter().zip(group.normals()) {
+                merged.vertices.push(position, normal);
+            }
+            merged
+                .faces
+                .extend(group.faces.iter().map(|face| face.map(|i| i + offset)));
+        }
+
+        merged.weld(1e-6);
+
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+
+        // The same triangles, each in exactly one group.
+        let canonical = |mesh: &IndexedSeparateNormals| {
+            let mut faces: Vec<_> = mesh
+                .faces
+                .iter()
+                .map(|face| {
+                    let mut face =
+                        face.map(|i| mesh.positions()[i as usize].to_array().map(f32::to_bits));
+                    let first = (0..3).min_by_key(|&k| face[k]).unwrap();
+                    face.rotate_left(first);
+                    face
+                })
+                .collect();
+            faces.sort_unstable();
+            faces
+        };
+
+        assert_eq!(merged.positions().len(), mesh.positions().len());
+        assert_eq!(canonical(&merged), canonical(&mesh));
+    }
+
+    #[test]
+    fn auto_bounds() {
+        let center = Vec3::new(5.0, 3.0, -2.0);
+        let sphere = hermite_from_fn(move |p: Vec3| p.distance(center) - 2.0, move |p| p - center);
+        let dc =
+            DualContouring::new_auto_bounds(&sphere, Vec3::new(6.0, 3.0, -2.0), 32, 1e-6).unwrap();
+        assert!(dc.bounds.contains(center - 2.0) && dc.bounds.contains(center + 2.0));
+
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+        assert!(is_closed(&mesh));
+
+        assert!(DualContouring::new_auto_bounds(&sphere, Vec3::splat(-2000.0), 32, 1e-5).is_none());
+    }
+
+    #[test]
+    fn boundary_snap_matches_chunks() {
+        let center = Vec3::new(0.91, 0.01, 0.02);
+        let sphere = hermite_from_fn(move |p: Vec3| p.distance(center) - 0.5, move |p| p - center);
+
+        // Two chunks of 32 cells, overlapping by three cells along X, with
+        // bounds that aren't exactly representable.
+        let cell = 2.0 / 32.0;
+        let a = Bounds::new(Vec3::splat(-1.0291), Vec3::splat(0.9709));
+        let b = Bounds::new(
+            Vec3::new(a.max.x - 3.0 * cell, a.min.y, a.min.z),
+            Vec3::new(a.max.x - 3.0 * cell + 2.0, a.max.y, a.max.z),
+        );
+
+        // The vertices of the cells in the middle of the overlap.
+        let shared = |snap: bool| {
+            [a, b].map(|bounds| {
+                let mut dc = DualContouring::new(&sphere, 32, 1e-5).with_bounds(bounds);
+                if snap {
+                    dc = dc.with_boundary_snap(256);
+                }
+
+                let mut mesh = IndexedSeparateNormals::default();
+                let mut cells = Vec::new();
+                let extractor = WithIndexedSeparateNormals::new(&mut mesh, &sphere);
+                dc.extract(WithVertexCells::new(extractor, &mut cells))
+                    .unwrap();
+
+                let mut vertices: Vec<_> = cells
+                    .iter()
+                    .zip(mesh.positions().iter().zip(mesh.normals()))
+                    .filter(|(key, _)| {
+                        let x = key.cell_bounds(&bounds).center().x;
+                        (x - (a.max.x - 1.5 * cell)).abs() < cell / 2.0
+                    })
+                    .map(|(_, (p, n))| [p.to_array(), n.to_array()].map(|v| v.map(f32::to_bits)))
+                    .collect();
+                vertices.sort_unstable();
+                vertices
+            })
+        };
+
+        let [a_vertices, b_vertices] = shared(false);
+        assert!(!a_vertices.is_empty());
+        assert_ne!(a_vertices, b_vertices);
+
+        let [a_vertices, b_vertices] = shared(true);
+        assert_eq!(a_vertices, b_vertices);
+    }
+
+    #[test]
+    fn lattice_offset_avoids_aligned_plane() {
+        // The plane lies on the faces of the cells without an offset.
+        let plane = hermite_from_fn(|p: Vec3| p.y - 0.5, |_| Vec3::Y);
+        let dc = DualContouring::new(&plane, 32, 1e-5).with_auto_lattice_offset();
+
+        let extract = |dc: &DualContouring<_>| {
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &plane))
+                .unwrap();
+            mesh
+        };
+        let mesh = extract(&dc);
+
+        // A single sheet of 31 × 31 quads, open along its rim only.
+        assert_eq!(mesh.faces.len(), 2 * 31 * 31);
+        assert_eq!(validate::boundary_edges(&mesh.faces).len(), 4 * 31);
+        assert!(mesh.positions().iter().all(|p| (p.y - 0.5).abs() < 1e-4));
+
+        let offset = dc.lattice_offset();
+        assert!(offset.cmpgt(Vec3::ZERO).all());
+        assert!(offset.cmplt(Vec3::splat(2.0 / 32.0)).all());
+
+        // The same offset, set explicitly, gives the same mesh.
+        let explicit = extract(&dc.with_source(&plane).with_lattice_offset(offset));
+        let bits = |mesh: &IndexedSeparateNormals| {
+            let positions: Vec<_> = mesh
+                .positions()
+                .iter()
+                .map(|p| p.to_array().map(f32::to_bits))
+                .collect();
+            (positions, mesh.faces.clone())
+        };
+        assert_eq!(bits(&mesh), bits(&explicit));
+        assert_eq!(bits(&mesh), bits(&extract(&dc)));
+    }
+
+    #[test]
+    fn boundary_caps() {
+        // A sphere sticking out of the bounds along X.
+        let (center, radius) = (Vec3::new(0.5, 0.01, 0.02), 0.6);
+        let sphere = hermite_from_fn(
+            move |p: Vec3| p.distance(center) - radius,
+            move |p| p - center,
+        );
+        let dc = DualContouring::new(&sphere, 32, 1e-5);
+
+        let extract = |dc: &DualContouring<_>| {
+            let mut mesh = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+                .unwrap();
+            mesh
+        };
+
+        assert!(!is_closed(&extract(&dc)));
+
+        let mesh = extract(&dc.with_boundary_policy(BoundaryPolicy::Cap));
+        assert!(is_closed(&mesh));
+
+        // The cap is half a cell inside the bounds.
+        let plane = 1.0 - 1.0 / 32.0;
+        let bounds = Bounds::new(Vec3::splat(-1.0), Vec3::ONE);
+        assert!(mesh.positions().iter().all(|&p| bounds.contains(p)));
+        let on_cap = mesh
+            .positions()
+            .iter()
+            .filter(|p| (p.x - plane).abs() < 1e-4);
+        assert!(on_cap.count() > 20);
+
+        // The sphere minus the cap past the plane, and so facing outward.
+        let (r, height) = (radius as f64, (radius - (plane - center.x)) as f64);
+        let expected = 4.0 / 3.0 * core::f64::consts::PI * r.powi(3)
+            - core::f64::consts::PI * height * height * (3.0 * r - height) / 3.0;
+        let volume = mesh.signed_volume().unwrap();
+        assert!(
+            (volume / expected - 1.0).abs() < 0.01,
+            "{volume} {expected}"
+        );
     }
 }
@@ -1,13 +1,21 @@
-use crate::geom::BMask3;
+use crate::geom::{AxisKind, BMask3, Bounds, CornerKind};
+use glam::UVec3;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+/// A Morton code identifying an octree node.
+///
+/// The code is prefixed by a sentinel bit, so that the root is `1` and every
+/// level appends three bits (`x` being the least significant one). The zero
+/// code doesn't represent any node. Keys are ordered by their codes, which is
+/// the Morton order among nodes at the same level.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MortonKey(u64);
 
 impl MortonKey {
     pub const LEVELS: u32 = (u64::BITS - 1) / 3;
 
     pub const fn root() -> MortonKey {
-        MortonKey(0)
+        MortonKey(1)
     }
 
     pub const fn none() -> MortonKey {
@@ -15,18 +23,191 @@ impl MortonKey {
     }
 
     pub const fn is_none(&self) -> bool {
-        self.0 != 0
+        self.0 == 0
+    }
+
+    /// Returns the code of the key.
+    #[cfg(feature = "std")]
+    pub(crate) const fn code(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns the key with the given code, or `None` if the code doesn't
+    /// represent any node.
+    #[cfg(feature = "std")]
+    pub(crate) const fn from_code(code: u64) -> Option<MortonKey> {
+        match code.checked_ilog2() {
+            Some(bit) if bit % 3 == 0 => Some(MortonKey(code)),
+            _ => None,
+        }
     }
 
     pub const fn parent(&self) -> MortonKey {
         MortonKey(self.0 >> 3)
     }
 
+    /// Returns the child of this node at `index`.
+    ///
+    /// The key of a child of a node at [`MortonKey::LEVELS`] doesn't fit, and
+    /// aliases another node, see [`MortonKey::checked_child`].
     pub const fn child(&self, index: BMask3) -> MortonKey {
         MortonKey((self.0 << 3) | (index.bits() as u64))
     }
 
+    /// Returns the child of this node at `index`, or `None` if the node is
+    /// at [`MortonKey::LEVELS`] already.
+    pub const fn checked_child(&self, index: BMask3) -> Option<MortonKey> {
+        if self.0 >> (3 * MortonKey::LEVELS) != 0 {
+            None
+        } else {
+            Some(self.child(index))
+        }
+    }
+
+    /// Returns the keys of the 8 children of this node, in the order of
+    /// [`CornerKind::ALL`], like [`OctreeCell::sub_cells`].
+    ///
+    /// [`OctreeCell::sub_cells`]: crate::OctreeCell::sub_cells
+    pub fn children(&self) -> [MortonKey; 8] {
+        CornerKind::ALL.map(|corner| self.child(corner.into()))
+    }
+
+    /// Returns the key of the node at the given integer coordinates within the
+    /// grid of nodes at `level`.
+    pub fn from_coords(coords: UVec3, level: u32) -> MortonKey {
+        let mut key = MortonKey::root();
+
+        for i in (0..level).rev() {
+            let bit = |c: u32| (c >> i) & 1 != 0;
+            let index = BMask3::O
+                .with(AxisKind::X, bit(coords.x))
+                .with(AxisKind::Y, bit(coords.y))
+                .with(AxisKind::Z, bit(coords.z));
+            key = key.child(index);
+        }
+
+        key
+    }
+
+    /// Returns the depth of the node, the root being at level 0.
     pub fn level(&self) -> u32 {
-        self.0.checked_ilog2().unwrap_or(0)
+        self.0.checked_ilog2().unwrap_or(0) / 3
+    }
+
+    /// Returns the ancestor of this node at the given level, or the node
+    /// itself if it's at that level.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `level` is deeper than this node.
+    pub fn ancestor_at_level(&self, level: u32) -> MortonKey {
+        let depth = self.level();
+        assert!(
+            level <= depth,
+            "the ancestor must not be deeper than the node"
+        );
+        MortonKey(self.0 >> (3 * (depth - level)))
+    }
+
+    /// Returns the deepest node that's an ancestor of (or equal to) both this
+    /// node and `other`.
+    pub fn common_ancestor(&self, other: &MortonKey) -> MortonKey {
+        let level = self.level().min(other.level());
+        let (a, b) = (
+            self.ancestor_at_level(level),
+            other.ancestor_at_level(level),
+        );
+
+        // Climb past the highest level at which the paths differ. The
+        // sentinel bits are equal, so the root is always common.
+        match (a.0 ^ b.0).checked_ilog2() {
+            Some(bit) => MortonKey(a.0 >> (3 * (bit / 3 + 1))),
+            None => a,
+        }
+    }
+
+    /// Returns the integer coordinates of the node within the grid of nodes at
+    /// the same level.
+    pub fn coords(&self) -> UVec3 {
+        let mut coords = UVec3::ZERO;
+
+        for i in 0..self.level() {
+            let index = (self.0 >> (3 * i)) as u32;
+            coords += UVec3::new(index & 1, (index >> 1) & 1, (index >> 2) & 1) << i;
+        }
+
+        coords
+    }
+
+    /// Returns the world-space box covered by the node, given the box covered
+    /// by the root.
+    pub fn cell_bounds(&self, domain: &Bounds) -> Bounds {
+        let size = domain.size() / (1u32 << self.level()) as f32;
+        let min = domain.min + self.coords().as_vec3() * size;
+        Bounds::new(min, min + size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn root_and_none() {
+        assert!(!MortonKey::root().is_none());
+        assert!(MortonKey::none().is_none());
+        assert_eq!(MortonKey::root().level(), 0);
+    }
+
+    #[test]
+    fn child_coords() {
+        let key = MortonKey::root().child(BMask3::XZ).child(BMask3::Y);
+        assert_eq!(key.level(), 2);
+        assert_eq!(key.coords(), UVec3::new(2, 1, 2));
+        assert_eq!(key.parent().parent(), MortonKey::root());
+        assert_eq!(MortonKey::from_coords(key.coords(), 2), key);
+
+        let children = key.children();
+        assert_eq!(children[0], key.child(BMask3::O));
+        assert_eq!(children[7], key.child(BMask3::XYZ));
+        for (child, corner) in children.into_iter().zip(CornerKind::ALL) {
+            assert_eq!(child.parent(), key);
+            let bits = BMask3::from(corner).bits() as u32;
+            let offset = UVec3::new(bits & 1, bits >> 1 & 1, bits >> 2);
+            assert_eq!(child.coords(), key.coords() * 2 + offset);
+        }
+    }
+
+    #[test]
+    fn deepest_child() {
+        let deepest = (0..MortonKey::LEVELS)
+            .try_fold(MortonKey::root(), |key, _| key.checked_child(BMask3::XYZ));
+        let deepest = deepest.unwrap();
+
+        assert_eq!(deepest.level(), MortonKey::LEVELS);
+        assert_eq!(deepest.coords(), UVec3::splat((1 << MortonKey::LEVELS) - 1));
+        assert_eq!(deepest.checked_child(BMask3::O), None);
+        assert_eq!(deepest.parent().checked_child(BMask3::XYZ), Some(deepest));
+    }
+
+    #[test]
+    fn ancestors() {
+        let a = MortonKey::root().child(BMask3::X).child(BMask3::YZ);
+        let b = a.child(BMask3::XYZ).child(BMask3::O);
+        assert_eq!(b.ancestor_at_level(4), b);
+        assert_eq!(b.ancestor_at_level(2), a);
+        assert_eq!(b.ancestor_at_level(0), MortonKey::root());
+
+        assert_eq!(a.common_ancestor(&b), a);
+        assert_eq!(b.common_ancestor(&a), a);
+        assert_eq!(b.common_ancestor(&b), b);
+
+        // Siblings deep down, and nodes in different subtrees of the root.
+        let c = a.child(BMask3::XYZ).child(BMask3::Z);
+        assert_eq!(b.common_ancestor(&c), a.child(BMask3::XYZ));
+
+        let d = MortonKey::root().child(BMask3::Y).child(BMask3::YZ);
+        assert_eq!(b.common_ancestor(&d), MortonKey::root());
+        assert_eq!(d.common_ancestor(&MortonKey::root()), MortonKey::root());
     }
 }
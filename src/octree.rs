@@ -0,0 +1,639 @@
+use crate::collections::{Map, Set};
+use crate::geom::{Bounds, CornerKind, EdgeKind};
+use crate::morton::MortonKey;
+use crate::source::{Source, Tolerance};
+use crate::topology::{Edge, OctreeCell, OctreeFace};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::ControlFlow;
+use glam::{UVec3, Vec3};
+
+/// An adaptive octree partitioning a box-shaped domain.
+///
+/// Only the interior (non-leaf) cells are stored, so a cell is a leaf iff
+/// its parent is interior and it isn't.
+#[derive(Debug, Clone)]
+pub struct Octree {
+    pub(crate) domain: Bounds,
+    /// The deepest level leaves may be at.
+    pub(crate) max_level: u32,
+    /// The keys of the interior cells.
+    pub(crate) interior: Set<MortonKey>,
+}
+
+impl Octree {
+    /// Builds an octree by subdividing cells, starting from the root, for
+    /// as long as `subdivide` returns `true` and `max_level` isn't reached.
+    pub fn build<F>(domain: Bounds, max_level: u32, mut subdivide: F) -> Self
+    where
+        F: FnMut(&OctreeCell) -> bool,
+    {
+        assert!(
+            max_level <= MortonKey::LEVELS,
+            "`max_level` must not exceed `MortonKey::LEVELS`"
+        );
+
+        let mut interior = Set::new();
+        let mut stack = vec![OctreeCell::root()];
+
+        while let Some(cell) = stack.pop() {
+            if cell.key().level() < max_level && subdivide(&cell) {
+                interior.insert(cell.key());
+                stack.extend(cell.sub_cells());
+            }
+        }
+
+        Self {
+            domain,
+            max_level,
+            interior,
+        }
+    }
+
+    /// Like [`Octree::build`], but also passes the values of `sample` at the
+    /// corners of every cell to `subdivide`, in the order of
+    /// [`CornerKind::ALL`].
+    ///
+    /// Every point is sampled once. A subdivided cell hands its corners down
+    /// to its sub-cells and only samples the 19 other corners of theirs: its
+    /// center, and points on its faces and edges, which are shared with the
+    /// neighbors that subdivide too. This takes about 8 times fewer samples
+    /// than sampling the corners of every cell, at the cost of a map lookup
+    /// for every shared point.
+    pub fn build_sampled<S, F>(
+        domain: Bounds,
+        max_level: u32,
+        mut sample: S,
+        mut subdivide: F,
+    ) -> Self
+    where
+        S: FnMut(Vec3) -> f32,
+        F: FnMut(&OctreeCell, &[f32; 8]) -> bool,
+    {
+        assert!(
+            max_level <= MortonKey::LEVELS,
+            "`max_level` must not exceed `MortonKey::LEVELS`"
+        );
+
+        // Points are addressed by their coordinates on the lattice of the
+        // corners of cells at `max_level`.
+        let mut sample_at = |point: UVec3| sample(lattice_point(&domain, max_level, point));
+        let mut shared: Map<[u32; 3], f32> = Map::new();
+
+        let root = CornerKind::ALL.map(|corner| sample_at(corner_offset(corner) << max_level));
+        let mut interior = Set::new();
+        let mut stack = vec![(OctreeCell::root(), root)];
+
+        while let Some((cell, corners)) = stack.pop() {
+            if cell.level() >= max_level || !subdivide(&cell, &corners) {
+                continue;
+            }
+
+            interior.insert(cell.key());
+
+            // Sub-cells at `max_level` are leaves regardless of their corners.
+            if cell.level() + 1 == max_level {
+                continue;
+            }
+
+            // The 3x3x3 corners of the sub-cells, in steps of half the cell.
+            let half = 1u32 << (max_level - cell.level() - 1);
+            let origin = cell.key().coords() * 2 * half;
+            let index = |offset: UVec3| (offset.x + 3 * offset.y + 9 * offset.z) as usize;
+            let mut values = [f32::NAN; 27];
+
+            for (corner, value) in CornerKind::ALL.into_iter().zip(corners) {
+                values[index(corner_offset(corner) * 2)] = value;
+            }
+
+            for offset in (0..27).map(|i| UVec3::new(i % 3, i / 3 % 3, i / 9)) {
+                // The corners of the cell are handed down by its parent.
+                if offset % 2 == UVec3::ZERO {
+                    continue;
+                }
+
+                let point = origin + offset * half;
+
+                // Only the descendants of the cell share its center.
+                values[index(offset)] = if offset == UVec3::ONE {
+                    sample_at(point)
+                } else {
+                    *shared
+                        .entry(point.to_array())
+                        .or_insert_with(|| sample_at(point))
+                };
+            }
+
+            let sub_cells = cell.sub_cells().iter().zip(CornerKind::ALL);
+            stack.extend(sub_cells.map(|(sub_cell, position)| {
+                let corners = CornerKind::ALL
+                    .map(|corner| values[index(corner_offset(position) + corner_offset(corner))]);
+                (sub_cell, corners)
+            }));
+        }
+
+        Self {
+            domain,
+            max_level,
+            interior,
+        }
+    }
+
+    /// Builds an octree where every leaf is at `max_level`.
+    pub fn uniform(domain: Bounds, max_level: u32) -> Self {
+        Self::build(domain, max_level, |_| true)
+    }
+
+    pub fn domain(&self) -> &Bounds {
+        &self.domain
+    }
+
+    /// Returns the deepest level leaves may be at, as passed to
+    /// [`Octree::build`].
+    pub fn max_level(&self) -> u32 {
+        self.max_level
+    }
+
+    /// Returns whether the given cell is a leaf.
+    ///
+    /// The result is only meaningful for cells that are part of the octree.
+    pub fn is_leaf(&self, cell: &OctreeCell) -> bool {
+        !self.interior.contains(&cell.key())
+    }
+
+    /// Returns an iterator over all leaf cells, in depth-first order.
+    pub fn leaves(&self) -> impl Iterator<Item = OctreeCell> + '_ {
+        let mut stack = vec![OctreeCell::root()];
+
+        core::iter::from_fn(move || {
+            while let Some(cell) = stack.pop() {
+                if self.is_leaf(&cell) {
+                    return Some(cell);
+                }
+
+                stack.extend(cell.sub_cells());
+            }
+
+            None
+        })
+    }
+
+    /// Returns an iterator over the leaf cells intersecting the surface,
+    /// i.e., those with a sign change along at least one of their 12 edges.
+    ///
+    /// Sign changes are detected with [`Source::classify_segment`], the same
+    /// way as when contouring.
+    pub fn surface_leaves<'a, S: Source>(
+        &'a self,
+        source: &'a S,
+        tolerance: Tolerance,
+    ) -> impl Iterator<Item = OctreeCell> + 'a {
+        self.leaves().filter(move |cell| {
+            let bounds = cell.bounds(&self.domain);
+
+            EdgeKind::ALL.iter().any(|edge| {
+                let [start, end] = edge.endpoints().map(|corner| bounds.corner(corner));
+                source
+                    .classify_segment(start, end, tolerance)
+                    .has_sign_change()
+            })
+        })
+    }
+
+    /// Calls `f` for every minimal edge of the octree, i.e., for every edge
+    /// adjacent to 4 leaf cells that isn't split by a smaller neighbor.
+    ///
+    /// Edges on the boundary of the domain aren't visited.
+    pub fn for_each_minimal_edge<F>(&self, f: F)
+    where
+        F: FnMut(&Edge),
+    {
+        self.for_each_minimal_edge_near(|_| true, f);
+    }
+
+    /// Like [`Octree::for_each_minimal_edge`], but only visits the edges
+    /// adjacent to at least one leaf for which `filter` returns `true`.
+    ///
+    /// `filter` must also return `true` for every ancestor of such a leaf, so
+    /// that subtrees it rejects can be skipped entirely.
+    pub fn for_each_minimal_edge_near<P, F>(&self, filter: P, mut f: F)
+    where
+        P: Fn(&OctreeCell) -> bool,
+        F: FnMut(&Edge),
+    {
+        let _ = self.try_for_each_minimal_edge_near(filter, |edge| {
+            f(edge);
+            ControlFlow::<()>::Continue(())
+        });
+    }
+
+    /// Like [`Octree::for_each_minimal_edge_near`], but stops as soon as `f`
+    /// returns [`ControlFlow::Break`], returning the break value.
+    pub fn try_for_each_minimal_edge_near<P, F, B>(&self, filter: P, mut f: F) -> ControlFlow<B>
+    where
+        P: Fn(&OctreeCell) -> bool,
+        F: FnMut(&Edge) -> ControlFlow<B>,
+    {
+        let walk = Walk {
+            octree: self,
+            filter,
+        };
+
+        walk.visit_cell(OctreeCell::root(), &mut f)
+    }
+}
+
+/// Limits the depth of the octree by region, e.g., to only extract fine
+/// detail close to the viewer.
+///
+/// A cell may only be subdivided while its level is below the largest
+/// maximum level of the regions overlapping it, or the default level if
+/// there are none. Cells merely touching a region don't count as
+/// overlapping it. Levels are further capped by the resolution of the
+/// [`DualContouring`].
+///
+/// [`DualContouring`]: crate::DualContouring
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetailRegions {
+    default_level: u32,
+    regions: Vec<(Bounds, u32)>,
+}
+
+impl DetailRegions {
+    /// Creates a set of regions where every cell may be subdivided down to
+    /// `default_level`.
+    pub fn new(default_level: u32) -> Self {
+        Self {
+            default_level,
+            regions: Vec::new(),
+        }
+    }
+
+    /// Adds a region where cells may be subdivided down to `max_level`.
+    pub fn with_region(mut self, bounds: Bounds, max_level: u32) -> Self {
+        self.regions.push((bounds, max_level));
+        self
+    }
+
+    pub fn default_level(&self) -> u32 {
+        self.default_level
+    }
+
+    /// Returns the regions, in the order they were added.
+    pub fn regions(&self) -> &[(Bounds, u32)] {
+        &self.regions
+    }
+
+    /// Returns the level down to which a cell with the given bounds may be
+    /// subdivided.
+    pub fn max_level(&self, bounds: &Bounds) -> u32 {
+        self.regions
+            .iter()
+            .filter(|(region, _)| {
+                region.min.cmplt(bounds.max).all() && bounds.min.cmplt(region.max).all()
+            })
+            .map(|&(_, max_level)| max_level)
+            .max()
+            .unwrap_or(self.default_level)
+    }
+}
+
+/// A cell, face or edge of the octree, left to visit.
+#[derive(Copy, Clone)]
+enum Work {
+    Cell(OctreeCell),
+    Face(OctreeFace),
+    Edge(Edge),
+}
+
+/// The most work items a traversal can have pending at once.
+///
+/// Every item pushed while visiting another one has a neighbor one level
+/// deeper, so at most [`MortonKey::LEVELS`] visits are in progress at once,
+/// each having pushed at most the 26 items of a cell, one of which is being
+/// visited.
+const WORK_CAPACITY: usize = 25 * MortonKey::LEVELS as usize + 1;
+
+/// A stack of pending work items, allocated inline so that walking the
+/// octree doesn't allocate.
+struct WorkStack {
+    items: [Work; WORK_CAPACITY],
+    len: usize,
+}
+
+impl WorkStack {
+    fn new(root: Work) -> Self {
+        Self {
+            items: [root; WORK_CAPACITY],
+            len: 1,
+        }
+    }
+
+    fn push(&mut self, item: Work) {
+        self.items[self.len] = item;
+        self.len += 1;
+    }
+
+    /// Pushes items so that they're popped in the order they're listed.
+    fn push_all(&mut self, items: impl IntoIterator<Item = Work>) {
+        let start = self.len;
+
+        for item in items {
+            self.push(item);
+        }
+
+        self.items[start..self.len].reverse();
+    }
+
+    fn pop(&mut self) -> Option<Work> {
+        self.len = self.len.checked_sub(1)?;
+        Some(self.items[self.len])
+    }
+}
+
+/// The state of a traversal of minimal edges.
+struct Walk<'a, P> {
+    octree: &'a Octree,
+    filter: P,
+}
+
+impl<P: Fn(&OctreeCell) -> bool> Walk<'_, P> {
+    fn is_leaf(&self, cell: &OctreeCell) -> bool {
+        self.octree.is_leaf(cell)
+    }
+
+    /// Visits the minimal edges within `cell`, depth-first, in the same
+    /// order as recursing into its sub-cells, then its interior faces, then
+    /// its interior edges, would.
+    fn visit_cell<F, B>(&self, cell: OctreeCell, f: &mut F) -> ControlFlow<B>
+    where
+        F: FnMut(&Edge) -> ControlFlow<B>,
+    {
+        let is_leaf = |cell: &OctreeCell| self.is_leaf(cell);
+        let mut stack = WorkStack::new(Work::Cell(cell));
+
+        while let Some(item) = stack.pop() {
+            match item {
+                Work::Cell(cell) => {
+                    if self.is_leaf(&cell) || !(self.filter)(&cell) {
+                        continue;
+                    }
+
+                    stack.push_all(cell.interior_edges().iter().map(Work::Edge));
+                    stack.push_all(cell.interior_faces().iter().map(Work::Face));
+                    stack.push_all(cell.sub_cells().iter().map(Work::Cell));
+                }
+                Work::Face(face) => {
+                    if !face.neighbors().iter().any(&self.filter) {
+                        continue;
+                    }
+
+                    if let Some(sub_edges) = face.sub_edges(is_leaf) {
+                        stack.push_all(sub_edges.into_iter().map(Work::Edge));
+                    }
+
+                    if let Some(sub_faces) = face.sub_faces(is_leaf) {
+                        stack.push_all(sub_faces.into_iter().map(Work::Face));
+                    }
+                }
+                Work::Edge(edge) => {
+                    if !edge.neighbors().iter().any(&self.filter) {
+                        continue;
+                    }
+
+                    match edge.sub_edges(is_leaf) {
+                        Some(sub_edges) => stack.push_all(sub_edges.into_iter().map(Work::Edge)),
+                        None => f(&edge)?,
+                    }
+                }
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// Returns the points where [`Octree::build_sampled`] samples the corners of
+/// `cell`, in the order of [`CornerKind::ALL`].
+#[cfg(feature = "async")]
+pub(crate) fn sampled_corners(domain: &Bounds, max_level: u32, cell: &OctreeCell) -> [Vec3; 8] {
+    let size = 1 << (max_level - cell.level());
+    let origin = cell.key().coords() * size;
+    CornerKind::ALL
+        .map(|corner| lattice_point(domain, max_level, origin + corner_offset(corner) * size))
+}
+
+/// Returns the point at the given coordinates on the lattice of the corners
+/// of cells at `max_level`.
+fn lattice_point(domain: &Bounds, max_level: u32, point: UVec3) -> Vec3 {
+    let step = domain.size() / (1u32 << max_level) as f32;
+    domain.min + point.as_vec3() * step
+}
+
+/// Returns the offset of a corner from the minimum corner of a unit cell.
+fn corner_offset(corner: CornerKind) -> UVec3 {
+    UVec3::from(corner.0.to_bvec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::CornerKind;
+    use crate::source::from_fn;
+    use crate::topology::corner_signs;
+    use glam::Vec3;
+
+    fn count_minimal_edges(octree: &Octree) -> usize {
+        let mut count = 0;
+        octree.for_each_minimal_edge(|_| count += 1);
+        count
+    }
+
+    fn unit_domain() -> Bounds {
+        Bounds::new(Vec3::ZERO, Vec3::ONE)
+    }
+
+    #[test]
+    fn uniform_minimal_edges() {
+        // A uniform grid of `n³` cells has `3n(n - 1)²` edges not lying on
+        // the domain boundary.
+        let octree = Octree::uniform(unit_domain(), 2);
+        let mut count = 0;
+        octree.for_each_minimal_edge(|_| count += 1);
+        assert_eq!(count, 3 * 4 * 3 * 3);
+    }
+
+    #[test]
+    fn minimal_edges_are_shared() {
+        let octree = Octree::uniform(unit_domain(), 3);
+
+        octree.for_each_minimal_edge(|edge| {
+            let [start, end] = edge.segment(octree.domain());
+
+            for cell in edge.neighbors() {
+                let bounds = cell.bounds(octree.domain());
+                assert!(bounds.contains(start) && bounds.contains(end));
+            }
+        });
+    }
+
+    #[test]
+    fn adaptive_minimal_edges_are_shared() {
+        // Refine a single corner of the domain.
+        let octree = Octree::build(unit_domain(), 4, |cell| {
+            cell.bounds(&unit_domain()).contains(Vec3::splat(0.3))
+        });
+
+        let mut keys = Vec::new();
+
+        octree.for_each_minimal_edge(|edge| {
+            let [start, end] = edge.segment(octree.domain());
+            keys.push((edge.key(), edge.axis()));
+
+            let node = edge.key().cell_bounds(octree.domain());
+            assert!(node.min.abs_diff_eq(start, 1e-6));
+
+            for cell in edge.neighbors() {
+                assert!(octree.is_leaf(&cell));
+                let bounds = cell.bounds(octree.domain());
+                assert!(bounds.contains(start) && bounds.contains(end));
+            }
+        });
+
+        assert!(!keys.is_empty());
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), count_minimal_edges(&octree));
+    }
+
+    #[test]
+    fn surface_leaves_straddle_the_surface() {
+        let octree = Octree::uniform(unit_domain(), 3);
+        let sphere = from_fn(|p: Vec3| (p - Vec3::splat(0.5)).length() - 0.3);
+
+        assert_eq!(octree.leaves().count(), 8 * 8 * 8);
+
+        let surface: Vec<_> = octree
+            .surface_leaves(&sphere, Tolerance::Absolute(1e-4))
+            .collect();
+        assert!(!surface.is_empty());
+
+        for cell in octree.leaves() {
+            let bounds = cell.bounds(octree.domain());
+            let signs = corner_signs(&sphere, bounds.min, bounds.size().x);
+            let straddles = signs.iter().any(|&s| s != signs[0]);
+            assert_eq!(surface.contains(&cell), straddles);
+        }
+    }
+
+    #[test]
+    fn walk_stops_on_break() {
+        let octree = Octree::uniform(unit_domain(), 2);
+        let mut count = 0;
+
+        let result = octree.try_for_each_minimal_edge_near(
+            |_| true,
+            |_| {
+                count += 1;
+
+                if count == 5 {
+                    ControlFlow::Break(count)
+                } else {
+                    ControlFlow::Continue(())
+                }
+            },
+        );
+
+        assert_eq!(result, ControlFlow::Break(5));
+        assert_eq!(count, 5);
+    }
+
+    #[test]
+    fn filtered_edges_are_a_subsequence() {
+        let octree = Octree::uniform(unit_domain(), 3);
+        let region = Bounds::new(Vec3::splat(0.2), Vec3::splat(0.4));
+        let near = |cell: &OctreeCell| cell.bounds(octree.domain()).intersects(&region);
+
+        let mut all = Vec::new();
+        octree.for_each_minimal_edge(|edge| all.push(*edge));
+
+        let mut filtered = Vec::new();
+        octree.for_each_minimal_edge_near(near, |edge| filtered.push(*edge));
+
+        let expected: Vec<_> = all
+            .into_iter()
+            .filter(|edge| edge.neighbors().iter().any(near))
+            .collect();
+
+        assert!(!filtered.is_empty());
+        assert_eq!(filtered, expected);
+    }
+
+    #[test]
+    fn cell_geometry() {
+        let octree = Octree::uniform(unit_domain(), 1);
+        let cell = octree.leaves().next().unwrap();
+        let bounds = cell.bounds(octree.domain());
+
+        let corners = cell.corner_positions(octree.domain());
+        for (corner, position) in CornerKind::ALL.into_iter().zip(corners) {
+            assert_eq!(position, bounds.corner(corner));
+        }
+
+        for (start, end) in cell.edge_segments(octree.domain()) {
+            assert!(corners.contains(&start) && corners.contains(&end));
+            assert!(((end - start).length() - 0.5).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn corners_are_sampled_once() {
+        let domain = unit_domain();
+        let center = Vec3::splat(0.4);
+        let mut sampled = Vec::new();
+
+        let octree = Octree::build_sampled(
+            domain,
+            6,
+            |p| {
+                sampled.push(p.to_array().map(f32::to_bits));
+                p.distance(center) - 0.3
+            },
+            |cell, corners| {
+                let bounds = cell.bounds(&domain);
+                let expected = CornerKind::ALL.map(|c| bounds.corner(c).distance(center) - 0.3);
+
+                for (value, expected) in corners.iter().zip(expected) {
+                    assert!((value - expected).abs() < 1e-6);
+                }
+
+                corners.iter().any(|&v| v.abs() < bounds.size().length())
+            },
+        );
+
+        // The distinct corners of all cells above the finest level, as points
+        // of the finest lattice.
+        let mut corners: Vec<UVec3> = octree
+            .interior
+            .iter()
+            .copied()
+            .chain(octree.leaves().map(|cell| cell.key()))
+            .filter(|key| key.level() < 6)
+            .flat_map(|key| {
+                let scale = 1 << (6 - key.level());
+                CornerKind::ALL.map(|corner| (key.coords() + corner_offset(corner)) * scale)
+            })
+            .collect();
+
+        corners.sort_by_key(|p| p.to_array());
+        corners.dedup();
+
+        let count = sampled.len();
+        sampled.sort();
+        sampled.dedup();
+
+        assert!(octree.leaves().count() > 8);
+        assert_eq!(count, sampled.len());
+        assert_eq!(count, corners.len());
+    }
+}
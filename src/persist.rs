@@ -0,0 +1,649 @@
+//! A compact binary format for octrees and placed vertices, so that the
+//! phases sampling the source needn't be run again, e.g., while iterating on
+//! extractor settings or export formats.
+//!
+//! A file starts with a header of:
+//!
+//! - a magic number, `DCOT` for an [`Octree`] or `DCPV` for
+//!   [`PlacedVertices`],
+//! - the version of the format, as a `u16`,
+//! - the domain as the minimum and maximum corners, as `f32`s,
+//! - the resolution along each axis, as `u32`s,
+//! - the length of the payload in bytes, and its FNV-1a hash, as `u64`s.
+//!
+//! All numbers are little-endian. In the payload, counts, indices and keys
+//! are LEB128 varints, sorted keys being encoded as the differences between
+//! consecutive ones, and positions and normals are raw `f32`s.
+//!
+//! Writers aren't buffered internally, so wrapping them in an
+//! [`io::BufWriter`] is recommended.
+
+use crate::collections::Set;
+use crate::geom::Bounds;
+use crate::morton::MortonKey;
+use crate::{DualContouring, ExtractionReport, Octree, PlacedVertices};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use glam::{UVec3, Vec3};
+use std::io::{self, Read, Write};
+
+const OCTREE_MAGIC: [u8; 4] = *b"DCOT";
+const PLACED_MAGIC: [u8; 4] = *b"DCPV";
+
+/// The version of the format, bumped on any change to it.
+const VERSION: u16 = 2;
+
+/// The reason a saved [`Octree`] or [`PlacedVertices`] couldn't be loaded.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum PersistError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The file doesn't start with the magic number of the expected kind.
+    #[error("not a saved {expected}")]
+    WrongMagic { expected: &'static str },
+    #[error("unsupported format version {version}")]
+    UnsupportedVersion { version: u16 },
+    /// The payload doesn't match the checksum of the header.
+    #[error("the checksum doesn't match the payload")]
+    ChecksumMismatch,
+    /// The payload is malformed despite matching its checksum.
+    #[error("malformed payload: {reason}")]
+    Malformed { reason: &'static str },
+    /// The file was saved with different parameters than those of the
+    /// extraction it's loaded for.
+    #[error("saved with {field} {saved}, but the extraction has {expected}")]
+    ParameterMismatch {
+        field: &'static str,
+        saved: String,
+        expected: String,
+    },
+}
+
+/// Returns the FNV-1a hash of `bytes`.
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// The parameters a file was saved with.
+struct Header {
+    domain: Bounds,
+    max_res: UVec3,
+}
+
+impl Header {
+    /// Checks the parameters against those of the extraction the file is
+    /// loaded for.
+    fn check(&self, domain: Bounds, max_res: UVec3) -> Result<(), PersistError> {
+        check_parameter("domain", self.domain, domain)?;
+        check_parameter("resolution", self.max_res, max_res)
+    }
+}
+
+fn check_parameter<T: PartialEq + fmt::Debug>(
+    field: &'static str,
+    saved: T,
+    expected: T,
+) -> Result<(), PersistError> {
+    if saved == expected {
+        return Ok(());
+    }
+
+    Err(PersistError::ParameterMismatch {
+        field,
+        saved: format!("{saved:?}"),
+        expected: format!("{expected:?}"),
+    })
+}
+
+fn write_file<W: Write>(
+    mut writer: W,
+    magic: [u8; 4],
+    header: &Header,
+    payload: &[u8],
+) -> io::Result<()> {
+    let mut bytes = Vec::with_capacity(64);
+    bytes.extend_from_slice(&magic);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+
+    for v in [header.domain.min, header.domain.max] {
+        bytes.extend(v.to_array().iter().flat_map(|c| c.to_le_bytes()));
+    }
+
+    bytes.extend(
+        header
+            .max_res
+            .to_array()
+            .iter()
+            .flat_map(|c| c.to_le_bytes()),
+    );
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&checksum(payload).to_le_bytes());
+
+    writer.write_all(&bytes)?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+/// Reads the header and the payload of a file of the kind of `magic`,
+/// checking the payload against its checksum.
+fn read_file<R: Read>(
+    mut reader: R,
+    magic: [u8; 4],
+    kind: &'static str,
+) -> Result<(Header, Vec<u8>), PersistError> {
+    let mut read = |bytes: &mut [u8]| reader.read_exact(bytes);
+
+    let mut file_magic = [0; 4];
+    read(&mut file_magic)?;
+
+    if file_magic != magic {
+        return Err(PersistError::WrongMagic { expected: kind });
+    }
+
+    let mut version = [0; 2];
+    read(&mut version)?;
+    let version = u16::from_le_bytes(version);
+
+    if version != VERSION {
+        return Err(PersistError::UnsupportedVersion { version });
+    }
+
+    let mut header = [0; 6 * 4 + 3 * 4 + 2 * 8];
+    read(&mut header)?;
+    let mut decoder = Decoder(&header);
+
+    let header = Header {
+        domain: Bounds::new(decoder.vec3()?, decoder.vec3()?),
+        max_res: UVec3::new(decoder.u32()?, decoder.u32()?, decoder.u32()?),
+    };
+    let (len, hash) = (decoder.u64()?, decoder.u64()?);
+
+    // The length isn't trusted to allocate up front.
+    let mut payload = Vec::new();
+    reader.take(len).read_to_end(&mut payload)?;
+
+    if payload.len() as u64 != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+
+    if checksum(&payload) != hash {
+        return Err(PersistError::ChecksumMismatch);
+    }
+
+    Ok((header, payload))
+}
+
+/// Appends the parts of a payload.
+#[derive(Default)]
+struct Encoder(Vec<u8>);
+
+impl Encoder {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+
+        self.0.push(value as u8);
+    }
+
+    fn vec3(&mut self, v: Vec3) {
+        self.0
+            .extend(v.to_array().iter().flat_map(|c| c.to_le_bytes()));
+    }
+
+    fn keys(&mut self, keys: &[MortonKey]) {
+        self.varint(keys.len() as u64);
+
+        for key in keys {
+            self.varint(key.code());
+        }
+    }
+
+    /// Encodes strictly increasing keys as the differences between them.
+    fn sorted_keys(&mut self, keys: &[MortonKey]) {
+        self.varint(keys.len() as u64);
+        let mut prev = 0;
+
+        for key in keys {
+            debug_assert!(key.code() > prev);
+            self.varint(key.code() - prev);
+            prev = key.code();
+        }
+    }
+}
+
+/// Reads the parts of a payload, failing rather than panicking if it's
+/// malformed.
+struct Decoder<'a>(&'a [u8]);
+
+fn malformed(reason: &'static str) -> PersistError {
+    PersistError::Malformed { reason }
+}
+
+impl Decoder<'_> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], PersistError> {
+        let (bytes, rest) = self
+            .0
+            .split_first_chunk()
+            .ok_or_else(|| malformed("unexpected end"))?;
+        self.0 = rest;
+        Ok(*bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, PersistError> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, PersistError> {
+        self.bytes().map(u64::from_le_bytes)
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, PersistError> {
+        let mut v = [0.0; 3];
+
+        for c in &mut v {
+            *c = f32::from_le_bytes(self.bytes()?);
+        }
+
+        Ok(Vec3::from_array(v))
+    }
+
+    fn varint(&mut self) -> Result<u64, PersistError> {
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let [byte] = self.bytes()?;
+            let bits = (byte & 0x7f) as u64;
+
+            if bits << shift >> shift != bits {
+                break;
+            }
+
+            value |= bits << shift;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(malformed("varint overflows 64 bits"))
+    }
+
+    fn varint_u32(&mut self) -> Result<u32, PersistError> {
+        u32::try_from(self.varint()?).map_err(|_| malformed("value overflows 32 bits"))
+    }
+
+    /// Reads the length of a sequence whose elements take at least
+    /// `min_size` bytes, which must fit in the rest of the payload.
+    fn len(&mut self, min_size: usize) -> Result<usize, PersistError> {
+        let len = self.varint()?;
+
+        if len > (self.0.len() / min_size) as u64 {
+            return Err(malformed("sequence longer than the payload"));
+        }
+
+        Ok(len as usize)
+    }
+
+    fn key(&mut self, code: u64) -> Result<MortonKey, PersistError> {
+        MortonKey::from_code(code).ok_or_else(|| malformed("invalid key"))
+    }
+
+    fn keys(&mut self) -> Result<Vec<MortonKey>, PersistError> {
+        let len = self.len(1)?;
+        (0..len)
+            .map(|_| {
+                let code = self.varint()?;
+                self.key(code)
+            })
+            .collect()
+    }
+
+    fn sorted_keys(&mut self) -> Result<Vec<MortonKey>, PersistError> {
+        let len = self.len(1)?;
+        let mut prev = 0u64;
+
+        (0..len)
+            .map(|_| {
+                let delta = self.varint()?;
+
+                if delta == 0 {
+                    return Err(malformed("keys aren't strictly increasing"));
+                }
+
+                prev = prev
+                    .checked_add(delta)
+                    .ok_or_else(|| malformed("invalid key"))?;
+                self.key(prev)
+            })
+            .collect()
+    }
+
+    fn finish(self) -> Result<(), PersistError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(malformed("trailing bytes"))
+        }
+    }
+}
+
+impl Octree {
+    /// Writes the octree in a compact binary format, see
+    /// [`Octree::read_from`].
+    pub fn write_to<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut interior: Vec<MortonKey> = self.interior.iter().copied().collect();
+        interior.sort_unstable();
+
+        let mut payload = Encoder::default();
+        payload.sorted_keys(&interior);
+
+        let header = Header {
+            domain: self.domain,
+            max_res: UVec3::splat(1 << self.max_level),
+        };
+        write_file(writer, OCTREE_MAGIC, &header, &payload.0)
+    }
+
+    /// Reads an octree written by [`Octree::write_to`], checking that it was
+    /// built by [`DualContouring::build_octree`] with the same bounds and
+    /// resolution as `dc`.
+    ///
+    /// With different resolutions per axis, the resolution of an octree is
+    /// the largest one.
+    pub fn read_from<R: Read, S>(reader: R, dc: &DualContouring<S>) -> Result<Self, PersistError> {
+        let (header, payload) = read_file(reader, OCTREE_MAGIC, "octree")?;
+        header.check(dc.octree_domain(), UVec3::splat(dc.max_res.max_element()))?;
+
+        let max_level = dc.max_level();
+        let mut decoder = Decoder(&payload);
+        let mut interior = Set::new();
+
+        // Parents come before their children, whose keys are larger.
+        for key in decoder.sorted_keys()? {
+            if key.level() >= max_level {
+                return Err(malformed("interior cell at the deepest level"));
+            }
+
+            if key != MortonKey::root() && !interior.contains(&key.parent()) {
+                return Err(malformed("interior cell without an interior parent"));
+            }
+
+            interior.insert(key);
+        }
+
+        decoder.finish()?;
+
+        Ok(Octree {
+            domain: header.domain,
+            max_level,
+            interior,
+        })
+    }
+}
+
+impl PlacedVertices {
+    /// Writes the placed vertices in a compact binary format, see
+    /// [`PlacedVertices::read_from`].
+    pub fn write_to<W: Write>(&self, writer: W) -> io::Result<()> {
+        let mut payload = Encoder::default();
+
+        payload.sorted_keys(&self.cells);
+
+        for &v in self.positions.iter().chain(&self.normals) {
+            payload.vec3(v);
+        }
+
+        payload.varint(self.faces.len() as u64);
+
+        for &i in self.faces.as_flattened() {
+            payload.varint(i as u64);
+        }
+
+        let report = &self.report;
+        payload.keys(&report.self_intersecting_cells);
+        payload.keys(&report.undefined_cells);
+
+        for count in [
+            report.best_sample_edges,
+            report.midpoint_edges,
+            report.skipped_edges,
+        ] {
+            payload.varint(count as u64);
+        }
+
+        payload.varint(report.budget_exceeded as u64);
+
+        for level in report.max_level_reached_per_octant {
+            payload.varint(level as u64);
+        }
+
+        payload.keys(&report.indeterminate_cells);
+
+        let header = Header {
+            domain: self.bounds,
+            max_res: self.max_res,
+        };
+        write_file(writer, PLACED_MAGIC, &header, &payload.0)
+    }
+
+    /// Reads placed vertices written by [`PlacedVertices::write_to`],
+    /// checking that they were placed by [`DualContouring::place_vertices`]
+    /// with the same bounds and resolution as `dc`, so that they can be
+    /// passed to its [`DualContouring::generate_faces`].
+    pub fn read_from<R: Read, S>(reader: R, dc: &DualContouring<S>) -> Result<Self, PersistError> {
+        let (header, payload) = read_file(reader, PLACED_MAGIC, "placed vertices")?;
+        header.check(dc.bounds, dc.max_res)?;
+
+        let mut decoder = Decoder(&payload);
+
+        let cells = decoder.sorted_keys()?;
+        if cells.iter().any(|key| key.level() > dc.max_level()) {
+            return Err(malformed("cell deeper than the resolution"));
+        }
+
+        let n = cells.len();
+        let mut vec3s = || {
+            (0..n)
+                .map(|_| decoder.vec3())
+                .collect::<Result<Vec<_>, _>>()
+        };
+        let (positions, normals) = (vec3s()?, vec3s()?);
+
+        let faces = (0..decoder.len(3)?)
+            .map(|_| {
+                let mut face = [0; 3];
+
+                for i in &mut face {
+                    *i = decoder.varint_u32()?;
+
+                    if *i as usize >= n {
+                        return Err(malformed("face index out of range"));
+                    }
+                }
+
+                Ok(face)
+            })
+            .collect::<Result<_, PersistError>>()?;
+
+        let mut report = ExtractionReport {
+            self_intersecting_cells: decoder.keys()?,
+            undefined_cells: decoder.keys()?,
+            best_sample_edges: decoder.varint()? as usize,
+            midpoint_edges: decoder.varint()? as usize,
+            skipped_edges: decoder.varint()? as usize,
+            ..ExtractionReport::default()
+        };
+
+        report.budget_exceeded = match decoder.varint()? {
+            0 => false,
+            1 => true,
+            _ => return Err(malformed("invalid flag")),
+        };
+
+        for level in &mut report.max_level_reached_per_octant {
+            *level = decoder.varint_u32()?;
+        }
+
+        report.indeterminate_cells = decoder.keys()?;
+
+        decoder.finish()?;
+
+        Ok(PlacedVertices {
+            bounds: header.domain,
+            max_res: header.max_res,
+            cells,
+            positions,
+            normals,
+            faces,
+            report,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{IndexedSeparateNormals, WithIndexedSeparateNormals};
+
+    fn sphere() -> impl crate::HermiteSource {
+        hermite_from_fn(
+            |p: Vec3| (p - Vec3::new(0.05, -0.03, 0.02)).length() - 0.6,
+            |p| p - Vec3::new(0.05, -0.03, 0.02),
+        )
+    }
+
+    #[test]
+    fn round_trips() {
+        let sphere = sphere();
+        let dc = DualContouring::new(&sphere, 32, 1e-6).assume_lipschitz(true);
+
+        let octree = dc.build_octree();
+        let mut bytes = Vec::new();
+        octree.write_to(&mut bytes).unwrap();
+        let loaded = Octree::read_from(&bytes[..], &dc).unwrap();
+
+        assert_eq!(loaded.domain(), octree.domain());
+        assert_eq!(loaded.max_level(), octree.max_level());
+        assert!(loaded.leaves().eq(octree.leaves()));
+
+        let placed = dc.place_vertices().unwrap();
+        let mut bytes = Vec::new();
+        placed.write_to(&mut bytes).unwrap();
+        let loaded = PlacedVertices::read_from(&bytes[..], &dc).unwrap();
+        assert_eq!(loaded, placed);
+
+        let mut indeterminate = placed.clone();
+        indeterminate.report.indeterminate_cells = placed.cells[..3].to_vec();
+        let mut indeterminate_bytes = Vec::new();
+        indeterminate.write_to(&mut indeterminate_bytes).unwrap();
+        let loaded_indeterminate =
+            PlacedVertices::read_from(&indeterminate_bytes[..], &dc).unwrap();
+        assert_eq!(loaded_indeterminate, indeterminate);
+
+        // Smaller than with fixed-size keys and indices.
+        let fixed = placed.positions().len() * (8 + 24) + placed.faces().len() * 12;
+        assert!(bytes.len() < fixed * 3 / 4, "{} {fixed}", bytes.len());
+
+        let mut expected = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut expected, &sphere))
+            .unwrap();
+
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.generate_faces(&loaded, WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+        assert!(!mesh.faces.is_empty());
+        assert_eq!(mesh.positions(), expected.positions());
+        assert_eq!(mesh.normals(), expected.normals());
+        assert_eq!(mesh.faces, expected.faces);
+    }
+
+    #[test]
+    fn parameter_mismatches() {
+        let sphere = sphere();
+        let dc = DualContouring::new(&sphere, 16, 1e-6);
+
+        let mut octree = Vec::new();
+        dc.build_octree().write_to(&mut octree).unwrap();
+        let mut placed = Vec::new();
+        dc.place_vertices().unwrap().write_to(&mut placed).unwrap();
+
+        let finer = DualContouring::new(&sphere, 32, 1e-6);
+        let error = Octree::read_from(&octree[..], &finer).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                PersistError::ParameterMismatch {
+                    field: "resolution",
+                    ..
+                }
+            ),
+            "{error}"
+        );
+
+        let moved = DualContouring::new(&sphere, 16, 1e-6)
+            .with_bounds(Bounds::new(Vec3::splat(-2.0), Vec3::splat(2.0)));
+        let error = PlacedVertices::read_from(&placed[..], &moved).unwrap_err();
+        assert!(
+            matches!(
+                error,
+                PersistError::ParameterMismatch {
+                    field: "domain",
+                    ..
+                }
+            ),
+            "{error}"
+        );
+
+        let error = PlacedVertices::read_from(&octree[..], &dc).unwrap_err();
+        assert!(matches!(error, PersistError::WrongMagic { .. }), "{error}");
+    }
+
+    #[test]
+    fn corrupted_files() {
+        let sphere = sphere();
+        let dc = DualContouring::new(&sphere, 8, 1e-6);
+
+        let mut file = Vec::new();
+        dc.place_vertices().unwrap().write_to(&mut file).unwrap();
+        assert!(PlacedVertices::read_from(&file[..], &dc).is_ok());
+
+        // Flipping any bit or truncating the file is an error, not a panic.
+        for i in 0..file.len() {
+            for bit in 0..8 {
+                let mut corrupted = file.clone();
+                corrupted[i] ^= 1 << bit;
+                assert!(PlacedVertices::read_from(&corrupted[..], &dc).is_err());
+            }
+
+            assert!(PlacedVertices::read_from(&file[..i], &dc).is_err());
+        }
+
+        // Payloads that match their checksums are still checked.
+        let header = Header {
+            domain: dc.bounds,
+            max_res: dc.max_res,
+        };
+        let payloads: [&[u8]; 5] = [
+            &[0xff; 12],
+            &[1, 1, 0],
+            &[2, 1, 1],
+            &[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x02],
+            &[0, 0x40],
+        ];
+
+        for payload in payloads {
+            let mut file = Vec::new();
+            write_file(&mut file, PLACED_MAGIC, &header, payload).unwrap();
+            let error = PlacedVertices::read_from(&file[..], &dc).unwrap_err();
+            assert!(matches!(error, PersistError::Malformed { .. }), "{error}");
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::contour::{place_vertex, walk_edges};
+use crate::extractor::SeparateNormals;
+use crate::octree::Octree;
+use crate::source::HermiteSource;
+use crate::{DualContouring, ExtractSurfaceError, ExtractionReport};
+use alloc::vec::Vec;
+use auto_impl::auto_impl;
+use glam::Vec3;
+
+/// Receives the oriented points of [`DualContouring::extract_points`].
+#[auto_impl(&mut, Box)]
+pub trait PointSink {
+    /// Receives a point along with the outward unit normal there.
+    fn point(&mut self, position: Vec3, normal: Vec3);
+}
+
+/// Which points [`DualContouring::extract_points`] emits.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum PointMode {
+    /// The vertices of the cells crossed by the surface, as placed for a
+    /// mesh, with the normals of the source there.
+    #[default]
+    CellVertices,
+    /// The intersections of the surface with the minimal edges of the
+    /// octree, along with their normals, which lie on the surface within the
+    /// tolerance. There is one per quad of the mesh.
+    EdgeIntersections,
+}
+
+/// A [`PointSink`] collecting the points into vectors.
+#[derive(Debug, Clone, Default)]
+pub struct CollectPoints {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+}
+
+impl PointSink for CollectPoints {
+    fn point(&mut self, position: Vec3, normal: Vec3) {
+        self.positions.push(position);
+        self.normals.push(normal);
+    }
+}
+
+/// Collects the points as vertices, e.g., to render them as splats with the
+/// same code as the vertices of a mesh.
+impl PointSink for SeparateNormals {
+    fn point(&mut self, position: Vec3, normal: Vec3) {
+        self.push(position, normal);
+    }
+}
+
+/// Feeds the points of the surface over the leaves of `octree` to `sink`,
+/// without generating any faces.
+pub(crate) fn extract_points<S, P>(
+    dc: &DualContouring<S>,
+    octree: &Octree,
+    mode: PointMode,
+    sink: &mut P,
+) -> Result<ExtractionReport, ExtractSurfaceError>
+where
+    S: HermiteSource,
+    P: PointSink,
+{
+    // Points are only emitted once the walk succeeds, like the vertices of
+    // a mesh.
+    let mut samples = Vec::new();
+    let contour = walk_edges(
+        dc,
+        octree,
+        || true,
+        |sample| {
+            if mode == PointMode::EdgeIntersections {
+                samples.push(*sample);
+            }
+        },
+    )?;
+
+    match mode {
+        PointMode::CellVertices => {
+            for (key, qef) in contour.cells() {
+                let position = place_vertex(*key, qef, octree.domain());
+                sink.point(position, dc.source.sample_normal(position));
+            }
+        }
+        PointMode::EdgeIntersections => {
+            for sample in samples {
+                sink.point(sample.point, sample.normal);
+            }
+        }
+    }
+
+    Ok(contour.report())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{IndexedSeparateNormals, WithIndexedSeparateNormals};
+
+    #[test]
+    fn sphere_points() {
+        let radius = 0.6;
+        let epsilon = 1e-4;
+        let sphere = hermite_from_fn(move |p: Vec3| p.length() - radius, |p| p);
+        let dc = DualContouring::new(&sphere, 32, epsilon);
+
+        let mut mesh = IndexedSeparateNormals::default();
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+
+        let mut vertices = CollectPoints::default();
+        dc.extract_points(&mut vertices).unwrap();
+        assert_eq!(vertices.positions, mesh.positions());
+        assert_eq!(vertices.normals, mesh.normals());
+
+        let mut splats = SeparateNormals::default();
+        dc.extract_points(&mut splats).unwrap();
+        assert_eq!(splats.positions, vertices.positions);
+        assert_eq!(splats.normals, vertices.normals);
+
+        let mut intersections = CollectPoints::default();
+        dc.with_point_mode(PointMode::EdgeIntersections)
+            .extract_points(&mut intersections)
+            .unwrap();
+        assert_eq!(intersections.positions.len(), mesh.faces.len() / 2);
+
+        for (p, n) in intersections.positions.iter().zip(&intersections.normals) {
+            assert!((p.length() - radius).abs() <= epsilon, "{p}");
+            assert!(n.abs_diff_eq(p.normalize(), 1e-4));
+        }
+    }
+}
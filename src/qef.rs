@@ -0,0 +1,249 @@
+use core::array;
+use glam::{DMat3, DVec3, Mat3, Vec3};
+
+/// Eigenvalues of `AᵀA` smaller than this fraction of the largest one are
+/// truncated when solving, which keeps nearly-planar configurations from
+/// throwing the solution far away from the mass point.
+const TRUNCATION: f64 = 0.1;
+
+/// Number of Jacobi sweeps used to diagonalize `AᵀA`.
+const SWEEPS: usize = 6;
+
+/// Defines a QEF over the given scalar, vector and matrix types, which only
+/// differ by precision.
+macro_rules! qef {
+    ($(#[$meta:meta])* $name:ident, $scalar:ident, $vec:ident, $mat:ident, $sqrt:path) => {
+        $(#[$meta])*
+        #[derive(Debug, Copy, Clone)]
+        pub struct $name {
+            ata: $mat,
+            atb: $vec,
+            btb: $scalar,
+            point_sum: $vec,
+            count: u32,
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    ata: $mat::ZERO,
+                    atb: $vec::ZERO,
+                    btb: 0.0,
+                    point_sum: $vec::ZERO,
+                    count: 0,
+                }
+            }
+        }
+
+        impl $name {
+            /// Adds the plane through `point` with the given `normal`.
+            pub fn add(&mut self, point: $vec, normal: $vec) {
+                let b = normal.dot(point);
+                self.ata += $mat::from_cols(normal * normal.x, normal * normal.y, normal * normal.z);
+                self.atb += normal * b;
+                self.btb += b * b;
+                self.point_sum += point;
+                self.count += 1;
+            }
+
+            /// Adds all planes of `other`, as if they were added one by one.
+            pub fn merge(&mut self, other: &Self) {
+                self.ata += other.ata;
+                self.atb += other.atb;
+                self.btb += other.btb;
+                self.point_sum += other.point_sum;
+                self.count += other.count;
+            }
+
+            /// Returns the average of all added points.
+            pub fn mass_point(&self) -> $vec {
+                self.point_sum / self.count.max(1) as $scalar
+            }
+
+            /// Evaluates the error at `x`, i.e., the sum of squared distances to
+            /// the accumulated planes.
+            pub fn error(&self, x: $vec) -> $scalar {
+                (x.dot(self.ata * x) - 2.0 * x.dot(self.atb) + self.btb).max(0.0)
+            }
+
+            /// Finds the point minimizing the error, preferring the solution
+            /// closest to the mass point when it's not unique. Returns the point
+            /// and its error.
+            pub fn solve(&self) -> ($vec, $scalar) {
+                let mass_point = self.mass_point();
+                let rhs = self.atb - self.ata * mass_point;
+                let x = mass_point + Self::pseudo_inverse(self.ata) * rhs;
+                (x, self.error(x))
+            }
+
+            /// Like `solve`, but also penalizes the squared distance to the mass
+            /// point, weighted by `weight` times the number of planes. The
+            /// larger the weight, the closer the solution is to the mass point.
+            pub fn solve_regularized(&self, weight: $scalar) -> ($vec, $scalar) {
+                let mass_point = self.mass_point();
+                let lambda = weight * self.count.max(1) as $scalar;
+                let lhs = self.ata + $mat::from_diagonal($vec::splat(lambda));
+                let rhs = self.atb - self.ata * mass_point;
+                let x = mass_point + lhs.inverse() * rhs;
+                (x, self.error(x))
+            }
+
+            /// Computes the truncated pseudo-inverse of a symmetric matrix.
+            fn pseudo_inverse(m: $mat) -> $mat {
+                let (values, vectors) = Self::symmetric_eigen(m);
+                let max = values.abs().max_element();
+
+                let inverse = values.to_array().map(|value| {
+                    if max > 0.0 && value.abs() > TRUNCATION as $scalar * max {
+                        1.0 / value
+                    } else {
+                        0.0
+                    }
+                });
+
+                vectors * $mat::from_diagonal($vec::from_array(inverse)) * vectors.transpose()
+            }
+
+            /// Diagonalizes a symmetric matrix using cyclic Jacobi rotations,
+            /// returning its eigenvalues and the matrix of the corresponding
+            /// (column) eigenvectors.
+            fn symmetric_eigen(m: $mat) -> ($vec, $mat) {
+                let mut a = m.to_cols_array_2d();
+                let mut v = $mat::IDENTITY.to_cols_array_2d();
+
+                for _ in 0..SWEEPS {
+                    for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+                        if a[p][q].abs() <= $scalar::EPSILON * (a[p][p].abs() + a[q][q].abs()) {
+                            a[p][q] = 0.0;
+                            a[q][p] = 0.0;
+                            continue;
+                        }
+
+                        let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                        let t = theta.signum() / (theta.abs() + $sqrt(theta * theta + 1.0));
+                        let c = 1.0 / $sqrt(t * t + 1.0);
+                        let s = t * c;
+
+                        for row in a.iter_mut().chain(&mut v) {
+                            let (rp, rq) = (row[p], row[q]);
+                            row[p] = c * rp - s * rq;
+                            row[q] = s * rp + c * rq;
+                        }
+
+                        let (ap, aq) = (a[p], a[q]);
+                        a[p] = array::from_fn(|k| c * ap[k] - s * aq[k]);
+                        a[q] = array::from_fn(|k| s * ap[k] + c * aq[k]);
+                    }
+                }
+
+                // `v` is stored as an array of columns, but the rotations above
+                // treat its first index as the row, hence the transpose.
+                let vectors = $mat::from_cols_array_2d(&v).transpose();
+                ($vec::new(a[0][0], a[1][1], a[2][2]), vectors)
+            }
+        }
+    };
+}
+
+qef!(
+    /// A quadratic error function accumulating Hermite data (intersection
+    /// points and normals) of a single cell.
+    Qef,
+    f32,
+    Vec3,
+    Mat3,
+    sqrt
+);
+
+qef!(
+    /// Like [`Qef`], in double precision, e.g., for Hermite data from a
+    /// [`HermiteSource64`](crate::source::HermiteSource64) far from the
+    /// origin.
+    Qef64,
+    f64,
+    DVec3,
+    DMat3,
+    sqrt64
+);
+
+/// `f32::sqrt`, which is only available with `std`.
+fn sqrt(x: f32) -> f32 {
+    #[cfg(feature = "std")]
+    return x.sqrt();
+
+    #[cfg(not(feature = "std"))]
+    return libm::sqrtf(x);
+}
+
+/// `f64::sqrt`, which is only available with `std`.
+fn sqrt64(x: f64) -> f64 {
+    #[cfg(feature = "std")]
+    return x.sqrt();
+
+    #[cfg(not(feature = "std"))]
+    return libm::sqrt(x);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sharp_corner() {
+        let mut qef = Qef::default();
+        qef.add(Vec3::new(0.5, 0.1, 0.2), Vec3::X);
+        qef.add(Vec3::new(0.3, 0.4, 0.8), Vec3::Y);
+        qef.add(Vec3::new(0.9, 0.7, 0.6), Vec3::Z);
+
+        let (x, error) = qef.solve();
+        assert!(x.abs_diff_eq(Vec3::new(0.5, 0.4, 0.6), 1e-5));
+        assert!(error < 1e-6);
+    }
+
+    #[test]
+    fn planar_stays_near_mass_point() {
+        let mut qef = Qef::default();
+        qef.add(Vec3::new(0.0, 0.5, 0.0), Vec3::Y);
+        qef.add(Vec3::new(1.0, 0.5, 1.0), Vec3::Y);
+
+        let (x, _) = qef.solve();
+        assert!(x.abs_diff_eq(Vec3::new(0.5, 0.5, 0.5), 1e-5));
+    }
+
+    #[test]
+    fn regularization_pulls_towards_mass_point() {
+        let mut qef = Qef::default();
+        qef.add(Vec3::new(0.5, 0.1, 0.2), Vec3::X);
+        qef.add(Vec3::new(0.3, 0.4, 0.8), Vec3::Y);
+        qef.add(Vec3::new(0.9, 0.7, 0.6), Vec3::Z);
+
+        let corner = Vec3::new(0.5, 0.4, 0.6);
+        let mass_point = qef.mass_point();
+        let (weak, _) = qef.solve_regularized(1e-3);
+        let (strong, _) = qef.solve_regularized(1e3);
+
+        assert!(weak.abs_diff_eq(corner, 1e-2));
+        assert!(strong.abs_diff_eq(mass_point, 1e-2));
+    }
+
+    #[test]
+    fn eigen_decomposition() {
+        let m = Mat3::from_cols_array(&[4.0, 1.0, 0.5, 1.0, 3.0, 0.2, 0.5, 0.2, 2.0]);
+        let (values, vectors) = Qef::symmetric_eigen(m);
+        let rebuilt = vectors * Mat3::from_diagonal(values) * vectors.transpose();
+        assert!(rebuilt.abs_diff_eq(m, 1e-4));
+    }
+
+    #[test]
+    fn sharp_corner_far_from_origin() {
+        let corner = DVec3::new(1e7 + 0.5, 2e7 + 0.4, -3e7 + 0.6);
+        let mut qef = Qef64::default();
+        qef.add(corner + DVec3::new(0.0, -0.3, -0.4), DVec3::X);
+        qef.add(corner + DVec3::new(-0.2, 0.0, 0.2), DVec3::Y);
+        qef.add(corner + DVec3::new(0.4, 0.3, 0.0), DVec3::Z);
+
+        let (x, error) = qef.solve();
+        assert!(x.abs_diff_eq(corner, 1e-6));
+        assert!(error < 1e-9);
+    }
+}
@@ -0,0 +1,483 @@
+//! Decimation of extracted meshes by edge collapses, guided by quadric error
+//! metrics, see [`simplify_mesh`].
+
+use crate::collections::{Map, Set};
+use crate::extractor::{plane_normal, IndexedSeparateNormals};
+use crate::qef::Qef64;
+use crate::source::HermiteSource;
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::f32::consts::PI;
+use glam::Vec3;
+
+/// How [`simplify_mesh`] decimates a mesh.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SimplifyOptions {
+    /// Edges whose adjacent faces' normals differ by more than this angle,
+    /// in radians, are feature edges, which are kept. Defaults to π, i.e.,
+    /// no feature edges.
+    ///
+    /// Feature edges are only collapsed along the feature, between vertices
+    /// on exactly two of them, and vertices where other numbers of them meet,
+    /// e.g., the corners of a box, never move. Boundary and non-manifold
+    /// edges are always feature edges.
+    pub preserve_feature_angle: f32,
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        Self {
+            preserve_feature_angle: PI,
+        }
+    }
+}
+
+/// Decimates `mesh` down to `target_faces` faces, or as close to it as
+/// possible, by collapsing its edges in the order of the quadric error of the
+/// merged vertices (Garland and Heckbert), and returns how many faces were
+/// removed.
+///
+/// Every vertex starts with the quadric of the planes of its faces, and a
+/// collapse merges the quadrics of both ends, placing the merged vertex where
+/// it's the smallest. Collapses that would make the mesh non-manifold (as
+/// told by the link condition) or flip a face are skipped, so a closed
+/// manifold mesh stays closed and manifold, and so does its genus. Feature
+/// edges are kept, see [`SimplifyOptions::preserve_feature_angle`].
+///
+/// The remaining vertices keep their order, and normals are recomputed from
+/// the faces around them, weighted by their angles, see
+/// [`NormalMode::AngleWeightedFromFaces`]. See [`simplify_mesh_with_source`]
+/// to sample them from the source instead.
+///
+/// [`NormalMode::AngleWeightedFromFaces`]: crate::NormalMode::AngleWeightedFromFaces
+pub fn simplify_mesh(
+    mesh: &mut IndexedSeparateNormals,
+    target_faces: usize,
+    options: SimplifyOptions,
+) -> usize {
+    let removed = collapse_edges(mesh, target_faces, options);
+    mesh.recompute_normals();
+    removed
+}
+
+/// Like [`simplify_mesh`], but samples the normals of the remaining vertices
+/// from `source`, which `mesh` must have been extracted from, in the same
+/// coordinate system.
+pub fn simplify_mesh_with_source(
+    mesh: &mut IndexedSeparateNormals,
+    target_faces: usize,
+    options: SimplifyOptions,
+    source: &impl HermiteSource,
+) -> usize {
+    let removed = collapse_edges(mesh, target_faces, options);
+    let vertices = &mut mesh.vertices;
+
+    for (normal, &position) in vertices.normals.iter_mut().zip(&vertices.positions) {
+        *normal = source.sample_normal(position);
+    }
+
+    removed
+}
+
+/// Returns the key of the edge between `a` and `b`, regardless of its
+/// direction.
+pub(crate) fn edge_key(a: u32, b: u32) -> [u32; 2] {
+    [a.min(b), a.max(b)]
+}
+
+/// Returns the edges whose adjacent faces' normals differ by more than
+/// `feature_angle`, given the faces around every edge, and the boundary and
+/// non-manifold edges.
+pub(crate) fn feature_edges(
+    edge_faces: &Map<[u32; 2], Vec<u32>>,
+    normals: &[Vec3],
+    feature_angle: f32,
+) -> Set<[u32; 2]> {
+    edge_faces
+        .iter()
+        .filter(|(_, faces)| match faces[..] {
+            [a, b] => normals[a as usize].angle_between(normals[b as usize]) > feature_angle,
+            _ => true,
+        })
+        .map(|(&key, _)| key)
+        .collect()
+}
+
+/// A collapse of the edge `from`-`to` moving `to` to `position`, ordered by
+/// its cost, the lowest first.
+struct Candidate {
+    cost: f64,
+    from: u32,
+    to: u32,
+    position: Vec3,
+    /// The versions of both vertices when the candidate was planned.
+    versions: [u32; 2],
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    /// Reversed, as [`BinaryHeap`] pops the largest item first. Ties are
+    /// broken by the vertices, so that the order is deterministic.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| (other.from, other.to).cmp(&(self.from, self.to)))
+    }
+}
+
+/// The state of a decimation.
+struct Decimator<'a> {
+    positions: &'a mut [Vec3],
+    faces: &'a mut [[u32; 3]],
+    face_alive: Vec<bool>,
+    /// The live faces around every vertex.
+    vertex_faces: Vec<Vec<u32>>,
+    quadrics: Vec<Qef64>,
+    /// Bumped whenever a vertex moves or is removed, which invalidates the
+    /// candidates planned with it.
+    versions: Vec<u32>,
+    features: Set<[u32; 2]>,
+    heap: BinaryHeap<Candidate>,
+}
+
+/// Collapses edges of `mesh` until it has at most `target_faces` faces or no
+/// collapse is left, then drops the removed faces and vertices, and returns
+/// how many faces were removed.
+fn collapse_edges(
+    mesh: &mut IndexedSeparateNormals,
+    target_faces: usize,
+    options: SimplifyOptions,
+) -> usize {
+    let count = mesh.faces.len();
+    let mut decimator = Decimator::new(
+        &mut mesh.vertices.positions,
+        &mut mesh.faces,
+        options.preserve_feature_angle,
+    );
+
+    let mut live = count;
+
+    while live > target_faces {
+        let Some(candidate) = decimator.heap.pop() else {
+            break;
+        };
+
+        live -= decimator.try_collapse(&candidate);
+    }
+
+    let alive = decimator.face_alive;
+    let mut alive = alive.into_iter();
+    mesh.faces.retain(|_| alive.next().unwrap());
+    mesh.remove_unreferenced_vertices();
+
+    count - mesh.faces.len()
+}
+
+impl<'a> Decimator<'a> {
+    fn new(positions: &'a mut [Vec3], faces: &'a mut [[u32; 3]], feature_angle: f32) -> Self {
+        let vertex_count = positions.len();
+        let mut vertex_faces = vec![Vec::new(); vertex_count];
+        let mut quadrics = vec![Qef64::default(); vertex_count];
+        let mut edge_faces: Map<[u32; 2], Vec<u32>> = Map::default();
+        let mut normals = Vec::with_capacity(faces.len());
+
+        for (f, face) in faces.iter().enumerate() {
+            let points = face.map(|i| positions[i as usize]);
+            let normal = plane_normal(&points).normalize_or_zero();
+            let centroid = points.iter().sum::<Vec3>() / 3.0;
+            normals.push(normal);
+
+            for (k, &i) in face.iter().enumerate() {
+                vertex_faces[i as usize].push(f as u32);
+                quadrics[i as usize].add(centroid.as_dvec3(), normal.as_dvec3());
+
+                let key = edge_key(i, face[(k + 1) % 3]);
+                edge_faces.entry(key).or_default().push(f as u32);
+            }
+        }
+
+        let features = feature_edges(&edge_faces, &normals, feature_angle);
+
+        let mut decimator = Self {
+            positions,
+            faces,
+            face_alive: vec![true; normals.len()],
+            vertex_faces,
+            quadrics,
+            versions: vec![0; vertex_count],
+            features,
+            heap: BinaryHeap::new(),
+        };
+
+        let mut edges: Vec<_> = edge_faces.into_keys().collect();
+        edges.sort_unstable();
+
+        for [a, b] in edges {
+            decimator.plan(a, b);
+        }
+
+        decimator
+    }
+
+    /// Returns the vertices sharing a live face with `v`, sorted.
+    fn neighbors(&self, v: u32) -> Vec<u32> {
+        let mut neighbors: Vec<u32> = self.vertex_faces[v as usize]
+            .iter()
+            .flat_map(|&f| self.faces[f as usize])
+            .filter(|&i| i != v)
+            .collect();
+
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Returns the number of feature edges at `v`.
+    fn feature_count(&self, v: u32) -> usize {
+        self.neighbors(v)
+            .into_iter()
+            .filter(|&n| self.features.contains(&edge_key(v, n)))
+            .count()
+    }
+
+    /// Pushes the cheapest allowed collapse of the edge between `a` and `b`,
+    /// if any.
+    fn plan(&mut self, a: u32, b: u32) {
+        let (count_a, count_b) = (self.feature_count(a), self.feature_count(b));
+        let (pa, pb) = (self.positions[a as usize], self.positions[b as usize]);
+        let mut quadric = self.quadrics[a as usize];
+        quadric.merge(&self.quadrics[b as usize]);
+
+        // Vertices on a crease may only slide along it, and those on other
+        // numbers of feature edges may not move at all.
+        let (from, to, positions) = if self.features.contains(&edge_key(a, b)) {
+            match (count_a, count_b) {
+                (2, 2) => (a, b, vec![pa, pb, (pa + pb) / 2.0]),
+                (2, _) => (a, b, vec![pb]),
+                (_, 2) => (b, a, vec![pa]),
+                _ => return,
+            }
+        } else {
+            match (count_a, count_b) {
+                (0, 0) => {
+                    let (optimum, _) = quadric.solve();
+                    (a, b, vec![optimum.as_vec3(), pa, pb, (pa + pb) / 2.0])
+                }
+                (0, _) => (a, b, vec![pb]),
+                (_, 0) => (b, a, vec![pa]),
+                _ => return,
+            }
+        };
+
+        let (cost, position) = positions
+            .into_iter()
+            .filter(|p| p.is_finite())
+            .map(|p| (quadric.error(p.as_dvec3()), p))
+            .min_by(|x, y| x.0.total_cmp(&y.0))
+            .unwrap_or((0.0, pb));
+
+        self.heap.push(Candidate {
+            cost,
+            from,
+            to,
+            position,
+            versions: [self.versions[from as usize], self.versions[to as usize]],
+        });
+    }
+
+    /// Returns whether collapsing the edge between `a` and `b` keeps the
+    /// mesh manifold, i.e., whether the only vertices adjacent to both are
+    /// the opposite ones of the faces around the edge.
+    fn satisfies_link_condition(&self, a: u32, b: u32) -> bool {
+        let neighbors_b = self.neighbors(b);
+        let mut shared: Vec<u32> = (self.neighbors(a).into_iter())
+            .filter(|n| neighbors_b.binary_search(n).is_ok())
+            .collect();
+
+        let mut opposite: Vec<u32> = self.vertex_faces[a as usize]
+            .iter()
+            .map(|&f| self.faces[f as usize])
+            .filter(|face| face.contains(&b))
+            .flat_map(|face| face.into_iter().filter(|&i| i != a && i != b))
+            .collect();
+
+        shared.sort_unstable();
+        opposite.sort_unstable();
+        opposite.dedup();
+        shared == opposite
+    }
+
+    /// Returns whether moving `from` and `to` to `position` flips or
+    /// collapses any face around them that survives the collapse.
+    fn flips_faces(&self, from: u32, to: u32, position: Vec3) -> bool {
+        let faces = self.vertex_faces[from as usize]
+            .iter()
+            .chain(&self.vertex_faces[to as usize]);
+
+        faces.map(|&f| self.faces[f as usize]).any(|face| {
+            if face.contains(&from) && face.contains(&to) {
+                return false;
+            }
+
+            let before = face.map(|i| self.positions[i as usize]);
+            let after = face.map(|i| {
+                if i == from || i == to {
+                    position
+                } else {
+                    self.positions[i as usize]
+                }
+            });
+
+            plane_normal(&after).dot(plane_normal(&before)) <= 0.0
+        })
+    }
+
+    /// Collapses the edge of `candidate` if it's still valid and allowed,
+    /// and returns how many faces were removed.
+    fn try_collapse(&mut self, candidate: &Candidate) -> usize {
+        let &Candidate {
+            from, to, position, ..
+        } = candidate;
+
+        let current = [self.versions[from as usize], self.versions[to as usize]];
+
+        if current != candidate.versions
+            || !self.satisfies_link_condition(from, to)
+            || self.flips_faces(from, to, position)
+        {
+            return 0;
+        }
+
+        // Faces around the edge are removed, the others move to `to`.
+        let mut removed = 0;
+
+        for f in core::mem::take(&mut self.vertex_faces[from as usize]) {
+            let face = &mut self.faces[f as usize];
+
+            if face.contains(&to) {
+                self.face_alive[f as usize] = false;
+                removed += 1;
+
+                for i in *face {
+                    self.vertex_faces[i as usize].retain(|&g| g != f);
+                }
+            } else {
+                *face = face.map(|i| if i == from { to } else { i });
+                self.vertex_faces[to as usize].push(f);
+            }
+        }
+
+        let merged = self.quadrics[from as usize];
+        self.quadrics[to as usize].merge(&merged);
+        self.positions[to as usize] = position;
+        self.versions[from as usize] += 1;
+        self.versions[to as usize] += 1;
+
+        // Feature edges of `from` now end at `to`.
+        let neighbors = self.neighbors(to);
+
+        for &n in &neighbors {
+            if self.features.remove(&edge_key(from, n)) {
+                self.features.insert(edge_key(to, n));
+            }
+        }
+
+        self.features.remove(&edge_key(from, to));
+
+        for n in neighbors {
+            self.plan(to, n);
+        }
+
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{from_fn, hermite_from_fn};
+    use crate::validate::boundary_edges;
+    use crate::{DualContouring, WithIndexedSeparateNormals};
+
+    fn extract(source: &impl HermiteSource, max_res: u32) -> IndexedSeparateNormals {
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(source, max_res, 1e-6)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, source))
+            .unwrap();
+        mesh
+    }
+
+    #[test]
+    fn decimated_sphere_stays_round() {
+        const RADIUS: f32 = 0.85;
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - RADIUS, |p| p);
+        let mut mesh = extract(&sphere, 64);
+        let count = mesh.faces.len();
+        assert!(count > 15_000, "{count}");
+
+        let removed = simplify_mesh(&mut mesh, 2000, SimplifyOptions::default());
+        assert_eq!(removed, count - mesh.faces.len());
+        assert!(mesh.faces.len() <= 2000 && mesh.faces.len() > 1900);
+        assert!(boundary_edges(&mesh.faces).is_empty());
+        assert_eq!(mesh.positions().len(), mesh.normals().len());
+
+        for (&position, &normal) in mesh.positions().iter().zip(mesh.normals()) {
+            let deviation = (position.length() - RADIUS).abs();
+            assert!(deviation < 0.01 * RADIUS, "{position}");
+            assert!(normal.dot(position.normalize()) > 0.9, "{normal}");
+        }
+
+        let volume = mesh.signed_volume().unwrap();
+        assert!((volume / (4.0 / 3.0 * PI as f64 * RADIUS.powi(3) as f64) - 1.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn decimated_cube_keeps_corners() {
+        let half = Vec3::splat(0.55);
+        let cube = from_fn(move |p: Vec3| {
+            let q = p.abs() - half;
+            q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+        });
+        let cube = crate::FiniteDifference::new(cube, 1e-4);
+        let mut mesh = extract(&cube, 32);
+
+        let options = SimplifyOptions {
+            preserve_feature_angle: 30f32.to_radians(),
+        };
+        simplify_mesh_with_source(&mut mesh, 100, options, &cube);
+
+        assert!(mesh.faces.len() <= 100, "{}", mesh.faces.len());
+        assert!(boundary_edges(&mesh.faces).is_empty());
+
+        let corners = mesh
+            .positions()
+            .iter()
+            .filter(|p| (p.abs() - half).abs().max_element() < 1e-3)
+            .count();
+        assert_eq!(corners, 8);
+
+        // Every vertex is still on the surface of the cube.
+        for &position in mesh.positions() {
+            assert!(
+                (position.abs() - half).abs().min_element() < 1e-3,
+                "{position}"
+            );
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! Smoothing of extracted meshes, see
+//! [`IndexedSeparateNormals::laplacian_smooth`] and
+//! [`IndexedSeparateNormals::taubin_smooth`].
+
+use crate::collections::Map;
+use crate::extractor::{plane_normal, IndexedSeparateNormals};
+use crate::simplify::{edge_key, feature_edges};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::f32::consts::PI;
+use glam::Vec3;
+
+/// How [`IndexedSeparateNormals::laplacian_smooth_with`] and
+/// [`IndexedSeparateNormals::taubin_smooth_with`] smooth a mesh.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SmoothOptions {
+    /// Edges whose adjacent faces' normals differ by more than this angle,
+    /// in radians, are feature edges, e.g., the sharp edges that dual
+    /// contouring places vertices on. Defaults to π, i.e., no feature edges.
+    ///
+    /// Vertices on exactly two feature edges only move toward their
+    /// neighbors along them, so that creases stay sharp, and vertices where
+    /// other numbers of them meet, e.g., the corners of a box, never move.
+    /// Boundary and non-manifold edges are always feature edges, so the rim
+    /// of an open mesh is only smoothed along itself.
+    pub preserve_feature_angle: f32,
+}
+
+impl Default for SmoothOptions {
+    fn default() -> Self {
+        Self {
+            preserve_feature_angle: PI,
+        }
+    }
+}
+
+impl IndexedSeparateNormals {
+    /// Smooths the mesh by moving every vertex `lambda` of the way toward
+    /// the average of its neighbors, `iterations` times, with the default
+    /// [`SmoothOptions`].
+    ///
+    /// See [`IndexedSeparateNormals::laplacian_smooth_with`].
+    pub fn laplacian_smooth(&mut self, iterations: usize, lambda: f32) {
+        self.laplacian_smooth_with(iterations, lambda, SmoothOptions::default());
+    }
+
+    /// Smooths the mesh by moving every vertex `lambda` of the way toward
+    /// the average of its neighbors, i.e., the vertices sharing an edge with
+    /// it, `iterations` times, e.g., to even out the bumps of a noisy source.
+    ///
+    /// `lambda` is usually between 0 and 1, and every iteration moves all
+    /// vertices at once, from their positions after the previous one. Closed
+    /// meshes shrink as they're smoothed, by more for more iterations and
+    /// larger values of `lambda`, which
+    /// [`IndexedSeparateNormals::taubin_smooth`] avoids. Feature edges are
+    /// kept sharp, see [`SmoothOptions::preserve_feature_angle`]. Normals
+    /// are recomputed from the faces around the vertices, weighted by their
+    /// angles, see [`NormalMode::AngleWeightedFromFaces`].
+    ///
+    /// [`NormalMode::AngleWeightedFromFaces`]: crate::NormalMode::AngleWeightedFromFaces
+    pub fn laplacian_smooth_with(
+        &mut self,
+        iterations: usize,
+        lambda: f32,
+        options: SmoothOptions,
+    ) {
+        self.smooth(iterations, &[lambda], options);
+    }
+
+    /// Smooths the mesh without shrinking it, by Taubin's λ|μ algorithm,
+    /// `iterations` times, with the default [`SmoothOptions`].
+    ///
+    /// See [`IndexedSeparateNormals::taubin_smooth_with`].
+    pub fn taubin_smooth(&mut self, iterations: usize, lambda: f32, mu: f32) {
+        self.taubin_smooth_with(iterations, lambda, mu, SmoothOptions::default());
+    }
+
+    /// Smooths the mesh without shrinking it, by Taubin's λ|μ algorithm:
+    /// every iteration is a step of
+    /// [`IndexedSeparateNormals::laplacian_smooth_with`] by `lambda`, which
+    /// shrinks the mesh, followed by one by `mu`, which is negative and
+    /// inflates it back.
+    ///
+    /// `mu` should be slightly larger than `lambda` in magnitude, e.g., with
+    /// a `lambda` of 0.5 and a `mu` of -0.53, which removes bumps spanning a
+    /// few edges, as a low-pass filter, and keeps larger shapes. Feature
+    /// edges and normals are handled as by
+    /// [`IndexedSeparateNormals::laplacian_smooth_with`].
+    pub fn taubin_smooth_with(
+        &mut self,
+        iterations: usize,
+        lambda: f32,
+        mu: f32,
+        options: SmoothOptions,
+    ) {
+        self.smooth(iterations, &[lambda, mu], options);
+    }
+
+    /// Moves every vertex toward the average of its neighbors, by each of
+    /// `factors` in turn, `iterations` times, then recomputes the normals.
+    fn smooth(&mut self, iterations: usize, factors: &[f32], options: SmoothOptions) {
+        let neighbors = smoothing_neighbors(self, options.preserve_feature_angle);
+        let positions = &mut self.vertices.positions;
+        let mut smoothed = positions.clone();
+
+        for _ in 0..iterations {
+            for &factor in factors {
+                for (i, neighbors) in neighbors.iter().enumerate() {
+                    if neighbors.is_empty() {
+                        continue;
+                    }
+
+                    let sum: Vec3 = neighbors.iter().map(|&n| positions[n as usize]).sum();
+                    let average = sum / neighbors.len() as f32;
+                    smoothed[i] = positions[i] + factor * (average - positions[i]);
+                }
+
+                positions.copy_from_slice(&smoothed);
+            }
+        }
+
+        self.recompute_normals();
+    }
+}
+
+/// Returns the neighbors every vertex of `mesh` moves toward: all of them,
+/// those along the feature edges of vertices on exactly two feature edges,
+/// and none for vertices on other numbers of feature edges, which stay put.
+fn smoothing_neighbors(mesh: &IndexedSeparateNormals, feature_angle: f32) -> Vec<Vec<u32>> {
+    let positions = &mesh.vertices.positions;
+    let mut neighbors = vec![Vec::new(); positions.len()];
+    let mut edge_faces: Map<[u32; 2], Vec<u32>> = Map::default();
+    let mut normals = Vec::with_capacity(mesh.faces.len());
+
+    for (f, face) in mesh.faces.iter().enumerate() {
+        normals.push(plane_normal(&face.map(|i| positions[i as usize])).normalize_or_zero());
+
+        for k in 0..3 {
+            let (a, b) = (face[k], face[(k + 1) % 3]);
+            neighbors[a as usize].push(b);
+            neighbors[b as usize].push(a);
+            edge_faces.entry(edge_key(a, b)).or_default().push(f as u32);
+        }
+    }
+
+    let features = feature_edges(&edge_faces, &normals, feature_angle);
+
+    for (v, neighbors) in neighbors.iter_mut().enumerate() {
+        neighbors.sort_unstable();
+        neighbors.dedup();
+
+        let along_features: Vec<u32> = (neighbors.iter().copied())
+            .filter(|&n| features.contains(&edge_key(v as u32, n)))
+            .collect();
+
+        match along_features.len() {
+            0 => {}
+            2 => *neighbors = along_features,
+            _ => neighbors.clear(),
+        }
+    }
+
+    neighbors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{from_fn, hermite_from_fn};
+    use crate::{DualContouring, WithIndexedSeparateNormals};
+    use glam::BVec3;
+
+    fn extract(source: &impl crate::HermiteSource) -> IndexedSeparateNormals {
+        let mut mesh = IndexedSeparateNormals::default();
+        let dc = DualContouring::new(source, 32, 1e-5);
+        dc.extract(WithIndexedSeparateNormals::new(&mut mesh, source))
+            .unwrap();
+        mesh
+    }
+
+    #[test]
+    fn smooths_noisy_sphere() {
+        let radius = 0.7;
+        let noise = |p: Vec3| 0.02 * (37.0 * p.x).sin() * (41.0 * p.y).sin() * (43.0 * p.z).sin();
+        let sphere = hermite_from_fn(move |p: Vec3| p.length() - radius + noise(p), |p| p);
+        let mut mesh = extract(&sphere);
+
+        // The spread of the distances from the center, which smoothing
+        // reduces even though the sphere shrinks.
+        let spread = |mesh: &IndexedSeparateNormals| {
+            let distances: Vec<f32> = mesh.positions().iter().map(|p| p.length()).collect();
+            let mean = distances.iter().sum::<f32>() / distances.len() as f32;
+            let variance = distances.iter().map(|d| (d - mean).powi(2)).sum::<f32>();
+            (variance / distances.len() as f32).sqrt()
+        };
+
+        let faces = mesh.faces.clone();
+        let before = spread(&mesh);
+        mesh.laplacian_smooth(5, 0.5);
+
+        assert_eq!(mesh.faces, faces);
+        assert!(spread(&mesh) < before / 2.0, "{before} {}", spread(&mesh));
+
+        for (p, n) in mesh.positions().iter().zip(mesh.normals()) {
+            assert!(n.is_normalized());
+            assert!(n.dot(p.normalize()) > 0.5);
+        }
+    }
+
+    #[test]
+    fn preserves_features() {
+        let half = Vec3::splat(0.55);
+        let cube = from_fn(move |p: Vec3| {
+            let q = p.abs() - half;
+            q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+        });
+        let cube = crate::FiniteDifference::new(cube, 1e-4);
+
+        // The extreme vertices along the diagonals, i.e., the corners.
+        let corners = |mesh: &IndexedSeparateNormals| {
+            let diagonals = (0..8).map(|k| {
+                Vec3::select(
+                    BVec3::new(k & 1 != 0, k & 2 != 0, k & 4 != 0),
+                    Vec3::ONE,
+                    -Vec3::ONE,
+                )
+            });
+
+            diagonals
+                .map(|d| {
+                    (mesh.positions().iter().copied())
+                        .max_by(|a, b| a.dot(d).total_cmp(&b.dot(d)))
+                        .unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let original = corners(&extract(&cube));
+        assert!(original.iter().all(|p| (p.abs() - half).length() < 1e-3));
+
+        let mut smoothed = extract(&cube);
+        smoothed.laplacian_smooth(10, 0.5);
+        assert!(corners(&smoothed)
+            .iter()
+            .all(|p| (p.abs() - half).length() > 0.01));
+
+        let mut preserved = extract(&cube);
+        let options = SmoothOptions {
+            preserve_feature_angle: 30f32.to_radians(),
+        };
+        preserved.laplacian_smooth_with(10, 0.5, options);
+        assert_eq!(corners(&preserved), original);
+
+        // Every vertex is still on the surface of the cube.
+        for &position in preserved.positions() {
+            assert!(
+                (position.abs() - half).abs().min_element() < 1e-3,
+                "{position}"
+            );
+        }
+    }
+
+    #[test]
+    fn taubin_keeps_radius() {
+        let radius = 0.7;
+        let noise = |p: Vec3| 0.02 * (37.0 * p.x).sin() * (41.0 * p.y).sin() * (43.0 * p.z).sin();
+        let sphere = hermite_from_fn(move |p: Vec3| p.length() - radius + noise(p), |p| p);
+
+        let mean_radius = |mesh: &IndexedSeparateNormals| {
+            let sum: f32 = mesh.positions().iter().map(|p| p.length()).sum();
+            sum / mesh.positions().len() as f32
+        };
+
+        let mut laplacian = extract(&sphere);
+        let mut taubin = extract(&sphere);
+        let before = mean_radius(&taubin);
+        let bumpy = |mesh: &IndexedSeparateNormals| {
+            (mesh.positions().iter()).any(|p| (p.length() - radius).abs() > 0.01)
+        };
+        assert!(bumpy(&taubin));
+
+        laplacian.laplacian_smooth(20, 0.5);
+        taubin.taubin_smooth(20, 0.5, -0.53);
+
+        let (shrunk, kept) = (mean_radius(&laplacian), mean_radius(&taubin));
+        assert!(shrunk < before * 0.99, "{before} {shrunk}");
+        assert!((kept / before - 1.0).abs() < 2e-3, "{before} {kept}");
+
+        // The bumps are gone all the same.
+        assert!(!bumpy(&taubin));
+    }
+}
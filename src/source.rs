@@ -1,6 +1,46 @@
+use crate::collections::Map;
+use crate::geom::{AxisKind, Bounds};
 use auto_impl::auto_impl;
-use glam::{vec3, Vec3};
+use core::cell::RefCell;
+use core::fmt;
+use glam::Vec3;
 
+mod capsule;
+mod csg;
+mod cylinder;
+#[cfg(test)]
+mod distance_check;
+mod double;
+mod grid;
+mod interval;
+mod mesh_distance;
+#[cfg(feature = "noise")]
+mod noise;
+mod plane;
+#[cfg(test)]
+mod rng;
+
+pub use capsule::Capsule;
+pub use csg::{Difference, Intersection, Union};
+pub use cylinder::Cylinder;
+pub use double::{
+    from_fn64, hermite_from_fn64, DoublePrecision, FromFn64, HermiteFromFn64, HermiteSample64,
+    HermiteSource64, Sample64, Source64,
+};
+pub use grid::{GridSource, Interpolation};
+pub(crate) use interval::{center_radius, magnitude, widen, IntervalFn};
+pub use interval::{FallbackInterval, IntervalSource};
+#[cfg(feature = "noise")]
+pub use noise::{Displace, FbmNoise, GradientSource, ValueNoise3};
+pub use plane::Plane;
+
+/// A value of a [`Source`] at a point.
+///
+/// Negative values are inside of the solid and positive values outside of
+/// it. Zeros of either sign are outside, so that `-0.0` and `+0.0` are always
+/// classified alike, unless the source puts them inside (see
+/// [`ZeroIsInside`]).
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Sample {
     pub point: Vec3,
     pub value: f32,
@@ -14,12 +54,89 @@ impl Sample {
     pub fn from_source(source: impl Source, point: Vec3) -> Self {
         Self::new(point, source.sample(point))
     }
+
+    /// Returns whether the sample is inside of the solid, i.e., its value is
+    /// negative. Zeros of either sign are outside.
+    pub fn is_inside(&self) -> bool {
+        is_inside(self.value)
+    }
+
+    /// Returns whether the sample is on the surface, i.e., the magnitude of
+    /// its value is at most `epsilon`.
+    pub fn on_surface(&self, epsilon: f32) -> bool {
+        self.value.abs() <= epsilon
+    }
+
+    /// Returns whether this sample is strictly closer to the surface than
+    /// `other`, judging by the magnitudes of their values.
+    pub fn closer_to_surface(&self, other: &Sample) -> bool {
+        self.value.abs() < other.value.abs()
+    }
+
+    /// Attaches a normal to this sample.
+    pub fn with_normal(self, normal: Vec3) -> HermiteSample {
+        HermiteSample::new(self.point, self.value, normal)
+    }
+}
+
+/// A sample of a [`HermiteSource`], i.e., a value along with the surface
+/// normal at the same point.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HermiteSample {
+    pub point: Vec3,
+    pub value: f32,
+    pub normal: Vec3,
+}
+
+impl HermiteSample {
+    pub fn new(point: Vec3, value: f32, normal: Vec3) -> Self {
+        Self {
+            point,
+            value,
+            normal,
+        }
+    }
+
+    pub fn from_source(source: impl HermiteSource, point: Vec3) -> Self {
+        source.sample_hermite(point)
+    }
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "value {} at {}", self.value, self.point)
+    }
+}
+
+impl fmt::Display for HermiteSample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "value {} at {} with normal {}",
+            self.value, self.point, self.normal
+        )
+    }
 }
 
-pub enum FindIntersectionError {
-    IterLimit(Sample),
+/// The reason no exact intersection was found. `IterLimit` carries the best
+/// estimate, which is a [`Sample`] or a [`HermiteSample`] depending on the
+/// query.
+#[derive(Debug, Copy, Clone, PartialEq, thiserror::Error)]
+pub enum FindIntersectionError<T = Sample> {
+    /// The iteration limit was reached. The value of the best estimate is its
+    /// residual.
+    #[error("the iteration limit was reached before converging (best sample: {0})")]
+    IterLimit(T),
+    /// Both ends of the segment are on the surface.
+    #[error("both ends of the segment are on the surface")]
     Indeterminate,
+    /// The segment doesn't cross the surface.
+    #[error("the segment doesn't cross the surface")]
     NoSolution,
+    /// The source is undefined at an end of the segment or somewhere along
+    /// it.
+    #[error("the source is undefined along the segment")]
+    Undefined,
 }
 
 pub enum Endpoint {
@@ -32,6 +149,8 @@ pub enum ClassifySegment {
     Intersects(Endpoint, f32),
     NoSolution,
     Indeterminate,
+    /// The source is undefined at either end of the segment.
+    Undefined,
 }
 
 impl ClassifySegment {
@@ -41,6 +160,112 @@ impl ClassifySegment {
             ClassifySegment::ChangesSign(_, _) | ClassifySegment::Intersects(_, _)
         )
     }
+
+    /// Returns where the segment from `start` to `end`, classified as `self`,
+    /// crosses the surface, or `None` if it doesn't.
+    ///
+    /// This is the endpoint on the surface for [`ClassifySegment::Intersects`],
+    /// and the linear interpolation of the values at both ends otherwise,
+    /// which is exact for linear fields and a starting guess for others.
+    pub fn intersection_point(&self, start: Vec3, end: Vec3) -> Option<Vec3> {
+        match *self {
+            ClassifySegment::ChangesSign(v_start, v_end) => {
+                Some(start.lerp(end, v_start / (v_start - v_end)))
+            }
+            ClassifySegment::Intersects(Endpoint::Start, _) => Some(start),
+            ClassifySegment::Intersects(Endpoint::End, _) => Some(end),
+            _ => None,
+        }
+    }
+}
+
+/// Which side of the surface a point is on. The inside is where the source
+/// is negative (see [`Sample`]).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum InsideOutside {
+    Inside,
+    Outside,
+}
+
+/// Which side of the surface points where a source is exactly zero are on.
+///
+/// Fields built with `min`/`max` or by clamping can be exactly zero over
+/// whole regions rather than on a thin surface. Exact zeros (of either sign)
+/// therefore belong to a side instead of being on the surface: they're
+/// outside by default, where the surface is the boundary of the points with
+/// negative values, and inside with `ZeroIsInside(true)`, where it's the
+/// boundary of the points with positive values. Nonzero values within the
+/// tolerance are still on the surface. See [`Source::zero_is_inside`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct ZeroIsInside(pub bool);
+
+impl ZeroIsInside {
+    /// Returns whether `value` is inside of the solid under this convention.
+    pub fn is_inside(self, value: f32) -> bool {
+        value < 0.0 || (self.0 && value == 0.0)
+    }
+}
+
+/// How close to the surface a point must be to be considered on it.
+///
+/// The tolerance plays two roles, which compare different quantities:
+/// [`value_threshold`] bounds the field values considered on the surface,
+/// which decides whether the ends of a segment lie on it and stops refining
+/// an intersection early, while [`length_threshold`] bounds the distance
+/// along the segment an intersection is refined to. Each variant derives
+/// both from a single value.
+///
+/// [`value_threshold`]: Tolerance::value_threshold
+/// [`length_threshold`]: Tolerance::length_threshold
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Tolerance {
+    /// A threshold on absolute field values, which doubles as the distance
+    /// along the segment to refine intersections to.
+    ///
+    /// This suits unit-scale distance fields, but not fields whose values
+    /// span many orders of magnitude.
+    Absolute(f32),
+    /// A fraction of the larger magnitude of the values at the ends of the
+    /// segment, and of the segment length.
+    Relative(f32),
+    /// A distance along the segment. Field values are converted to distances
+    /// using the secant slope of the field over the segment, so the result
+    /// doesn't depend on the scale of the field.
+    Spatial(f32),
+}
+
+impl Tolerance {
+    /// Returns the inner value.
+    pub fn get(self) -> f32 {
+        match self {
+            Tolerance::Absolute(t) | Tolerance::Relative(t) | Tolerance::Spatial(t) => t,
+        }
+    }
+
+    /// Returns the largest field value magnitude considered to be on the
+    /// surface, given the values at the ends of a segment of length `length`.
+    pub fn value_threshold(self, v_start: f32, v_end: f32, length: f32) -> f32 {
+        match self {
+            Tolerance::Absolute(t) => t,
+            Tolerance::Relative(t) => t * v_start.abs().max(v_end.abs()),
+            Tolerance::Spatial(t) => t * (v_end - v_start).abs() / length,
+        }
+    }
+
+    /// Returns the bracket length below which the intersection of a segment
+    /// of length `length` is no longer refined.
+    pub fn length_threshold(self, length: f32) -> f32 {
+        match self {
+            Tolerance::Absolute(t) | Tolerance::Spatial(t) => t,
+            Tolerance::Relative(t) => t * length,
+        }
+    }
+}
+
+impl From<f32> for Tolerance {
+    fn from(value: f32) -> Self {
+        Tolerance::Absolute(value)
+    }
 }
 
 #[auto_impl(&, &mut, Box, Rc, Arc)]
@@ -48,23 +273,73 @@ pub trait Source {
     /// Samples the source at a given point.
     fn sample(&self, point: Vec3) -> f32;
 
-    fn classify_segment(&self, start: Vec3, end: Vec3, epsilon: f32) -> ClassifySegment {
+    /// Returns whether the source is defined at a given point.
+    ///
+    /// Points where [`sample`] returns a non-finite value are undefined
+    /// regardless. Sources with explicitly masked-out regions, such as
+    /// volumes with missing voxels, can override this to mark them. Edges
+    /// with an undefined end aren't contoured.
+    ///
+    /// [`sample`]: Source::sample
+    fn is_defined(&self, _point: Vec3) -> bool {
+        true
+    }
+
+    /// Returns which side of the surface points where the source is exactly
+    /// zero are on. Defaults to the outside.
+    ///
+    /// Sources that are zero over whole regions, e.g., plateaus left by
+    /// clamping, can override this, or be wrapped in a [`ZeroSide`], to put
+    /// the surface around the other side of those regions.
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        ZeroIsInside::default()
+    }
+
+    /// Returns a Lipschitz constant of the source, if known, i.e., a bound
+    /// `L` such that the values at any two points differ by at most `L`
+    /// times their distance. Defaults to `None`.
+    ///
+    /// The values then bound the distances to the surface, times `L`,
+    /// arbitrarily far from it, e.g., for exact signed distance fields, with
+    /// an `L` of 1, which is what [`DualContouring::assume_lipschitz`] relies
+    /// on.
+    ///
+    /// [`DualContouring::assume_lipschitz`]: crate::DualContouring::assume_lipschitz
+    fn lipschitz_bound(&self) -> Option<f32> {
+        None
+    }
+
+    /// Classifies the segment from `start` to `end` by whether and where the
+    /// surface crosses it.
+    ///
+    /// An end with a nonzero value within the tolerance is on the surface.
+    /// Otherwise, the surface crosses the segment if its ends are on
+    /// different sides of it, as told by [`Source::zero_is_inside`].
+    fn classify_segment(&self, start: Vec3, end: Vec3, tolerance: Tolerance) -> ClassifySegment {
         debug_assert!(start != end);
-        debug_assert!(epsilon > 0.0);
+        debug_assert!(tolerance.get() > 0.0);
+
+        let (Some(v_start), Some(v_end)) = (sample_defined(self, start), sample_defined(self, end))
+        else {
+            return ClassifySegment::Undefined;
+        };
+        let threshold = tolerance.value_threshold(v_start, v_end, start.distance(end));
+        let zeros = self.zero_is_inside();
 
-        let v_start = self.sample(start);
-        let v_end = self.sample(end);
+        // Exact zeros may stand for whole regions, so they're on a side of the
+        // surface rather than on it.
+        let on_surface = |v: f32| v != 0.0 && v.abs() <= threshold;
 
         // We must consider either `(true, false)` or `(false, true)` as having
         // a solution, but never both simultaneously. This exclusivity is
         // needed to preserve the invariant that one intersection point can
         // only belong to a single segment under an adaptive grid.
-        match (v_start.abs() <= epsilon, v_end.abs() <= epsilon) {
+        match (on_surface(v_start), on_surface(v_end)) {
             (true, false) => ClassifySegment::Intersects(Endpoint::Start, v_start),
             (true, true) => ClassifySegment::Indeterminate,
             (false, true) => ClassifySegment::NoSolution,
             _ => {
-                if v_start.is_sign_negative() != v_end.is_sign_negative() {
+                if zeros.is_inside(v_start) != zeros.is_inside(v_end) {
                     ClassifySegment::ChangesSign(v_start, v_end)
                 } else {
                     ClassifySegment::NoSolution
@@ -77,13 +352,13 @@ pub trait Source {
         &self,
         start: Vec3,
         end: Vec3,
-        epsilon: f32,
+        tolerance: Tolerance,
         max_iter: usize,
     ) -> Result<Sample, FindIntersectionError> {
         debug_assert!(start != end);
-        debug_assert!(epsilon > 0.0);
+        debug_assert!(tolerance.get() > 0.0);
 
-        match self.classify_segment(start, end, epsilon) {
+        let (v_start, v_end) = match self.classify_segment(start, end, tolerance) {
             ClassifySegment::Intersects(endpoint, value) => {
                 return match endpoint {
                     Endpoint::Start => Ok(Sample::new(start, value)),
@@ -92,32 +367,40 @@ pub trait Source {
             }
             ClassifySegment::NoSolution => return Err(FindIntersectionError::NoSolution),
             ClassifySegment::Indeterminate => return Err(FindIntersectionError::Indeterminate),
-            _ => {}
-        }
+            ClassifySegment::Undefined => return Err(FindIntersectionError::Undefined),
+            ClassifySegment::ChangesSign(v_start, v_end) => (v_start, v_end),
+        };
+
+        let length = start.distance(end);
+        let value_threshold = tolerance.value_threshold(v_start, v_end, length);
+        let length_threshold = tolerance.length_threshold(length);
+
+        let zeros = self.zero_is_inside();
 
         let mut a = start;
         let mut b = end;
-        let mut v_a = self.sample(a);
-        let mut v_b = self.sample(b);
+        let mut v_a = v_start;
+        let mut v_b = v_end;
 
         for _ in 0..max_iter {
-            if v_a.is_sign_negative() == v_b.is_sign_negative() {
+            if zeros.is_inside(v_a) == zeros.is_inside(v_b) {
                 return Err(FindIntersectionError::NoSolution);
             }
 
-            if (a - b).length_squared() <= epsilon * epsilon {
-                let c = (a + b) / 2.0;
-                return Ok(Sample::new(c, self.sample(c)));
-            }
-
+            // The source may be undefined between two defined ends, in which
+            // case there's no meaningful intersection.
             let c = (a + b) / 2.0;
-            let v_c = self.sample(c);
+            let v_c = sample_defined(self, c).ok_or(FindIntersectionError::Undefined)?;
 
-            if v_c.abs() <= epsilon {
+            // A zero midpoint may be anywhere in a region of zeros, so the
+            // bisection goes on towards its boundary.
+            if (a - b).length_squared() <= length_threshold * length_threshold
+                || (v_c != 0.0 && v_c.abs() <= value_threshold)
+            {
                 return Ok(Sample::new(c, v_c));
             }
 
-            if v_a.is_sign_negative() != v_c.is_sign_negative() {
+            if zeros.is_inside(v_a) != zeros.is_inside(v_c) {
                 b = c;
                 v_b = v_c;
             } else {
@@ -126,14 +409,80 @@ pub trait Source {
             }
         }
 
-        let best = Sample::from_source(self, (a + b) / 2.0);
-        Err(FindIntersectionError::IterLimit(best))
+        let c = (a + b) / 2.0;
+        let v_c = sample_defined(self, c).ok_or(FindIntersectionError::Undefined)?;
+        Err(FindIntersectionError::IterLimit(Sample::new(c, v_c)))
     }
 }
 
+/// A [`Source`] that also knows the gradient of its field.
+///
+/// Implementations must provide at least one of [`gradient`] and
+/// [`sample_normal`], as each defaults to the other.
+///
+/// [`gradient`]: HermiteSource::gradient
+/// [`sample_normal`]: HermiteSource::sample_normal
 #[auto_impl(&, &mut, Box, Rc, Arc)]
 pub trait HermiteSource: Source {
-    fn sample_normal(&self, point: Vec3) -> Vec3;
+    /// Returns the gradient of the field at a given point, which points
+    /// outward and isn't normalized.
+    ///
+    /// Defaults to [`HermiteSource::sample_normal`], i.e., to a gradient of
+    /// magnitude 1, for sources that only know the direction of their
+    /// gradient.
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.sample_normal(point)
+    }
+
+    /// Returns the outward unit normal at a given point, or zero if the
+    /// gradient vanishes there.
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        self.gradient(point).normalize_or_zero()
+    }
+
+    /// Samples both the value and the normal at a given point.
+    ///
+    /// Sources that compute the gradient as a by-product of the value (e.g.,
+    /// with automatic differentiation) can override this to avoid doing the
+    /// work twice.
+    fn sample_hermite(&self, point: Vec3) -> HermiteSample {
+        HermiteSample::new(point, self.sample(point), self.sample_normal(point))
+    }
+
+    /// Like [`Source::find_intersection`], but also returns the normal at the
+    /// intersection point.
+    fn find_hermite_intersection(
+        &self,
+        start: Vec3,
+        end: Vec3,
+        tolerance: Tolerance,
+        max_iter: usize,
+    ) -> Result<HermiteSample, FindIntersectionError<HermiteSample>> {
+        let with_normal = |sample: Sample| sample.with_normal(self.sample_normal(sample.point));
+
+        match self.find_intersection(start, end, tolerance, max_iter) {
+            Ok(sample) => Ok(with_normal(sample)),
+            Err(FindIntersectionError::IterLimit(sample)) => {
+                Err(FindIntersectionError::IterLimit(with_normal(sample)))
+            }
+            Err(FindIntersectionError::Indeterminate) => Err(FindIntersectionError::Indeterminate),
+            Err(FindIntersectionError::NoSolution) => Err(FindIntersectionError::NoSolution),
+            Err(FindIntersectionError::Undefined) => Err(FindIntersectionError::Undefined),
+        }
+    }
+}
+
+/// Returns whether a value is inside of the solid, with exact zeros outside.
+/// This is the sign convention used throughout the crate (see [`Sample`]),
+/// unless a source overrides [`Source::zero_is_inside`].
+pub(crate) fn is_inside(value: f32) -> bool {
+    value < 0.0
+}
+
+/// Samples `source` at `point`, unless it's undefined there.
+pub(crate) fn sample_defined<S: Source + ?Sized>(source: &S, point: Vec3) -> Option<f32> {
+    let value = source.sample(point);
+    (value.is_finite() && source.is_defined(point)).then_some(value)
 }
 
 pub struct FiniteDifference<S> {
@@ -151,13 +500,834 @@ impl<S: Source> Source for FiniteDifference<S> {
     fn sample(&self, point: Vec3) -> f32 {
         self.source.sample(point)
     }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+impl<S: Source> HermiteSource for FiniteDifference<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        let h = self.epsilon;
+        let diff = |axis: Vec3| {
+            let lo = sample_defined(&self.source, point - axis * h);
+            let hi = sample_defined(&self.source, point + axis * h);
+
+            // Fall back to one-sided differences next to undefined regions.
+            match (lo, hi) {
+                (Some(lo), Some(hi)) => hi - lo,
+                (Some(lo), None) => 2.0 * (self.source.sample(point) - lo),
+                (None, Some(hi)) => 2.0 * (hi - self.source.sample(point)),
+                (None, None) => f32::NAN,
+            }
+        };
+        Vec3::new(diff(Vec3::X), diff(Vec3::Y), diff(Vec3::Z)) / (2.0 * h)
+    }
+}
+
+impl<S: IntervalSource> IntervalSource for FiniteDifference<S> {
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        self.source.sample_interval(min, max)
+    }
+}
+
+/// A source whose surface is the level set of another source at `isovalue`,
+/// i.e., whose values are those of the source minus `isovalue`.
+///
+/// See [`DualContouring::extract_isosurfaces`] to extract several level sets
+/// of the same source without sampling it again for each of them.
+///
+/// [`DualContouring::extract_isosurfaces`]: crate::DualContouring::extract_isosurfaces
+#[derive(Debug, Clone)]
+pub struct Isovalue<S> {
+    source: S,
+    isovalue: f32,
+}
+
+impl<S> Isovalue<S> {
+    pub fn new(source: S, isovalue: f32) -> Self {
+        Self { source, isovalue }
+    }
+
+    pub fn isovalue(&self) -> f32 {
+        self.isovalue
+    }
+}
+
+impl<S: Source> Source for Isovalue<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.source.sample(point) - self.isovalue
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        self.source.lipschitz_bound()
+    }
+}
+
+impl<S: HermiteSource> HermiteSource for Isovalue<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.source.gradient(point)
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        self.source.sample_normal(point)
+    }
+}
+
+impl<S: IntervalSource> IntervalSource for Isovalue<S> {
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let (lo, hi) = self.source.sample_interval(min, max);
+        (lo - self.isovalue, hi - self.isovalue)
+    }
+}
+
+/// A source whose inside and outside are swapped, i.e., whose values and
+/// gradients are those of another source negated, e.g., to extract a field
+/// with the opposite sign convention.
+#[derive(Debug, Clone)]
+pub struct Negated<S> {
+    source: S,
+}
+
+impl<S> Negated<S> {
+    pub fn new(source: S) -> Self {
+        Self { source }
+    }
+}
+
+impl<S: Source> Source for Negated<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        -self.source.sample(point)
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(point)
+    }
+
+    /// Swaps the side of exact zeros too, so that they stay on the same side
+    /// of the surface.
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        ZeroIsInside(!self.source.zero_is_inside().0)
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        self.source.lipschitz_bound()
+    }
+}
+
+impl<S: HermiteSource> HermiteSource for Negated<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        -self.source.gradient(point)
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        -self.source.sample_normal(point)
+    }
+}
+
+impl<S: IntervalSource> IntervalSource for Negated<S> {
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let (lo, hi) = self.source.sample_interval(min, max);
+        (-hi, -lo)
+    }
+}
+
+/// A source with the same values as another one, whose exact zeros are on
+/// the given side of the surface, see [`ZeroIsInside`].
+#[derive(Debug, Clone)]
+pub struct ZeroSide<S> {
+    source: S,
+    zero_is_inside: ZeroIsInside,
+}
+
+impl<S> ZeroSide<S> {
+    pub fn new(source: S, zero_is_inside: ZeroIsInside) -> Self {
+        Self {
+            source,
+            zero_is_inside,
+        }
+    }
+}
+
+impl<S: Source> Source for ZeroSide<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.source.sample(point)
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.zero_is_inside
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        self.source.lipschitz_bound()
+    }
 }
 
-impl<S: HermiteSource> HermiteSource for FiniteDifference<S> {
+impl<S: HermiteSource> HermiteSource for ZeroSide<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.source.gradient(point)
+    }
+
     fn sample_normal(&self, point: Vec3) -> Vec3 {
-        let v_x = self.source.sample(point + Vec3::X * self.epsilon);
-        let v_y = self.source.sample(point + Vec3::Y * self.epsilon);
-        let v_z = self.source.sample(point + Vec3::Z * self.epsilon);
-        (vec3(v_x, v_y, v_z) - self.sample(point)).normalize_or_zero()
+        self.source.sample_normal(point)
+    }
+}
+
+impl<S: IntervalSource> IntervalSource for ZeroSide<S> {
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        self.source.sample_interval(min, max)
+    }
+}
+
+/// A source tiling space with copies of another one, by folding every point
+/// into the period around the origin before sampling it, e.g., to repeat a
+/// single primitive across the whole grid.
+///
+/// The period must be at least twice the extent of the primitive from the
+/// origin along every axis, or the copies are cut off where the folding
+/// wraps around, and the surface intersects itself. Gradients and normals are
+/// those of the source at the folded point, unchanged.
+#[derive(Debug, Clone)]
+pub struct Repeat<S> {
+    source: S,
+    period: Vec3,
+}
+
+impl<S> Repeat<S> {
+    pub fn new(source: S, period: Vec3) -> Self {
+        Self { source, period }
+    }
+
+    pub fn period(&self) -> Vec3 {
+        self.period
+    }
+
+    fn fold(&self, point: Vec3) -> Vec3 {
+        point - self.period * (point / self.period).round()
+    }
+}
+
+impl<S: Source> Source for Repeat<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.source.sample(self.fold(point))
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(self.fold(point))
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+impl<S: HermiteSource> HermiteSource for Repeat<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.source.gradient(self.fold(point))
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        self.source.sample_normal(self.fold(point))
+    }
+}
+
+impl<S: IntervalSource> IntervalSource for Repeat<S> {
+    /// Folds the box like its points along the axes it doesn't wrap around
+    /// on, and bounds the source over a whole period along the others.
+    fn sample_interval(&self, mut min: Vec3, mut max: Vec3) -> (f32, f32) {
+        let (lo, hi) = ((min / self.period).round(), (max / self.period).round());
+
+        for i in 0..3 {
+            let period = self.period[i];
+
+            if lo[i] == hi[i] {
+                min[i] -= period * lo[i];
+                max[i] -= period * lo[i];
+            } else {
+                // Folded points may round past half a period.
+                max[i] = period.abs() / 2.0 * (1.0 + f32::EPSILON);
+                min[i] = -max[i];
+            }
+        }
+
+        self.source.sample_interval(min, max)
+    }
+}
+
+/// A source symmetric across the plane through the origin perpendicular to
+/// `axis`, which reflects points on the negative side of the plane before
+/// sampling another source, e.g., to model only half of a symmetric shape.
+///
+/// The gradients and normals at reflected points are reflected back.
+#[derive(Debug, Clone)]
+pub struct Mirror<S> {
+    source: S,
+    axis: AxisKind,
+}
+
+impl<S> Mirror<S> {
+    pub fn new(source: S, axis: AxisKind) -> Self {
+        Self { source, axis }
+    }
+
+    pub fn axis(&self) -> AxisKind {
+        self.axis
+    }
+
+    /// Returns the reflected point, and whether it was reflected.
+    fn fold(&self, mut point: Vec3) -> (Vec3, bool) {
+        let i = self.axis as usize;
+        let reflected = point[i] < 0.0;
+        point[i] = point[i].abs();
+        (point, reflected)
+    }
+
+    fn unfold(&self, mut v: Vec3, reflected: bool) -> Vec3 {
+        if reflected {
+            v[self.axis as usize] = -v[self.axis as usize];
+        }
+
+        v
+    }
+}
+
+impl<S: Source> Source for Mirror<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.source.sample(self.fold(point).0)
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(self.fold(point).0)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+impl<S: HermiteSource> HermiteSource for Mirror<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        let (point, reflected) = self.fold(point);
+        self.unfold(self.source.gradient(point), reflected)
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        let (point, reflected) = self.fold(point);
+        self.unfold(self.source.sample_normal(point), reflected)
+    }
+}
+
+impl<S: IntervalSource> IntervalSource for Mirror<S> {
+    fn sample_interval(&self, mut min: Vec3, mut max: Vec3) -> (f32, f32) {
+        let i = self.axis as usize;
+
+        (min[i], max[i]) = if min[i] >= 0.0 {
+            (min[i], max[i])
+        } else if max[i] <= 0.0 {
+            (-max[i], -min[i])
+        } else {
+            (0.0, max[i].max(-min[i]))
+        };
+
+        self.source.sample_interval(min, max)
+    }
+}
+
+/// Memoizes the values of a source by point, so that extracting several
+/// surfaces from it samples every point once.
+///
+/// Gradients aren't memoized, as they're only needed at the intersections,
+/// which differ between surfaces.
+pub(crate) struct SampleCache<S> {
+    source: S,
+    values: RefCell<Map<[u32; 3], f32>>,
+}
+
+impl<S> SampleCache<S> {
+    pub(crate) fn new(source: S) -> Self {
+        Self {
+            source,
+            values: RefCell::default(),
+        }
+    }
+}
+
+impl<S: Source> Source for SampleCache<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        let key = point.to_array().map(f32::to_bits);
+
+        if let Some(&value) = self.values.borrow().get(&key) {
+            return value;
+        }
+
+        let value = self.source.sample(point);
+        self.values.borrow_mut().insert(key, value);
+        value
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+impl<S: HermiteSource> HermiteSource for SampleCache<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.source.gradient(point)
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        self.source.sample_normal(point)
+    }
+}
+
+/// Clips a source to a box, as the intersection of its inside with the box,
+/// which closes the surface where it leaves the box.
+///
+/// Outside of the source, the value is the distance to the box where that's
+/// larger, so that the gradient of the box takes over on the cap.
+pub(crate) struct Capped<S> {
+    source: S,
+    bounds: Bounds,
+}
+
+impl<S> Capped<S> {
+    pub(crate) fn new(source: S, bounds: Bounds) -> Self {
+        Self { source, bounds }
+    }
+
+    /// Returns the signed distance to the box, and the direction away from
+    /// it.
+    fn box_distance(&self, point: Vec3) -> (f32, Vec3) {
+        let offset = point - self.bounds.center();
+        let q = offset.abs() - self.bounds.size() / 2.0;
+        let outside = q.max(Vec3::ZERO);
+
+        if outside != Vec3::ZERO {
+            return (outside.length(), outside.normalize() * offset.signum());
+        }
+
+        let axis = (0..3).fold(0, |max, i| if q[i] > q[max] { i } else { max });
+        (q[axis], Vec3::AXES[axis] * offset[axis].signum())
+    }
+}
+
+impl<S: Source> Source for Capped<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.source.sample(point).max(self.box_distance(point).0)
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+impl<S: HermiteSource> HermiteSource for Capped<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        match self.box_distance(point) {
+            (distance, direction) if distance > self.source.sample(point) => direction,
+            _ => self.source.gradient(point),
+        }
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        match self.box_distance(point) {
+            (distance, direction) if distance > self.source.sample(point) => direction,
+            _ => self.source.sample_normal(point),
+        }
+    }
+}
+
+/// Creates a new source that samples the given closure.
+///
+/// This is a lightweight alternative to defining a dedicated type, mirroring
+/// [`std::iter::from_fn`]. The returned source is `Send` and `Sync` whenever
+/// the closure is.
+pub fn from_fn<F>(f: F) -> FromFn<F>
+where
+    F: Fn(Vec3) -> f32,
+{
+    FromFn(f)
+}
+
+/// A source whose values are produced by a closure.
+///
+/// This `struct` is created by the [`from_fn`] function.
+#[derive(Clone)]
+pub struct FromFn<F>(F);
+
+impl<F: Fn(Vec3) -> f32> Source for FromFn<F> {
+    fn sample(&self, point: Vec3) -> f32 {
+        (self.0)(point)
+    }
+}
+
+impl<F> fmt::Debug for FromFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromFn").finish_non_exhaustive()
+    }
+}
+
+/// Creates a new Hermite source from a value closure and a gradient closure.
+///
+/// The gradient doesn't need to be normalized. Like [`from_fn`], the returned
+/// source is `Send` and `Sync` whenever both closures are.
+pub fn hermite_from_fn<F, G>(f: F, gradient: G) -> HermiteFromFn<F, G>
+where
+    F: Fn(Vec3) -> f32,
+    G: Fn(Vec3) -> Vec3,
+{
+    HermiteFromFn { f, gradient }
+}
+
+/// A Hermite source whose values and gradients are produced by closures.
+///
+/// This `struct` is created by the [`hermite_from_fn`] function.
+#[derive(Clone)]
+pub struct HermiteFromFn<F, G> {
+    f: F,
+    gradient: G,
+}
+
+impl<F: Fn(Vec3) -> f32, G> Source for HermiteFromFn<F, G> {
+    fn sample(&self, point: Vec3) -> f32 {
+        (self.f)(point)
+    }
+}
+
+impl<F: Fn(Vec3) -> f32, G: Fn(Vec3) -> Vec3> HermiteSource for HermiteFromFn<F, G> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        (self.gradient)(point)
+    }
+}
+
+impl<F, G> fmt::Debug for HermiteFromFn<F, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HermiteFromFn").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::boxed::Box;
+    use alloc::string::ToString;
+
+    fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+    #[test]
+    fn from_fn_samples_closure() {
+        let source = from_fn(|p: Vec3| p.length() - 1.0);
+        assert_send_sync(&source);
+        assert_eq!(source.sample(Vec3::ZERO), -1.0);
+        assert_eq!(source.sample(Vec3::X * 3.0), 2.0);
+    }
+
+    #[test]
+    fn hermite_from_fn_normalizes_gradient() {
+        let source = hermite_from_fn(|p: Vec3| p.y, |_| Vec3::Y * 4.0);
+        assert_send_sync(&source);
+        assert_eq!(source.sample(Vec3::splat(2.0)), 2.0);
+        assert_eq!(source.sample_normal(Vec3::ZERO), Vec3::Y);
+        assert_eq!(source.gradient(Vec3::ZERO), Vec3::Y * 4.0);
+    }
+
+    #[test]
+    fn finite_difference_of_plain_source() {
+        let source = FiniteDifference::new(from_fn(|p: Vec3| p.length() - 1.0), 1e-3);
+        let normal = source.sample_normal(Vec3::new(0.0, 2.0, 0.0));
+        assert!(normal.abs_diff_eq(Vec3::Y, 1e-4));
+
+        let source = FiniteDifference::new(from_fn(|p: Vec3| 3.0 * p.x - p.z), 1e-2);
+        let gradient = source.gradient(Vec3::new(0.5, 1.0, -2.0));
+        assert!(gradient.abs_diff_eq(Vec3::new(3.0, 0.0, -1.0), 1e-3));
+    }
+
+    #[test]
+    fn spatial_tolerance_is_scale_invariant() {
+        for scale in [1e-8, 1.0, 1e8] {
+            let source = from_fn(move |p: Vec3| scale * (p.x - 0.3));
+            let sample = source
+                .find_intersection(Vec3::ZERO, Vec3::X, Tolerance::Spatial(1e-4), 32)
+                .unwrap();
+            assert!((sample.point.x - 0.3).abs() <= 1e-4);
+        }
+    }
+
+    #[test]
+    fn find_intersection_error_display() {
+        let error = FindIntersectionError::IterLimit(Sample::new(Vec3::new(0.5, 0.0, 1.0), -0.25));
+        assert_eq!(
+            error.to_string(),
+            "the iteration limit was reached before converging \
+             (best sample: value -0.25 at [0.5, 0, 1])"
+        );
+
+        let error: Box<dyn core::error::Error> =
+            Box::new(FindIntersectionError::<Sample>::NoSolution);
+        assert_eq!(error.to_string(), "the segment doesn't cross the surface");
+    }
+
+    #[test]
+    fn hermite_intersection_carries_normal() {
+        let source = hermite_from_fn(|p: Vec3| p.x - 0.25, |_| Vec3::X * 2.0);
+        let sample = source
+            .find_hermite_intersection(Vec3::ZERO, Vec3::X, Tolerance::Absolute(1e-4), 32)
+            .unwrap();
+
+        assert!((sample.point.x - 0.25).abs() <= 1e-4);
+        assert_eq!(sample.normal, Vec3::X);
+    }
+
+    #[test]
+    fn negated_swaps_sides() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.5, |p| p * 2.0);
+        let negated = Negated::new(&sphere);
+
+        assert!(Sample::from_source(&negated, Vec3::X).is_inside());
+        assert!(!Sample::from_source(&negated, Vec3::ZERO).is_inside());
+        assert_eq!(negated.gradient(Vec3::X), Vec3::NEG_X * 2.0);
+
+        let sample = negated
+            .find_hermite_intersection(Vec3::ZERO, Vec3::X, Tolerance::Absolute(1e-4), 32)
+            .unwrap();
+        assert!((sample.point.x - 0.5).abs() <= 1e-4);
+        assert_eq!(sample.normal, Vec3::NEG_X);
+    }
+
+    #[test]
+    fn repeat_tiles_space() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.25, |p| p * 4.0);
+        let repeated = Repeat::new(&sphere, Vec3::new(1.0, 2.0, 1.0));
+
+        // The center of a copy, halfway between copies, and on a copy.
+        assert_eq!(repeated.sample(Vec3::new(3.0, -4.0, 0.0)), -0.25);
+        assert_eq!(
+            repeated.sample(Vec3::new(0.5, 1.0, 0.0)),
+            0.5 * 5f32.sqrt() - 0.25
+        );
+        assert_eq!(repeated.sample(Vec3::new(2.25, 0.0, 0.0)), 0.0);
+        assert_eq!(
+            repeated.gradient(Vec3::new(-2.125, 0.0, 0.0)),
+            Vec3::NEG_X * 0.5
+        );
+    }
+
+    #[test]
+    fn mirror_reflects_normals() {
+        let sphere = hermite_from_fn(
+            |p: Vec3| p.distance(Vec3::new(0.5, 0.0, 0.0)) - 0.25,
+            |p| p - Vec3::new(0.5, 0.0, 0.0),
+        );
+        let mirrored = Mirror::new(&sphere, AxisKind::X);
+
+        assert_eq!(mirrored.sample(Vec3::new(-0.5, 0.0, 0.0)), -0.25);
+        assert_eq!(
+            mirrored.sample(Vec3::new(-0.5, 1.0, 0.0)),
+            sphere.sample(Vec3::new(0.5, 1.0, 0.0))
+        );
+        assert_eq!(
+            mirrored.gradient(Vec3::new(-1.0, 0.0, 0.0)),
+            Vec3::NEG_X * 0.5
+        );
+        assert_eq!(mirrored.gradient(Vec3::new(1.0, 0.0, 0.0)), Vec3::X * 0.5);
+        assert_eq!(
+            mirrored.sample_normal(Vec3::new(-0.5, 0.0, -1.0)),
+            Vec3::NEG_Z
+        );
+
+        let sample = mirrored
+            .find_hermite_intersection(
+                Vec3::new(-0.5, 0.0, 0.0),
+                Vec3::NEG_X,
+                Tolerance::Absolute(1e-4),
+                32,
+            )
+            .unwrap();
+        assert!((sample.point.x + 0.75).abs() <= 1e-4);
+        assert_eq!(sample.normal, Vec3::NEG_X);
+    }
+
+    #[test]
+    fn undefined_segments() {
+        let holey = from_fn(|p: Vec3| if p.x.abs() < 0.1 { f32::NAN } else { p.x - 0.5 });
+        let tolerance = Tolerance::Absolute(1e-4);
+
+        assert!(matches!(
+            holey.classify_segment(Vec3::ZERO, Vec3::X, tolerance),
+            ClassifySegment::Undefined
+        ));
+
+        // Both ends are defined, but bisection runs into the hole.
+        assert_eq!(
+            holey.find_intersection(Vec3::NEG_X, Vec3::X, tolerance, 32),
+            Err(FindIntersectionError::Undefined)
+        );
+
+        assert!(holey
+            .find_intersection(Vec3::X * 0.2, Vec3::X, tolerance, 32)
+            .is_ok_and(|sample| sample.value.is_finite()));
+    }
+
+    #[test]
+    fn sign_convention() {
+        let at = |value| Sample::new(Vec3::ZERO, value);
+
+        assert!(at(-1e-30).is_inside());
+        assert!(!at(-0.0).is_inside());
+        assert!(!at(0.0).is_inside());
+        assert!(!at(f32::NAN).is_inside());
+
+        assert!(at(-0.5).on_surface(0.5));
+        assert!(at(0.5).on_surface(0.5));
+        assert!(!at(0.5f32.next_up()).on_surface(0.5));
+        assert!(at(-0.0).on_surface(0.0));
+
+        assert!(at(-0.25).closer_to_surface(&at(0.5)));
+        assert!(!at(0.5).closer_to_surface(&at(-0.5)));
+        assert!(!at(0.0).closer_to_surface(&at(-0.0)));
+    }
+
+    #[test]
+    fn signed_zeros_classify_alike() {
+        let tolerance = Tolerance::Absolute(1e-4);
+
+        for zero in [0.0, -0.0] {
+            // A zero at the start is outside, whatever its sign, unless zeros
+            // are inside.
+            let source = from_fn(move |p: Vec3| if p.x == 0.0 { zero } else { p.x - 0.5 });
+            assert!(matches!(
+                source.classify_segment(Vec3::ZERO, Vec3::X, tolerance),
+                ClassifySegment::NoSolution
+            ));
+            assert!(matches!(
+                ZeroSide::new(&source, ZeroIsInside(true)).classify_segment(
+                    Vec3::ZERO,
+                    Vec3::X,
+                    tolerance
+                ),
+                ClassifySegment::ChangesSign(_, _)
+            ));
+
+            // A zero midpoint doesn't end bisection, which converges on the
+            // boundary of the zeros instead.
+            let source = from_fn(move |p: Vec3| if p.x == 0.5 { zero } else { p.x - 0.5 });
+            let sample = source
+                .find_intersection(Vec3::ZERO, Vec3::X, tolerance, 32)
+                .unwrap();
+            assert!((sample.point.x - 0.5).abs() <= 1e-4);
+            assert!(sample.value != 0.0 && sample.value.abs() <= 1e-4);
+        }
+    }
+
+    /// Asserts that the surface of `source` is a single sheet at `y` facing
+    /// up across the whole bounds, without holes.
+    fn assert_flat_sheet(source: impl HermiteSource, y: f32) {
+        let mut mesh = crate::IndexedSeparateNormals::default();
+        crate::DualContouring::new(&source, 32, 1e-6)
+            .extract(crate::WithIndexedSeparateNormals::new(&mut mesh, &source))
+            .unwrap();
+
+        let positions = mesh.positions();
+        assert!(!mesh.faces.is_empty());
+
+        for face in &mesh.faces {
+            let [a, b, c] = face.map(|i| positions[i as usize]);
+            assert!((b - a).cross(c - a).y > 0.0);
+        }
+
+        for position in positions {
+            assert!((position.y - y).abs() < 1e-4, "{position}");
+        }
+
+        let mut edges = alloc::collections::BTreeMap::new();
+
+        for face in &mesh.faces {
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+
+        // Only edges along the rim, in the outermost cells, have one face.
+        let rim = |i: u32| {
+            let p = positions[i as usize];
+            p.x.abs().max(p.z.abs()) > 1.0 - 3.0 / 32.0
+        };
+
+        for (&(a, b), &count) in &edges {
+            assert!(count == 2 || (count == 1 && rim(a) && rim(b)), "{a} {b}");
+        }
+    }
+
+    #[test]
+    fn zero_plateaus() {
+        // Zeros above the sheet, which are outside.
+        assert_flat_sheet(
+            hermite_from_fn(|p: Vec3| (p.y - 0.3).clamp(-1.0, 0.0), |_| Vec3::Y),
+            0.3,
+        );
+
+        // Zeros below the sheet, which must be inside, both off and on the
+        // lattice.
+        for y in [0.3, 0.0] {
+            let plateau = hermite_from_fn(move |p: Vec3| (p.y - y).max(0.0), |_| Vec3::Y);
+            assert_flat_sheet(ZeroSide::new(&plateau, ZeroIsInside(true)), y);
+        }
+
+        // A clamped plane, zero only on the sheet, which is on the lattice.
+        assert_flat_sheet(
+            hermite_from_fn(|p: Vec3| p.y.clamp(0.0, 1.0) - 0.5, |_| Vec3::Y),
+            0.5,
+        );
+
+        // Swapping the inside and outside swaps the side of the zeros too.
+        let plateau = hermite_from_fn(|p: Vec3| -(p.y - 0.3).clamp(-1.0, 0.0), |_| Vec3::NEG_Y);
+        assert!(plateau.zero_is_inside() == ZeroIsInside(false));
+        assert!(Negated::new(&plateau).zero_is_inside() == ZeroIsInside(true));
+        assert_flat_sheet(
+            Negated::new(ZeroSide::new(&plateau, ZeroIsInside(true))),
+            0.3,
+        );
+    }
+
+    #[test]
+    fn segment_intersection_point() {
+        let tolerance = Tolerance::Absolute(1e-4);
+        let plane = from_fn(|p: Vec3| p.x + p.y - 0.5);
+        let point = |start, end| {
+            plane
+                .classify_segment(start, end, tolerance)
+                .intersection_point(start, end)
+        };
+
+        let crossing = point(Vec3::ZERO, Vec3::ONE).unwrap();
+        assert!(crossing.abs_diff_eq(Vec3::splat(0.25), 1e-6));
+        assert_eq!(point(Vec3::X * 0.50005, Vec3::X), Some(Vec3::X * 0.50005));
+        assert_eq!(point(Vec3::ZERO, Vec3::X * 0.25), None);
+        assert_eq!(point(Vec3::X * 0.5, Vec3::Y * 0.5), None);
     }
 }
@@ -0,0 +1,131 @@
+use super::{center_radius, magnitude, widen, HermiteSource, IntervalSource, Source};
+use glam::Vec3;
+
+/// The exact signed distance field of a capsule, i.e., of the points within
+/// `radius` of the segment from `a` to `b`.
+///
+/// If `a == b`, the capsule is a sphere.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Capsule {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f32,
+}
+
+impl Capsule {
+    pub fn new(a: Vec3, b: Vec3, radius: f32) -> Self {
+        Self { a, b, radius }
+    }
+
+    /// Returns the point of the segment closest to `point`.
+    fn closest_point(&self, point: Vec3) -> Vec3 {
+        let ab = self.b - self.a;
+        let length_squared = ab.length_squared();
+
+        if length_squared == 0.0 {
+            return self.a;
+        }
+
+        let t = ((point - self.a).dot(ab) / length_squared).clamp(0.0, 1.0);
+        self.a + ab * t
+    }
+}
+
+impl Source for Capsule {
+    fn sample(&self, point: Vec3) -> f32 {
+        point.distance(self.closest_point(point)) - self.radius
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        Some(1.0)
+    }
+}
+
+impl HermiteSource for Capsule {
+    /// Points away from the segment, and vanishes on it.
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        (point - self.closest_point(point)).normalize_or_zero()
+    }
+}
+
+impl IntervalSource for Capsule {
+    /// Bounds the distances within the box by the distance from its center,
+    /// give or take half its diagonal.
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let (center, radius) = center_radius(min, max);
+        let value = self.sample(center);
+        let magnitude = magnitude(min, max).max(self.a.abs().max(self.b.abs()).max_element());
+        widen(value, value, radius, magnitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::distance_check::{check_distance_field, circle, steps};
+    use crate::source::rng::Rng;
+    use crate::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+    use alloc::vec::Vec;
+    use core::f64::consts::PI;
+
+    #[test]
+    fn distances_and_gradients() {
+        let capsule = Capsule::new(Vec3::ZERO, Vec3::X, 0.5);
+
+        // Along the side, and past either end.
+        assert_eq!(capsule.sample(Vec3::new(0.3, 2.0, 0.0)), 1.5);
+        assert_eq!(capsule.gradient(Vec3::new(0.3, 2.0, 0.0)), Vec3::Y);
+        assert_eq!(capsule.sample(Vec3::new(-2.0, 0.0, 0.0)), 1.5);
+        assert_eq!(capsule.gradient(Vec3::new(3.0, 0.0, 0.0)), Vec3::X);
+        assert_eq!(capsule.sample(Vec3::new(0.5, 0.0, 0.0)), -0.5);
+
+        // A degenerate segment is a sphere.
+        let sphere = Capsule::new(Vec3::ONE, Vec3::ONE, 0.5);
+        assert_eq!(sphere.sample(Vec3::new(1.0, 3.0, 1.0)), 1.5);
+        assert_eq!(sphere.gradient(Vec3::new(1.0, 1.0, 0.0)), Vec3::NEG_Z);
+        assert_eq!(sphere.gradient(Vec3::ONE), Vec3::ZERO);
+    }
+
+    #[test]
+    fn capsule_volume() {
+        let (a, b, radius) = (Vec3::new(-0.4, -0.2, 0.1), Vec3::new(0.3, 0.4, -0.1), 0.3);
+        let capsule = Capsule::new(a, b, radius);
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(capsule, 64, 1e-6)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, capsule))
+            .unwrap();
+
+        let (r, length) = (radius as f64, a.distance(b) as f64);
+        let expected = PI * r * r * length + 4.0 / 3.0 * PI * r.powi(3);
+        let volume = mesh.signed_volume().unwrap();
+        assert!((volume / expected - 1.0).abs() < 0.02, "{volume}");
+    }
+
+    #[test]
+    fn distance_field() {
+        let mut rng = Rng(0x6c07_8965);
+        let (a, b, radius) = (rng.point(-0.5, 0.5), rng.point(-0.5, 0.5), 0.3);
+        let capsule = Capsule::new(a, b, radius);
+
+        let spacing = 0.02;
+        let axis = (b - a).normalize();
+        let (u, v) = axis.any_orthonormal_pair();
+        let length = a.distance(b);
+        let mut surface = Vec::new();
+
+        for t in steps(0.0, length, (length / spacing).ceil() as usize) {
+            surface.extend(circle(a + axis * t, u, v, radius, spacing));
+        }
+
+        // The hemispheres, as rings of latitude.
+        let rings = (core::f32::consts::FRAC_PI_2 * radius / spacing).ceil() as usize;
+
+        for angle in steps(0.0, core::f32::consts::FRAC_PI_2, rings) {
+            let (sin, cos) = angle.sin_cos();
+            surface.extend(circle(a - axis * radius * cos, u, v, radius * sin, spacing));
+            surface.extend(circle(b + axis * radius * cos, u, v, radius * sin, spacing));
+        }
+
+        check_distance_field(&capsule, &surface, spacing, &rng.far_points(200));
+    }
+}
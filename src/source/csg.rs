@@ -0,0 +1,254 @@
+//! Boolean combinations of sources, as the minimum and maximum of their
+//! values.
+//!
+//! The results of distance fields are bounds of distance fields rather than
+//! exact ones, which [`DualContouring::assume_lipschitz`] still allows. The
+//! sources should put exact zeros on the same side of the surface (see
+//! [`ZeroIsInside`]), which is taken from the first one.
+//!
+//! [`DualContouring::assume_lipschitz`]: crate::DualContouring::assume_lipschitz
+
+use super::{HermiteSource, IntervalSource, Source, ZeroIsInside};
+use glam::Vec3;
+
+/// Returns the larger of the Lipschitz constants of two sources, which bounds
+/// those of their minimum and maximum, if both are known.
+fn lipschitz_bound(a: &impl Source, b: &impl Source) -> Option<f32> {
+    Some(a.lipschitz_bound()?.max(b.lipschitz_bound()?))
+}
+
+/// The union of the insides of two sources, i.e., the minimum of their
+/// values. Gradients and normals are those of the source with the smaller
+/// value, the first one on ties.
+#[derive(Debug, Clone)]
+pub struct Union<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Union<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: Source, B: Source> Source for Union<A, B> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.a.sample(point).min(self.b.sample(point))
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.a.is_defined(point) && self.b.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.a.zero_is_inside()
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        lipschitz_bound(&self.a, &self.b)
+    }
+}
+
+impl<A: HermiteSource, B: HermiteSource> HermiteSource for Union<A, B> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        if self.a.sample(point) <= self.b.sample(point) {
+            self.a.gradient(point)
+        } else {
+            self.b.gradient(point)
+        }
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        if self.a.sample(point) <= self.b.sample(point) {
+            self.a.sample_normal(point)
+        } else {
+            self.b.sample_normal(point)
+        }
+    }
+}
+
+impl<A: IntervalSource, B: IntervalSource> IntervalSource for Union<A, B> {
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let (a, b) = (
+            self.a.sample_interval(min, max),
+            self.b.sample_interval(min, max),
+        );
+        (a.0.min(b.0), a.1.min(b.1))
+    }
+}
+
+/// The intersection of the insides of two sources, i.e., the maximum of
+/// their values. Gradients and normals are those of the source with the
+/// larger value, the first one on ties.
+#[derive(Debug, Clone)]
+pub struct Intersection<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Intersection<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: Source, B: Source> Source for Intersection<A, B> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.a.sample(point).max(self.b.sample(point))
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.a.is_defined(point) && self.b.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.a.zero_is_inside()
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        lipschitz_bound(&self.a, &self.b)
+    }
+}
+
+impl<A: HermiteSource, B: HermiteSource> HermiteSource for Intersection<A, B> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        if self.a.sample(point) >= self.b.sample(point) {
+            self.a.gradient(point)
+        } else {
+            self.b.gradient(point)
+        }
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        if self.a.sample(point) >= self.b.sample(point) {
+            self.a.sample_normal(point)
+        } else {
+            self.b.sample_normal(point)
+        }
+    }
+}
+
+impl<A: IntervalSource, B: IntervalSource> IntervalSource for Intersection<A, B> {
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let (a, b) = (
+            self.a.sample_interval(min, max),
+            self.b.sample_interval(min, max),
+        );
+        (a.0.max(b.0), a.1.max(b.1))
+    }
+}
+
+/// The inside of a source with the inside of another one carved out of it,
+/// i.e., the maximum of the values of the first one and the negated values
+/// of the second one. Gradients and normals are those of the first source
+/// where its value is the larger one, and the negated ones of the second
+/// source otherwise.
+#[derive(Debug, Clone)]
+pub struct Difference<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Difference<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: Source, B: Source> Source for Difference<A, B> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.a.sample(point).max(-self.b.sample(point))
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.a.is_defined(point) && self.b.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.a.zero_is_inside()
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        lipschitz_bound(&self.a, &self.b)
+    }
+}
+
+impl<A: HermiteSource, B: HermiteSource> HermiteSource for Difference<A, B> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        if self.a.sample(point) >= -self.b.sample(point) {
+            self.a.gradient(point)
+        } else {
+            -self.b.gradient(point)
+        }
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        if self.a.sample(point) >= -self.b.sample(point) {
+            self.a.sample_normal(point)
+        } else {
+            -self.b.sample_normal(point)
+        }
+    }
+}
+
+impl<A: IntervalSource, B: IntervalSource> IntervalSource for Difference<A, B> {
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let (a, b) = (
+            self.a.sample_interval(min, max),
+            self.b.sample_interval(min, max),
+        );
+        (a.0.max(-b.1), a.1.max(-b.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{hermite_from_fn, Capsule};
+    use crate::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+    use glam::Vec3Swizzles;
+
+    #[test]
+    fn values_and_normals() {
+        let a = Capsule::new(Vec3::ZERO, Vec3::ZERO, 0.5);
+        let b = Capsule::new(Vec3::X * 0.5, Vec3::X * 0.5, 0.25);
+
+        let union = Union::new(a, b);
+        assert_eq!(union.sample(Vec3::X * 0.75), 0.0);
+        assert_eq!(union.sample_normal(Vec3::X * 0.7), Vec3::X);
+        assert_eq!(union.sample_normal(Vec3::NEG_X), Vec3::NEG_X);
+
+        let intersection = Intersection::new(a, b);
+        assert_eq!(intersection.sample(Vec3::X * 0.25), 0.0);
+        assert_eq!(intersection.sample_normal(Vec3::X * 0.2), Vec3::NEG_X);
+
+        // The carved side points into the carved-out source.
+        let difference = Difference::new(a, b);
+        assert_eq!(difference.sample(Vec3::X * 0.5), 0.25);
+        assert_eq!(difference.sample_normal(Vec3::X * 0.3), Vec3::X);
+        assert_eq!(difference.sample_normal(Vec3::NEG_X), Vec3::NEG_X);
+
+        // Distance fields combine into bounds of them, unless either source
+        // isn't known to be one.
+        assert_eq!(difference.lipschitz_bound(), Some(1.0));
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        assert_eq!(Union::new(a, &sphere).lipschitz_bound(), None);
+    }
+
+    #[test]
+    fn carved_sphere_is_closed() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let carved = Difference::new(&sphere, Capsule::new(Vec3::NEG_Y, Vec3::Y, 0.2));
+
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(&carved, 32, 1e-6)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &carved))
+            .unwrap();
+
+        // A ring, which the axis passes through.
+        assert!(!mesh.faces.is_empty());
+        assert!(crate::validate::boundary_edges(&mesh.faces).is_empty());
+        assert!(mesh.positions().iter().all(|p| p.xz().length() > 0.15));
+    }
+}
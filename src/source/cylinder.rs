@@ -0,0 +1,197 @@
+use super::{center_radius, magnitude, widen, HermiteSource, IntervalSource, Source};
+use crate::geom::AxisKind;
+use glam::{Vec2, Vec3};
+
+/// The exact signed distance field of a solid cylinder with flat caps,
+/// centered at `center` and aligned with `axis`, which spans `half_height`
+/// along the axis on either side of the center.
+///
+/// A `half_height` of infinity makes an infinite cylinder without caps, see
+/// [`Cylinder::infinite`], whose distances are those from its side.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Cylinder {
+    pub center: Vec3,
+    pub axis: AxisKind,
+    pub radius: f32,
+    pub half_height: f32,
+}
+
+impl Cylinder {
+    pub fn new(center: Vec3, axis: AxisKind, radius: f32, half_height: f32) -> Self {
+        Self {
+            center,
+            axis,
+            radius,
+            half_height,
+        }
+    }
+
+    /// Creates an infinite cylinder around the line through `center` along
+    /// `axis`.
+    pub fn infinite(center: Vec3, axis: AxisKind, radius: f32) -> Self {
+        Self::new(center, axis, radius, f32::INFINITY)
+    }
+
+    /// Returns the signed distances of a point from the side and from the
+    /// caps, along with the unit directions away from them.
+    fn components(&self, point: Vec3) -> (Vec2, [Vec3; 2]) {
+        let axis = Vec3::AXES[self.axis as usize];
+        let offset = point - self.center;
+        let height = offset.dot(axis);
+        let radial = offset - axis * height;
+
+        let distances = Vec2::new(
+            radial.length() - self.radius,
+            height.abs() - self.half_height,
+        );
+
+        (
+            distances,
+            [radial.normalize_or_zero(), axis * height.signum()],
+        )
+    }
+}
+
+impl Source for Cylinder {
+    fn sample(&self, point: Vec3) -> f32 {
+        let (q, _) = self.components(point);
+        q.max_element().min(0.0) + q.max(Vec2::ZERO).length()
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        Some(1.0)
+    }
+}
+
+impl HermiteSource for Cylinder {
+    /// Points away from the closest of the side and the caps inside, and away
+    /// from the closest point of the surface outside, which is on the rim
+    /// past both the side and a cap.
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        let (q, [side, cap]) = self.components(point);
+
+        if q.x > 0.0 && q.y > 0.0 {
+            (side * q.x + cap * q.y) / q.length()
+        } else if q.x > q.y {
+            side
+        } else {
+            cap
+        }
+    }
+}
+
+impl IntervalSource for Cylinder {
+    /// Bounds the distances within the box by the distance from its center,
+    /// give or take half its diagonal.
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let (center, radius) = center_radius(min, max);
+        let value = self.sample(center);
+        let magnitude = magnitude(min, max).max(self.center.abs().max_element());
+        widen(value, value, radius, magnitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::distance_check::{check_distance_field, circle, steps};
+    use crate::source::rng::Rng;
+    use crate::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+    use alloc::vec::Vec;
+    use core::f64::consts::PI;
+
+    #[test]
+    fn distances_and_gradients() {
+        let cylinder = Cylinder::new(Vec3::ZERO, AxisKind::Y, 1.0, 2.0);
+
+        // The side, a cap, and the rim.
+        assert_eq!(cylinder.sample(Vec3::new(3.0, 1.0, 0.0)), 2.0);
+        assert_eq!(cylinder.gradient(Vec3::new(3.0, 1.0, 0.0)), Vec3::X);
+        assert_eq!(cylinder.sample(Vec3::new(0.0, -5.0, 0.5)), 3.0);
+        assert_eq!(cylinder.gradient(Vec3::new(0.0, -5.0, 0.5)), Vec3::NEG_Y);
+        assert_eq!(cylinder.sample(Vec3::new(0.0, 6.0, -4.0)), 5.0);
+        assert_eq!(
+            cylinder.gradient(Vec3::new(0.0, 6.0, -4.0)),
+            Vec3::new(0.0, 0.8, -0.6)
+        );
+
+        // Inside, the closest of the side and the caps.
+        assert_eq!(cylinder.sample(Vec3::new(0.25, 0.5, 0.0)), -0.75);
+        assert_eq!(cylinder.gradient(Vec3::new(0.25, 0.5, 0.0)), Vec3::X);
+        assert_eq!(cylinder.sample(Vec3::new(0.5, 1.75, 0.0)), -0.25);
+        assert_eq!(cylinder.gradient(Vec3::new(0.5, 1.75, 0.0)), Vec3::Y);
+    }
+
+    #[test]
+    fn sharp_rims() {
+        let cylinder = Cylinder::new(Vec3::new(0.03, -0.05, 0.02), AxisKind::Z, 0.55, 0.4);
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(cylinder, 32, 1e-6)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, cylinder))
+            .unwrap();
+
+        let (r, h) = (0.55f64, 0.4f64);
+        let volume = mesh.signed_volume().unwrap();
+        assert!(
+            (volume / (PI * r * r * 2.0 * h) - 1.0).abs() < 0.01,
+            "{volume}"
+        );
+
+        // Vertices on the rims stay on them rather than cutting corners.
+        for &position in mesh.positions() {
+            assert!(cylinder.sample(position).abs() < 2e-3, "{position}");
+        }
+    }
+
+    /// Returns points of the side of a cylinder along Y, from `-half_height`
+    /// to `half_height`, at most `spacing` apart.
+    fn side(cylinder: &Cylinder, half_height: f32, spacing: f32) -> Vec<Vec3> {
+        let count = (2.0 * half_height / spacing).ceil() as usize;
+
+        steps(-half_height, half_height, count)
+            .flat_map(|h| {
+                let center = cylinder.center + Vec3::Y * h;
+                circle(center, Vec3::Z, Vec3::X, cylinder.radius, spacing)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn distance_field() {
+        let mut rng = Rng(0x1b87_3593);
+        let cylinder = Cylinder::new(rng.point(-0.2, 0.2), AxisKind::Y, 0.5, 0.4);
+
+        let spacing = 0.02;
+        let mut surface = side(&cylinder, cylinder.half_height, spacing);
+        let rings = (cylinder.radius / spacing).ceil() as usize;
+
+        for radius in steps(0.0, cylinder.radius, rings) {
+            for h in [-cylinder.half_height, cylinder.half_height] {
+                let center = cylinder.center + Vec3::Y * h;
+                surface.extend(circle(center, Vec3::Z, Vec3::X, radius, spacing));
+            }
+        }
+
+        check_distance_field(&cylinder, &surface, spacing, &rng.far_points(200));
+    }
+
+    #[test]
+    fn infinite_distance_field() {
+        let mut rng = Rng(0x85eb_ca6b);
+        let cylinder = Cylinder::infinite(rng.point(-0.2, 0.2), AxisKind::Y, 0.5);
+
+        // The side around the feet of the points, which are all on it.
+        let spacing = 0.02;
+        let surface = side(&cylinder, 1.5, spacing);
+        let points: Vec<Vec3> = (rng.far_points(200).into_iter())
+            .map(|p| Vec3::new(p.x, cylinder.center.y + rng.range(-1.0, 1.0), p.z))
+            .collect();
+
+        check_distance_field(&cylinder, &surface, spacing, &points);
+
+        // Far along the axis, as close to the side as near the center.
+        let point = cylinder.center + Vec3::new(2.0, 1e6, 0.0);
+        assert_eq!(cylinder.sample(point), 1.5);
+        assert_eq!(cylinder.gradient(point), Vec3::X);
+    }
+}
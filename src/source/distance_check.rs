@@ -0,0 +1,91 @@
+//! A property test of distance fields, shared by the primitives: their values
+//! must bound the distances to their surfaces everywhere, as told by
+//! [`Source::lipschitz_bound`], and their gradients must have unit length
+//! away from the medial axis.
+
+use super::{HermiteSource, Source};
+use alloc::vec::Vec;
+use glam::Vec3;
+
+/// Checks that the values of `source` at `points` bound their distances to
+/// its surface, given as points `surface` with every point of the surface
+/// near them within `spacing` of one of them, and that its gradients there
+/// have unit length, unless they're about as close to several parts of the
+/// surface.
+///
+/// The distance to the surface is estimated by the distance to the closest
+/// point of `surface`, which exceeds it by at most `spacing`. The value must
+/// be at most [`Source::lipschitz_bound`] times the distance, and, for exact
+/// distance fields with a bound of 1, at least the distance. The gradient
+/// must point away from the closest point outside and toward it inside. The
+/// closest points of the surface to `points` must be near `surface`.
+pub(super) fn check_distance_field(
+    source: &impl HermiteSource,
+    surface: &[Vec3],
+    spacing: f32,
+    points: &[Vec3],
+) {
+    let lipschitz = Source::lipschitz_bound(source).expect("primitives bound their slopes");
+
+    for &point in points {
+        let (closest, distance) = surface
+            .iter()
+            .map(|&q| (q, q.distance(point)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+
+        let value = source.sample(point);
+        let tolerance = 1e-4 * (1.0 + point.length());
+        assert!(
+            value.abs() <= lipschitz * distance + tolerance,
+            "{value} at {point}, {distance} from the surface"
+        );
+
+        if lipschitz == 1.0 {
+            assert!(
+                value.abs() >= distance - spacing - tolerance,
+                "{value} at {point}, {distance} from the surface"
+            );
+        }
+
+        // Points of the surface as close as the closest one, give or take the
+        // spacing, lie within `reach` of it unless the point is near the
+        // medial axis.
+        let reach = 2.0 * (2.0 * distance * spacing + spacing * spacing).sqrt() + 2.0 * spacing;
+        let on_medial_axis = surface
+            .iter()
+            .any(|&q| q.distance(point) <= distance + spacing && q.distance(closest) > reach);
+
+        if on_medial_axis || distance < 10.0 * spacing {
+            continue;
+        }
+
+        let gradient = source.gradient(point);
+        assert!(
+            (gradient.length() - 1.0).abs() < 1e-3,
+            "{gradient} at {point}"
+        );
+
+        let away = (point - closest) / distance * value.signum();
+        assert!(gradient.dot(away) > 0.99, "{gradient} at {point}");
+    }
+}
+
+/// Returns points around the circle of radius `radius` around `center`, in
+/// the plane spanned by the orthonormal `u` and `v`, at most `spacing`
+/// apart, or only `center` for a radius of 0.
+pub(super) fn circle(center: Vec3, u: Vec3, v: Vec3, radius: f32, spacing: f32) -> Vec<Vec3> {
+    let count = (core::f32::consts::TAU * radius / spacing).ceil().max(1.0) as usize;
+
+    (0..count)
+        .map(|k| {
+            let angle = core::f32::consts::TAU * k as f32 / count as f32;
+            center + radius * (u * angle.cos() + v * angle.sin())
+        })
+        .collect()
+}
+
+/// Returns `count + 1` numbers evenly spaced from `lo` to `hi`.
+pub(super) fn steps(lo: f32, hi: f32, count: usize) -> impl Iterator<Item = f32> + Clone {
+    (0..=count).map(move |k| lo + (hi - lo) * k as f32 / count as f32)
+}
@@ -0,0 +1,350 @@
+//! Sources evaluated in double precision, for fields that are numerically
+//! sensitive or far from the origin, where single precision bisection
+//! stalls.
+
+use super::{
+    ClassifySegment, Endpoint, FindIntersectionError, HermiteSource, Sample, Source, Tolerance,
+    ZeroIsInside,
+};
+use auto_impl::auto_impl;
+use core::fmt;
+use glam::{DVec3, Vec3};
+
+/// A value of a [`Source64`] at a point, see [`Sample`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sample64 {
+    pub point: DVec3,
+    pub value: f64,
+}
+
+impl Sample64 {
+    pub fn new(point: DVec3, value: f64) -> Self {
+        Self { point, value }
+    }
+}
+
+/// A sample of a [`HermiteSource64`], see [`HermiteSample`].
+///
+/// [`HermiteSample`]: super::HermiteSample
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HermiteSample64 {
+    pub point: DVec3,
+    pub value: f64,
+    pub normal: DVec3,
+}
+
+impl HermiteSample64 {
+    pub fn new(point: DVec3, value: f64, normal: DVec3) -> Self {
+        Self {
+            point,
+            value,
+            normal,
+        }
+    }
+}
+
+/// Like [`Source`], in double precision.
+///
+/// Extract it through a [`DoublePrecision`], which finds intersections in
+/// double precision.
+#[auto_impl(&, &mut, Box, Rc, Arc)]
+pub trait Source64 {
+    /// Samples the source at a given point.
+    fn sample(&self, point: DVec3) -> f64;
+
+    /// See [`Source::is_defined`].
+    fn is_defined(&self, _point: DVec3) -> bool {
+        true
+    }
+
+    /// See [`Source::zero_is_inside`].
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        ZeroIsInside::default()
+    }
+}
+
+/// Like [`HermiteSource`], in double precision.
+///
+/// Implementations must provide at least one of [`gradient`] and
+/// [`sample_normal`], as each defaults to the other.
+///
+/// [`gradient`]: HermiteSource64::gradient
+/// [`sample_normal`]: HermiteSource64::sample_normal
+#[auto_impl(&, &mut, Box, Rc, Arc)]
+pub trait HermiteSource64: Source64 {
+    /// See [`HermiteSource::gradient`].
+    fn gradient(&self, point: DVec3) -> DVec3 {
+        self.sample_normal(point)
+    }
+
+    /// See [`HermiteSource::sample_normal`].
+    fn sample_normal(&self, point: DVec3) -> DVec3 {
+        self.gradient(point).normalize_or_zero()
+    }
+
+    /// See [`HermiteSource::sample_hermite`].
+    fn sample_hermite(&self, point: DVec3) -> HermiteSample64 {
+        HermiteSample64::new(point, self.sample(point), self.sample_normal(point))
+    }
+}
+
+/// Adapts a [`Source64`] to be extracted, in the frame translated to
+/// `origin`.
+///
+/// Points of the extraction are relative to `origin`, so that a surface far
+/// from the origin is extracted in single precision around it, e.g., with
+/// bounds around zero. The source is sampled at `origin` plus the point, in
+/// double precision, and intersections are bisected in double precision too.
+/// Values are rounded to single precision, keeping their sign.
+#[derive(Clone)]
+pub struct DoublePrecision<S> {
+    source: S,
+    origin: DVec3,
+}
+
+impl<S> DoublePrecision<S> {
+    pub fn new(source: S, origin: DVec3) -> Self {
+        Self { source, origin }
+    }
+
+    pub fn origin(&self) -> DVec3 {
+        self.origin
+    }
+}
+
+impl<S: Source64> DoublePrecision<S> {
+    fn sample_local(&self, point: DVec3) -> Option<f64> {
+        let world = self.origin + point;
+        let value = self.source.sample(world);
+        (value.is_finite() && self.source.is_defined(world)).then_some(value)
+    }
+}
+
+/// Rounds a value to single precision, keeping nonzero values nonzero so
+/// that they stay on the same side of the surface.
+fn narrow(value: f64) -> f32 {
+    let narrowed = value as f32;
+
+    if narrowed != 0.0 || value == 0.0 {
+        narrowed
+    } else if value > 0.0 {
+        f32::from_bits(1)
+    } else {
+        -f32::from_bits(1)
+    }
+}
+
+impl<S: Source64> Source for DoublePrecision<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        narrow(self.source.sample(self.origin + point.as_dvec3()))
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(self.origin + point.as_dvec3())
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+
+    /// Like the default, with the bisection in double precision.
+    fn find_intersection(
+        &self,
+        start: Vec3,
+        end: Vec3,
+        tolerance: Tolerance,
+        max_iter: usize,
+    ) -> Result<Sample, FindIntersectionError> {
+        match self.classify_segment(start, end, tolerance) {
+            ClassifySegment::Intersects(Endpoint::Start, value) => Ok(Sample::new(start, value)),
+            ClassifySegment::Intersects(Endpoint::End, value) => Ok(Sample::new(end, value)),
+            ClassifySegment::NoSolution => Err(FindIntersectionError::NoSolution),
+            ClassifySegment::Indeterminate => Err(FindIntersectionError::Indeterminate),
+            ClassifySegment::Undefined => Err(FindIntersectionError::Undefined),
+            ClassifySegment::ChangesSign(v_start, v_end) => {
+                let length = start.distance(end);
+                let value_threshold = tolerance.value_threshold(v_start, v_end, length) as f64;
+                let length_threshold = tolerance.length_threshold(length) as f64;
+                let zeros = self.zero_is_inside();
+
+                let (mut a, mut b) = (start.as_dvec3(), end.as_dvec3());
+                let (mut v_a, mut v_b) = (v_start as f64, v_end as f64);
+                let undefined = FindIntersectionError::Undefined;
+
+                for _ in 0..max_iter {
+                    if zeros.is_inside(narrow(v_a)) == zeros.is_inside(narrow(v_b)) {
+                        return Err(FindIntersectionError::NoSolution);
+                    }
+
+                    let c = (a + b) / 2.0;
+                    let v_c = self.sample_local(c).ok_or(undefined)?;
+
+                    if (a - b).length_squared() <= length_threshold * length_threshold
+                        || (v_c != 0.0 && v_c.abs() <= value_threshold)
+                    {
+                        return Ok(Sample::new(c.as_vec3(), narrow(v_c)));
+                    }
+
+                    if zeros.is_inside(narrow(v_a)) != zeros.is_inside(narrow(v_c)) {
+                        b = c;
+                        v_b = v_c;
+                    } else {
+                        a = c;
+                        v_a = v_c;
+                    }
+                }
+
+                let c = (a + b) / 2.0;
+                let v_c = self.sample_local(c).ok_or(undefined)?;
+                Err(FindIntersectionError::IterLimit(Sample::new(
+                    c.as_vec3(),
+                    narrow(v_c),
+                )))
+            }
+        }
+    }
+}
+
+impl<S: HermiteSource64> HermiteSource for DoublePrecision<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.source
+            .gradient(self.origin + point.as_dvec3())
+            .as_vec3()
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        self.source
+            .sample_normal(self.origin + point.as_dvec3())
+            .as_vec3()
+    }
+}
+
+impl<S> fmt::Debug for DoublePrecision<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DoublePrecision")
+            .field("origin", &self.origin)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Creates a new double precision source that samples the given closure,
+/// like [`from_fn`](super::from_fn).
+pub fn from_fn64<F>(f: F) -> FromFn64<F>
+where
+    F: Fn(DVec3) -> f64,
+{
+    FromFn64(f)
+}
+
+/// A double precision source whose values are produced by a closure.
+///
+/// This `struct` is created by the [`from_fn64`] function.
+#[derive(Clone)]
+pub struct FromFn64<F>(F);
+
+impl<F: Fn(DVec3) -> f64> Source64 for FromFn64<F> {
+    fn sample(&self, point: DVec3) -> f64 {
+        (self.0)(point)
+    }
+}
+
+impl<F> fmt::Debug for FromFn64<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromFn64").finish_non_exhaustive()
+    }
+}
+
+/// Creates a new double precision Hermite source from a value closure and a
+/// gradient closure, like [`hermite_from_fn`](super::hermite_from_fn).
+pub fn hermite_from_fn64<F, G>(f: F, gradient: G) -> HermiteFromFn64<F, G>
+where
+    F: Fn(DVec3) -> f64,
+    G: Fn(DVec3) -> DVec3,
+{
+    HermiteFromFn64 { f, gradient }
+}
+
+/// A double precision Hermite source whose values and gradients are produced
+/// by closures.
+///
+/// This `struct` is created by the [`hermite_from_fn64`] function.
+#[derive(Clone)]
+pub struct HermiteFromFn64<F, G> {
+    f: F,
+    gradient: G,
+}
+
+impl<F: Fn(DVec3) -> f64, G> Source64 for HermiteFromFn64<F, G> {
+    fn sample(&self, point: DVec3) -> f64 {
+        (self.f)(point)
+    }
+}
+
+impl<F: Fn(DVec3) -> f64, G: Fn(DVec3) -> DVec3> HermiteSource64 for HermiteFromFn64<F, G> {
+    fn gradient(&self, point: DVec3) -> DVec3 {
+        (self.gradient)(point)
+    }
+}
+
+impl<F, G> fmt::Debug for HermiteFromFn64<F, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HermiteFromFn64").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::validate::boundary_edges;
+    use crate::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+    use core::f64::consts::PI;
+
+    #[test]
+    fn narrowing_keeps_sides() {
+        assert_eq!(narrow(0.25), 0.25);
+        assert_eq!(narrow(0.0), 0.0);
+        assert!(narrow(1e-60) > 0.0);
+        assert!(narrow(-1e-60) < 0.0);
+    }
+
+    #[test]
+    fn sphere_far_from_origin() {
+        let center = DVec3::new(1e7 + 0.013, -2e7 + 0.021, 3e7 - 0.017);
+        let radius = 0.6;
+        let sphere = hermite_from_fn64(
+            move |p: DVec3| p.distance(center) - radius,
+            move |p| p - center,
+        );
+        let origin = DVec3::new(1e7, -2e7, 3e7);
+        let source = DoublePrecision::new(&sphere, origin);
+
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(&source, 32, 1e-6)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &source))
+            .unwrap();
+
+        let expected = 4.0 / 3.0 * PI * radius.powi(3);
+        let volume = mesh.signed_volume().unwrap();
+        assert!((volume / expected - 1.0).abs() < 0.01, "{volume}");
+        assert!(boundary_edges(&mesh.faces).is_empty());
+
+        // The mesh matches the same sphere around the origin in single
+        // precision.
+        let local = (center - origin).as_vec3();
+        let near = hermite_from_fn(move |p: Vec3| p.distance(local) - 0.6, move |p| p - local);
+        let mut expected = IndexedSeparateNormals::default();
+        DualContouring::new(&near, 32, 1e-6)
+            .extract(WithIndexedSeparateNormals::new(&mut expected, &near))
+            .unwrap();
+
+        assert_eq!(mesh.faces, expected.faces);
+        for (a, b) in mesh.positions().iter().zip(expected.positions()) {
+            assert!(a.abs_diff_eq(*b, 1e-5), "{a} {b}");
+        }
+
+        // In single precision, the center isn't even distinguishable from
+        // the origin.
+        assert_eq!(center.as_vec3(), origin.as_vec3());
+    }
+}
@@ -0,0 +1,574 @@
+use super::mesh_distance::MeshDistance;
+use super::{HermiteSource, Source};
+use crate::geom::Bounds;
+use alloc::vec::Vec;
+use glam::{UVec3, Vec3};
+#[cfg(feature = "std")]
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// How a [`GridSource`] interpolates its values between the grid points.
+///
+/// Every mode reproduces the values at the grid points.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Interpolation {
+    /// The value of the closest grid point, which makes blocky surfaces out
+    /// of the faces between the voxels around the grid points.
+    Nearest,
+    /// Trilinear interpolation of the 8 grid points around, which is
+    /// continuous, but whose gradient jumps across the grid cells.
+    #[default]
+    Trilinear,
+    /// Catmull-Rom interpolation of the 64 grid points around, whose
+    /// gradient is continuous too, which makes for smoother surfaces and
+    /// normals, at the cost of 8 times as many reads per sample. Fields up
+    /// to quadratic are reproduced exactly away from the bounds.
+    Tricubic,
+}
+
+/// A source interpolating values sampled on a regular grid, such as a volume
+/// texture.
+///
+/// The grid points span `bounds`, with the first and the last sample along
+/// each axis lying on its faces. Values are stored with X varying fastest,
+/// then Y, then Z. Points outside of `bounds` are clamped to it. Values are
+/// interpolated trilinearly unless set otherwise by
+/// [`GridSource::with_interpolation`].
+#[derive(Debug, Clone)]
+pub struct GridSource {
+    dims: UVec3,
+    bounds: Bounds,
+    values: Vec<f32>,
+    interpolation: Interpolation,
+}
+
+impl GridSource {
+    /// Creates a new `GridSource` from `dims.x * dims.y * dims.z` values.
+    pub fn new(dims: UVec3, bounds: Bounds, values: Vec<f32>) -> Self {
+        assert!(
+            dims.cmpge(UVec3::splat(2)).all(),
+            "`dims` must be at least 2 along every axis"
+        );
+
+        assert_eq!(
+            values.len() as u64,
+            dims.as_u64vec3().element_product(),
+            "`values` must have exactly one value per grid point"
+        );
+
+        Self {
+            dims,
+            bounds,
+            values,
+            interpolation: Interpolation::default(),
+        }
+    }
+
+    /// Sets how values are interpolated between the grid points.
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Samples the signed distance to a closed triangle mesh, negative
+    /// inside, e.g., to edit an extracted mesh as a field and extract it
+    /// again.
+    ///
+    /// The faces must be consistently wound counter-clockwise around their
+    /// outward normals, as [`IndexedSeparateNormals`] are by default. The
+    /// sign of each distance is that of the angle-weighted pseudonormal of
+    /// the closest face, edge or vertex, which is exact for such meshes but
+    /// unreliable near the boundary of open ones. Degenerate faces are
+    /// ignored.
+    ///
+    /// Distances are found through a bounding volume hierarchy over the
+    /// faces, starting from the closest face to the previous grid point, so
+    /// that only the faces close to each grid point are tested. Many more
+    /// are around the medial axis of the mesh, e.g., close to the center of a
+    /// sphere, where they're all about as close.
+    /// Without faces, every value is infinite.
+    ///
+    /// [`IndexedSeparateNormals`]: crate::IndexedSeparateNormals
+    pub fn from_mesh(positions: &[Vec3], faces: &[[u32; 3]], dims: UVec3, bounds: Bounds) -> Self {
+        let mesh = MeshDistance::new(positions, faces);
+        // `new` rejects dimensions below 2 once the values are sampled.
+        let spacing = bounds.size() / (dims.max(UVec3::splat(2)) - 1).as_vec3();
+
+        let mut values = Vec::with_capacity(dims.as_u64vec3().element_product() as usize);
+        // Adjacent grid points are usually closest to the same triangle.
+        let mut hint = 0;
+
+        for z in 0..dims.z {
+            for y in 0..dims.y {
+                for x in 0..dims.x {
+                    let point = bounds.min + UVec3::new(x, y, z).as_vec3() * spacing;
+                    values.push(mesh.signed_distance(point, &mut hint));
+                }
+            }
+        }
+
+        Self::new(dims, bounds, values)
+    }
+
+    /// Reads a grid from a stream of raw little-endian `f32` values.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: Read>(mut reader: R, dims: UVec3, bounds: Bounds) -> io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        if bytes.len() as u64 != dims.as_u64vec3().element_product() * 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the size of the volume doesn't match its dimensions",
+            ));
+        }
+
+        let values = bytes
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+            .collect();
+
+        Ok(Self::new(dims, bounds, values))
+    }
+
+    /// Reads a grid from a file of raw little-endian `f32` values.
+    #[cfg(feature = "std")]
+    pub fn open(path: impl AsRef<Path>, dims: UVec3, bounds: Bounds) -> io::Result<Self> {
+        Self::from_reader(io::BufReader::new(File::open(path)?), dims, bounds)
+    }
+
+    /// Returns the number of grid points along each axis.
+    pub fn dims(&self) -> UVec3 {
+        self.dims
+    }
+
+    /// Returns the box spanned by the grid points.
+    pub fn bounds(&self) -> &Bounds {
+        &self.bounds
+    }
+
+    /// Returns the values at the grid points, with x varying fastest.
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    /// Returns how values are interpolated between grid points.
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Returns the distance between adjacent grid points.
+    pub fn spacing(&self) -> Vec3 {
+        self.bounds.size() / (self.dims - 1).as_vec3()
+    }
+
+    fn value(&self, index: UVec3) -> f32 {
+        let [x, y, z] = index.to_array().map(|i| i as usize);
+        let [width, height, _] = self.dims.to_array().map(|n| n as usize);
+        self.values[x + width * (y + height * z)]
+    }
+
+    /// Returns the grid cell containing `point` clamped to the bounds, and
+    /// the position of the point within it, from 0 to 1 along each axis.
+    fn locate(&self, point: Vec3) -> (UVec3, Vec3) {
+        let point = point.clamp(self.bounds.min, self.bounds.max);
+        let grid = (point - self.bounds.min) / self.spacing();
+
+        let cell = grid.floor().as_uvec3().min(self.dims - 2);
+        let t = (grid - cell.as_vec3()).clamp(Vec3::ZERO, Vec3::ONE);
+        (cell, t)
+    }
+
+    /// Returns the values at the corners of `cell`, indexed by their offsets
+    /// from the first one, with X in the lowest bit.
+    fn corners(&self, cell: UVec3) -> [f32; 8] {
+        core::array::from_fn(|k| {
+            let k = k as u32;
+            self.value(cell + UVec3::new(k & 1, (k >> 1) & 1, k >> 2))
+        })
+    }
+
+    fn trilinear(&self, point: Vec3) -> f32 {
+        let (cell, t) = self.locate(point);
+        let c = self.corners(cell);
+
+        let x00 = lerp(c[0], c[1], t.x);
+        let x10 = lerp(c[2], c[3], t.x);
+        let x01 = lerp(c[4], c[5], t.x);
+        let x11 = lerp(c[6], c[7], t.x);
+
+        lerp(lerp(x00, x10, t.y), lerp(x01, x11, t.y), t.z)
+    }
+
+    /// Returns the gradient of the trilinear interpolant, see
+    /// [`GridSource::gradient`].
+    fn trilinear_gradient(&self, point: Vec3) -> Vec3 {
+        let (cell, t) = self.locate(point);
+        let c = self.corners(cell);
+
+        // The differences along each axis, interpolated over the other two.
+        let dx = bilerp(
+            [c[1] - c[0], c[3] - c[2], c[5] - c[4], c[7] - c[6]],
+            t.y,
+            t.z,
+        );
+        let dy = bilerp(
+            [c[2] - c[0], c[3] - c[1], c[6] - c[4], c[7] - c[5]],
+            t.x,
+            t.z,
+        );
+        let dz = bilerp(
+            [c[4] - c[0], c[5] - c[1], c[6] - c[2], c[7] - c[3]],
+            t.x,
+            t.y,
+        );
+
+        Vec3::new(dx, dy, dz) / self.spacing()
+    }
+
+    /// Returns the gradient of the faces between the voxels around the grid
+    /// points, see [`GridSource::gradient`].
+    fn nearest_gradient(&self, point: Vec3) -> Vec3 {
+        let (cell, t) = self.locate(point);
+        let nearest = cell + t.round().as_uvec3();
+
+        // The axis along which the point is the closest to a face, which is
+        // halfway between two grid points.
+        let distances = (t - 0.5).abs();
+        let axis = (0..3)
+            .min_by(|&a, &b| distances[a].total_cmp(&distances[b]))
+            .unwrap();
+
+        let mut below = nearest;
+        let mut above = nearest;
+        below[axis] = cell[axis];
+        above[axis] = cell[axis] + 1;
+
+        let mut gradient = Vec3::ZERO;
+        gradient[axis] = (self.value(above) - self.value(below)) / self.spacing()[axis];
+        gradient
+    }
+
+    /// Returns the Catmull-Rom interpolant and its gradient.
+    fn tricubic(&self, point: Vec3) -> (f32, Vec3) {
+        let (cell, t) = self.locate(point);
+        let [(wx, dx), (wy, dy), (wz, dz)] = [t.x, t.y, t.z].map(catmull_rom);
+        let max = self.dims - 1;
+
+        let mut value = 0.0;
+        let mut gradient = Vec3::ZERO;
+
+        for k in 0..4 {
+            for j in 0..4 {
+                for i in 0..4 {
+                    // The grid points around the cell, repeating the first
+                    // and the last ones beyond the bounds.
+                    let index = (cell + UVec3::new(i, j, k)).saturating_sub(UVec3::ONE);
+                    let v = self.value(index.min(max));
+                    let (i, j, k) = (i as usize, j as usize, k as usize);
+
+                    value += wx[i] * wy[j] * wz[k] * v;
+                    gradient += Vec3::new(
+                        dx[i] * wy[j] * wz[k],
+                        wx[i] * dy[j] * wz[k],
+                        wx[i] * wy[j] * dz[k],
+                    ) * v;
+                }
+            }
+        }
+
+        (value, gradient / self.spacing())
+    }
+}
+
+/// Returns the Catmull-Rom weights of 4 consecutive samples at `t` between
+/// the middle two, along with their derivatives.
+fn catmull_rom(t: f32) -> ([f32; 4], [f32; 4]) {
+    let (t2, t3) = (t * t, t * t * t);
+
+    let weights = [
+        (-t3 + 2.0 * t2 - t) / 2.0,
+        (3.0 * t3 - 5.0 * t2 + 2.0) / 2.0,
+        (-3.0 * t3 + 4.0 * t2 + t) / 2.0,
+        (t3 - t2) / 2.0,
+    ];
+    let derivatives = [
+        (-3.0 * t2 + 4.0 * t - 1.0) / 2.0,
+        (9.0 * t2 - 10.0 * t) / 2.0,
+        (-9.0 * t2 + 8.0 * t + 1.0) / 2.0,
+        (3.0 * t2 - 2.0 * t) / 2.0,
+    ];
+
+    (weights, derivatives)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Interpolates `values` at the corners of a unit square, with X in the
+/// lowest bit, at `(u, v)`.
+fn bilerp(values: [f32; 4], u: f32, v: f32) -> f32 {
+    lerp(
+        lerp(values[0], values[1], u),
+        lerp(values[2], values[3], u),
+        v,
+    )
+}
+
+impl Source for GridSource {
+    fn sample(&self, point: Vec3) -> f32 {
+        match self.interpolation {
+            Interpolation::Nearest => {
+                let (cell, t) = self.locate(point);
+                self.value(cell + t.round().as_uvec3())
+            }
+            Interpolation::Trilinear => self.trilinear(point),
+            Interpolation::Tricubic => self.tricubic(point).0,
+        }
+    }
+}
+
+impl HermiteSource for GridSource {
+    /// Returns the gradient of the interpolant within the grid cell
+    /// containing `point`, the lower one on the faces between cells, which
+    /// is 0 along the axes the point is outside of the bounds along. Like the
+    /// values, it's undefined where any grid point they're interpolated from
+    /// is undefined (non-finite).
+    ///
+    /// With [`Interpolation::Nearest`], the values are constant within the
+    /// voxels, so this is instead the gradient across the face of the voxel
+    /// of the closest grid point that's the closest to `point`, along its
+    /// axis, which gives the blocky surfaces flat normals.
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        let gradient = match self.interpolation {
+            Interpolation::Nearest => self.nearest_gradient(point),
+            Interpolation::Trilinear => self.trilinear_gradient(point),
+            Interpolation::Tricubic => self.tricubic(point).1,
+        };
+
+        let inside = point.cmpge(self.bounds.min) & point.cmple(self.bounds.max);
+        Vec3::select(inside, gradient, Vec3::ZERO)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::validate::boundary_edges;
+    use crate::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+
+    fn unit_grid() -> GridSource {
+        // f(x, y, z) = x + 2y + 4z, sampled at the corners of the unit cube.
+        let values = (0..8)
+            .map(|i| (i & 1) as f32 + 2.0 * (i >> 1 & 1) as f32 + 4.0 * (i >> 2) as f32)
+            .collect();
+
+        GridSource::new(UVec3::splat(2), Bounds::new(Vec3::ZERO, Vec3::ONE), values)
+    }
+
+    #[test]
+    fn interpolates_linear_field_exactly() {
+        let grid = unit_grid();
+        let p = Vec3::new(0.25, 0.5, 0.75);
+        assert!((grid.sample(p) - 4.25).abs() < 1e-6);
+        assert!(grid
+            .sample_normal(p)
+            .abs_diff_eq(Vec3::new(1.0, 2.0, 4.0).normalize(), 1e-5));
+        assert!(grid.gradient(p).abs_diff_eq(Vec3::new(1.0, 2.0, 4.0), 1e-5));
+    }
+
+    #[test]
+    fn differentiates_interpolant() {
+        // A field that trilinear interpolation doesn't reproduce.
+        let dims = UVec3::splat(5);
+        let bounds = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let spacing = bounds.size() / 4.0;
+        let values = (0..125)
+            .map(|i| {
+                let index = UVec3::new(i % 5, i / 5 % 5, i / 25);
+                let p = bounds.min + index.as_vec3() * spacing;
+                p.x * p.y + p.z * p.z
+            })
+            .collect();
+        let grid = GridSource::new(dims, bounds, values);
+
+        // Central differences of the interpolant, within a cell.
+        let h = 1e-3;
+        for p in [Vec3::new(0.2, -0.7, 0.4), Vec3::new(-0.9, 0.6, -0.3)] {
+            let expected =
+                Vec3::from_array(Vec3::AXES.map(|axis| {
+                    (grid.sample(p + axis * h) - grid.sample(p - axis * h)) / (2.0 * h)
+                }));
+            assert!(grid.gradient(p).abs_diff_eq(expected, 1e-2), "{p}");
+        }
+
+        // The values are clamped outside of the bounds, hence constant.
+        let gradient = grid.gradient(Vec3::new(2.0, 0.3, -0.6));
+        assert_eq!(gradient.x, 0.0);
+        assert!(gradient.y != 0.0 && gradient.z != 0.0);
+    }
+
+    /// Samples `f` at the 5³ grid points spanning `[-1, 1]³`.
+    fn sampled_grid(f: impl Fn(Vec3) -> f32) -> GridSource {
+        let dims = UVec3::splat(5);
+        let bounds = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let values = (0..125)
+            .map(|i| f(bounds.min + UVec3::new(i % 5, i / 5 % 5, i / 25).as_vec3() * 0.5))
+            .collect();
+        GridSource::new(dims, bounds, values)
+    }
+
+    const MODES: [Interpolation; 3] = [
+        Interpolation::Nearest,
+        Interpolation::Trilinear,
+        Interpolation::Tricubic,
+    ];
+
+    #[test]
+    fn reproduces_grid_points() {
+        let field = |p: Vec3| (3.0 * p.x).sin() * p.y + p.z.powi(3);
+        let grid = sampled_grid(field);
+        assert_eq!(grid.interpolation(), Interpolation::Trilinear);
+
+        for mode in MODES {
+            let grid = grid.clone().with_interpolation(mode);
+
+            for (i, &value) in grid.values().iter().enumerate() {
+                let index = UVec3::new(i as u32 % 5, i as u32 / 5 % 5, i as u32 / 25);
+                let point = grid.bounds().min + index.as_vec3() * grid.spacing();
+                assert!(
+                    (grid.sample(point) - value).abs() <= 1e-6,
+                    "{mode:?} at {point}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_is_blocky() {
+        let grid = sampled_grid(|p| p.x + 2.0 * p.y + 4.0 * p.z)
+            .with_interpolation(Interpolation::Nearest);
+
+        // Constant within the voxel of the grid point at (0.5, 0, -0.5).
+        for offset in [Vec3::ZERO, Vec3::new(0.2, -0.24, 0.1), Vec3::splat(-0.2)] {
+            assert_eq!(grid.sample(Vec3::new(0.5, 0.0, -0.5) + offset), -1.5);
+        }
+
+        // Across the face of the voxel along Y, i.e., between the grid
+        // points 0.5 apart along Y.
+        let gradient = grid.gradient(Vec3::new(0.4, 0.26, -0.55));
+        assert_eq!(gradient, Vec3::new(0.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn tricubic_reproduces_quadratics() {
+        let field = |p: Vec3| p.x * p.y + p.z * p.z - 0.5 * p.x;
+        let gradient = |p: Vec3| Vec3::new(p.y - 0.5, p.x, 2.0 * p.z);
+        let grid = sampled_grid(field);
+        let tricubic = grid.clone().with_interpolation(Interpolation::Tricubic);
+
+        // Away from the first and last cells, where the grid points are
+        // repeated.
+        for p in [Vec3::new(0.2, -0.3, 0.4), Vec3::new(-0.45, 0.1, -0.05)] {
+            assert!((tricubic.sample(p) - field(p)).abs() < 1e-5, "{p}");
+            assert!(tricubic.gradient(p).abs_diff_eq(gradient(p), 1e-4), "{p}");
+            assert!((grid.sample(p) - field(p)).abs() > 1e-3, "{p}");
+        }
+
+        // The gradient is that of the interpolant, even near the bounds.
+        let h = 1e-3;
+        for p in [Vec3::new(0.9, -0.8, 0.7), Vec3::new(-0.95, 0.6, -0.3)] {
+            let expected = Vec3::from_array(Vec3::AXES.map(|axis| {
+                (tricubic.sample(p + axis * h) - tricubic.sample(p - axis * h)) / (2.0 * h)
+            }));
+            assert!(tricubic.gradient(p).abs_diff_eq(expected, 1e-2), "{p}");
+        }
+    }
+
+    #[test]
+    fn tricubic_normals() {
+        // A sphere on a coarse grid, whose normals trilinear interpolation
+        // gets wrong by a few degrees.
+        let dims = UVec3::splat(9);
+        let bounds = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let values = (0..729)
+            .map(|i| {
+                let index = UVec3::new(i % 9, i / 9 % 9, i / 81);
+                (bounds.min + index.as_vec3() * 0.25).length() - 0.6
+            })
+            .collect();
+        let grid = GridSource::new(dims, bounds, values);
+
+        let mean_error = |mode| {
+            let grid = grid.clone().with_interpolation(mode);
+            let mesh = extract(&grid);
+            let errors = (mesh.positions().iter().zip(mesh.normals()))
+                .map(|(p, n)| p.normalize().angle_between(*n));
+            errors.sum::<f32>() / mesh.positions().len() as f32
+        };
+
+        let trilinear = mean_error(Interpolation::Trilinear);
+        let tricubic = mean_error(Interpolation::Tricubic);
+        assert!(tricubic < trilinear / 2.0, "{tricubic} vs. {trilinear}");
+    }
+
+    fn extract(source: &impl HermiteSource) -> IndexedSeparateNormals {
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(source, 32, 1e-6)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, source))
+            .unwrap();
+        mesh
+    }
+
+    #[test]
+    fn round_trips_meshes() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p);
+        let mesh = extract(&sphere);
+
+        // Twice the resolution of the extraction.
+        let bounds = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let grid = GridSource::from_mesh(mesh.positions(), &mesh.faces, UVec3::splat(65), bounds);
+
+        // The signs are those of the sphere everywhere, and the distances
+        // close to it.
+        for (i, &value) in grid.values().iter().enumerate() {
+            let index = UVec3::new(i as u32 % 65, i as u32 / 65 % 65, i as u32 / 65 / 65);
+            let point = bounds.min + index.as_vec3() * grid.spacing();
+            let expected = sphere.sample(point);
+
+            assert!((value - expected).abs() < 0.01, "{point} {value}");
+        }
+
+        let again = extract(&grid);
+        assert!(boundary_edges(&again.faces).is_empty());
+
+        // Every vertex of either mesh is within a cell of the grid from the
+        // other one.
+        for (a, b) in [(&mesh, &again), (&again, &mesh)] {
+            let distance = MeshDistance::new(b.positions(), &b.faces);
+
+            for &position in a.positions() {
+                let d = distance.signed_distance(position, &mut 0);
+                assert!(d.abs() < 2.0 / 64.0, "{position} {d}");
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn reads_raw_volume() {
+        let grid = unit_grid();
+        let bytes: Vec<u8> = grid.values().iter().flat_map(|v| v.to_le_bytes()).collect();
+
+        let read = GridSource::from_reader(&bytes[..], grid.dims(), *grid.bounds()).unwrap();
+        assert_eq!(read.values(), grid.values());
+
+        let truncated = GridSource::from_reader(&bytes[1..], grid.dims(), *grid.bounds());
+        assert_eq!(truncated.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
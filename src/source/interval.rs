@@ -0,0 +1,225 @@
+//! Bounds of the values of sources over boxes, see [`IntervalSource`].
+
+use super::{HermiteSource, Source, ZeroIsInside};
+use crate::geom::{Bounds, CornerKind};
+use auto_impl::auto_impl;
+use glam::Vec3;
+
+/// A source that bounds its values over boxes, e.g., by interval arithmetic,
+/// so that cells the surface provably doesn't cross are skipped without
+/// sampling them, see [`DualContouring::with_interval_culling`].
+///
+/// Unlike [`DualContouring::assume_lipschitz`], this holds for any field
+/// whose bounds are known, not only distance fields, e.g., for polynomials,
+/// noise, or combinations of fields that don't preserve distances.
+///
+/// [`DualContouring::with_interval_culling`]: crate::DualContouring::with_interval_culling
+/// [`DualContouring::assume_lipschitz`]: crate::DualContouring::assume_lipschitz
+#[auto_impl(&, &mut, Box, Rc, Arc)]
+pub trait IntervalSource: Source {
+    /// Returns the smallest and the largest values of the source over the
+    /// box from `min` to `max`, or bounds of them: every value
+    /// [`Source::sample`] returns within the box must lie within them.
+    ///
+    /// The bounds needn't be tight, but the looser they are, the fewer cells
+    /// are skipped. Infinite bounds are always valid.
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32);
+}
+
+/// The interval of an [`IntervalSource`] as a function of the source, so that
+/// it can be stored for sources that don't implement the trait.
+pub(crate) type IntervalFn<S> = fn(&S, Vec3, Vec3) -> (f32, f32);
+
+/// The rounding error bounds are widened by, relative to the magnitudes
+/// involved.
+const ROUNDING: f32 = 1e-5;
+
+/// Returns the center of the box from `min` to `max` and the distance from
+/// it to the corners.
+pub(crate) fn center_radius(min: Vec3, max: Vec3) -> (Vec3, f32) {
+    ((min + max) / 2.0, (max - min).length() / 2.0)
+}
+
+/// Widens the bounds `lo` and `hi` by `radius`, and by the rounding errors
+/// of computing values from coordinates of up to `magnitude`.
+///
+/// For a source Lipschitz continuous with a constant of 1, the values within
+/// `radius` of a point are bounded by its value widened by `radius`.
+pub(crate) fn widen(lo: f32, hi: f32, radius: f32, magnitude: f32) -> (f32, f32) {
+    let pad = radius + (lo.abs().max(hi.abs()) + radius + magnitude) * ROUNDING;
+    (lo - pad, hi + pad)
+}
+
+/// Returns the largest coordinate of the box from `min` to `max` in
+/// magnitude.
+pub(crate) fn magnitude(min: Vec3, max: Vec3) -> f32 {
+    min.abs().max(max.abs()).max_element()
+}
+
+/// Bounds the values of a source without an [`IntervalSource`] impl from its
+/// values at the corners of boxes, given a Lipschitz constant of the source,
+/// so that [interval culling] applies to it too.
+///
+/// Every point of a box is within half its diagonal of a corner, so the
+/// values are bounded by those at the corners, widened by the Lipschitz
+/// constant times half the diagonal. Every box thus takes 8 samples, and the
+/// bounds are only as valid as the constant. Boxes with an undefined corner
+/// are unbounded.
+///
+/// [interval culling]: crate::DualContouring::with_interval_culling
+#[derive(Debug, Clone)]
+pub struct FallbackInterval<S> {
+    source: S,
+    lipschitz: f32,
+}
+
+impl<S> FallbackInterval<S> {
+    pub fn new(source: S, lipschitz: f32) -> Self {
+        assert!(
+            lipschitz.is_finite() && lipschitz >= 0.0,
+            "`lipschitz` must be finite and at least 0"
+        );
+
+        Self { source, lipschitz }
+    }
+
+    pub fn lipschitz(&self) -> f32 {
+        self.lipschitz
+    }
+}
+
+impl<S: Source> Source for FallbackInterval<S> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.source.sample(point)
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.source.is_defined(point)
+    }
+
+    fn zero_is_inside(&self) -> ZeroIsInside {
+        self.source.zero_is_inside()
+    }
+}
+
+impl<S: HermiteSource> HermiteSource for FallbackInterval<S> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.source.gradient(point)
+    }
+
+    fn sample_normal(&self, point: Vec3) -> Vec3 {
+        self.source.sample_normal(point)
+    }
+}
+
+impl<S: Source> IntervalSource for FallbackInterval<S> {
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let bounds = Bounds::new(min, max);
+        let (mut lo, mut hi) = (f32::INFINITY, f32::NEG_INFINITY);
+
+        for corner in CornerKind::ALL {
+            let value = self.source.sample(bounds.corner(corner));
+
+            if !value.is_finite() {
+                return (f32::NEG_INFINITY, f32::INFINITY);
+            }
+
+            (lo, hi) = (lo.min(value), hi.max(value));
+        }
+
+        let (_, radius) = center_radius(min, max);
+        widen(lo, hi, self.lipschitz * radius, magnitude(min, max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::AxisKind;
+    use crate::source::rng::Rng;
+    use crate::source::{
+        hermite_from_fn, Capsule, Cylinder, Difference, Intersection, Isovalue, Mirror, Negated,
+        Plane, Repeat, Union,
+    };
+    use alloc::boxed::Box;
+
+    fn random_tree(rng: &mut Rng, depth: u32) -> Box<dyn IntervalSource> {
+        let kind = if depth == 0 {
+            rng.next() % 3
+        } else {
+            rng.next() % 10
+        };
+
+        match kind {
+            0 => Box::new(Capsule::new(
+                rng.point(-1.0, 1.0),
+                rng.point(-1.0, 1.0),
+                0.3,
+            )),
+            1 => {
+                let axis = AxisKind::ALL[rng.next() as usize % 3];
+                Box::new(Cylinder::new(rng.point(-1.0, 1.0), axis, 0.4, 0.6))
+            }
+            2 => Box::new(Plane::new(rng.point(-1.0, 1.0), rng.range(-1.0, 1.0))),
+            3 => Box::new(Union::new(
+                random_tree(rng, depth - 1),
+                random_tree(rng, depth - 1),
+            )),
+            4 => Box::new(Intersection::new(
+                random_tree(rng, depth - 1),
+                random_tree(rng, depth - 1),
+            )),
+            5 => Box::new(Difference::new(
+                random_tree(rng, depth - 1),
+                random_tree(rng, depth - 1),
+            )),
+            6 => Box::new(Negated::new(random_tree(rng, depth - 1))),
+            7 => Box::new(Isovalue::new(
+                random_tree(rng, depth - 1),
+                rng.range(-0.5, 0.5),
+            )),
+            8 => {
+                let axis = AxisKind::ALL[rng.next() as usize % 3];
+                Box::new(Mirror::new(random_tree(rng, depth - 1), axis))
+            }
+            _ => Box::new(Repeat::new(
+                random_tree(rng, depth - 1),
+                rng.point(0.5, 2.0),
+            )),
+        }
+    }
+
+    #[test]
+    fn intervals_enclose_sampled_values() {
+        let mut rng = Rng(0x9e37_79b9);
+
+        for _ in 0..200 {
+            let source = random_tree(&mut rng, 3);
+            let min = rng.point(-3.0, 3.0);
+            let max = min + rng.point(0.0, 1.0);
+            let (lo, hi) = source.sample_interval(min, max);
+
+            for _ in 0..1000 {
+                let t = rng.point(0.0, 1.0);
+                let value = source.sample(min + (max - min) * t);
+                assert!(lo <= value && value <= hi, "{value} in {lo}..{hi}");
+            }
+        }
+    }
+
+    #[test]
+    fn fallback_widens_corner_values() {
+        let plane = hermite_from_fn(|p: Vec3| 2.0 * p.x, |_| Vec3::X);
+        let fallback = FallbackInterval::new(&plane, 2.0);
+        let (lo, hi) = fallback.sample_interval(Vec3::ZERO, Vec3::ONE);
+
+        // The corners span `[0, 2]`, widened by twice half the diagonal.
+        let pad = 3f32.sqrt();
+        assert!(lo <= -pad && lo > -pad - 1e-3);
+        assert!(hi >= 2.0 + pad && hi < 2.0 + pad + 1e-3);
+
+        let hole = hermite_from_fn(|p: Vec3| 1.0 / p.x, |_| Vec3::X);
+        let (lo, hi) = FallbackInterval::new(&hole, 1.0).sample_interval(Vec3::ZERO, Vec3::ONE);
+        assert_eq!((lo, hi), (f32::NEG_INFINITY, f32::INFINITY));
+    }
+}
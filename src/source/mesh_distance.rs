@@ -0,0 +1,400 @@
+//! Signed distances to a closed triangle mesh, to bake it into a
+//! [`GridSource`](super::GridSource).
+//!
+//! Distances are found with a bounding volume hierarchy over the faces, and
+//! signs with the angle-weighted pseudonormal of the closest feature, i.e., the
+//! face, edge or vertex the closest point lies on (Bærentzen and Aanæs, 2005).
+//! Unlike ray parity, that's exact for any point of a closed, consistently
+//! oriented mesh, however close to the surface.
+
+use crate::collections::Map;
+use alloc::vec::Vec;
+use glam::Vec3;
+
+/// The most faces in a leaf of the hierarchy.
+const LEAF_SIZE: usize = 2;
+
+/// The deepest the hierarchy can get: splits halve the faces of a node, so
+/// that's enough for any number of faces indexed by `u32`.
+const MAX_DEPTH: usize = 64;
+
+/// The part of a triangle its closest point to another point lies on.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Feature {
+    /// The corner of the given index.
+    Vertex(usize),
+    /// The edge from the corner of the given index to the next one.
+    Edge(usize),
+    Face,
+}
+
+/// Returns the closest point of a triangle to `point`, with the feature it
+/// lies on, following Ericson's Real-Time Collision Detection (5.1.5).
+///
+/// The triangle must not be degenerate.
+fn closest_point(point: Vec3, [a, b, c]: [Vec3; 3]) -> (Vec3, Feature) {
+    let (ab, ac) = (b - a, c - a);
+
+    let ap = point - a;
+    let (d1, d2) = (ab.dot(ap), ac.dot(ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return (a, Feature::Vertex(0));
+    }
+
+    let bp = point - b;
+    let (d3, d4) = (ab.dot(bp), ac.dot(bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return (b, Feature::Vertex(1));
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return (a + ab * (d1 / (d1 - d3)), Feature::Edge(0));
+    }
+
+    let cp = point - c;
+    let (d5, d6) = (ab.dot(cp), ac.dot(cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return (c, Feature::Vertex(2));
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return (a + ac * (d2 / (d2 - d6)), Feature::Edge(2));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 >= d3 && d5 >= d6 {
+        let t = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return (b + (c - b) * t, Feature::Edge(1));
+    }
+
+    let denom = 1.0 / (va + vb + vc);
+    (a + ab * (vb * denom) + ac * (vc * denom), Feature::Face)
+}
+
+/// Returns the key of an undirected edge.
+fn edge_key(a: u32, b: u32) -> (u32, u32) {
+    (a.min(b), a.max(b))
+}
+
+/// Returns the squared distance from `point` to the box between `min` and
+/// `max`, or 0 if it's inside.
+fn box_distance_squared(point: Vec3, min: Vec3, max: Vec3) -> f32 {
+    (min - point)
+        .max(point - max)
+        .max(Vec3::ZERO)
+        .length_squared()
+}
+
+/// A node of the hierarchy, bounding `len` consecutive triangles from
+/// `start` if it's a leaf, or its children at `start` and `start + 1`
+/// otherwise, in which case `len` is 0.
+#[derive(Debug, Copy, Clone)]
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    start: u32,
+    len: u32,
+}
+
+/// A face of the mesh, in the order of the leaves of the hierarchy.
+#[derive(Debug, Copy, Clone)]
+struct Triangle {
+    points: [Vec3; 3],
+    face: u32,
+}
+
+/// The signed distance field of a closed triangle mesh, negative inside.
+///
+/// The faces must be consistently wound counter-clockwise around their
+/// outward normals, as extracted by default.
+pub(crate) struct MeshDistance<'a> {
+    faces: &'a [[u32; 3]],
+    nodes: Vec<Node>,
+    triangles: Vec<Triangle>,
+    face_normals: Vec<Vec3>,
+    edge_normals: Map<(u32, u32), Vec3>,
+    vertex_normals: Vec<Vec3>,
+}
+
+impl<'a> MeshDistance<'a> {
+    /// Builds the hierarchy and the pseudonormals of a mesh.
+    ///
+    /// Degenerate faces are left out: the points of such a face lie on its
+    /// longest edge, which is shared with another face of a closed mesh.
+    pub(crate) fn new(positions: &[Vec3], faces: &'a [[u32; 3]]) -> Self {
+        let points = |face: &[u32; 3]| face.map(|i| positions[i as usize]);
+
+        let mut face_normals = Vec::with_capacity(faces.len());
+        let mut edge_normals: Map<(u32, u32), Vec3> = Map::default();
+        let mut vertex_normals = alloc::vec![Vec3::ZERO; positions.len()];
+
+        for face in faces {
+            let p = points(face);
+            let normal = (p[1] - p[0]).cross(p[2] - p[0]).normalize_or_zero();
+            face_normals.push(normal);
+
+            if normal == Vec3::ZERO {
+                continue;
+            }
+
+            for i in 0..3 {
+                let (next, prev) = ((i + 1) % 3, (i + 2) % 3);
+                let angle = (p[next] - p[i]).angle_between(p[prev] - p[i]);
+                vertex_normals[face[i] as usize] += normal * angle;
+                *edge_normals
+                    .entry(edge_key(face[i], face[next]))
+                    .or_default() += normal;
+            }
+        }
+
+        let triangles: Vec<Triangle> = faces
+            .iter()
+            .zip(&face_normals)
+            .enumerate()
+            .filter(|(_, (_, normal))| **normal != Vec3::ZERO)
+            .map(|(i, (face, _))| Triangle {
+                points: points(face),
+                face: i as u32,
+            })
+            .collect();
+
+        let (nodes, triangles) = build_hierarchy(triangles);
+
+        Self {
+            faces,
+            nodes,
+            triangles,
+            face_normals,
+            edge_normals,
+            vertex_normals,
+        }
+    }
+
+    /// Returns the signed distance from `point` to the mesh, or infinity if
+    /// it has no faces.
+    ///
+    /// `hint` is the index of a triangle close to `point`, e.g., the closest
+    /// one to a nearby point, the distance to which bounds the search from
+    /// the start. It's updated to the closest triangle.
+    pub(crate) fn signed_distance(&self, point: Vec3, hint: &mut usize) -> f32 {
+        if self.triangles.is_empty() {
+            return f32::INFINITY;
+        }
+
+        let (closest, index, feature) = self.closest(point, (*hint).min(self.triangles.len() - 1));
+        *hint = index;
+
+        let face_index = self.triangles[index].face as usize;
+        let face = self.faces[face_index];
+        let normal = match feature {
+            Feature::Face => self.face_normals[face_index],
+            Feature::Edge(i) => self.edge_normals[&edge_key(face[i], face[(i + 1) % 3])],
+            Feature::Vertex(i) => self.vertex_normals[face[i] as usize],
+        };
+
+        let offset = point - closest;
+        let distance = offset.length();
+
+        if offset.dot(normal) < 0.0 {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    /// Returns the closest point of the mesh to `point`, with the index of
+    /// its triangle and the feature it lies on, starting from the triangle
+    /// at `hint` and visiting the nearer child of every node first.
+    fn closest(&self, point: Vec3, hint: usize) -> (Vec3, usize, Feature) {
+        let (closest, feature) = closest_point(point, self.triangles[hint].points);
+        let mut best_squared = point.distance_squared(closest);
+        let mut best = (closest, hint, feature);
+
+        // Nodes to visit, with their squared distances from the point.
+        let mut stack = [(0u32, 0.0f32); MAX_DEPTH + 1];
+        let mut len = 1;
+
+        while len > 0 {
+            len -= 1;
+            let (index, distance_squared) = stack[len];
+
+            if distance_squared >= best_squared {
+                continue;
+            }
+
+            let node = &self.nodes[index as usize];
+
+            if node.len > 0 {
+                let start = node.start as usize;
+
+                for i in start..start + node.len as usize {
+                    let (closest, feature) = closest_point(point, self.triangles[i].points);
+                    let distance_squared = point.distance_squared(closest);
+
+                    if distance_squared < best_squared {
+                        best_squared = distance_squared;
+                        best = (closest, i, feature);
+                    }
+                }
+
+                continue;
+            }
+
+            let [near, far] = [node.start, node.start + 1].map(|child| {
+                let child_node = &self.nodes[child as usize];
+                (
+                    child,
+                    box_distance_squared(point, child_node.min, child_node.max),
+                )
+            });
+
+            // The nearer child is popped first.
+            let (first, second) = if near.1 <= far.1 {
+                (near, far)
+            } else {
+                (far, near)
+            };
+
+            stack[len] = second;
+            stack[len + 1] = first;
+            len += 2;
+        }
+
+        best
+    }
+}
+
+/// Builds a bounding volume hierarchy by splitting the triangles of every
+/// node at the median of their centroids along the longest axis, returning
+/// the nodes and the triangles in the order of the leaves.
+fn build_hierarchy(mut triangles: Vec<Triangle>) -> (Vec<Node>, Vec<Triangle>) {
+    let mut nodes = Vec::new();
+
+    if triangles.is_empty() {
+        return (nodes, triangles);
+    }
+
+    let centroid = |t: &Triangle| t.points.iter().sum::<Vec3>() / 3.0;
+    let placeholder = Node {
+        min: Vec3::ZERO,
+        max: Vec3::ZERO,
+        start: 0,
+        len: 0,
+    };
+
+    nodes.push(placeholder);
+    let mut pending = alloc::vec![(0, 0, triangles.len())];
+
+    while let Some((node, start, end)) = pending.pop() {
+        let range = &mut triangles[start..end];
+
+        let (mut min, mut max) = (Vec3::INFINITY, Vec3::NEG_INFINITY);
+        let (mut centroid_min, mut centroid_max) = (Vec3::INFINITY, Vec3::NEG_INFINITY);
+
+        for triangle in range.iter() {
+            for point in triangle.points {
+                (min, max) = (min.min(point), max.max(point));
+            }
+
+            let c = centroid(triangle);
+            (centroid_min, centroid_max) = (centroid_min.min(c), centroid_max.max(c));
+        }
+
+        if range.len() <= LEAF_SIZE {
+            nodes[node] = Node {
+                min,
+                max,
+                start: start as u32,
+                len: range.len() as u32,
+            };
+            continue;
+        }
+
+        let extent = centroid_max - centroid_min;
+        let axis = if extent.x >= extent.y.max(extent.z) {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let mid = range.len() / 2;
+        range.select_nth_unstable_by(mid, |a, b| centroid(a)[axis].total_cmp(&centroid(b)[axis]));
+
+        let children = nodes.len();
+        nodes.extend([placeholder; 2]);
+        nodes[node] = Node {
+            min,
+            max,
+            start: children as u32,
+            len: 0,
+        };
+
+        pending.push((children, start, start + mid));
+        pending.push((children + 1, start + mid, end));
+    }
+
+    (nodes, triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The unit cube, with outward faces.
+    fn cube() -> (Vec<Vec3>, Vec<[u32; 3]>) {
+        let positions = (0..8)
+            .map(|i| Vec3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32))
+            .collect();
+        let faces = alloc::vec![
+            [0, 2, 1],
+            [1, 2, 3],
+            [4, 5, 6],
+            [5, 7, 6],
+            [0, 1, 4],
+            [1, 5, 4],
+            [2, 6, 3],
+            [3, 6, 7],
+            [0, 4, 2],
+            [2, 4, 6],
+            [1, 3, 5],
+            [3, 7, 5],
+        ];
+
+        (positions, faces)
+    }
+
+    #[test]
+    fn cube_distances() {
+        let (positions, faces) = cube();
+        let mesh = MeshDistance::new(&positions, &faces);
+
+        // Closest to a face, an edge and a vertex, inside and outside.
+        let cases = [
+            (Vec3::new(0.5, 0.5, 0.5), -0.5),
+            (Vec3::new(0.5, 0.4, 0.1), -0.1),
+            (Vec3::new(0.5, 0.5, 1.25), 0.25),
+            (Vec3::new(-0.3, 0.5, -0.4), 0.5),
+            (Vec3::new(1.2, 1.4, 1.2), Vec3::new(0.2, 0.4, 0.2).length()),
+            (Vec3::new(2.0, 1.0, 1.0), 1.0),
+        ];
+
+        // From every triangle, including out of range hints.
+        for (point, expected) in cases {
+            for mut hint in 0..=faces.len() {
+                let distance = mesh.signed_distance(point, &mut hint);
+                assert!((distance - expected).abs() < 1e-6, "{point} {distance}");
+            }
+        }
+
+        // Just off the diagonal of a face, where the two faces on it are
+        // about as close as the edge.
+        let distance = mesh.signed_distance(Vec3::new(0.5, 0.5 + 1e-6, -1e-3), &mut 0);
+        assert!((distance - 1e-3).abs() < 1e-6, "{distance}");
+
+        let empty = MeshDistance::new(&positions, &[]);
+        assert_eq!(empty.signed_distance(Vec3::ZERO, &mut 0), f32::INFINITY);
+    }
+}
@@ -0,0 +1,426 @@
+//! Procedural noise sources with analytic gradients.
+
+use super::{HermiteFromFn, HermiteSource, Source};
+use glam::{IVec3, Vec3};
+
+/// A source whose gradient is known, rather than only its direction.
+///
+/// Unlike [`HermiteSource::gradient`], which may default to a unit normal,
+/// the gradient is exact, which is what combining sources through the chain
+/// rule requires.
+pub trait GradientSource: Source {
+    fn sample_gradient(&self, point: Vec3) -> Vec3;
+
+    /// Samples the value and the gradient at once, which is cheaper for
+    /// sources computing both together.
+    fn sample_with_gradient(&self, point: Vec3) -> (f32, Vec3) {
+        (self.sample(point), self.sample_gradient(point))
+    }
+}
+
+impl<F: Fn(Vec3) -> f32, G: Fn(Vec3) -> Vec3> GradientSource for HermiteFromFn<F, G> {
+    fn sample_gradient(&self, point: Vec3) -> Vec3 {
+        (self.gradient)(point)
+    }
+}
+
+/// Seedable value noise in `[-1, 1]`, interpolating random values at the
+/// points of an integer lattice with a quintic fade, so that it has a
+/// continuous gradient.
+///
+/// Points are mapped to the lattice as `point * frequency + offset`. The
+/// gradient vanishes at lattice points, so the lattice shouldn't line up
+/// with the octree: the default offset is derived from the seed, and is
+/// never a multiple of a power of two.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ValueNoise3 {
+    seed: u32,
+    frequency: f32,
+    offset: Vec3,
+}
+
+impl ValueNoise3 {
+    pub fn new(seed: u32) -> Self {
+        // Values in `(0, 1)` with their lowest bit set, so that they aren't
+        // on any dyadic grid coarser than `2^-24`.
+        let offset = [0, 1, 2].map(|axis| {
+            let bits = hash(IVec3::splat(axis), seed ^ 0x9e37_79b9) >> 8 | 1;
+            bits as f32 / (1 << 24) as f32
+        });
+
+        Self {
+            seed,
+            frequency: 1.0,
+            offset: Vec3::from_array(offset),
+        }
+    }
+
+    /// Sets the number of lattice cells per unit length. Defaults to 1.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        assert!(
+            frequency.is_finite() && frequency > 0.0,
+            "`frequency` must be finite and greater than 0"
+        );
+
+        self.frequency = frequency;
+        self
+    }
+
+    /// Sets the offset of the lattice, in lattice cells.
+    pub fn with_offset(mut self, offset: Vec3) -> Self {
+        assert!(offset.is_finite(), "`offset` must be finite");
+        self.offset = offset;
+        self
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    pub fn offset(&self) -> Vec3 {
+        self.offset
+    }
+
+    fn lattice_value(&self, point: IVec3) -> f32 {
+        hash(point, self.seed) as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+impl Source for ValueNoise3 {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.sample_with_gradient(point).0
+    }
+}
+
+impl GradientSource for ValueNoise3 {
+    fn sample_gradient(&self, point: Vec3) -> Vec3 {
+        self.sample_with_gradient(point).1
+    }
+
+    fn sample_with_gradient(&self, point: Vec3) -> (f32, Vec3) {
+        let p = point * self.frequency + self.offset;
+        let cell = p.floor();
+        let t = p - cell;
+        let cell = cell.as_ivec3();
+
+        // Quintic fade and its derivative.
+        let s = t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+        let ds = t * t * (t * (t - 2.0) + 1.0) * 30.0;
+
+        let v = |x, y, z| self.lattice_value(cell + IVec3::new(x, y, z));
+        let (v000, v100, v010, v110) = (v(0, 0, 0), v(1, 0, 0), v(0, 1, 0), v(1, 1, 0));
+        let (v001, v101, v011, v111) = (v(0, 0, 1), v(1, 0, 1), v(0, 1, 1), v(1, 1, 1));
+
+        // Trilinear interpolation, expanded as a polynomial in `s`.
+        let k1 = v100 - v000;
+        let k2 = v010 - v000;
+        let k3 = v001 - v000;
+        let k4 = v000 - v100 - v010 + v110;
+        let k5 = v000 - v010 - v001 + v011;
+        let k6 = v000 - v100 - v001 + v101;
+        let k7 = -v000 + v100 + v010 - v110 + v001 - v101 - v011 + v111;
+
+        let value = v000
+            + k1 * s.x
+            + k2 * s.y
+            + k3 * s.z
+            + k4 * s.x * s.y
+            + k5 * s.y * s.z
+            + k6 * s.z * s.x
+            + k7 * s.x * s.y * s.z;
+
+        let gradient = Vec3::new(
+            k1 + k4 * s.y + k6 * s.z + k7 * s.y * s.z,
+            k2 + k5 * s.z + k4 * s.x + k7 * s.z * s.x,
+            k3 + k6 * s.x + k5 * s.y + k7 * s.x * s.y,
+        ) * ds
+            * self.frequency;
+
+        (value, gradient)
+    }
+}
+
+impl HermiteSource for ValueNoise3 {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.sample_gradient(point)
+    }
+}
+
+/// Fractal Brownian motion: a sum of octaves of [`ValueNoise3`] of
+/// increasing frequency and decreasing amplitude, normalized to `[-1, 1]`.
+///
+/// Every octave has its own seed, hence its own lattice offset, so that the
+/// lattices of successive octaves don't line up either.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct FbmNoise {
+    base: ValueNoise3,
+    octaves: u32,
+    lacunarity: f32,
+    gain: f32,
+}
+
+impl FbmNoise {
+    /// Creates a new `FbmNoise` whose first octave is `base`, with a
+    /// lacunarity of 2 and a gain of 0.5.
+    pub fn new(base: ValueNoise3, octaves: u32) -> Self {
+        assert!(octaves > 0, "`octaves` must be greater than 0");
+
+        Self {
+            base,
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    /// Sets the frequency ratio between successive octaves.
+    pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+        assert!(
+            lacunarity.is_finite() && lacunarity > 0.0,
+            "`lacunarity` must be finite and greater than 0"
+        );
+
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Sets the amplitude ratio between successive octaves.
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        assert!(
+            gain.is_finite() && gain > 0.0,
+            "`gain` must be finite and greater than 0"
+        );
+
+        self.gain = gain;
+        self
+    }
+
+    /// Returns the octaves along with their amplitudes, which sum to 1.
+    fn octaves(&self) -> impl Iterator<Item = (ValueNoise3, f32)> + '_ {
+        let mut frequency = self.base.frequency;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+
+        for _ in 0..self.octaves {
+            total += amplitude;
+            amplitude *= self.gain;
+        }
+
+        amplitude = 1.0 / total;
+
+        (0..self.octaves).map(move |i| {
+            let octave = if i == 0 {
+                self.base
+            } else {
+                ValueNoise3::new(self.base.seed.wrapping_add(i)).with_frequency(frequency)
+            };
+
+            let item = (octave, amplitude);
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+            item
+        })
+    }
+}
+
+impl Source for FbmNoise {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.octaves()
+            .map(|(octave, amplitude)| amplitude * octave.sample(point))
+            .sum()
+    }
+}
+
+impl GradientSource for FbmNoise {
+    fn sample_gradient(&self, point: Vec3) -> Vec3 {
+        self.sample_with_gradient(point).1
+    }
+
+    fn sample_with_gradient(&self, point: Vec3) -> (f32, Vec3) {
+        self.octaves().fold(
+            (0.0, Vec3::ZERO),
+            |(value, gradient), (octave, amplitude)| {
+                let (v, g) = octave.sample_with_gradient(point);
+                (value + amplitude * v, gradient + amplitude * g)
+            },
+        )
+    }
+}
+
+impl HermiteSource for FbmNoise {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.sample_gradient(point)
+    }
+}
+
+/// Perturbs a base source by a noise field: its value is
+/// `base + amplitude * noise`, and its gradient follows by the chain rule.
+///
+/// The result is generally no longer a distance field, even if the base is,
+/// since the noise adds up to `amplitude` times its own Lipschitz constant to
+/// the slope. [`DualContouring::assume_lipschitz`] isn't safe to enable
+/// unless the result is scaled back accordingly.
+///
+/// [`DualContouring::assume_lipschitz`]: crate::DualContouring::assume_lipschitz
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Displace<S, N> {
+    base: S,
+    noise: N,
+    amplitude: f32,
+}
+
+impl<S, N> Displace<S, N> {
+    pub fn new(base: S, noise: N, amplitude: f32) -> Self {
+        assert!(amplitude.is_finite(), "`amplitude` must be finite");
+        Self {
+            base,
+            noise,
+            amplitude,
+        }
+    }
+}
+
+impl<S: Source, N: Source> Source for Displace<S, N> {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.base.sample(point) + self.amplitude * self.noise.sample(point)
+    }
+
+    fn is_defined(&self, point: Vec3) -> bool {
+        self.base.is_defined(point) && self.noise.is_defined(point)
+    }
+}
+
+impl<S: GradientSource, N: GradientSource> GradientSource for Displace<S, N> {
+    fn sample_gradient(&self, point: Vec3) -> Vec3 {
+        self.sample_with_gradient(point).1
+    }
+
+    fn sample_with_gradient(&self, point: Vec3) -> (f32, Vec3) {
+        let (base, base_gradient) = self.base.sample_with_gradient(point);
+        let (noise, noise_gradient) = self.noise.sample_with_gradient(point);
+
+        (
+            base + self.amplitude * noise,
+            base_gradient + self.amplitude * noise_gradient,
+        )
+    }
+}
+
+impl<S: GradientSource, N: GradientSource> HermiteSource for Displace<S, N> {
+    fn gradient(&self, point: Vec3) -> Vec3 {
+        self.sample_gradient(point)
+    }
+}
+
+/// Hashes a lattice point to a well-mixed integer.
+fn hash(p: IVec3, seed: u32) -> u32 {
+    let mut h = seed
+        .wrapping_mul(0x27d4_eb2d)
+        .wrapping_add((p.x as u32).wrapping_mul(0x8da6_b343))
+        .wrapping_add((p.y as u32).wrapping_mul(0xd816_3841))
+        .wrapping_add((p.z as u32).wrapping_mul(0xcb1a_b31f));
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+    use std::collections::HashMap;
+
+    /// Deterministic points in `[-2, 2]³`.
+    fn points() -> impl Iterator<Item = Vec3> {
+        (0..500).map(|i| {
+            let c = [0, 1, 2].map(|axis| hash(IVec3::new(i, axis, 0), 7) as f32 / u32::MAX as f32);
+            Vec3::from_array(c) * 4.0 - 2.0
+        })
+    }
+
+    fn assert_gradient_matches(source: &impl GradientSource) {
+        const H: f32 = 1e-3;
+
+        for point in points() {
+            let gradient = source.sample_gradient(point);
+            let numeric = Vec3::from_array([Vec3::X, Vec3::Y, Vec3::Z].map(|axis| {
+                let central = |h: f32| {
+                    (source.sample(point + axis * h) - source.sample(point - axis * h)) / (2.0 * h)
+                };
+
+                // Richardson extrapolation cancels out the leading error term,
+                // which the higher octaves would otherwise make significant.
+                (4.0 * central(H) - central(2.0 * H)) / 3.0
+            }));
+
+            assert!(
+                (gradient - numeric).length() <= 1e-3 * gradient.length().max(1.0),
+                "{gradient} != {numeric} at {point}"
+            );
+        }
+    }
+
+    #[test]
+    fn gradients_match_central_differences() {
+        let noise = ValueNoise3::new(1).with_frequency(1.5);
+        assert_gradient_matches(&noise);
+
+        let fbm = FbmNoise::new(noise, 3);
+        assert_gradient_matches(&fbm);
+
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 1.0, |p| p.normalize());
+        assert_gradient_matches(&Displace::new(sphere, fbm, 0.1));
+    }
+
+    #[test]
+    fn noise_is_bounded_and_seeded() {
+        let a = FbmNoise::new(ValueNoise3::new(1), 4);
+        let b = FbmNoise::new(ValueNoise3::new(2), 4);
+
+        for point in points() {
+            assert!(a.sample(point).abs() <= 1.0);
+        }
+
+        assert!(points().any(|p| a.sample(p) != b.sample(p)));
+        assert_eq!(a.sample(Vec3::ONE), a.sample(Vec3::ONE));
+    }
+
+    #[test]
+    fn displaced_sphere_is_closed() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.6, |p| p.normalize_or_zero());
+        let fbm = FbmNoise::new(ValueNoise3::new(3).with_frequency(2.0), 4);
+        let source = Displace::new(sphere, fbm, 0.1);
+
+        // Corners within the tolerance of the surface are attributed to a
+        // single edge, which can leave holes around them, and the noise
+        // makes such corners common unless the tolerance is tight.
+        let mut mesh = IndexedSeparateNormals::default();
+        DualContouring::new(&source, 64, 1e-6)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &source))
+            .unwrap();
+
+        assert!(!mesh.faces.is_empty());
+        assert!(mesh.positions().iter().all(|p| p.is_finite()));
+        assert!(mesh.normals().iter().all(|n| n.is_finite()));
+
+        // Every undirected edge is shared by exactly two faces.
+        let mut edges = HashMap::new();
+
+        for face in &mesh.faces {
+            for i in 0..3 {
+                let (a, b) = (face[i], face[(i + 1) % 3]);
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+
+        assert!(edges.values().all(|&count| count == 2));
+    }
+}
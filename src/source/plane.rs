@@ -0,0 +1,122 @@
+use super::{magnitude, widen, HermiteSource, IntervalSource, Source};
+use glam::Vec3;
+
+/// The exact signed distance field of the half-space below a plane, i.e., of
+/// the points `p` where `normal.dot(p) <= offset`, with a unit `normal`
+/// pointing out of it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Plane {
+    normal: Vec3,
+    offset: f32,
+}
+
+impl Plane {
+    /// Creates the plane of the points `p` where `normal.dot(p) == offset`,
+    /// after normalizing `normal`, and scaling `offset` along.
+    ///
+    /// # Panics
+    ///
+    /// If `normal` can't be normalized or `offset` isn't finite.
+    pub fn new(normal: Vec3, offset: f32) -> Self {
+        let length = normal.length();
+        assert!(
+            length.is_finite() && length > 0.0,
+            "`normal` must be finite and nonzero"
+        );
+        assert!(offset.is_finite(), "`offset` must be finite");
+
+        Self {
+            normal: normal / length,
+            offset: offset / length,
+        }
+    }
+
+    /// Creates the plane through `point` with normal `normal`, see
+    /// [`Plane::new`].
+    pub fn from_point_normal(point: Vec3, normal: Vec3) -> Self {
+        Self::new(normal, normal.dot(point))
+    }
+
+    /// Returns the unit normal, pointing out of the half-space.
+    pub fn normal(&self) -> Vec3 {
+        self.normal
+    }
+
+    /// Returns the signed distance of the plane from the origin, along the
+    /// normal.
+    pub fn offset(&self) -> f32 {
+        self.offset
+    }
+}
+
+impl Source for Plane {
+    fn sample(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) - self.offset
+    }
+
+    fn lipschitz_bound(&self) -> Option<f32> {
+        Some(1.0)
+    }
+}
+
+impl HermiteSource for Plane {
+    fn gradient(&self, _point: Vec3) -> Vec3 {
+        self.normal
+    }
+}
+
+impl IntervalSource for Plane {
+    /// Returns the values at the corners of the box closest to and farthest
+    /// from the half-space, which bound the values of a linear field.
+    fn sample_interval(&self, min: Vec3, max: Vec3) -> (f32, f32) {
+        let value = self.sample((min + max) / 2.0);
+        let spread = self.normal.abs().dot(max - min) / 2.0;
+        let magnitude = magnitude(min, max).max(self.offset.abs());
+        widen(value - spread, value + spread, 0.0, magnitude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::distance_check::check_distance_field;
+    use crate::source::rng::Rng;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn distances_and_gradients() {
+        let plane = Plane::from_point_normal(Vec3::new(0.0, 2.0, 0.0), Vec3::new(0.0, 3.0, 4.0));
+
+        assert_eq!(plane.normal(), Vec3::new(0.0, 0.6, 0.8));
+        assert_eq!(plane.offset(), 1.2);
+        assert_eq!(plane.sample(Vec3::new(5.0, 2.0, 0.0)), 0.0);
+        assert!((plane.sample(Vec3::new(0.0, 2.6, 0.8)) - 1.0).abs() < 1e-6);
+        assert_eq!(plane.gradient(Vec3::ZERO), Vec3::new(0.0, 0.6, 0.8));
+    }
+
+    #[test]
+    fn distance_field() {
+        let mut rng = Rng(0x2545_f491);
+        let normal = rng.point(-1.0, 1.0).normalize();
+        let plane = Plane::new(normal, 0.3);
+        let (u, v) = normal.any_orthonormal_pair();
+        let origin = normal * plane.offset();
+
+        // A patch around the feet of the points, which are all in it.
+        let spacing = 0.02;
+        let steps = (-100..=100).map(|k| k as f32 * spacing);
+        let surface: Vec<Vec3> = steps
+            .clone()
+            .flat_map(|a| steps.clone().map(move |b| origin + u * a + v * b))
+            .collect();
+
+        let points: Vec<Vec3> = (rng.far_points(200).into_iter())
+            .map(|p| {
+                let (a, b) = (rng.range(-1.0, 1.0), rng.range(-1.0, 1.0));
+                origin + u * a + v * b + normal * p.x
+            })
+            .collect();
+
+        check_distance_field(&plane, &surface, spacing, &points);
+    }
+}
@@ -0,0 +1,35 @@
+//! A xorshift generator, for reproducible random points in tests.
+
+use alloc::vec::Vec;
+use glam::Vec3;
+
+pub(super) struct Rng(pub u32);
+
+impl Rng {
+    pub fn next(&mut self) -> u32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0
+    }
+
+    /// Returns a number in `[lo, hi)`.
+    pub fn range(&mut self, lo: f32, hi: f32) -> f32 {
+        lo + (hi - lo) * (self.next() >> 8) as f32 / (1 << 24) as f32
+    }
+
+    pub fn point(&mut self, lo: f32, hi: f32) -> Vec3 {
+        Vec3::new(self.range(lo, hi), self.range(lo, hi), self.range(lo, hi))
+    }
+
+    /// Returns `count` points around the origin, from within a unit of it to
+    /// a hundred units away, where naive distance fields tend to break.
+    pub fn far_points(&mut self, count: usize) -> Vec<Vec3> {
+        (0..count)
+            .map(|k| {
+                let scale = [1.0, 3.0, 10.0, 100.0][k % 4];
+                self.point(-scale, scale)
+            })
+            .collect()
+    }
+}
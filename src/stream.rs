@@ -0,0 +1,378 @@
+use crate::extractor::{
+    Extractor, IndexedSeparateNormals, Orientation, OutputConvention, ParallelExtractor,
+};
+use crate::source::HermiteSource;
+use glam::Vec3;
+use std::io::{self, Read, Write};
+
+/// Identifies the chunked mesh format, including its version.
+const MAGIC: [u8; 8] = *b"DCMESH\0\x01";
+
+/// Number of records buffered before a chunk is written out.
+const CHUNK_LEN: usize = 4096;
+
+const VERTEX_CHUNK: u8 = b'V';
+const FACE_CHUNK: u8 = b'F';
+const END_CHUNK: u8 = b'E';
+
+/// Size of a vertex record (position and normal) and of a face record.
+const VERTEX_SIZE: usize = 24;
+const FACE_SIZE: usize = 12;
+
+/// Counts of the elements written by a [`StreamingWriter`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct MeshStats {
+    pub vertices: u64,
+    pub faces: u64,
+}
+
+/// An extractor writing the mesh to a stream in a simple chunked binary
+/// format as it's extracted, which [`IndexedSeparateNormals::read_chunked`]
+/// reads back.
+///
+/// The stream starts with an 8-byte magic number, followed by chunks made of
+/// a tag byte and a little-endian `u32` record count. Vertex chunks (`V`)
+/// hold positions and normals as six `f32`, and face chunks (`F`) hold three
+/// `u32` vertex indices. The final chunk (`E`) holds the total vertex and
+/// face counts as two `u64` instead. All values are little-endian.
+///
+/// Unlike [`ObjWriter`], this doesn't retain any vertex: only the chunk being
+/// filled and the running counts are kept in memory. Writes are made one
+/// chunk at a time, so the writer needn't be buffered.
+///
+/// I/O errors can't be reported while extracting, so the first one is kept,
+/// further writes are skipped, and the extraction fails with
+/// [`ExtractSurfaceError::Io`] once it's over.
+///
+/// [`ObjWriter`]: crate::ObjWriter
+/// [`ExtractSurfaceError::Io`]: crate::ExtractSurfaceError::Io
+#[derive(Debug)]
+pub struct StreamingWriter<W, S> {
+    writer: W,
+    source: S,
+    convention: OutputConvention,
+    /// The tag and the records of the chunk being filled.
+    chunk: Option<(u8, Vec<u8>)>,
+    records: usize,
+    stats: MeshStats,
+    started: bool,
+    finished: bool,
+    error: Option<io::Error>,
+}
+
+impl<W: Write, S> StreamingWriter<W, S> {
+    pub fn new(writer: W, source: S) -> Self {
+        Self::new_with(writer, source, OutputConvention::default())
+    }
+
+    pub fn new_with(writer: W, source: S, convention: OutputConvention) -> Self {
+        Self {
+            writer,
+            source,
+            convention,
+            chunk: None,
+            records: 0,
+            stats: MeshStats::default(),
+            started: false,
+            finished: false,
+            error: None,
+        }
+    }
+
+    /// Writes out what's left of the mesh, if the extraction didn't already,
+    /// and returns the number of elements written, or the first error
+    /// encountered while writing.
+    pub fn finish(mut self) -> io::Result<MeshStats> {
+        if !self.finished {
+            self.finish_stream();
+        }
+
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.stats),
+        }
+    }
+
+    fn push_record(&mut self, tag: u8, record: &[u8]) {
+        if self.chunk.as_ref().is_some_and(|(t, _)| *t != tag) || self.records == CHUNK_LEN {
+            self.flush_chunk();
+        }
+
+        let (_, buf) = self
+            .chunk
+            .get_or_insert_with(|| (tag, Vec::with_capacity(CHUNK_LEN * record.len())));
+        buf.extend_from_slice(record);
+        self.records += 1;
+    }
+
+    fn flush_chunk(&mut self) {
+        let records = std::mem::take(&mut self.records) as u32;
+
+        if let Some((tag, buf)) = self.chunk.take() {
+            self.write(|w| {
+                w.write_all(&[tag])?;
+                w.write_all(&records.to_le_bytes())?;
+                w.write_all(&buf)
+            });
+        }
+    }
+
+    fn finish_stream(&mut self) {
+        self.finished = true;
+        self.flush_chunk();
+
+        let MeshStats { vertices, faces } = self.stats;
+        self.write(|w| {
+            w.write_all(&[END_CHUNK])?;
+            w.write_all(&vertices.to_le_bytes())?;
+            w.write_all(&faces.to_le_bytes())?;
+            w.flush()
+        });
+    }
+
+    fn write(&mut self, f: impl FnOnce(&mut W) -> io::Result<()>) {
+        if self.error.is_some() {
+            return;
+        }
+
+        if !self.started {
+            self.started = true;
+            self.error = self.writer.write_all(&MAGIC).err();
+        }
+
+        if self.error.is_none() {
+            self.error = f(&mut self.writer).err();
+        }
+    }
+}
+
+impl<W: Write, S: HermiteSource> Extractor for StreamingWriter<W, S> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        let record = vertex_record(&self.source, &self.convention, position);
+        self.push_record(VERTEX_CHUNK, &record);
+        self.stats.vertices += 1;
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        let record = face_record(orient_face(&self.convention, face));
+        self.push_record(FACE_CHUNK, &record);
+        self.stats.faces += 1;
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if !self.finished {
+            self.finish_stream();
+        }
+
+        // The error is kept for `StreamingWriter::finish`.
+        match &self.error {
+            Some(error) => Err(io::Error::new(error.kind(), error.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<W: Write, S: HermiteSource + Clone> ParallelExtractor for StreamingWriter<W, S> {
+    type Local = LocalStreamingWriter<S>;
+
+    fn make_local(&self) -> Self::Local {
+        LocalStreamingWriter {
+            source: self.source.clone(),
+            convention: self.convention,
+            vertices: Vec::new(),
+            faces: Vec::new(),
+        }
+    }
+
+    fn merge(&mut self, local: Self::Local, vertex_offset: u32) {
+        for record in local.vertices.chunks_exact(VERTEX_SIZE) {
+            self.push_record(VERTEX_CHUNK, record);
+            self.stats.vertices += 1;
+        }
+
+        for face in local.faces {
+            self.push_record(FACE_CHUNK, &face_record(face.map(|i| i + vertex_offset)));
+            self.stats.faces += 1;
+        }
+    }
+
+    fn finish_merged(&mut self) -> io::Result<()> {
+        Extractor::finish(self)
+    }
+}
+
+/// The local extractor of [`StreamingWriter`], keeping the records in memory
+/// until they're merged, and written out in order.
+pub struct LocalStreamingWriter<S> {
+    source: S,
+    convention: OutputConvention,
+    vertices: Vec<u8>,
+    /// Faces in the output convention, indexing the local vertices.
+    faces: Vec<[u32; 3]>,
+}
+
+impl<S: HermiteSource> Extractor for LocalStreamingWriter<S> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        let record = vertex_record(&self.source, &self.convention, position);
+        self.vertices.extend_from_slice(&record);
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        self.faces.push(orient_face(&self.convention, face));
+    }
+}
+
+/// Encodes a vertex, along with the normal of `source` there.
+fn vertex_record(
+    source: &impl HermiteSource,
+    convention: &OutputConvention,
+    position: Vec3,
+) -> [u8; VERTEX_SIZE] {
+    let normal = source.sample_normal(position);
+    let mut record = [0; VERTEX_SIZE];
+
+    let vertex = [
+        convention.transform(position),
+        convention.transform_normal(normal),
+    ];
+
+    for (i, v) in vertex.into_iter().enumerate() {
+        for (j, c) in v.to_array().into_iter().enumerate() {
+            let offset = 4 * (3 * i + j);
+            record[offset..offset + 4].copy_from_slice(&c.to_le_bytes());
+        }
+    }
+
+    record
+}
+
+/// Maps a face wound counter-clockwise in the source's coordinate system to
+/// the output convention.
+fn orient_face(convention: &OutputConvention, mut face: [u32; 3]) -> [u32; 3] {
+    // A mirroring flip reverses the winding, and so does facing inward.
+    if convention.flip_axis.is_some() != (convention.orientation == Orientation::Inward) {
+        face.reverse();
+    }
+
+    convention.wind(face)
+}
+
+fn face_record(face: [u32; 3]) -> [u8; FACE_SIZE] {
+    let mut record = [0; FACE_SIZE];
+
+    for (k, i) in face.into_iter().enumerate() {
+        record[4 * k..4 * k + 4].copy_from_slice(&i.to_le_bytes());
+    }
+
+    record
+}
+
+impl IndexedSeparateNormals {
+    /// Reads a mesh written by a [`StreamingWriter`].
+    pub fn read_chunked<R: Read>(mut reader: R) -> io::Result<Self> {
+        let invalid = |message| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+
+        if magic != MAGIC {
+            return Err(invalid("not a chunked mesh"));
+        }
+
+        let mut mesh = IndexedSeparateNormals::default();
+        let mut buf = Vec::new();
+
+        loop {
+            let mut tag = [0];
+            reader.read_exact(&mut tag)?;
+
+            let record_size = match tag[0] {
+                VERTEX_CHUNK => VERTEX_SIZE,
+                FACE_CHUNK => FACE_SIZE,
+                END_CHUNK => break,
+                _ => return Err(invalid("unknown chunk")),
+            };
+
+            let mut count = [0; 4];
+            reader.read_exact(&mut count)?;
+
+            buf.resize(u32::from_le_bytes(count) as usize * record_size, 0);
+            reader.read_exact(&mut buf)?;
+
+            let words = buf
+                .chunks_exact(4)
+                .map(|word| <[u8; 4]>::try_from(word).unwrap());
+
+            if tag[0] == VERTEX_CHUNK {
+                let floats: Vec<f32> = words.map(f32::from_le_bytes).collect();
+
+                for record in floats.chunks_exact(6) {
+                    mesh.vertices.push(
+                        Vec3::from_slice(&record[..3]),
+                        Vec3::from_slice(&record[3..]),
+                    );
+                }
+            } else {
+                let indices: Vec<u32> = words.map(u32::from_le_bytes).collect();
+
+                mesh.faces
+                    .extend(indices.chunks_exact(3).map(|f| [f[0], f[1], f[2]]));
+            }
+        }
+
+        let mut totals = [0; 16];
+        reader.read_exact(&mut totals)?;
+        let vertices = u64::from_le_bytes(totals[..8].try_into().unwrap());
+        let faces = u64::from_le_bytes(totals[8..].try_into().unwrap());
+
+        if vertices != mesh.vertices.positions.len() as u64 || faces != mesh.faces.len() as u64 {
+            return Err(invalid("the element counts don't match the chunks"));
+        }
+
+        Ok(mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{AxisKind, DualContouring, Winding, WithIndexedSeparateNormals};
+
+    #[test]
+    fn streamed_sphere_matches_in_memory() {
+        let sphere = hermite_from_fn(|p: Vec3| p.length() - 0.7, |p| p);
+        let dc = DualContouring::new(&sphere, 64, 1e-4);
+
+        for convention in [
+            OutputConvention::default(),
+            OutputConvention::new(Winding::Cw, Some(AxisKind::X)),
+            OutputConvention::new(Winding::Cw, Some(AxisKind::X))
+                .with_orientation(Orientation::Inward),
+        ] {
+            let mut expected = IndexedSeparateNormals::default();
+            dc.extract(WithIndexedSeparateNormals::new_with(
+                &mut expected,
+                &sphere,
+                convention,
+            ))
+            .unwrap();
+
+            let mut bytes = Vec::new();
+            let mut writer = StreamingWriter::new_with(&mut bytes, &sphere, convention);
+            dc.extract(&mut writer).unwrap();
+            let stats = writer.finish().unwrap();
+
+            assert_eq!(stats.vertices, expected.positions().len() as u64);
+            assert_eq!(stats.faces, expected.triangle_count() as u64);
+
+            let mesh = IndexedSeparateNormals::read_chunked(&bytes[..]).unwrap();
+            assert_eq!(mesh.positions(), expected.positions());
+            assert_eq!(mesh.normals(), expected.normals());
+            assert_eq!(mesh.faces, expected.faces);
+
+            // Truncated streams are rejected.
+            assert!(IndexedSeparateNormals::read_chunked(&bytes[..bytes.len() - 1]).is_err());
+        }
+    }
+}
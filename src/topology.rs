@@ -1,6 +1,9 @@
-use crate::geom::{AxisKind, CornerKind, EdgeKind, FaceKind};
+use crate::geom::{AxisKind, BMask3, Bounds, CornerKind, EdgeKind, FaceKind};
 use crate::morton::MortonKey;
-use iter_seq::{AsSequence, ConstLen, Sequence};
+use crate::source::Source;
+use core::array;
+use glam::{UVec3, Vec3};
+use iter_seq::{AsSequence, ConstLen, ConstMaxLen, ConstMinLen, Sequence};
 
 /// An octree node/cell.
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -18,18 +21,82 @@ impl OctreeCell {
         }
     }
 
+    /// Returns the root cell, covering the whole domain.
+    pub fn root() -> Self {
+        OctreeCell(MortonKey::root())
+    }
+
     /// Retrieves the MortonKey code ("key") corresponding this cell.
     pub fn key(&self) -> MortonKey {
         self.0
     }
 
+    /// Returns the depth of this cell, the root being at level 0.
+    pub fn level(&self) -> u32 {
+        self.0.level()
+    }
+
+    /// Returns whether this cell contains `point`, given the box covered by
+    /// the root.
+    ///
+    /// Cells are half-open, except along the upper faces of the domain, so
+    /// every point of the domain is contained in exactly one cell of each
+    /// level, and points outside of it in none.
+    pub fn contains_point(&self, domain: &Bounds, point: Vec3) -> bool {
+        if !domain.contains(point) {
+            return false;
+        }
+
+        let cells = (1u32 << self.level()) as f32;
+        let grid = (point - domain.min) / domain.size() * cells;
+        let coords = grid.floor().as_uvec3().min(UVec3::splat(cells as u32 - 1));
+        coords == self.0.coords()
+    }
+
+    /// Returns the world-space box covered by this cell, given the box
+    /// covered by the root.
+    pub fn bounds(&self, domain: &Bounds) -> Bounds {
+        self.0.cell_bounds(domain)
+    }
+
+    /// Returns the world-space positions of the corners of this cell, in the
+    /// order of [`CornerKind::ALL`], given the box covered by the root.
+    pub fn corner_positions(&self, domain: &Bounds) -> [Vec3; 8] {
+        let bounds = self.bounds(domain);
+        CornerKind::ALL.map(|corner| bounds.corner(corner))
+    }
+
+    /// Returns the world-space endpoints of the edges of this cell, in the
+    /// order of [`EdgeKind::ALL`], given the box covered by the root.
+    pub fn edge_segments(&self, domain: &Bounds) -> [(Vec3, Vec3); 12] {
+        let bounds = self.bounds(domain);
+
+        EdgeKind::ALL.map(|edge| {
+            let [start, end] = edge.endpoints();
+            (bounds.corner(start), bounds.corner(end))
+        })
+    }
+
     /// Retrieves the sub-cell of this cell.
     ///
     /// This method does not distinguish between interior and leaf cells,
     /// so the caller must ensure that `self` is not a leaf to preserve
     /// the expected behavior.
     fn sub_cell(&self, corner: CornerKind) -> OctreeCell {
-        OctreeCell(self.0.child(corner.0))
+        let key = self.0.checked_child(corner.0);
+        OctreeCell(key.expect("cells at `MortonKey::LEVELS` can't be subdivided"))
+    }
+
+    /// Retrieves the sub-cell of this cell, or `self` if it's a leaf.
+    fn descend<L>(&self, corner: CornerKind, is_leaf: &mut L) -> OctreeCell
+    where
+        L: FnMut(&OctreeCell) -> bool,
+    {
+        if is_leaf(self) {
+            *self
+        } else {
+            self.sub_cell(corner)
+        }
     }
 
     /// Returns the children of this octree cell.
@@ -37,10 +104,12 @@ impl OctreeCell {
     /// This method does not distinguish between interior and leaf cells, so
     /// the returned array will always contain 8 elements.
     #[inline]
-    pub fn sub_cells(&self) -> impl Sequence<Item = OctreeCell> + ConstLen<8> + use<'_> {
-        CornerKind::ALL
-            .as_sequence()
-            .map(|corner| self.sub_cell(*corner))
+    pub fn sub_cells(&self) -> CellSeq<impl Sequence<Item = OctreeCell> + ConstLen<8> + use<'_>> {
+        CellSeq(
+            CornerKind::ALL
+                .as_sequence()
+                .map(|corner| self.sub_cell(*corner)),
+        )
     }
 
     /// Returns this cell's interior faces, i.e., those between the
@@ -48,10 +117,14 @@ impl OctreeCell {
     ///
     /// This method does not distinguish between interior and leaf cells, so
     /// the returned iterator will always yield 12 elements.
-    pub fn interior_faces(&self) -> impl Sequence<Item = OctreeFace> + ConstLen<12> + use<'_> {
-        EdgeKind::ALL
-            .as_sequence()
-            .map(|edge| OctreeFace::from_edge(*self, *edge))
+    pub fn interior_faces(
+        &self,
+    ) -> CellSeq<impl Sequence<Item = OctreeFace> + ConstLen<12> + use<'_>> {
+        CellSeq(
+            EdgeKind::ALL
+                .as_sequence()
+                .map(|edge| OctreeFace::from_edge(*self, *edge)),
+        )
     }
 
     /// Returns this cell's interior edges, i.e., those adjacent to 4 sub-cells
@@ -59,15 +132,12 @@ impl OctreeCell {
     ///
     /// This method does not distinguish between interior and leaf cells, so
     /// the returned iterator will always yield 6 elements.
-    pub fn interior_edges(&self) -> impl Sequence<Item = Edge> + ConstLen<6> + use<'_> {
-        FaceKind::ALL
-            .as_sequence()
-            .map(|face| Edge::from_face(self, *face))
-    }
-
-    /// Retrieves the sub-cells of this cell adjacent to the given face.
-    fn face_sub_cells(&self, face: FaceKind) -> [OctreeCell; 4] {
-        face.corners().map(|corner| self.sub_cell(corner))
+    pub fn interior_edges(&self) -> CellSeq<impl Sequence<Item = Edge> + ConstLen<6> + use<'_>> {
+        CellSeq(
+            FaceKind::ALL
+                .as_sequence()
+                .map(|face| Edge::from_face(self, *face)),
+        )
     }
 
     /// Retrieves the sub-cells of this cell adjacent to the given edge.
@@ -76,6 +146,147 @@ impl OctreeCell {
     }
 }
 
+/// The sub-cells, interior faces or interior edges of a cell, returned by
+/// [`OctreeCell::sub_cells`], [`OctreeCell::interior_faces`] and
+/// [`OctreeCell::interior_edges`].
+///
+/// This is a [`Sequence`] of known length, which can also be consumed as a
+/// standard iterator, e.g., by a `for` loop, without importing `iter_seq`.
+#[derive(Debug, Copy, Clone)]
+pub struct CellSeq<S>(S);
+
+impl<S: Sequence> CellSeq<S> {
+    /// Converts this sequence into an iterator over its items.
+    ///
+    /// This takes precedence over [`Sequence::into_iter`], which is otherwise
+    /// ambiguous with [`IntoIterator::into_iter`] when `Sequence` is in scope.
+    #[allow(clippy::should_implement_trait)]
+    pub fn into_iter(self) -> S::Iter {
+        self.0.into_iter()
+    }
+
+    /// Returns an iterator over the items, like [`CellSeq::into_iter`].
+    pub fn iter(self) -> S::Iter {
+        self.0.into_iter()
+    }
+}
+
+impl<S: Sequence> Sequence for CellSeq<S> {
+    type Item = S::Item;
+    type Iter = S::Iter;
+
+    fn into_iter(self) -> S::Iter {
+        self.0.into_iter()
+    }
+}
+
+// SAFETY: the wrapped sequence yields the same items.
+unsafe impl<S: ConstMinLen<N>, const N: usize> ConstMinLen<N> for CellSeq<S> {}
+
+// SAFETY: the wrapped sequence yields the same items.
+unsafe impl<S: ConstMaxLen<N>, const N: usize> ConstMaxLen<N> for CellSeq<S> {}
+
+impl<S: Sequence> IntoIterator for CellSeq<S> {
+    type Item = S::Item;
+    type IntoIter = S::Iter;
+
+    fn into_iter(self) -> S::Iter {
+        self.0.into_iter()
+    }
+}
+
+/// Returns the corners of a cell adjacent to the 4 cells around an edge along
+/// `axis`, with the `axis` bit set to `half`.
+///
+/// The corners are listed counter-clockwise when looking against `axis`, so
+/// the `i`-th corner also encodes the position of the `i`-th cell around the
+/// edge along the two other axes.
+fn ring(axis: AxisKind, half: bool) -> [CornerKind; 4] {
+    let [_, positive] = axis.faces();
+
+    positive
+        .corners()
+        .map(|corner| CornerKind(corner.0.with(axis, half)))
+}
+
+/// Returns the two axes orthogonal to `axis`, in the order `X`, `Y`, `Z`.
+const fn orthogonal_axes(axis: AxisKind) -> [AxisKind; 2] {
+    match axis {
+        AxisKind::X => [AxisKind::Y, AxisKind::Z],
+        AxisKind::Y => [AxisKind::X, AxisKind::Z],
+        AxisKind::Z => [AxisKind::X, AxisKind::Y],
+    }
+}
+
+/// Returns the position of the cell around an edge along `axis` at the given
+/// corner of [`ring`], as indexed by [`Edge::neighbors_by_position`].
+fn position_index(axis: AxisKind, corner: CornerKind) -> usize {
+    let [low, high] = orthogonal_axes(axis);
+    corner.0.has(low) as usize | ((corner.0.has(high) as usize) << 1)
+}
+
+/// Returns the index in [`EdgeKind::ALL`] of the edge of the cell at
+/// `position` around an edge along `axis` (see
+/// [`Edge::neighbors_by_position`]) which that edge lies on.
+///
+/// The edge lies on the opposite side of the cell from its position.
+pub(crate) fn edge_kind_index(axis: AxisKind, position: usize) -> usize {
+    let [low, high] = orthogonal_axes(axis);
+    let start = BMask3::O
+        .with(low, position & 1 == 0)
+        .with(high, position & 2 == 0);
+
+    EdgeKind::ALL
+        .iter()
+        .position(|edge| edge.axis() == axis && edge.endpoints()[0].0 == start)
+        .unwrap()
+}
+
+/// Returns the order in which to connect the vertices of the 4 cells around
+/// a minimal edge along `axis` into a quad, as positions around the edge
+/// (see [`Edge::neighbors_by_position`]).
+///
+/// The cells are listed counter-clockwise when looking against `axis`, so
+/// the quad faces along `axis`, as it must when the inside of the surface
+/// is towards the start of the edge. With `sign_flip`, i.e., when the
+/// inside is towards the end, the order is reversed. Either way, the quad is
+/// never self-crossing.
+pub fn edge_quad_order(axis: AxisKind, sign_flip: bool) -> [usize; 4] {
+    let mut order = ring(axis, false).map(|corner| position_index(axis, corner));
+
+    if sign_flip {
+        order.reverse();
+    }
+
+    order
+}
+
+/// Samples the corners of the cube with the minimum corner `cell_origin` and
+/// edges of length `cell_size`, and returns whether each is inside of the
+/// surface, in the order of [`CornerKind::ALL`].
+///
+/// Exact zeros are on the side given by [`Source::zero_is_inside`], and
+/// undefined values count as outside.
+pub fn corner_signs(source: &impl Source, cell_origin: Vec3, cell_size: f32) -> [bool; 8] {
+    let bounds = Bounds::new(cell_origin, cell_origin + cell_size);
+    bounds_corner_signs(source, &bounds)
+}
+
+/// Like [`corner_signs`], for the corners of `bounds` exactly.
+pub(crate) fn bounds_corner_signs(source: &impl Source, bounds: &Bounds) -> [bool; 8] {
+    let zeros = source.zero_is_inside();
+
+    CornerKind::ALL.map(|corner| {
+        let point = bounds.corner(corner);
+        zeros.is_inside(source.sample(point)) && source.is_defined(point)
+    })
+}
+
+/// Returns the mask of the two axes orthogonal to `axis`.
+fn orthogonal_mask(axis: AxisKind) -> BMask3 {
+    BMask3::XYZ.with(axis, false)
+}
+
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct OctreeFace {
     normal: AxisKind,
@@ -89,11 +300,79 @@ impl OctreeFace {
         Self { normal, neighbors }
     }
 
-    pub fn sub_faces<L>(&self, _is_leaf: L) -> Option<[OctreeFace; 4]>
+    /// Returns the axis orthogonal to this face.
+    pub fn normal(&self) -> AxisKind {
+        self.normal
+    }
+
+    /// Returns the cells on both sides of this face, the one with the lower
+    /// coordinate along the normal axis being first.
+    pub fn neighbors(&self) -> [OctreeCell; 2] {
+        self.neighbors
+    }
+
+    /// Splits this face into the 4 faces between the sub-cells of its
+    /// neighbors.
+    ///
+    /// Leaf neighbors stand in for their own sub-cells. Returns `None` if both
+    /// neighbors are leaves.
+    pub fn sub_faces<L>(&self, mut is_leaf: L) -> Option<[OctreeFace; 4]>
+    where
+        L: FnMut(&OctreeCell) -> bool,
+    {
+        let [a, b] = self.neighbors;
+
+        if is_leaf(&a) && is_leaf(&b) {
+            return None;
+        }
+
+        let [negative, _] = self.normal.faces();
+
+        Some(negative.corners().map(|corner| OctreeFace {
+            normal: self.normal,
+            neighbors: [
+                a.descend(CornerKind(corner.0.with(self.normal, true)), &mut is_leaf),
+                b.descend(corner, &mut is_leaf),
+            ],
+        }))
+    }
+
+    /// Returns the 4 edges lying on this face and adjacent to the sub-cells of
+    /// its neighbors.
+    ///
+    /// Leaf neighbors stand in for their own sub-cells. Returns `None` if both
+    /// neighbors are leaves.
+    pub fn sub_edges<L>(&self, mut is_leaf: L) -> Option<[Edge; 4]>
     where
         L: FnMut(&OctreeCell) -> bool,
     {
-        todo!()
+        let [a, b] = self.neighbors;
+
+        if is_leaf(&a) && is_leaf(&b) {
+            return None;
+        }
+
+        let u = self.normal.next();
+        let v = u.next();
+
+        let edge = |axis: AxisKind, half: bool, is_leaf: &mut L| {
+            let neighbors = ring(axis, half).map(|corner| {
+                // The position along the face normal selects the neighbor,
+                // the sub-cell of which must be on the face.
+                let neighbor = if corner.0.has(self.normal) { b } else { a };
+                let corner = CornerKind(corner.0.toggle(self.normal.to_mask()));
+                neighbor.descend(corner, is_leaf)
+            });
+
+            Edge::new(axis, neighbors)
+        };
+
+        Some([
+            edge(u, false, &mut is_leaf),
+            edge(u, true, &mut is_leaf),
+            edge(v, false, &mut is_leaf),
+            edge(v, true, &mut is_leaf),
+        ])
     }
 }
 
@@ -110,14 +389,213 @@ impl Edge {
 
     fn from_face(cell: &OctreeCell, face: FaceKind) -> Edge {
         let axis = face.normal_axis();
-        let neighbors = cell.face_sub_cells(face);
+        let half = face == axis.faces()[1];
+        let neighbors = ring(axis, half).map(|corner| cell.sub_cell(corner));
         Self { axis, neighbors }
     }
 
-    pub fn sub_edges<L>(&self, _is_leaf: L) -> Option<[Edge; 2]>
+    /// Returns the axis this edge is parallel to.
+    pub fn axis(&self) -> AxisKind {
+        self.axis
+    }
+
+    /// Returns the 4 cells around this edge, listed counter-clockwise when
+    /// looking against the edge axis.
+    ///
+    /// Leaf cells coarser than the edge may appear more than once.
+    pub fn neighbors(&self) -> [OctreeCell; 4] {
+        self.neighbors
+    }
+
+    /// Returns the 4 cells around this edge, indexed by their position
+    /// along the two other axes: the `i`-th cell is on the positive side of
+    /// the first of them (in the order `X`, `Y`, `Z`) iff bit 0 of `i` is
+    /// set, and of the second iff bit 1 is.
+    pub fn neighbors_by_position(&self) -> [OctreeCell; 4] {
+        let mut cells = self.neighbors;
+
+        for (cell, corner) in self.neighbors.iter().zip(ring(self.axis, false)) {
+            cells[position_index(self.axis, corner)] = *cell;
+        }
+
+        cells
+    }
+
+    /// Splits this edge in halves along its axis.
+    ///
+    /// Leaf neighbors stand in for their own sub-cells. Returns `None` if all
+    /// neighbors are leaves, i.e., if this is a minimal edge.
+    pub fn sub_edges<L>(&self, mut is_leaf: L) -> Option<[Edge; 2]>
     where
         L: FnMut(&OctreeCell) -> bool,
     {
-        todo!()
+        if self.neighbors.iter().all(&mut is_leaf) {
+            return None;
+        }
+
+        let orthogonal = orthogonal_mask(self.axis);
+
+        Some([false, true].map(|half| {
+            let ring = ring(self.axis, half);
+
+            let neighbors = array::from_fn(|i| {
+                // The edge lies on the opposite side of each neighbor from
+                // its position around the edge.
+                let corner = CornerKind(ring[i].0.toggle(orthogonal));
+                self.neighbors[i].descend(corner, &mut is_leaf)
+            });
+
+            Edge::new(self.axis, neighbors)
+        }))
+    }
+
+    /// Returns the key identifying this edge among the minimal edges of an
+    /// octree, along with its axis.
+    ///
+    /// This is the key of the node of the same size as the edge whose minimum
+    /// corner is the start of the edge, which need not be a cell of the
+    /// octree. Keys order edges by size, then in Morton order.
+    pub fn key(&self) -> MortonKey {
+        let (cell, start) = self.start();
+        let coords = cell.key().coords() + UVec3::from(start.to_bvec());
+        MortonKey::from_coords(coords, cell.key().level())
+    }
+
+    /// Returns the world-space endpoints of this edge, given the box covered
+    /// by the root.
+    ///
+    /// The extent of the edge is that of its smallest neighbor, so this is
+    /// only meaningful for minimal edges.
+    pub fn segment(&self, domain: &Bounds) -> [Vec3; 2] {
+        let (cell, start) = self.start();
+        let end = start.with(self.axis, true);
+
+        let bounds = cell.bounds(domain);
+        [
+            bounds.corner(CornerKind(start)),
+            bounds.corner(CornerKind(end)),
+        ]
+    }
+
+    /// Returns the smallest neighbor of this edge, along with its corner the
+    /// edge starts at.
+    fn start(&self) -> (OctreeCell, BMask3) {
+        let (index, cell) = self
+            .neighbors
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, cell)| cell.key().level())
+            .unwrap();
+
+        let start = ring(self.axis, false)[index]
+            .0
+            .toggle(orthogonal_mask(self.axis));
+
+        (*cell, start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn points_are_in_one_cell_per_level() {
+        let domain = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let root = OctreeCell::root();
+        let children = CornerKind::ALL.map(|corner| root.sub_cell(corner));
+        let grandchildren = children.map(|cell| cell.sub_cell(CornerKind::ALL[7]));
+
+        let points = [
+            Vec3::ZERO,
+            Vec3::splat(1.0),
+            Vec3::splat(-1.0),
+            Vec3::new(0.5, -0.25, 0.75),
+        ];
+
+        for point in points {
+            assert!(root.contains_point(&domain, point));
+            assert_eq!(root.level(), 0);
+
+            let containing = children
+                .iter()
+                .filter(|cell| cell.contains_point(&domain, point))
+                .count();
+            assert_eq!(containing, 1);
+        }
+
+        // The center belongs to the upper octant, as cells are half-open.
+        assert!(children[7].contains_point(&domain, Vec3::ZERO));
+        assert!(grandchildren[7].contains_point(&domain, Vec3::splat(1.0)));
+        assert_eq!(grandchildren[7].level(), 2);
+        assert!(!root.contains_point(&domain, Vec3::new(0.0, 1.5, 0.0)));
+    }
+
+    /// Returns the unit vector along `axis`.
+    fn unit(axis: AxisKind) -> Vec3 {
+        Vec3::from(axis.to_mask().to_bvec()).normalize()
+    }
+
+    #[test]
+    fn edge_quads_are_convex_and_face_outward() {
+        for axis in AxisKind::ALL {
+            let [low, high] = orthogonal_axes(axis);
+
+            // The centers of the cells around an edge along `axis` through
+            // the origin, by position.
+            let centers: [Vec3; 4] = core::array::from_fn(|i| {
+                let side = |bit: usize| if i & bit != 0 { 0.5 } else { -0.5 };
+                unit(low) * side(1) + unit(high) * side(2) + unit(axis) * 0.5
+            });
+
+            for sign_flip in [false, true] {
+                let quad = edge_quad_order(axis, sign_flip).map(|i| centers[i]);
+
+                let mut sorted = edge_quad_order(axis, sign_flip);
+                sorted.sort();
+                assert_eq!(sorted, [0, 1, 2, 3]);
+
+                // Every corner turns the same way, along the expected normal.
+                let outward = if sign_flip { -unit(axis) } else { unit(axis) };
+
+                for i in 0..4 {
+                    let [a, b, c] = [0, 1, 2].map(|j| quad[(i + j) % 4]);
+                    let turn = (b - a).cross(c - b);
+                    assert!(turn.dot(outward) > 0.0, "{axis:?} {sign_flip}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cell_sequences_are_iterators() {
+        let root = OctreeCell::root();
+        let cells: Vec<OctreeCell> = root.sub_cells().into_iter().collect();
+        assert_eq!(cells, root.sub_cells().collect_array::<8>());
+
+        let mut count = 0;
+        for face in root.interior_faces() {
+            assert!(face.neighbors().iter().all(|cell| cells.contains(cell)));
+            count += 1;
+        }
+        assert_eq!(count, 12);
+        assert_eq!(root.interior_edges().iter().count(), 6);
+    }
+
+    #[test]
+    fn neighbors_by_position() {
+        let domain = Bounds::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+
+        for edge in OctreeCell::root().interior_edges() {
+            let [low, high] = orthogonal_axes(edge.axis());
+            let [start, _] = edge.segment(&domain);
+
+            for (i, cell) in edge.neighbors_by_position().iter().enumerate() {
+                let offset = cell.bounds(&domain).center() - start;
+                assert_eq!(offset.dot(unit(low)) > 0.0, i & 1 != 0);
+                assert_eq!(offset.dot(unit(high)) > 0.0, i & 2 != 0);
+            }
+        }
     }
 }
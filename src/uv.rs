@@ -0,0 +1,320 @@
+//! Texture coordinates of extracted meshes, projected from the positions
+//! once at extraction time rather than per pixel, e.g., to bake lightmaps.
+
+use crate::collections::Map;
+use crate::extractor::{plane_normal, Extractor, FinishError, WithIndexedSeparateNormals};
+use crate::geom::AxisKind;
+use crate::morton::MortonKey;
+use crate::source::HermiteSource;
+use crate::IndexedSeparateNormals;
+use alloc::vec::Vec;
+use core::fmt;
+use glam::{Vec2, Vec3};
+
+/// How [`WithUvs`] computes the texture coordinates of a vertex.
+#[derive(Copy, Clone)]
+pub enum UvMode<'a> {
+    /// Projects along the axis of the largest component of the normal, as
+    /// the dominant projection of triplanar mapping: the `yz`, `zx` or `xy`
+    /// components of the position for `X`, `Y` and `Z` respectively.
+    TriplanarDominant,
+    /// Projects along the given axis, like [`UvMode::TriplanarDominant`].
+    PlanarAxis(AxisKind),
+    /// Calls the closure with the position and the normal of the vertex.
+    Custom(&'a dyn Fn(Vec3, Vec3) -> Vec2),
+}
+
+impl UvMode<'_> {
+    fn uv(&self, position: Vec3, normal: Vec3, scale: f32) -> Vec2 {
+        match self {
+            UvMode::TriplanarDominant => project(position, dominant_axis(normal)) * scale,
+            UvMode::PlanarAxis(axis) => project(position, *axis) * scale,
+            UvMode::Custom(f) => f(position, normal),
+        }
+    }
+}
+
+impl fmt::Debug for UvMode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UvMode::TriplanarDominant => f.write_str("TriplanarDominant"),
+            UvMode::PlanarAxis(axis) => f.debug_tuple("PlanarAxis").field(axis).finish(),
+            UvMode::Custom(_) => f.debug_tuple("Custom").finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Returns the axis of the largest component of `normal` in magnitude, the
+/// first one on ties.
+fn dominant_axis(normal: Vec3) -> AxisKind {
+    let normal = normal.abs();
+
+    AxisKind::ALL.into_iter().fold(AxisKind::X, |max, axis| {
+        if normal[axis as usize] > normal[max as usize] {
+            axis
+        } else {
+            max
+        }
+    })
+}
+
+/// Projects a position along `axis`, onto the two other axes in cyclic
+/// order.
+fn project(position: Vec3, axis: AxisKind) -> Vec2 {
+    let u = axis.next();
+    Vec2::new(position[u as usize], position[u.next() as usize])
+}
+
+/// Wraps a [`WithIndexedSeparateNormals`], computing the texture coordinates
+/// of the vertices it extracts with a [`UvMode`].
+///
+/// The coordinates are appended to a vector parallel to the positions of the
+/// mesh once the extraction finishes, in the output coordinate system, so
+/// that normals computed from the faces are known.
+///
+/// With [`UvMode::TriplanarDominant`], the faces around a vertex may be
+/// projected along another axis than the vertex, which stretches the texture
+/// across them. [`with_seam_splitting`] avoids that by duplicating such
+/// vertices once per axis, at the cost of disconnecting the mesh along the
+/// seams.
+///
+/// [`with_seam_splitting`]: WithUvs::with_seam_splitting
+pub struct WithUvs<'a, S> {
+    inner: WithIndexedSeparateNormals<'a, S>,
+    uvs: &'a mut Vec<Vec2>,
+    mode: UvMode<'a>,
+    scale: f32,
+    split_seams: bool,
+}
+
+impl<'a, S> WithUvs<'a, S> {
+    /// Creates the extractor, with a scale of 1 and without seam splitting.
+    ///
+    /// `uvs` must be parallel to the positions of the mesh `inner` extracts
+    /// into.
+    pub fn new(
+        mut inner: WithIndexedSeparateNormals<'a, S>,
+        uvs: &'a mut Vec<Vec2>,
+        mode: UvMode<'a>,
+    ) -> Self {
+        let (_, (vertex_offset, _)) = inner.buffer();
+        debug_assert_eq!(uvs.len(), vertex_offset);
+
+        Self {
+            inner,
+            uvs,
+            mode,
+            scale: 1.0,
+            split_seams: false,
+        }
+    }
+
+    /// Sets the factor projected positions are multiplied by, i.e., the
+    /// number of times the texture repeats per unit of length. It doesn't
+    /// apply to [`UvMode::Custom`].
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Sets whether every face is projected along the dominant axis of its
+    /// plane with [`UvMode::TriplanarDominant`], duplicating the vertices
+    /// projected along another axis. Other modes project all faces around a
+    /// vertex alike, so this has no effect on them.
+    pub fn with_seam_splitting(mut self, split_seams: bool) -> Self {
+        self.split_seams = split_seams;
+        self
+    }
+}
+
+impl<S: HermiteSource> Extractor for WithUvs<'_, S> {
+    fn extract_vertex(&mut self, position: Vec3) {
+        self.inner.extract_vertex(position);
+    }
+
+    fn extract_vertex_with_cell(&mut self, position: Vec3, cell: MortonKey) {
+        self.inner.extract_vertex_with_cell(position, cell);
+    }
+
+    fn extract_vertex_with_normal(&mut self, position: Vec3, normal: Vec3, cell: MortonKey) {
+        self.inner
+            .extract_vertex_with_normal(position, normal, cell);
+    }
+
+    fn extract_face(&mut self, face: [u32; 3]) {
+        self.inner.extract_face(face);
+    }
+
+    fn reserve(&mut self, vertices: usize, faces: usize) {
+        self.uvs.reserve(vertices);
+        self.inner.reserve(vertices, faces);
+    }
+
+    fn reserve_hint(&self, vertices: usize, faces: usize) -> usize {
+        vertices * size_of::<Vec2>() + self.inner.reserve_hint(vertices, faces)
+    }
+
+    fn should_continue(&self) -> bool {
+        self.inner.should_continue()
+    }
+
+    fn finish(&mut self) -> Result<(), FinishError> {
+        self.inner.finish()?;
+
+        let (mesh, (vertex_offset, face_offset)) = self.inner.buffer();
+        let IndexedSeparateNormals { vertices, faces } = mesh;
+        let (mode, scale) = (&self.mode, self.scale);
+
+        self.uvs.extend(
+            vertices.positions[vertex_offset..]
+                .iter()
+                .zip(&vertices.normals[vertex_offset..])
+                .map(|(&position, &normal)| mode.uv(position, normal, scale)),
+        );
+
+        if !self.split_seams || !matches!(mode, UvMode::TriplanarDominant) {
+            return Ok(());
+        }
+
+        // The copies of a vertex, by the axis they're projected along.
+        let mut copies: Map<(u32, u8), u32> = Map::default();
+
+        for face in &mut faces[face_offset..] {
+            let normal = plane_normal(&face.map(|i| vertices.positions[i as usize]));
+
+            // Degenerate faces have no plane, and keep their vertices.
+            if normal == Vec3::ZERO {
+                continue;
+            }
+
+            let axis = dominant_axis(normal);
+
+            for i in face {
+                let (position, normal) = (
+                    vertices.positions[*i as usize],
+                    vertices.normals[*i as usize],
+                );
+
+                if dominant_axis(normal) == axis {
+                    continue;
+                }
+
+                *i = *copies.entry((*i, axis as u8)).or_insert_with(|| {
+                    vertices.push(position, normal);
+                    self.uvs.push(project(position, axis) * scale);
+                    vertices.positions.len() as u32 - 1
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::DualContouring;
+
+    const CENTER: Vec3 = Vec3::new(0.03, -0.02, 0.01);
+
+    /// The exact distance field of an axis-aligned cube, with its gradient.
+    fn cube() -> impl HermiteSource {
+        hermite_from_fn(
+            |p: Vec3| {
+                let q = (p - CENTER).abs() - 0.5;
+                q.max_element().min(0.0) + q.max(Vec3::ZERO).length()
+            },
+            |p: Vec3| {
+                let offset = p - CENTER;
+                let q = offset.abs() - 0.5;
+                let outside = q.max(Vec3::ZERO);
+
+                if outside != Vec3::ZERO {
+                    return outside.normalize() * offset.signum();
+                }
+
+                let axis = dominant_axis(q + 1.0);
+                Vec3::AXES[axis as usize] * offset[axis as usize].signum()
+            },
+        )
+    }
+
+    fn extract_cube(mode: UvMode<'_>, split_seams: bool) -> (IndexedSeparateNormals, Vec<Vec2>) {
+        let cube = cube();
+        let mut mesh = IndexedSeparateNormals::default();
+        let mut uvs = Vec::new();
+        let extractor = WithUvs::new(
+            WithIndexedSeparateNormals::new(&mut mesh, &cube),
+            &mut uvs,
+            mode,
+        )
+        .with_scale(2.0)
+        .with_seam_splitting(split_seams);
+        DualContouring::new(&cube, 16, 1e-6)
+            .extract(extractor)
+            .unwrap();
+
+        assert_eq!(uvs.len(), mesh.positions().len());
+        (mesh, uvs)
+    }
+
+    /// Returns the faces lying on a face of the cube, with the axis of its
+    /// normal.
+    fn cube_faces(mesh: &IndexedSeparateNormals) -> Vec<([u32; 3], AxisKind)> {
+        mesh.faces
+            .iter()
+            .filter_map(|&face| {
+                let normal = plane_normal(&face.map(|i| mesh.positions()[i as usize]));
+                let axis = dominant_axis(normal);
+                let on_face = (normal.normalize().abs() - Vec3::AXES[axis as usize]).length();
+                (on_face < 1e-4).then_some((face, axis))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cube_faces_are_projected_alike() {
+        let (mesh, uvs) = extract_cube(UvMode::TriplanarDominant, true);
+        let faces = cube_faces(&mesh);
+        assert!(faces.len() > mesh.faces.len() / 2);
+
+        // Every face of the cube is projected along its normal, so that the
+        // coordinates are an affine function of the position.
+        for (face, axis) in faces {
+            for i in face.map(|i| i as usize) {
+                let expected = project(mesh.positions()[i], axis) * 2.0;
+                assert_eq!(uvs[i], expected, "{face:?}");
+            }
+        }
+
+        // Vertices on the edges of the cube are split, and those projected
+        // along the normal of the vertex otherwise disagree with their faces.
+        let (unsplit, unsplit_uvs) = extract_cube(UvMode::TriplanarDominant, false);
+        assert!(mesh.positions().len() > unsplit.positions().len());
+        assert!(cube_faces(&unsplit).iter().any(|(face, axis)| {
+            face.iter().any(|&i| {
+                let i = i as usize;
+                unsplit_uvs[i] != project(unsplit.positions()[i], *axis) * 2.0
+            })
+        }));
+    }
+
+    #[test]
+    fn planar_and_custom_modes() {
+        let (planar, uvs) = extract_cube(UvMode::PlanarAxis(AxisKind::Y), true);
+        for (position, uv) in planar.positions().iter().zip(&uvs) {
+            assert_eq!(*uv, Vec2::new(position.z, position.x) * 2.0);
+        }
+
+        let (mesh, uvs) = extract_cube(
+            UvMode::Custom(&|p: Vec3, n: Vec3| p.truncate() + n.truncate()),
+            true,
+        );
+        assert_eq!(mesh.positions(), planar.positions());
+        for ((position, normal), uv) in mesh.positions().iter().zip(mesh.normals()).zip(&uvs) {
+            assert_eq!(*uv, position.truncate() + normal.truncate());
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! Geometric checks and measurements on extracted meshes.
+
+use crate::collections::Map;
+use alloc::vec::Vec;
+use glam::{DVec3, Vec3};
+
+/// The reason a mesh couldn't be measured.
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum MeasureError {
+    /// The mesh has boundary edges, so it doesn't enclose a volume.
+    #[error("the mesh is open along {boundary_edge_count} boundary edges")]
+    OpenMesh { boundary_edge_count: usize },
+}
+
+/// Returns whether two triangles intersect, touching included.
+///
+/// This is a separating axis test, so it also handles coplanar and
+/// degenerate triangles.
+pub fn triangles_intersect(a: [Vec3; 3], b: [Vec3; 3]) -> bool {
+    let edges = |t: [Vec3; 3]| [t[1] - t[0], t[2] - t[1], t[0] - t[2]];
+    let (edges_a, edges_b) = (edges(a), edges(b));
+    let normals = [edges_a[0].cross(edges_a[1]), edges_b[0].cross(edges_b[1])];
+
+    let all_edges = edges_a.iter().chain(&edges_b);
+
+    // Face normals, edge-edge directions, and in-plane edge normals (for the
+    // coplanar and degenerate cases).
+    let mut axes = normals
+        .into_iter()
+        .chain(
+            edges_a
+                .iter()
+                .flat_map(|ea| edges_b.iter().map(move |eb| ea.cross(*eb))),
+        )
+        .chain(
+            normals
+                .iter()
+                .flat_map(|n| all_edges.clone().map(move |e| n.cross(*e))),
+        );
+
+    !axes.any(|axis| separates(axis, &a, &b))
+}
+
+/// Returns whether the segment between `start` and `end` intersects the
+/// triangle, touching included.
+pub fn segment_intersects_triangle(start: Vec3, end: Vec3, triangle: [Vec3; 3]) -> bool {
+    triangles_intersect(triangle, [start, end, end])
+}
+
+/// Returns whether two faces of an indexed mesh intersect anywhere other than
+/// along the vertices and edges they share.
+///
+/// Faces sharing an edge are assumed to only meet along it, which holds
+/// unless they're coplanar and folded over each other.
+pub fn faces_intersect(positions: &[Vec3], a: [u32; 3], b: [u32; 3]) -> bool {
+    let points = |face: [u32; 3]| face.map(|i| positions[i as usize]);
+    let shared = a.iter().filter(|v| b.contains(v)).count();
+
+    match shared {
+        0 => triangles_intersect(points(a), points(b)),
+        1 => {
+            // Two triangles sharing a vertex intersect elsewhere iff the edge
+            // opposite to it in one of them intersects the other.
+            let opposite = |face: [u32; 3], other: [u32; 3]| {
+                let i = face.iter().position(|v| other.contains(v)).unwrap();
+                let p = points(face);
+                (p[(i + 1) % 3], p[(i + 2) % 3])
+            };
+
+            let (a1, a2) = opposite(a, b);
+            let (b1, b2) = opposite(b, a);
+
+            segment_intersects_triangle(a1, a2, points(b))
+                || segment_intersects_triangle(b1, b2, points(a))
+        }
+        _ => false,
+    }
+}
+
+/// Finds all pairs of intersecting faces of an indexed mesh, as defined by
+/// [`faces_intersect`]. Each pair is reported once, with the smaller index
+/// first.
+pub fn find_self_intersections(positions: &[Vec3], faces: &[[u32; 3]]) -> Vec<[usize; 2]> {
+    let bounds: Vec<(Vec3, Vec3)> = faces
+        .iter()
+        .map(|face| {
+            let [a, b, c] = face.map(|i| positions[i as usize]);
+            (a.min(b).min(c), a.max(b).max(c))
+        })
+        .collect();
+
+    // Sweep along X, only testing faces whose bounding boxes overlap.
+    let mut order: Vec<usize> = (0..faces.len()).collect();
+    order.sort_by(|&i, &j| bounds[i].0.x.total_cmp(&bounds[j].0.x));
+
+    let mut pairs = Vec::new();
+
+    for (k, &i) in order.iter().enumerate() {
+        for &j in &order[k + 1..] {
+            if bounds[j].0.x > bounds[i].1.x {
+                break;
+            }
+
+            let overlap =
+                bounds[i].0.cmple(bounds[j].1).all() && bounds[j].0.cmple(bounds[i].1).all();
+
+            if overlap && faces_intersect(positions, faces[i], faces[j]) {
+                pairs.push([i.min(j), i.max(j)]);
+            }
+        }
+    }
+
+    pairs.sort();
+    pairs
+}
+
+/// Finds the boundary edges of an indexed mesh, i.e., the directed edges of
+/// faces not matched by an opposite edge of another face. An edge used
+/// several times is reported as many times as it's unmatched.
+///
+/// The edges are sorted, and a closed mesh has none.
+pub fn boundary_edges(faces: &[[u32; 3]]) -> Vec<[u32; 2]> {
+    let mut counts: Map<[u32; 2], isize> = Map::new();
+
+    for face in faces {
+        for i in 0..3 {
+            let (a, b) = (face[i], face[(i + 1) % 3]);
+            *counts.entry([a, b]).or_default() += 1;
+            *counts.entry([b, a]).or_default() -= 1;
+        }
+    }
+
+    let mut edges: Vec<[u32; 2]> = counts
+        .into_iter()
+        .flat_map(|(edge, excess)| core::iter::repeat_n(edge, excess.max(0) as usize))
+        .collect();
+
+    edges.sort();
+    edges
+}
+
+/// Returns the total area of the faces of an indexed mesh.
+pub fn surface_area(positions: &[Vec3], faces: &[[u32; 3]]) -> f64 {
+    faces
+        .iter()
+        .map(|face| triangle_area(face.map(|i| positions[i as usize])))
+        .sum()
+}
+
+/// Returns the area of a triangle, computed in `f64`.
+pub(crate) fn triangle_area(triangle: [Vec3; 3]) -> f64 {
+    let [a, b, c] = triangle.map(|p| p.as_dvec3());
+    (b - a).cross(c - a).length() / 2.0
+}
+
+/// Returns the signed volume of the tetrahedron formed by the origin and a
+/// triangle, computed in `f64`, which is positive if the triangle is wound
+/// counter-clockwise when looking at it from the side opposite the origin.
+pub(crate) fn tetrahedron_volume(triangle: [Vec3; 3]) -> f64 {
+    let [a, b, c] = triangle.map(|p| p.as_dvec3());
+    a.dot(b.cross(c)) / 6.0
+}
+
+/// Returns the volume enclosed by a closed indexed mesh, which is positive if
+/// its faces are wound counter-clockwise when looking at their outer side.
+///
+/// This sums the signed volumes of the tetrahedra formed by the origin and
+/// every face, which only adds up to the enclosed volume if the mesh is
+/// closed, so an open mesh is rejected with [`MeasureError::OpenMesh`].
+pub fn signed_volume(positions: &[Vec3], faces: &[[u32; 3]]) -> Result<f64, MeasureError> {
+    let boundary_edge_count = boundary_edges(faces).len();
+
+    if boundary_edge_count > 0 {
+        return Err(MeasureError::OpenMesh {
+            boundary_edge_count,
+        });
+    }
+
+    Ok(faces
+        .iter()
+        .map(|face| tetrahedron_volume(face.map(|i| positions[i as usize])))
+        .sum())
+}
+
+/// Returns the centroid of the surface of an indexed mesh, i.e., the mean of
+/// the centroids of its faces weighted by their areas.
+///
+/// Returns NaN if the mesh has no area.
+pub fn centroid(positions: &[Vec3], faces: &[[u32; 3]]) -> Vec3 {
+    let (moment, area) = faces
+        .iter()
+        .map(|face| {
+            let triangle = face.map(|i| positions[i as usize]);
+            let area = triangle_area(triangle);
+            let [a, b, c] = triangle.map(|p| p.as_dvec3());
+            ((a + b + c) * (area / 3.0), area)
+        })
+        .fold((DVec3::ZERO, 0.0), |(moment, total), (m, area)| {
+            (moment + m, total + area)
+        });
+
+    (moment / area).as_vec3()
+}
+
+fn separates(axis: Vec3, a: &[Vec3; 3], b: &[Vec3; 3]) -> bool {
+    if axis == Vec3::ZERO {
+        return false;
+    }
+
+    let project = |t: &[Vec3; 3]| {
+        let d = t.map(|p| p.dot(axis));
+        (d[0].min(d[1]).min(d[2]), d[0].max(d[1]).max(d[2]))
+    };
+
+    let (min_a, max_a) = project(a);
+    let (min_b, max_b) = project(b);
+    max_a < min_b || max_b < min_a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::hermite_from_fn;
+    use crate::{DualContouring, IndexedSeparateNormals, WithIndexedSeparateNormals};
+    use alloc::vec;
+
+    const T: [Vec3; 3] = [Vec3::ZERO, Vec3::X, Vec3::Y];
+
+    #[test]
+    fn piercing_triangles() {
+        let pierce = [
+            Vec3::new(0.2, 0.2, -1.0),
+            Vec3::new(0.2, 0.2, 1.0),
+            Vec3::new(0.3, 0.3, 1.0),
+        ];
+        assert!(triangles_intersect(T, pierce));
+
+        let beside = pierce.map(|p| p + Vec3::X * 2.0);
+        assert!(!triangles_intersect(T, beside));
+    }
+
+    #[test]
+    fn coplanar_triangles() {
+        assert!(triangles_intersect(
+            T,
+            T.map(|p| p + Vec3::new(0.2, 0.2, 0.0))
+        ));
+        assert!(!triangles_intersect(
+            T,
+            T.map(|p| p + Vec3::new(0.6, 0.6, 0.0))
+        ));
+    }
+
+    #[test]
+    fn shared_vertices() {
+        let positions = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::Y,
+            Vec3::new(0.5, 0.5, 1.0),
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(0.5, 0.5, -1.0),
+        ];
+
+        // Sharing an edge, or a vertex only.
+        assert!(!faces_intersect(&positions, [0, 1, 2], [1, 0, 3]));
+        assert!(!faces_intersect(&positions, [0, 1, 2], [0, 4, 3]));
+
+        // Sharing a vertex, while the opposite edge pierces the other face.
+        assert!(faces_intersect(&positions, [0, 1, 2], [0, 3, 5]));
+    }
+
+    #[test]
+    fn finds_pairs_once() {
+        let positions = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::Y,
+            Vec3::new(0.2, 0.2, -1.0),
+            Vec3::new(0.2, 0.2, 1.0),
+            Vec3::new(0.3, 0.3, 1.0),
+        ];
+
+        let faces = [[0, 1, 2], [3, 4, 5]];
+        assert_eq!(find_self_intersections(&positions, &faces), vec![[0, 1]]);
+    }
+
+    #[test]
+    fn sphere_measurements() {
+        let radius = 0.8;
+        let sphere = hermite_from_fn(move |p: Vec3| p.length() - radius, |p| p);
+        let mut mesh = IndexedSeparateNormals::default();
+
+        DualContouring::new(&sphere, 64, 1e-4)
+            .extract(WithIndexedSeparateNormals::new(&mut mesh, &sphere))
+            .unwrap();
+
+        let r = radius as f64;
+        let volume = mesh.signed_volume().unwrap();
+        let expected = 4.0 / 3.0 * core::f64::consts::PI * r.powi(3);
+        assert!((volume / expected - 1.0).abs() < 0.02, "{volume}");
+
+        let area = mesh.surface_area();
+        let expected = 4.0 * core::f64::consts::PI * r.powi(2);
+        assert!((area / expected - 1.0).abs() < 0.02, "{area}");
+
+        assert!(mesh.centroid().length() < 1e-3);
+
+        // Removing a face leaves a hole bounded by its 3 edges.
+        mesh.faces.pop();
+        assert_eq!(
+            mesh.signed_volume(),
+            Err(MeasureError::OpenMesh {
+                boundary_edge_count: 3
+            })
+        );
+    }
+
+    #[test]
+    fn tetrahedron_measurements() {
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::Y, Vec3::Z];
+        let faces = [[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]];
+
+        assert!(boundary_edges(&faces).is_empty());
+        assert_eq!(boundary_edges(&faces[1..]), vec![[0, 1], [1, 2], [2, 0]]);
+        assert!((signed_volume(&positions, &faces).unwrap() - 1.0 / 6.0).abs() < 1e-12);
+
+        let inverted = faces.map(|[a, b, c]| [a, c, b]);
+        assert!((signed_volume(&positions, &inverted).unwrap() + 1.0 / 6.0).abs() < 1e-12);
+
+        let area = surface_area(&positions, &faces);
+        assert!((area - (1.5 + 3f64.sqrt() / 2.0)).abs() < 1e-6);
+    }
+}
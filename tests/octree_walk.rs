@@ -0,0 +1,147 @@
+//! Checks properties of the octree walk that can't be observed from inside
+//! the library's own test binary.
+
+use core::cell::Cell;
+use dual_contouring::source::hermite_from_fn;
+use dual_contouring::{
+    Bounds, DetailRegions, DualContouring, IndexedSeparateNormals, Octree, OctreeCell,
+    WithIndexedSeparateNormals, WithVertexCells,
+};
+use glam::Vec3;
+use std::alloc::{GlobalAlloc, Layout, System};
+
+/// Returns the address of a local in the calling frame, which tells how deep
+/// the stack is at that point.
+#[inline(never)]
+fn stack_address() -> usize {
+    let marker = 0u8;
+    core::hint::black_box(&marker) as *const u8 as usize
+}
+
+/// Extracts a plane through a region refined down to `level`, and returns the
+/// deepest the stack got below `base` while sampling it.
+fn extraction_depth(level: u32, base: usize) -> usize {
+    let deepest = Cell::new(0);
+    let plane = hermite_from_fn(
+        |p: Vec3| {
+            deepest.set(deepest.get().max(base - stack_address()));
+            p.x - 0.3
+        },
+        |_| Vec3::X,
+    );
+
+    // Only a tiny region around a point on the plane is refined to `level`.
+    let point = Vec3::new(0.3, -0.2, 0.1);
+    let regions = DetailRegions::new(3).with_region(Bounds::new(point - 1e-3, point + 1e-3), level);
+    let dc = DualContouring::new(&plane, 1 << level, 1e-6).with_detail_regions(regions);
+
+    let mut mesh = IndexedSeparateNormals::default();
+    let mut cells = Vec::new();
+    dc.extract(WithVertexCells::new(
+        WithIndexedSeparateNormals::new(&mut mesh, &plane),
+        &mut cells,
+    ))
+    .unwrap();
+
+    assert!(!mesh.faces.is_empty());
+    assert!(cells.iter().any(|cell| cell.level() == level));
+
+    deepest.get()
+}
+
+#[test]
+fn deep_extraction_is_iterative() {
+    // Runs on a fresh thread so that it gets the default stack size.
+    let (shallow, deep) = std::thread::spawn(|| {
+        let base = stack_address();
+        (extraction_depth(3, base), extraction_depth(12, base))
+    })
+    .join()
+    .unwrap();
+
+    // A recursive traversal would sample from frames nested once more per
+    // level, so refining nine more levels would deepen the stack by as many
+    // frames.
+    assert!(deep <= shallow + 1024, "{shallow} vs {deep} bytes");
+}
+
+#[test]
+fn deep_walk_is_iterative() {
+    // Only the cells around a single point are refined down to level 12.
+    let domain = Bounds::new(-Vec3::ONE, Vec3::ONE);
+    let point = Vec3::new(0.3, -0.2, 0.1);
+    let octree = Octree::build(domain, 12, |cell| cell.bounds(&domain).contains(point));
+
+    let mut levels = Vec::new();
+    let mut addresses = Vec::new();
+
+    octree.for_each_minimal_edge(|edge| {
+        levels.extend(edge.neighbors().iter().map(|cell| cell.level()));
+        addresses.push(stack_address());
+    });
+
+    assert!(levels.contains(&1) && levels.contains(&12));
+    // A recursive walk would call back from deeper frames for deeper edges.
+    assert!(addresses.iter().all(|&address| address == addresses[0]));
+}
+
+/// Counts the heap allocations made by the current thread.
+struct CountingAllocator;
+
+std::thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn walk_does_not_allocate() {
+    let domain = Bounds::new(Vec3::ZERO, Vec3::ONE);
+    let octree = Octree::build(domain, 6, |cell| {
+        cell.bounds(&domain).contains(Vec3::splat(0.3))
+    });
+
+    let before = ALLOCATIONS.with(Cell::get);
+    let mut count = 0;
+    let mut sum = Vec3::ZERO;
+    let mut splits = 0;
+
+    let root = OctreeCell::root();
+    let is_leaf = |cell: &OctreeCell| octree.is_leaf(cell);
+
+    octree.for_each_minimal_edge(|edge| {
+        count += 1;
+
+        for cell in edge.neighbors() {
+            sum += cell.corner_positions(octree.domain())[0];
+            sum += cell.edge_segments(octree.domain())[0].1;
+        }
+
+        // The interior faces and edges of the root next to its refined
+        // sub-cell split.
+        for face in root.interior_faces() {
+            splits += face.sub_faces(is_leaf).is_some() as usize;
+            splits += face.sub_edges(is_leaf).is_some() as usize;
+        }
+
+        for edge in root.interior_edges() {
+            splits += edge.sub_edges(is_leaf).is_some() as usize;
+        }
+    });
+
+    assert!(count > 0 && sum.is_finite());
+    assert!(splits > 0);
+    assert_eq!(ALLOCATIONS.with(Cell::get), before);
+}